@@ -34,9 +34,13 @@ pub use cached::CachedOnDiskCorpus;
 pub mod minimizer;
 
 pub mod nop;
+#[cfg(feature = "otlp_monitor")]
+pub mod otlp;
 #[cfg(all(feature = "cmin", unix))]
 pub use minimizer::*;
 pub use nop::NopCorpus;
+#[cfg(feature = "otlp_monitor")]
+pub use otlp::OtlpCorpus;
 
 /// An abstraction for the index that identify a testcase in the corpus
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
@@ -0,0 +1,195 @@
+//! The [`OtlpCorpus`] wraps another corpus and records a span for each operation that changes it, or touches the disk,
+//! for OpenTelemetry. See [`crate::monitors::otlp`].
+
+use core::{
+    cell::{Ref, RefCell, RefMut},
+    time::Duration,
+};
+
+use libafl_bolts::current_time;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    Error,
+    corpus::{Corpus, CorpusId, EnableDisableCorpus, HasTestcase, Testcase},
+    monitors::otlp::OtlpExporter,
+};
+
+/// A corpus recording `corpus.add`, `corpus.replace`, `corpus.remove`, `corpus.load_input` and `corpus.store_input` spans
+/// to the [`OtlpExporter::global`] exporter, with the corpus size after the operation as attribute.
+#[derive(Default, Serialize, Deserialize, Clone, Debug)]
+pub struct OtlpCorpus<C> {
+    inner: C,
+}
+
+impl<C> OtlpCorpus<C> {
+    /// Wraps the `inner` corpus
+    pub fn new(inner: C) -> Self {
+        Self { inner }
+    }
+
+    /// The wrapped corpus
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    /// Records the span of an operation, from `start` to now, if there is an exporter
+    fn record<I>(&self, operation: &'static str, start: Duration, ok: bool)
+    where
+        C: Corpus<I>,
+    {
+        if let Some(exporter) = OtlpExporter::global() {
+            exporter.record(
+                exporter
+                    .span(operation, start)
+                    .attribute("corpus.count", self.inner.count_all())
+                    .attribute("corpus.ok", ok),
+            );
+        }
+    }
+}
+
+impl<C, I> Corpus<I> for OtlpCorpus<C>
+where
+    C: Corpus<I>,
+{
+    #[inline]
+    fn count(&self) -> usize {
+        self.inner.count()
+    }
+
+    #[inline]
+    fn count_disabled(&self) -> usize {
+        self.inner.count_disabled()
+    }
+
+    #[inline]
+    fn count_all(&self) -> usize {
+        self.inner.count_all()
+    }
+
+    fn add(&mut self, testcase: Testcase<I>) -> Result<CorpusId, Error> {
+        let start = current_time();
+        let result = self.inner.add(testcase);
+        self.record("corpus.add", start, result.is_ok());
+        result
+    }
+
+    fn add_disabled(&mut self, testcase: Testcase<I>) -> Result<CorpusId, Error> {
+        let start = current_time();
+        let result = self.inner.add_disabled(testcase);
+        self.record("corpus.add", start, result.is_ok());
+        result
+    }
+
+    fn replace(&mut self, id: CorpusId, testcase: Testcase<I>) -> Result<Testcase<I>, Error> {
+        let start = current_time();
+        let result = self.inner.replace(id, testcase);
+        self.record("corpus.replace", start, result.is_ok());
+        result
+    }
+
+    fn remove(&mut self, id: CorpusId) -> Result<Testcase<I>, Error> {
+        let start = current_time();
+        let result = self.inner.remove(id);
+        self.record("corpus.remove", start, result.is_ok());
+        result
+    }
+
+    #[inline]
+    fn get(&self, id: CorpusId) -> Result<&RefCell<Testcase<I>>, Error> {
+        self.inner.get(id)
+    }
+
+    #[inline]
+    fn get_from_all(&self, id: CorpusId) -> Result<&RefCell<Testcase<I>>, Error> {
+        self.inner.get_from_all(id)
+    }
+
+    #[inline]
+    fn current(&self) -> &Option<CorpusId> {
+        self.inner.current()
+    }
+
+    #[inline]
+    fn current_mut(&mut self) -> &mut Option<CorpusId> {
+        self.inner.current_mut()
+    }
+
+    #[inline]
+    fn next(&self, id: CorpusId) -> Option<CorpusId> {
+        self.inner.next(id)
+    }
+
+    #[inline]
+    fn peek_free_id(&self) -> CorpusId {
+        self.inner.peek_free_id()
+    }
+
+    #[inline]
+    fn prev(&self, id: CorpusId) -> Option<CorpusId> {
+        self.inner.prev(id)
+    }
+
+    #[inline]
+    fn first(&self) -> Option<CorpusId> {
+        self.inner.first()
+    }
+
+    #[inline]
+    fn last(&self) -> Option<CorpusId> {
+        self.inner.last()
+    }
+
+    #[inline]
+    fn nth(&self, nth: usize) -> CorpusId {
+        self.inner.nth(nth)
+    }
+
+    #[inline]
+    fn nth_from_all(&self, nth: usize) -> CorpusId {
+        self.inner.nth_from_all(nth)
+    }
+
+    fn load_input_into(&self, testcase: &mut Testcase<I>) -> Result<(), Error> {
+        let start = current_time();
+        let result = self.inner.load_input_into(testcase);
+        self.record("corpus.load_input", start, result.is_ok());
+        result
+    }
+
+    fn store_input_from(&self, testcase: &Testcase<I>) -> Result<(), Error> {
+        let start = current_time();
+        let result = self.inner.store_input_from(testcase);
+        self.record("corpus.store_input", start, result.is_ok());
+        result
+    }
+}
+
+impl<C, I> HasTestcase<I> for OtlpCorpus<C>
+where
+    C: HasTestcase<I>,
+{
+    fn testcase(&self, id: CorpusId) -> Result<Ref<'_, Testcase<I>>, Error> {
+        self.inner.testcase(id)
+    }
+
+    fn testcase_mut(&self, id: CorpusId) -> Result<RefMut<'_, Testcase<I>>, Error> {
+        self.inner.testcase_mut(id)
+    }
+}
+
+impl<C> EnableDisableCorpus for OtlpCorpus<C>
+where
+    C: EnableDisableCorpus,
+{
+    #[inline]
+    fn disable(&mut self, id: CorpusId) -> Result<(), Error> {
+        self.inner.disable(id)
+    }
+
+    #[inline]
+    fn enable(&mut self, id: CorpusId) -> Result<(), Error> {
+        self.inner.enable(id)
+    }
+}
@@ -0,0 +1,76 @@
+//! Async streams between nodes for the `tokio`-based event managers, optionally secured with TLS.
+//!
+//! See [`libafl_bolts::tls`] for how nodes authenticate each other.
+
+use alloc::boxed::Box;
+use core::fmt::Debug;
+
+#[cfg(feature = "tls")]
+use libafl_bolts::tls::{TLS_SESSION_ACK, TlsConfig};
+#[cfg(feature = "tls")]
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    net::TcpStream,
+};
+
+#[cfg(feature = "tls")]
+use crate::Error;
+
+/// A stream to another node, plaintext or TLS.
+pub(crate) trait AsyncStream: AsyncRead + AsyncWrite + Unpin + Send + Sync + Debug {}
+
+impl<T> AsyncStream for T where T: AsyncRead + AsyncWrite + Unpin + Send + Sync + Debug {}
+
+/// A boxed [`AsyncStream`]
+pub(crate) type BoxedAsyncStream = Box<dyn AsyncStream>;
+
+/// Wraps a plaintext stream
+pub(crate) fn plain(stream: TcpStream) -> BoxedAsyncStream {
+    Box::new(stream)
+}
+
+/// Runs the server side of the TLS handshake on an accepted stream, and acknowledges the session
+#[cfg(feature = "tls")]
+pub(crate) async fn tls_accept(
+    tls_config: &TlsConfig,
+    stream: TcpStream,
+) -> Result<BoxedAsyncStream, Error> {
+    let acceptor = tokio_rustls::TlsAcceptor::from(tls_config.server_config());
+    let mut stream = acceptor
+        .accept(stream)
+        .await
+        .map_err(|err| Error::os_error(err, "TLS handshake with client failed"))?;
+    stream
+        .write_all(&[TLS_SESSION_ACK])
+        .await
+        .map_err(|err| Error::os_error(err, "Could not acknowledge TLS session"))?;
+    stream
+        .flush()
+        .await
+        .map_err(|err| Error::os_error(err, "Could not acknowledge TLS session"))?;
+    Ok(Box::new(stream))
+}
+
+/// Runs the client side of the TLS handshake on a connected stream, and waits for the server to acknowledge the session
+#[cfg(feature = "tls")]
+pub(crate) async fn tls_connect(
+    tls_config: &TlsConfig,
+    stream: TcpStream,
+) -> Result<BoxedAsyncStream, Error> {
+    let connector = tokio_rustls::TlsConnector::from(tls_config.client_config());
+    let mut stream = connector
+        .connect(TlsConfig::server_name(), stream)
+        .await
+        .map_err(|err| Error::os_error(err, "TLS handshake with server failed"))?;
+    let ack = stream
+        .read_u8()
+        .await
+        .map_err(|err| Error::os_error(err, "TLS session was rejected by the server"))?;
+    if ack != TLS_SESSION_ACK {
+        return Err(Error::illegal_state(format!(
+            "Unexpected TLS session acknowledgement {ack:#x}"
+        )));
+    }
+    Ok(Box::new(stream))
+}
@@ -23,11 +23,12 @@ use tokio::{
 
 use crate::{
     events::{
-        EventWithStats,
+        Event, EventWithStats,
         centralized::_LLMP_TAG_TO_MAIN,
         multi_machine::{MultiMachineMsg, TcpMultiMachineState},
     },
     inputs::Input,
+    monitors::stats::{AggregatorOps, UserStats, UserStatsValue},
 };
 
 /// The Receiving side of the multi-machine architecture
@@ -68,6 +69,19 @@ impl<A, I> TcpMultiMachineLlmpSenderHook<A, I> {
     }
 }
 
+impl<A, I> Drop for TcpMultiMachineLlmpSenderHook<A, I> {
+    /// Tells the other nodes that this node leaves the tree.
+    fn drop(&mut self) {
+        // We can't block inside of a runtime, the connections will just be dropped.
+        if tokio::runtime::Handle::try_current().is_ok() {
+            return;
+        }
+        let shared_state = self.shared_state.clone();
+        self.rt
+            .block_on(async move { shared_state.write().await.leave().await });
+    }
+}
+
 impl<A, I> TcpMultiMachineLlmpReceiverHook<A, I>
 where
     A: Clone + Display + ToSocketAddrs + Send + Sync + 'static,
@@ -226,6 +240,27 @@ where
 
             new_msgs.extend(msgs_to_forward?);
 
+            // Report the link statistics to the monitor, through the main node
+            if let Some(link_stats) = state_wr_lock.stats_to_report() {
+                for (link, stats) in link_stats {
+                    for (stat, value) in stats.entries() {
+                        let event = EventWithStats::with_current_time(
+                            Event::UpdateUserStats {
+                                name: format!("mm {link} {stat}").into(),
+                                value: UserStats::new(
+                                    UserStatsValue::Number(value),
+                                    AggregatorOps::Sum,
+                                ),
+                                phantom: PhantomData,
+                            },
+                            0,
+                        );
+                        let (flags, buf) = Self::try_compress(&mut state_wr_lock, &event)?;
+                        new_msgs.push((_LLMP_TAG_TO_MAIN, flags, buf));
+                    }
+                }
+            }
+
             Ok(())
         });
 
@@ -1,5 +1,5 @@
 //! Hooks called on broker side
-use alloc::vec::Vec;
+use alloc::{string::ToString, vec::Vec};
 use core::marker::PhantomData;
 
 use libafl_bolts::{
@@ -10,11 +10,13 @@ use libafl_bolts::{
 use libafl_bolts::{compress::GzipCompressor, llmp::LLMP_FLAG_COMPRESSED};
 use serde::de::DeserializeOwned;
 
+#[cfg(feature = "std")]
+use crate::events::CampaignCheckpoint;
 #[cfg(feature = "llmp_compression")]
 use crate::events::llmp::COMPRESS_THRESHOLD;
 use crate::{
     Error,
-    events::{BrokerEventResult, Event, llmp::LLMP_TAG_EVENT_TO_BOTH},
+    events::{BrokerEventResult, Event, LogSeverity, llmp::LLMP_TAG_EVENT_TO_BOTH},
     monitors::{Monitor, stats::ClientStatsManager},
 };
 
@@ -24,6 +26,12 @@ pub mod centralized;
 #[cfg(all(unix, feature = "std"))]
 pub use centralized::*;
 
+/// Objective action hook
+#[cfg(feature = "std")]
+pub mod objective;
+#[cfg(feature = "std")]
+pub use objective::*;
+
 /// Multi-machine hook
 #[cfg(all(unix, feature = "multi_machine"))]
 pub mod centralized_multi_machine;
@@ -40,6 +48,9 @@ pub struct StdLlmpEventHook<I, MT> {
     compressor: GzipCompressor,
     phantom: PhantomData<I>,
     client_stats_manager: ClientStatsManager,
+    /// The checkpoint to save the stats to, whenever a client saved its state
+    #[cfg(feature = "std")]
+    checkpoint: Option<CampaignCheckpoint>,
 }
 
 impl<I, MT, SHM, SP> LlmpHook<SHM, SP> for StdLlmpEventHook<I, MT>
@@ -74,6 +85,14 @@ where
                 &*msg
             };
             let event: EventWithStats<I> = postcard::from_bytes(event_bytes)?;
+            #[cfg(feature = "std")]
+            if let (Event::ControlAck { command }, Some(checkpoint)) =
+                (event.event(), &self.checkpoint)
+            {
+                if command.saves_state() {
+                    checkpoint.save_broker(self.client_stats_manager.snapshot())?;
+                }
+            }
             match Self::handle_in_broker(
                 monitor,
                 &mut self.client_stats_manager,
@@ -109,12 +128,26 @@ where
             #[cfg(feature = "llmp_compression")]
             compressor: GzipCompressor::with_threshold(COMPRESS_THRESHOLD),
             client_stats_manager: ClientStatsManager::default(),
+            #[cfg(feature = "std")]
+            checkpoint: None,
             phantom: PhantomData,
         })
     }
 
+    /// Saves the stats of all clients to the [`CampaignCheckpoint`], whenever a client acknowledges
+    /// a command it saved its state for. Restore them with [`ClientStatsManager::restore`].
+    #[cfg(feature = "std")]
+    pub fn set_checkpoint(&mut self, checkpoint: CampaignCheckpoint) {
+        self.checkpoint = Some(checkpoint);
+    }
+
+    /// The stats of all clients, as shown by the monitor
+    pub fn client_stats_manager_mut(&mut self) -> &mut ClientStatsManager {
+        &mut self.client_stats_manager
+    }
+
     /// Handle arriving events in the broker
-    fn handle_in_broker(
+    pub(crate) fn handle_in_broker(
         monitor: &mut MT,
         client_stats_manager: &mut ClientStatsManager,
         client_id: ClientId,
@@ -126,6 +159,17 @@ where
         client_stats_manager.update_client_stats_for(client_id, |client_stat| {
             client_stat.update_executions(stats.executions, stats.time);
         })?;
+        if let Some(change) = client_stats_manager.update_history() {
+            let log = EventWithStats::new(
+                Event::Log {
+                    severity_level: LogSeverity::Warn,
+                    message: change.to_string(),
+                    phantom: PhantomData,
+                },
+                stats.clone(),
+            );
+            Self::handle_in_broker(monitor, client_stats_manager, client_id, &log)?;
+        }
 
         let event = event.event();
         match &event {
@@ -198,8 +242,13 @@ where
                 log::log!((*severity_level).into(), "{message}");
                 Ok(BrokerEventResult::Handled)
             }
-            Event::Stop => Ok(BrokerEventResult::Forward),
-            //_ => Ok(BrokerEventResult::Forward),
+            Event::Stop | Event::Pause | Event::Resume | Event::Checkpoint => {
+                Ok(BrokerEventResult::Forward)
+            }
+            Event::ControlAck { command } => {
+                log::info!("Client {client_id:?} acknowledged {command}");
+                Ok(BrokerEventResult::Handled)
+            } //_ => Ok(BrokerEventResult::Forward),
         }
     }
 }
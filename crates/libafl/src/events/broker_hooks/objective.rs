@@ -0,0 +1,313 @@
+//! A broker hook that hands every new objective to a user-configured action, such as a triage pipeline.
+//!
+//! Each [`Event::Objective`] turns into an [`ObjectiveReport`], which an [`ObjectiveAction`] receives as JSON:
+//! a local command gets it on stdin, a local HTTP endpoint as a `POST` body, and a named pipe as one line.
+//! The actions run on a separate thread, in the order the objectives arrived, so that they never stall the broker.
+
+use alloc::{string::String, vec::Vec};
+use core::{marker::PhantomData, net::SocketAddr, time::Duration};
+use std::{
+    fs::{self, OpenOptions},
+    io::{Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    path::PathBuf,
+    process::{Command, Stdio},
+    sync::mpsc::{Sender, channel},
+    thread,
+};
+
+use libafl_bolts::{
+    ClientId,
+    llmp::{Flags, LlmpBrokerInner, LlmpHook, LlmpMsgHookResult, Tag},
+};
+#[cfg(feature = "llmp_compression")]
+use libafl_bolts::{compress::GzipCompressor, llmp::LLMP_FLAG_COMPRESSED};
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "llmp_compression")]
+use crate::events::llmp::COMPRESS_THRESHOLD;
+use crate::{
+    Error,
+    events::{Event, EventWithStats, llmp::LLMP_TAG_EVENT_TO_BOTH},
+    inputs::Input,
+};
+
+/// How long a webhook may take to accept a report
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// What an [`ObjectiveAction`] learns about a new objective
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ObjectiveReport {
+    /// The client that found the objective
+    pub client_id: u32,
+    /// Where the broker wrote the input to, if the client shared it, and the hook has an objectives dir
+    pub input_path: Option<PathBuf>,
+    /// The name of the input, a hash of its contents, to deduplicate reports by
+    pub input_name: Option<String>,
+    /// The number of objectives of the client, including this one
+    pub objective_size: usize,
+    /// The executions of the client so far
+    pub executions: u64,
+    /// The time of the objective, in seconds since the epoch
+    pub time_secs: u64,
+}
+
+/// What to do with each new objective
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ObjectiveAction {
+    /// Runs the program with the given arguments, the [`ObjectiveReport`] as JSON on its stdin,
+    /// and the input path, if any, in the `LIBAFL_OBJECTIVE_INPUT` env variable.
+    Command(Vec<String>),
+    /// `POST`s the [`ObjectiveReport`] as JSON to a local `http://host:port/path` endpoint.
+    Webhook(String),
+    /// Writes the [`ObjectiveReport`] as one line of JSON to a named pipe, or any other file.
+    NamedPipe(PathBuf),
+}
+
+impl ObjectiveAction {
+    /// Runs this action for one report
+    pub fn run(&self, report: &ObjectiveReport) -> Result<(), Error> {
+        let json = serde_json::to_vec(report)
+            .map_err(|err| Error::serialize(format!("Failed to json-ify the report: {err:?}")))?;
+        match self {
+            Self::Command(argv) => {
+                let Some((program, args)) = argv.split_first() else {
+                    return Err(Error::illegal_argument("Empty objective command"));
+                };
+                let mut command = Command::new(program);
+                command.args(args).stdin(Stdio::piped());
+                if let Some(input_path) = &report.input_path {
+                    command.env("LIBAFL_OBJECTIVE_INPUT", input_path);
+                }
+                let mut child = command.spawn()?;
+                child.stdin.take().unwrap().write_all(&json)?;
+                let status = child.wait()?;
+                if !status.success() {
+                    log::warn!("Objective command {program} exited with {status}");
+                }
+            }
+            Self::Webhook(url) => {
+                let (addr, host, path) = parse_http_url(url)?;
+                let mut stream = TcpStream::connect_timeout(&addr, WEBHOOK_TIMEOUT)?;
+                stream.set_read_timeout(Some(WEBHOOK_TIMEOUT))?;
+                stream.set_write_timeout(Some(WEBHOOK_TIMEOUT))?;
+                write!(
+                    stream,
+                    "POST {path} HTTP/1.1\r\nHost: {host}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    json.len()
+                )?;
+                stream.write_all(&json)?;
+
+                let mut response = String::new();
+                stream.read_to_string(&mut response)?;
+                let status_line = response.lines().next().unwrap_or_default();
+                if !status_line
+                    .split(' ')
+                    .nth(1)
+                    .is_some_and(|code| code.starts_with('2'))
+                {
+                    log::warn!("Objective webhook {url} answered {status_line:?}");
+                }
+            }
+            Self::NamedPipe(path) => {
+                let mut pipe = OpenOptions::new().append(true).create(true).open(path)?;
+                let mut line = json;
+                line.push(b'\n');
+                pipe.write_all(&line)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Splits a `http://host:port/path` url into the address to connect to, the host, and the path
+pub(crate) fn parse_http_url(url: &str) -> Result<(SocketAddr, &str, &str), Error> {
+    let Some(rest) = url.strip_prefix("http://") else {
+        return Err(Error::illegal_argument(format!(
+            "Need a plain http:// url, got {url}"
+        )));
+    };
+    let (host, path) = rest.find('/').map_or((rest, "/"), |i| rest.split_at(i));
+    let addr = if host.contains(':') {
+        host.to_socket_addrs()?.next()
+    } else {
+        (host, 80).to_socket_addrs()?.next()
+    };
+    let addr = addr.ok_or_else(|| Error::illegal_argument(format!("Cannot resolve {host}")))?;
+    Ok((addr, host, path))
+}
+
+/// An LLMP broker hook, running [`ObjectiveAction`]s for each new objective of the clients.
+/// Put it before the [`StdLlmpEventHook`](super::StdLlmpEventHook), which handles objectives for good.
+#[derive(Debug)]
+pub struct ObjectiveLlmpHook<I> {
+    objectives_dir: Option<PathBuf>,
+    reports: Option<Sender<ObjectiveReport>>,
+    #[cfg(feature = "llmp_compression")]
+    compressor: GzipCompressor,
+    phantom: PhantomData<I>,
+}
+
+impl<I> ObjectiveLlmpHook<I> {
+    /// Creates a new [`ObjectiveLlmpHook`], running the `actions` for each objective.
+    /// If `objectives_dir` is set, the broker writes the inputs the clients share to it,
+    /// see [`crate::fuzzer::HasObjective::set_share_objectives`].
+    #[must_use]
+    pub fn new(actions: Vec<ObjectiveAction>, objectives_dir: Option<PathBuf>) -> Self {
+        let reports = (!actions.is_empty()).then(|| {
+            let (sender, receiver) = channel::<ObjectiveReport>();
+            thread::spawn(move || {
+                for report in receiver {
+                    for action in &actions {
+                        if let Err(e) = action.run(&report) {
+                            log::error!("Objective action {action:?} failed: {e}");
+                        }
+                    }
+                }
+            });
+            sender
+        });
+        Self {
+            objectives_dir,
+            reports,
+            #[cfg(feature = "llmp_compression")]
+            compressor: GzipCompressor::with_threshold(COMPRESS_THRESHOLD),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Builds the report for an objective, writing its input to the objectives dir
+    fn report(
+        &self,
+        client_id: ClientId,
+        event: &EventWithStats<I>,
+    ) -> Result<Option<ObjectiveReport>, Error>
+    where
+        I: Input,
+    {
+        let Event::Objective {
+            input,
+            objective_size,
+        } = event.event()
+        else {
+            return Ok(None);
+        };
+        let input_name = input.as_ref().map(|input| input.generate_name(None));
+        let mut input_path = None;
+        if let (Some(input), Some(name), Some(dir)) = (input, &input_name, &self.objectives_dir) {
+            fs::create_dir_all(dir)?;
+            let path = dir.join(name);
+            input.to_file(&path)?;
+            input_path = Some(path);
+        }
+        Ok(Some(ObjectiveReport {
+            client_id: client_id.0,
+            input_path,
+            input_name,
+            objective_size: *objective_size,
+            executions: event.stats().executions,
+            time_secs: event.stats().time.as_secs(),
+        }))
+    }
+}
+
+impl<I, SHM, SP> LlmpHook<SHM, SP> for ObjectiveLlmpHook<I>
+where
+    I: Input,
+{
+    fn on_new_message(
+        &mut self,
+        _broker_inner: &mut LlmpBrokerInner<SHM, SP>,
+        client_id: ClientId,
+        msg_tag: &mut Tag,
+        #[cfg(feature = "llmp_compression")] msg_flags: &mut Flags,
+        #[cfg(not(feature = "llmp_compression"))] _msg_flags: &mut Flags,
+        msg: &mut [u8],
+        _new_msgs: &mut Vec<(Tag, Flags, Vec<u8>)>,
+    ) -> Result<LlmpMsgHookResult, Error> {
+        let Some(reports) = &self.reports else {
+            return Ok(LlmpMsgHookResult::ForwardToClients);
+        };
+        if *msg_tag != LLMP_TAG_EVENT_TO_BOTH {
+            return Ok(LlmpMsgHookResult::ForwardToClients);
+        }
+
+        #[cfg(not(feature = "llmp_compression"))]
+        let event_bytes = msg;
+        #[cfg(feature = "llmp_compression")]
+        let compressed;
+        #[cfg(feature = "llmp_compression")]
+        let event_bytes = if *msg_flags & LLMP_FLAG_COMPRESSED == LLMP_FLAG_COMPRESSED {
+            compressed = self.compressor.decompress(msg)?;
+            &compressed
+        } else {
+            &*msg
+        };
+        let event: EventWithStats<I> = postcard::from_bytes(event_bytes)?;
+        if let Some(report) = self.report(client_id, &event)? {
+            if reports.send(report).is_err() {
+                log::error!("The objective action thread is gone, dropping the report");
+            }
+        }
+        Ok(LlmpMsgHookResult::ForwardToClients)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::String;
+    use std::{
+        io::{BufRead, BufReader, Read, Write},
+        net::TcpListener,
+        thread,
+    };
+
+    use super::{ObjectiveAction, ObjectiveReport};
+
+    #[test]
+    fn test_objective_webhook() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+            let mut content_length = 0;
+            loop {
+                let mut header = String::new();
+                reader.read_line(&mut header).unwrap();
+                if header == "\r\n" {
+                    break;
+                }
+                if let Some(len) = header.strip_prefix("Content-Length: ") {
+                    content_length = len.trim().parse().unwrap();
+                }
+            }
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body).unwrap();
+            reader
+                .get_mut()
+                .write_all(b"HTTP/1.1 204 No Content\r\n\r\n")
+                .unwrap();
+            (request_line, body)
+        });
+
+        let report = ObjectiveReport {
+            client_id: 3,
+            input_path: None,
+            input_name: Some("deadbeef".into()),
+            objective_size: 1,
+            executions: 1234,
+            time_secs: 42,
+        };
+        ObjectiveAction::Webhook(format!("http://{addr}/crashes"))
+            .run(&report)
+            .unwrap();
+
+        let (request_line, body) = server.join().unwrap();
+        assert_eq!(request_line, "POST /crashes HTTP/1.1\r\n");
+        let received: ObjectiveReport = serde_json::from_slice(&body).unwrap();
+        assert_eq!(received, report);
+    }
+}
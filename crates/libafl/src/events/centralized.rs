@@ -11,6 +11,8 @@ use alloc::string::String;
 use core::{fmt::Debug, marker::PhantomData, time::Duration};
 use std::process;
 
+#[cfg(feature = "multi_machine")]
+use libafl_bolts::llmp::LLMP_FLAG_FROM_MM;
 use libafl_bolts::{
     ClientId,
     llmp::{LlmpClient, LlmpClientDescription, Tag},
@@ -30,8 +32,9 @@ use crate::{
     common::HasMetadata,
     events::{
         Event, EventConfig, EventFirer, EventManagerId, EventReceiver, EventRestarter,
-        HasEventManagerId, LogSeverity, ProgressReporter, SendExiting, std_maybe_report_progress,
-        std_report_progress,
+        HasEventManagerId, LogSeverity, ProgressReporter, SendExiting,
+        journal::{EventJournalWriter, JournalDirection},
+        std_maybe_report_progress, std_report_progress,
     },
     inputs::Input,
     state::{HasExecutions, HasLastReportTime, MaybeHasClientPerfMonitor, Stoppable},
@@ -48,6 +51,8 @@ pub struct CentralizedEventManager<EM, I, S, SHM, SP> {
     #[cfg(feature = "llmp_compression")]
     compressor: GzipCompressor,
     is_main: bool,
+    /// Journals the events forwarded to, or received by, the main node
+    journal: Option<EventJournalWriter>,
     phantom: PhantomData<(I, S)>,
 }
 
@@ -63,6 +68,7 @@ impl CentralizedEventManager<(), (), (), (), ()> {
 #[derive(Debug)]
 pub struct CentralizedEventManagerBuilder {
     is_main: bool,
+    journal: Option<EventJournalWriter>,
 }
 
 impl Default for CentralizedEventManagerBuilder {
@@ -75,13 +81,25 @@ impl CentralizedEventManagerBuilder {
     /// The constructor
     #[must_use]
     pub fn new() -> Self {
-        Self { is_main: false }
+        Self {
+            is_main: false,
+            journal: None,
+        }
     }
 
     /// Make this a main evaluator node
     #[must_use]
-    pub fn is_main(self, is_main: bool) -> Self {
-        Self { is_main }
+    pub fn is_main(mut self, is_main: bool) -> Self {
+        self.is_main = is_main;
+        self
+    }
+
+    /// Journal the events sent to the main node (for secondary nodes), or received from the secondary nodes
+    /// (for the main node). The journal can be replayed with a [`ReplayEventManager`](crate::events::journal::ReplayEventManager).
+    #[must_use]
+    pub fn journal(mut self, journal: EventJournalWriter) -> Self {
+        self.journal = Some(journal);
+        self
     }
 
     /// Creates a new [`CentralizedEventManager`].
@@ -99,6 +117,7 @@ impl CentralizedEventManagerBuilder {
             #[cfg(feature = "llmp_compression")]
             compressor: GzipCompressor::with_threshold(COMPRESS_THRESHOLD),
             is_main: self.is_main,
+            journal: self.journal,
             phantom: PhantomData,
         })
     }
@@ -184,6 +203,9 @@ where
             };
 
             if should_be_forwarded {
+                if let Some(journal) = &mut self.journal {
+                    journal.record(JournalDirection::Fired, self.client.sender().id(), &event)?;
+                }
                 self.forward_to_main(&event)?;
                 if is_tc {
                     // early return here because we only send it to centralized not main broker.
@@ -267,10 +289,14 @@ where
     fn try_receive(&mut self, state: &mut S) -> Result<Option<(EventWithStats<I>, bool)>, Error> {
         if self.is_main {
             // main node
-            self.receive_from_secondary(state)
-            // self.inner.process(fuzzer, state, executor)
+            if let Some(received) = self.receive_from_secondary(state)? {
+                return Ok(Some(received));
+            }
+            // The main node gets all testcases from the secondary nodes.
+            // From the broker, it only takes the control commands, see [`crate::events::control`].
+            while self.inner.try_receive(state)?.is_some() {}
+            Ok(None)
         } else {
-            // The main node does not process incoming events from the broker ATM
             self.inner.try_receive(state)
         }
     }
@@ -403,6 +429,19 @@ where
                 "Processor received message {}",
                 event.event().name_detailed()
             );
+            if let Some(journal) = &mut self.journal {
+                journal.record(JournalDirection::Received, client_id, &event)?;
+            }
+
+            // Stats of the multi-machine links, reported by the local broker.
+            // Stats coming from other machines are not ours to display.
+            #[cfg(feature = "multi_machine")]
+            if matches!(event.event(), Event::UpdateUserStats { .. })
+                && _flags & LLMP_FLAG_FROM_MM != LLMP_FLAG_FROM_MM
+            {
+                self.inner.fire(state, event)?;
+                continue;
+            }
 
             let event_name = event.event().name_detailed();
 
@@ -0,0 +1,174 @@
+//! Campaign checkpoints on disk, to restore a campaign after a reboot, or on another machine.
+//!
+//! On every `checkpoint`, `pause` and `stop` command (see [`crate::events::control`]),
+//! each client writes its whole serialized state to the [`CampaignCheckpoint`] directory,
+//! including the scheduler metadata, feedback history maps, tokens and RNG.
+//! The broker writes a [`StatsSnapshot`] of the campaign next to them, once each client acknowledged the command,
+//! so that the run time and the stats of all clients continue where they left off.
+//! Launching with [`Launcher::restore_checkpoint`](crate::events::launcher::Launcher) set
+//! starts every client from its state in the directory, instead of from a fresh one.
+//!
+//! Clients are matched to their state by the id the launcher gave them, so restore on the same cores.
+//! Corpora that live on disk are not part of the checkpoint, copy them along.
+
+use core::time::Duration;
+use std::{
+    fs::{self, File},
+    io::{ErrorKind, Write},
+    path::{Path, PathBuf},
+};
+
+use libafl_bolts::current_time;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+
+use crate::{Error, monitors::stats::StatsSnapshot};
+
+/// The name of the file the broker writes its state to
+const BROKER_CHECKPOINT_FILE: &str = "broker.checkpoint";
+
+/// What the broker keeps across a checkpoint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BrokerCheckpoint {
+    /// When the checkpoint was written
+    pub checkpoint_time: Duration,
+    /// The stats of the campaign, including its run time, see [`crate::monitors::stats::ClientStatsManager::restore`]
+    pub stats: StatsSnapshot,
+}
+
+/// A directory holding the checkpoint of a campaign
+#[derive(Debug, Clone)]
+pub struct CampaignCheckpoint {
+    dir: PathBuf,
+}
+
+impl CampaignCheckpoint {
+    /// Writes checkpoints to, and restores them from, the given directory
+    pub fn new<P>(dir: P) -> Self
+    where
+        P: AsRef<Path>,
+    {
+        Self {
+            dir: dir.as_ref().to_path_buf(),
+        }
+    }
+
+    /// The directory of this checkpoint
+    #[must_use]
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn client_path(&self, client_id: usize) -> PathBuf {
+        self.dir.join(format!("client_{client_id}.checkpoint"))
+    }
+
+    /// Writes `value` to `path`, replacing the old file only once the new one is complete
+    fn write<T>(&self, path: &Path, value: &T) -> Result<(), Error>
+    where
+        T: Serialize,
+    {
+        fs::create_dir_all(&self.dir)?;
+        let mut tmp_path = path.to_path_buf().into_os_string();
+        tmp_path.push(".tmp");
+
+        let mut file = File::create(&tmp_path)?;
+        file.write_all(&postcard::to_allocvec(value)?)?;
+        file.sync_all()?;
+        fs::rename(&tmp_path, path)?;
+        Ok(())
+    }
+
+    fn read<T>(path: &Path) -> Result<Option<T>, Error>
+    where
+        T: DeserializeOwned,
+    {
+        match fs::read(path) {
+            Ok(bytes) => Ok(Some(postcard::from_bytes(&bytes)?)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Saves the state of the client with the given launcher id
+    pub fn save_client<S>(&self, client_id: usize, state: &S) -> Result<(), Error>
+    where
+        S: Serialize,
+    {
+        self.write(&self.client_path(client_id), state)?;
+        log::info!(
+            "Checkpoint: saved the state of client {client_id} to {}",
+            self.dir.display()
+        );
+        Ok(())
+    }
+
+    /// Loads the state of the client with the given launcher id, if it was saved
+    pub fn load_client<S>(&self, client_id: usize) -> Result<Option<S>, Error>
+    where
+        S: DeserializeOwned,
+    {
+        Self::read(&self.client_path(client_id))
+    }
+
+    /// Saves the state of the broker, with the current stats of the campaign
+    pub fn save_broker(&self, stats: StatsSnapshot) -> Result<(), Error> {
+        let broker = BrokerCheckpoint {
+            checkpoint_time: current_time(),
+            stats,
+        };
+        self.write(&self.dir.join(BROKER_CHECKPOINT_FILE), &broker)
+    }
+
+    /// Loads the state of the broker, if it was saved
+    pub fn load_broker(&self) -> Result<Option<BrokerCheckpoint>, Error> {
+        Self::read(&self.dir.join(BROKER_CHECKPOINT_FILE))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+    use core::time::Duration;
+    use std::{env, fs, process};
+
+    use libafl_bolts::{ClientId, current_time};
+
+    use super::CampaignCheckpoint;
+    use crate::monitors::stats::ClientStatsManager;
+
+    #[test]
+    fn test_campaign_checkpoint() {
+        let dir = env::temp_dir().join(format!("libafl_checkpoint_test_{}", process::id()));
+        let checkpoint = CampaignCheckpoint::new(&dir);
+        assert!(checkpoint.load_client::<Vec<u8>>(1).unwrap().is_none());
+        assert!(checkpoint.load_broker().unwrap().is_none());
+
+        checkpoint.save_client(1, &vec![1_u8, 2, 3]).unwrap();
+        checkpoint.save_client(1, &vec![4_u8]).unwrap();
+
+        let mut stats = ClientStatsManager::default();
+        stats.set_start_time(current_time().saturating_sub(Duration::from_secs(42)));
+        stats.client_stats_insert(ClientId(1)).unwrap();
+        stats
+            .update_client_stats_for(ClientId(1), |client| {
+                client.update_executions(1337, current_time());
+                client.update_corpus_size(7);
+            })
+            .unwrap();
+        checkpoint.save_broker(stats.snapshot()).unwrap();
+
+        assert_eq!(
+            checkpoint.load_client::<Vec<u8>>(1).unwrap(),
+            Some(vec![4_u8])
+        );
+        assert!(checkpoint.load_client::<Vec<u8>>(2).unwrap().is_none());
+
+        let mut restored = ClientStatsManager::default();
+        restored.restore(checkpoint.load_broker().unwrap().unwrap().stats);
+        let global_stats = restored.global_stats();
+        assert_eq!(global_stats.total_execs, 1337);
+        assert_eq!(global_stats.corpus_size, 7);
+        assert!(global_stats.run_time >= Duration::from_secs(42));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,359 @@
+//! Campaign-wide control: pause, resume, stop or checkpoint all clients from outside of the fuzzer.
+//!
+//! The broker watches a [`ControlFile`]. Writing a [`ControlCommand`] (`pause`, `resume`, `stop` or `checkpoint`)
+//! to it broadcasts the matching [`Event`] to all clients.
+//! Clients carry out the command at the next stage boundary. For all commands but `resume`, they first save their state,
+//! so that the campaign can be killed and restarted from there. Then, they acknowledge the command.
+//! The broker appends every acknowledgement to the `.ack` file next to the control file, as `<command> <client id>`.
+//!
+//! For LLMP-based managers, such as the ones of the [`Launcher`](crate::events::launcher::Launcher)
+//! and the [`CentralizedLauncher`](crate::events::launcher::CentralizedLauncher), add an [`LlmpControlHook`]
+//! to the broker. For the TCP manager, see `TcpEventBroker::set_control_file`.
+
+use alloc::{collections::VecDeque, vec::Vec};
+use core::{marker::PhantomData, time::Duration};
+use std::{
+    fs::{self, OpenOptions},
+    io::{ErrorKind, Write},
+    path::{Path, PathBuf},
+    thread,
+};
+
+use libafl_bolts::{
+    ClientId, current_time,
+    llmp::{Flags, LLMP_FLAG_INITIALIZED, LlmpBrokerInner, LlmpHook, LlmpMsgHookResult, Tag},
+};
+#[cfg(feature = "llmp_compression")]
+use libafl_bolts::{compress::GzipCompressor, llmp::LLMP_FLAG_COMPRESSED};
+use serde::{Serialize, de::DeserializeOwned};
+
+#[cfg(feature = "llmp_compression")]
+use crate::events::llmp::COMPRESS_THRESHOLD;
+use crate::{
+    Error,
+    events::{ControlCommand, Event, EventWithStats, llmp::LLMP_TAG_EVENT_TO_BOTH},
+    state::Stoppable,
+};
+
+/// The broker looks for new commands at most this often
+pub(crate) const CONTROL_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How long a paused client sleeps, before it looks for new events again
+const PAUSED_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Paused clients send a heartbeat this often, so that the broker keeps looking for new commands
+const PAUSED_HEARTBEAT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A file, watched by the broker, to control all clients of a campaign.
+///
+/// Each line of the file is a [`ControlCommand`]. The broker removes the file once it read the commands.
+/// To not race the broker, write the commands to a temporary file first, and then rename it.
+#[derive(Debug, Clone)]
+pub struct ControlFile {
+    path: PathBuf,
+    ack_path: PathBuf,
+    last_poll: Duration,
+}
+
+impl ControlFile {
+    /// Watches the control file at the given path.
+    /// Acknowledgements are written to the same path, with `.ack` appended.
+    pub fn new<P>(path: P) -> Self
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref().to_path_buf();
+        let mut ack_path = path.clone().into_os_string();
+        ack_path.push(".ack");
+        Self {
+            path,
+            ack_path: ack_path.into(),
+            last_poll: Duration::ZERO,
+        }
+    }
+
+    /// The path of the control file
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The path of the file the acknowledgements are appended to
+    #[must_use]
+    pub fn ack_path(&self) -> &Path {
+        &self.ack_path
+    }
+
+    /// Takes the commands written to the control file.
+    /// Looks at the file at most every 100 milliseconds, and returns no commands in between.
+    pub fn poll(&mut self) -> Result<Vec<ControlCommand>, Error> {
+        let now = current_time();
+        if now.saturating_sub(self.last_poll) < CONTROL_POLL_INTERVAL {
+            return Ok(Vec::new());
+        }
+        self.last_poll = now;
+
+        let contents = match fs::read_to_string(&self.path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        fs::remove_file(&self.path)?;
+
+        let mut commands = Vec::new();
+        for line in contents.lines().filter(|line| !line.trim().is_empty()) {
+            match line.parse() {
+                Ok(command) => {
+                    log::info!("Control: broadcasting {command}");
+                    commands.push(command);
+                }
+                Err(e) => log::error!("Control: ignoring {line:?}: {e}"),
+            }
+        }
+        Ok(commands)
+    }
+
+    /// Records that the client carried out the command
+    pub fn acknowledge(
+        &mut self,
+        client_id: ClientId,
+        command: ControlCommand,
+    ) -> Result<(), Error> {
+        log::info!("Control: client {} acknowledged {command}", client_id.0);
+        let mut ack_file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.ack_path)?;
+        writeln!(ack_file, "{command} {}", client_id.0)?;
+        Ok(())
+    }
+}
+
+/// An LLMP broker hook, broadcasting the commands of a [`ControlFile`] to all clients,
+/// and recording their acknowledgements. Does nothing without a control file.
+///
+/// Acknowledgements are passed on to the following hooks, put it before the [`crate::events::StdLlmpEventHook`],
+/// which handles them and saves the broker checkpoint, see [`crate::events::StdLlmpEventHook::set_checkpoint`].
+/// The control file is polled whenever a message arrives at the broker.
+/// Running clients send a heartbeat at least every 15 seconds, paused clients every second.
+#[derive(Debug)]
+pub struct LlmpControlHook<I> {
+    control: Option<ControlFile>,
+    #[cfg(feature = "llmp_compression")]
+    compressor: GzipCompressor,
+    phantom: PhantomData<I>,
+}
+
+impl<I> LlmpControlHook<I> {
+    /// Creates a new [`LlmpControlHook`], watching the given control file
+    #[must_use]
+    pub fn new(control: Option<ControlFile>) -> Self {
+        Self {
+            control,
+            #[cfg(feature = "llmp_compression")]
+            compressor: GzipCompressor::with_threshold(COMPRESS_THRESHOLD),
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<I, SHM, SP> LlmpHook<SHM, SP> for LlmpControlHook<I>
+where
+    I: DeserializeOwned + Serialize,
+{
+    fn on_new_message(
+        &mut self,
+        _broker_inner: &mut LlmpBrokerInner<SHM, SP>,
+        client_id: ClientId,
+        msg_tag: &mut Tag,
+        #[cfg(feature = "llmp_compression")] msg_flags: &mut Flags,
+        #[cfg(not(feature = "llmp_compression"))] _msg_flags: &mut Flags,
+        msg: &mut [u8],
+        new_msgs: &mut Vec<(Tag, Flags, Vec<u8>)>,
+    ) -> Result<LlmpMsgHookResult, Error> {
+        let Some(control) = &mut self.control else {
+            return Ok(LlmpMsgHookResult::ForwardToClients);
+        };
+
+        for command in control.poll()? {
+            let event = EventWithStats::<I>::with_current_time(command.to_event(), 0);
+            new_msgs.push((
+                LLMP_TAG_EVENT_TO_BOTH,
+                LLMP_FLAG_INITIALIZED,
+                postcard::to_allocvec(&event)?,
+            ));
+        }
+
+        if *msg_tag != LLMP_TAG_EVENT_TO_BOTH {
+            return Ok(LlmpMsgHookResult::ForwardToClients);
+        }
+        #[cfg(not(feature = "llmp_compression"))]
+        let event_bytes = msg;
+        #[cfg(feature = "llmp_compression")]
+        let compressed;
+        #[cfg(feature = "llmp_compression")]
+        let event_bytes = if *msg_flags & LLMP_FLAG_COMPRESSED == LLMP_FLAG_COMPRESSED {
+            compressed = self.compressor.decompress(msg)?;
+            &compressed
+        } else {
+            &*msg
+        };
+        let event: EventWithStats<I> = postcard::from_bytes(event_bytes)?;
+        if let Event::ControlAck { command } = event.event() {
+            control.acknowledge(client_id, *command)?;
+            // The `StdLlmpEventHook` also handles acknowledgements, to save the broker checkpoint
+        }
+        Ok(LlmpMsgHookResult::ForwardToClients)
+    }
+}
+
+/// The client side of the campaign control, used by the event managers.
+///
+/// While paused, received testcases are held back, and handed to the fuzzer once it resumes.
+#[derive(Debug)]
+pub struct ClientControl<I> {
+    paused: bool,
+    deferred: VecDeque<(EventWithStats<I>, bool)>,
+    last_heartbeat: Duration,
+}
+
+impl<I> Default for ClientControl<I> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<I> ClientControl<I> {
+    /// Creates a new, running, [`ClientControl`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            paused: false,
+            deferred: VecDeque::new(),
+            last_heartbeat: Duration::ZERO,
+        }
+    }
+
+    /// If the client is paused
+    #[must_use]
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Carries out the command, apart from saving the state, which is up to the event manager.
+    pub fn apply<S>(&mut self, state: &mut S, command: ControlCommand)
+    where
+        S: Stoppable,
+    {
+        log::info!("Control: received {command}");
+        match command {
+            ControlCommand::Pause => self.paused = true,
+            ControlCommand::Resume => self.paused = false,
+            ControlCommand::Stop => {
+                self.paused = false;
+                state.request_stop();
+            }
+            ControlCommand::Checkpoint => {}
+        }
+    }
+
+    /// Passes a received event on to the fuzzer, or holds it back while paused
+    pub fn pass(
+        &mut self,
+        received: (EventWithStats<I>, bool),
+    ) -> Option<(EventWithStats<I>, bool)> {
+        if self.paused {
+            self.deferred.push_back(received);
+            None
+        } else {
+            Some(received)
+        }
+    }
+
+    /// The next event that was held back, once the client is no longer paused
+    pub fn next_deferred(&mut self) -> Option<(EventWithStats<I>, bool)> {
+        if self.paused {
+            None
+        } else {
+            self.deferred.pop_front()
+        }
+    }
+
+    /// Waits a little, while paused.
+    /// Returns `true` if the event manager should send a heartbeat.
+    pub fn wait_paused(&mut self) -> bool {
+        thread::sleep(PAUSED_POLL_INTERVAL);
+        let now = current_time();
+        if now.saturating_sub(self.last_heartbeat) >= PAUSED_HEARTBEAT_INTERVAL {
+            self.last_heartbeat = now;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, process};
+
+    use libafl_bolts::ClientId;
+
+    use super::{ClientControl, ControlFile};
+    use crate::{
+        events::{ControlCommand, Event, EventWithStats},
+        inputs::BytesInput,
+        state::{NopState, Stoppable},
+    };
+
+    #[test]
+    fn test_control_file() {
+        let path = env::temp_dir().join(format!("libafl_control_test_{}", process::id()));
+        let mut control = ControlFile::new(&path);
+        let _ = fs::remove_file(control.ack_path());
+        assert!(control.poll().unwrap().is_empty());
+
+        fs::write(&path, "pause\n\nbogus\nCheckpoint\n").unwrap();
+        // Polled too recently
+        assert!(control.poll().unwrap().is_empty());
+        std::thread::sleep(super::CONTROL_POLL_INTERVAL);
+        assert_eq!(
+            control.poll().unwrap(),
+            [ControlCommand::Pause, ControlCommand::Checkpoint]
+        );
+        assert!(!path.exists());
+
+        control
+            .acknowledge(ClientId(3), ControlCommand::Pause)
+            .unwrap();
+        assert_eq!(fs::read_to_string(control.ack_path()).unwrap(), "pause 3\n");
+        fs::remove_file(control.ack_path()).unwrap();
+    }
+
+    #[test]
+    fn test_client_control() {
+        let mut state = NopState::<BytesInput>::new();
+        let mut control = ClientControl::<BytesInput>::new();
+        let heartbeat = || {
+            (
+                EventWithStats::with_current_time(Event::Heartbeat, 0),
+                false,
+            )
+        };
+
+        control.apply(&mut state, ControlCommand::Pause);
+        assert!(control.is_paused());
+        assert!(control.pass(heartbeat()).is_none());
+        assert!(control.next_deferred().is_none());
+
+        control.apply(&mut state, ControlCommand::Resume);
+        assert!(control.next_deferred().is_some());
+        assert!(control.next_deferred().is_none());
+        assert!(control.pass(heartbeat()).is_some());
+
+        control.apply(&mut state, ControlCommand::Pause);
+        control.apply(&mut state, ControlCommand::Stop);
+        assert!(!control.is_paused());
+        assert!(state.stop_requested());
+    }
+}
@@ -1,11 +1,16 @@
-//! Hooks for event managers, especifically these are used to hook before `try_receive`.
+//! Hooks for event managers, especifically these are used to hook before `try_receive`, and on `fire`.
 //!
 //! This will allow user to define pre/post-processing code when the event manager receives any message from
-//! other clients
+//! other clients, or sends a message to them
 use libafl_bolts::ClientId;
 
 use crate::{Error, events::EventWithStats};
 
+#[cfg(feature = "otlp_monitor")]
+pub mod otlp;
+#[cfg(feature = "otlp_monitor")]
+pub use otlp::OtlpEventManagerHook;
+
 /// The `broker_hooks` that are run before and after the event manager calls `try_receive`
 pub trait EventManagerHook<I, S> {
     /// The hook that runs before `try_receive`
@@ -16,9 +21,19 @@ pub trait EventManagerHook<I, S> {
         client_id: ClientId,
         event: &EventWithStats<I>,
     ) -> Result<bool, Error>;
+
+    /// The hook that runs when the event manager fires an event, with the id of this client
+    fn on_fire(
+        &mut self,
+        _state: &mut S,
+        _client_id: ClientId,
+        _event: &EventWithStats<I>,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
 }
 
-/// The tuples contains `broker_hooks` to be executed for `try_receive`
+/// The tuples contains `broker_hooks` to be executed for `try_receive`, and on `fire`
 pub trait EventManagerHooksTuple<I, S> {
     /// The hook that runs before `try_receive`
     fn pre_receive_all(
@@ -27,6 +42,14 @@ pub trait EventManagerHooksTuple<I, S> {
         client_id: ClientId,
         event: &EventWithStats<I>,
     ) -> Result<bool, Error>;
+
+    /// The hook that runs when the event manager fires an event
+    fn on_fire_all(
+        &mut self,
+        state: &mut S,
+        client_id: ClientId,
+        event: &EventWithStats<I>,
+    ) -> Result<(), Error>;
 }
 
 impl<I, S> EventManagerHooksTuple<I, S> for () {
@@ -39,6 +62,16 @@ impl<I, S> EventManagerHooksTuple<I, S> for () {
     ) -> Result<bool, Error> {
         Ok(true)
     }
+
+    /// The hook that runs when the event manager fires an event
+    fn on_fire_all(
+        &mut self,
+        _state: &mut S,
+        _client_id: ClientId,
+        _event: &EventWithStats<I>,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
 }
 
 impl<Head, Tail, I, S> EventManagerHooksTuple<I, S> for (Head, Tail)
@@ -57,4 +90,15 @@ where
         let second = self.1.pre_receive_all(state, client_id, event)?;
         Ok(first & second)
     }
+
+    /// The hook that runs when the event manager fires an event
+    fn on_fire_all(
+        &mut self,
+        state: &mut S,
+        client_id: ClientId,
+        event: &EventWithStats<I>,
+    ) -> Result<(), Error> {
+        self.0.on_fire(state, client_id, event)?;
+        self.1.on_fire_all(state, client_id, event)
+    }
 }
@@ -0,0 +1,62 @@
+//! An event manager hook recording a span for each event, for OpenTelemetry.
+//! See [`crate::monitors::otlp`].
+
+use alloc::string::ToString;
+
+use libafl_bolts::{ClientId, current_time};
+
+use crate::{
+    Error,
+    events::{EventManagerHook, EventWithStats},
+    monitors::otlp::OtlpExporter,
+};
+
+/// Records the events of this client to the [`OtlpExporter::global`] exporter:
+/// a `receive_event` span for each incoming event, from the time the other client fired it, to its arrival,
+/// and a `fire_event` span, without duration, for each outgoing event.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct OtlpEventManagerHook;
+
+impl OtlpEventManagerHook {
+    /// Creates a new [`OtlpEventManagerHook`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl<I, S> EventManagerHook<I, S> for OtlpEventManagerHook {
+    fn pre_receive(
+        &mut self,
+        _state: &mut S,
+        client_id: ClientId,
+        event: &EventWithStats<I>,
+    ) -> Result<bool, Error> {
+        if let Some(exporter) = OtlpExporter::global() {
+            exporter.record(
+                exporter
+                    .span("receive_event", event.stats().time)
+                    .attribute("event.name", event.event().name().to_string())
+                    .attribute("event.client_id", u64::from(client_id.0)),
+            );
+        }
+        Ok(true)
+    }
+
+    fn on_fire(
+        &mut self,
+        _state: &mut S,
+        client_id: ClientId,
+        event: &EventWithStats<I>,
+    ) -> Result<(), Error> {
+        if let Some(exporter) = OtlpExporter::global() {
+            exporter.record(
+                exporter
+                    .span("fire_event", current_time())
+                    .attribute("event.name", event.event().name().to_string())
+                    .attribute("event.client_id", u64::from(client_id.0)),
+            );
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,312 @@
+//! A coverage map shared by all clients of a campaign, and the broker hook dropping testcases it already knows.
+//!
+//! The launcher creates the [`GlobalCoverageMap`] in shared memory, and passes it on to all clients.
+//! Each time a client adds a testcase to its corpus, the
+//! [`GlobalCoverageFeedback`](crate::feedbacks::global_coverage::GlobalCoverageFeedback) merges its coverage
+//! into the global map. If the testcase added nothing globally, the feedback marks it as redundant,
+//! and the [`GlobalCoverageLlmpHook`] of the broker drops it, instead of forwarding it to all other clients.
+
+use alloc::vec::Vec;
+use core::{
+    marker::PhantomData,
+    mem::size_of,
+    slice,
+    sync::atomic::{AtomicU8, AtomicU64, Ordering},
+};
+
+use libafl_bolts::{
+    ClientId, hash_std,
+    llmp::{Flags, LlmpBrokerInner, LlmpHook, LlmpMsgHookResult, Tag},
+    shmem::{ShMem, ShMemProvider},
+};
+#[cfg(feature = "llmp_compression")]
+use libafl_bolts::{compress::GzipCompressor, llmp::LLMP_FLAG_COMPRESSED};
+use serde::{Serialize, de::DeserializeOwned};
+
+#[cfg(unix)]
+use crate::events::centralized::_LLMP_TAG_TO_MAIN;
+#[cfg(feature = "llmp_compression")]
+use crate::events::llmp::COMPRESS_THRESHOLD;
+use crate::{
+    Error,
+    events::{Event, EventWithStats, llmp::LLMP_TAG_EVENT_TO_BOTH},
+};
+
+/// The env variable the launcher passes the [`GlobalCoverageMap`] to its clients with
+pub const GLOBAL_COVERAGE_ENV: &str = "_LIBAFL_GLOBAL_COVERAGE";
+
+/// The number of redundant testcases the map remembers, until the broker saw them
+const REDUNDANT_SLOTS: usize = 1 << 14;
+
+/// The header: map length, number of redundant slots, and number of covered entries
+const HEADER_WORDS: usize = 3;
+
+/// The hash [`GlobalCoverageMap`] remembers redundant testcases by
+pub fn input_hash<I>(input: &I) -> Result<u64, Error>
+where
+    I: Serialize,
+{
+    // An empty slot is 0
+    Ok(hash_std(&postcard::to_allocvec(input)?) | 1)
+}
+
+/// A coverage map in shared memory, that all clients update atomically.
+///
+/// Keeps the maximum of every entry, like the history map of a `MaxMapFeedback`,
+/// and the hashes of recent testcases that added nothing to it.
+#[derive(Debug, Clone)]
+pub struct GlobalCoverageMap<SHM> {
+    shmem: SHM,
+    map_len: usize,
+}
+
+impl<SHM> GlobalCoverageMap<SHM>
+where
+    SHM: ShMem,
+{
+    /// Creates a new, empty, global map, for coverage maps of `map_len` entries
+    pub fn new<SP>(shmem_provider: &mut SP, map_len: usize) -> Result<Self, Error>
+    where
+        SP: ShMemProvider<ShMem = SHM>,
+    {
+        let size = (HEADER_WORDS + REDUNDANT_SLOTS) * size_of::<u64>() + map_len;
+        let mut shmem = shmem_provider.new_shmem(size)?;
+        shmem.fill(0);
+        let ret = Self { shmem, map_len };
+        ret.header()[0].store(map_len as u64, Ordering::Relaxed);
+        ret.header()[1].store(REDUNDANT_SLOTS as u64, Ordering::Relaxed);
+        Ok(ret)
+    }
+
+    /// Attaches to the global map the launcher created, if it created one
+    #[expect(clippy::cast_ptr_alignment)]
+    pub fn from_env<SP>(shmem_provider: &mut SP) -> Result<Option<Self>, Error>
+    where
+        SP: ShMemProvider<ShMem = SHM>,
+    {
+        if std::env::var(GLOBAL_COVERAGE_ENV).is_err() {
+            return Ok(None);
+        }
+        let shmem = shmem_provider.existing_from_env(GLOBAL_COVERAGE_ENV)?;
+        if shmem.len() < (HEADER_WORDS + REDUNDANT_SLOTS) * size_of::<u64>() {
+            return Err(Error::illegal_state("The global coverage map is too small"));
+        }
+        // # Safety
+        // The header is part of the map, and 8-byte aligned
+        let map_len = unsafe { &*shmem.as_ptr().cast::<AtomicU64>() }.load(Ordering::Relaxed);
+        Ok(Some(Self {
+            shmem,
+            map_len: map_len as usize,
+        }))
+    }
+
+    /// Passes this map on to the clients spawned from now on.
+    ///
+    /// # Safety
+    /// Writes to the process env. Should only be called from a single thread at a time.
+    pub unsafe fn write_to_env(&self) -> Result<(), Error> {
+        unsafe { self.shmem.write_to_env(GLOBAL_COVERAGE_ENV) }
+    }
+
+    fn header(&self) -> &[AtomicU64] {
+        // # Safety
+        // Shared memory is page-aligned, and large enough. Atomics have the same layout as their values.
+        unsafe { slice::from_raw_parts(self.shmem.as_ptr().cast(), HEADER_WORDS) }
+    }
+
+    #[expect(clippy::cast_ptr_alignment)]
+    fn redundant(&self) -> &[AtomicU64] {
+        // # Safety
+        // The slots follow the header, so they are 8-byte aligned
+        unsafe {
+            slice::from_raw_parts(
+                self.shmem.as_ptr().cast::<AtomicU64>().add(HEADER_WORDS),
+                REDUNDANT_SLOTS,
+            )
+        }
+    }
+
+    fn entries(&self) -> &[AtomicU8] {
+        // # Safety
+        // The entries follow the slots, and the map was allocated large enough
+        unsafe {
+            slice::from_raw_parts(
+                self.shmem
+                    .as_ptr()
+                    .add((HEADER_WORDS + REDUNDANT_SLOTS) * size_of::<u64>())
+                    .cast(),
+                self.map_len,
+            )
+        }
+    }
+
+    /// The number of entries of the map
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.map_len
+    }
+
+    /// If the map has no entries
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.map_len == 0
+    }
+
+    /// The number of entries any client covered
+    #[must_use]
+    pub fn covered(&self) -> usize {
+        self.header()[2].load(Ordering::Relaxed) as usize
+    }
+
+    /// Merges the `(index, value)` entries of a coverage map into the global map.
+    /// Returns `true` if any of them was larger than in the global map, i.e., if they added something globally.
+    /// Indexes beyond the end of the global map are ignored.
+    pub fn merge<It>(&self, entries: It) -> bool
+    where
+        It: IntoIterator<Item = (usize, u8)>,
+    {
+        let global = self.entries();
+        let mut newly_covered = 0;
+        let mut interesting = false;
+        for (index, value) in entries {
+            let Some(entry) = global.get(index) else {
+                continue;
+            };
+            let old = entry.fetch_max(value, Ordering::Relaxed);
+            if value > old {
+                interesting = true;
+                if old == 0 {
+                    newly_covered += 1;
+                }
+            }
+        }
+        if newly_covered > 0 {
+            self.header()[2].fetch_add(newly_covered, Ordering::Relaxed);
+        }
+        interesting
+    }
+
+    /// Remembers that the testcase with this hash added nothing globally.
+    /// If the broker does not see it soon, a later one may take its place.
+    pub fn mark_redundant(&self, input_hash: u64) {
+        let slot = input_hash as usize % REDUNDANT_SLOTS;
+        self.redundant()[slot].store(input_hash, Ordering::Relaxed);
+    }
+
+    /// Returns `true`, and forgets it, if the testcase with this hash was marked as redundant
+    pub fn take_redundant(&self, input_hash: u64) -> bool {
+        let slot = input_hash as usize % REDUNDANT_SLOTS;
+        self.redundant()[slot]
+            .compare_exchange(input_hash, 0, Ordering::Relaxed, Ordering::Relaxed)
+            .is_ok()
+    }
+}
+
+/// An LLMP broker hook, dropping the testcases that added nothing to the [`GlobalCoverageMap`].
+/// Put it after the other hooks, so that the monitor still sees the corpus sizes.
+#[derive(Debug)]
+pub struct GlobalCoverageLlmpHook<I, SHM> {
+    global_map: Option<GlobalCoverageMap<SHM>>,
+    dropped: u64,
+    #[cfg(feature = "llmp_compression")]
+    compressor: GzipCompressor,
+    phantom: PhantomData<I>,
+}
+
+impl<I, SHM> GlobalCoverageLlmpHook<I, SHM> {
+    /// Creates a new [`GlobalCoverageLlmpHook`]. Forwards all testcases without a global map.
+    #[must_use]
+    pub fn new(global_map: Option<GlobalCoverageMap<SHM>>) -> Self {
+        Self {
+            global_map,
+            dropped: 0,
+            #[cfg(feature = "llmp_compression")]
+            compressor: GzipCompressor::with_threshold(COMPRESS_THRESHOLD),
+            phantom: PhantomData,
+        }
+    }
+
+    /// The number of testcases this hook dropped
+    #[must_use]
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+}
+
+impl<I, SHM, SHM2, SP> LlmpHook<SHM2, SP> for GlobalCoverageLlmpHook<I, SHM>
+where
+    I: DeserializeOwned + Serialize,
+    SHM: ShMem,
+{
+    fn on_new_message(
+        &mut self,
+        _broker_inner: &mut LlmpBrokerInner<SHM2, SP>,
+        client_id: ClientId,
+        msg_tag: &mut Tag,
+        #[cfg(feature = "llmp_compression")] msg_flags: &mut Flags,
+        #[cfg(not(feature = "llmp_compression"))] _msg_flags: &mut Flags,
+        msg: &mut [u8],
+        _new_msgs: &mut Vec<(Tag, Flags, Vec<u8>)>,
+    ) -> Result<LlmpMsgHookResult, Error> {
+        let Some(global_map) = &self.global_map else {
+            return Ok(LlmpMsgHookResult::ForwardToClients);
+        };
+        #[cfg(unix)]
+        let is_event = *msg_tag == LLMP_TAG_EVENT_TO_BOTH || *msg_tag == _LLMP_TAG_TO_MAIN;
+        #[cfg(not(unix))]
+        let is_event = *msg_tag == LLMP_TAG_EVENT_TO_BOTH;
+        if !is_event {
+            return Ok(LlmpMsgHookResult::ForwardToClients);
+        }
+
+        #[cfg(not(feature = "llmp_compression"))]
+        let event_bytes = msg;
+        #[cfg(feature = "llmp_compression")]
+        let compressed;
+        #[cfg(feature = "llmp_compression")]
+        let event_bytes = if *msg_flags & LLMP_FLAG_COMPRESSED == LLMP_FLAG_COMPRESSED {
+            compressed = self.compressor.decompress(msg)?;
+            &compressed
+        } else {
+            &*msg
+        };
+        let event: EventWithStats<I> = postcard::from_bytes(event_bytes)?;
+        if let Event::NewTestcase { input, .. } = event.event() {
+            if global_map.take_redundant(input_hash(input)?) {
+                self.dropped += 1;
+                log::debug!(
+                    "Dropping a testcase of client {client_id:?} that adds no global coverage ({} so far)",
+                    self.dropped
+                );
+                return Ok(LlmpMsgHookResult::Handled);
+            }
+        }
+        Ok(LlmpMsgHookResult::ForwardToClients)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use libafl_bolts::shmem::{ShMemProvider, StdShMemProvider};
+
+    use super::{GlobalCoverageMap, input_hash};
+    use crate::inputs::BytesInput;
+
+    #[test]
+    fn test_global_coverage_map() {
+        let mut shmem_provider = StdShMemProvider::new().unwrap();
+        let global_map = GlobalCoverageMap::new(&mut shmem_provider, 16).unwrap();
+        assert_eq!(global_map.len(), 16);
+
+        assert!(global_map.merge([(1, 1), (3, 2)]));
+        assert!(!global_map.merge([(1, 1), (3, 1), (100, 5)]));
+        assert!(global_map.merge([(3, 4)]));
+        assert_eq!(global_map.covered(), 2);
+
+        let hash = input_hash(&BytesInput::new(vec![1, 2, 3])).unwrap();
+        assert!(!global_map.take_redundant(hash));
+        global_map.mark_redundant(hash);
+        assert!(global_map.take_redundant(hash));
+        assert!(!global_map.take_redundant(hash));
+    }
+}
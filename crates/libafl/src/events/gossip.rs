@@ -0,0 +1,795 @@
+//! A gossip-based event manager, sharing events between clients without a central broker.
+//!
+//! Each [`GossipEventManager`] listens for peers, and connects to a list of seed peers.
+//! Events are sent to a random subset of the peers (the fanout), which relay them further until their
+//! time-to-live runs out. Every node remembers the hashes of the events it has seen, so that events are
+//! only handled once, no matter how many peers relayed them.
+//!
+//! There is no single point of failure: peers may leave at any time, the remaining nodes keep fuzzing,
+//! and try to reconnect to lost seed peers in the background.
+
+use alloc::{collections::VecDeque, vec::Vec};
+use core::{marker::PhantomData, net::SocketAddr, num::NonZeroUsize, time::Duration};
+use std::{
+    collections::HashSet,
+    io::{ErrorKind, Read, Write},
+    net::{TcpListener, TcpStream, ToSocketAddrs},
+};
+
+use libafl_bolts::{
+    ClientId, current_time, hash_std,
+    rands::{Rand, StdRand},
+};
+use serde::{Serialize, de::DeserializeOwned};
+
+use super::{AwaitRestartSafe, SendExiting, std_maybe_report_progress, std_report_progress};
+use crate::{
+    Error, HasMetadata,
+    events::{
+        Event, EventConfig, EventFirer, EventManagerHooksTuple, EventManagerId, EventReceiver,
+        EventRestarter, EventWithStats, HasEventManagerId, ProgressReporter, StdLlmpEventHook,
+        std_on_restart,
+    },
+    monitors::{Monitor, NopMonitor, stats::ClientStatsManager},
+    state::{
+        HasCurrentStageId, HasExecutions, HasLastReportTime, MaybeHasClientPerfMonitor, Stoppable,
+    },
+};
+
+/// The number of event hashes a node remembers, to drop events it has already seen
+const SEEN_CAPACITY: usize = 1 << 16;
+
+/// `len: u32 | id: u64 | origin: u32 | ttl: u8`, followed by the serialized event.
+/// `len` counts everything after itself.
+const FRAME_HEADER_LEN: usize = 4 + 8 + 4 + 1;
+
+/// The largest `len` of a frame, as large as the initial shared map of an LLMP client.
+/// A peer announcing a longer frame is dropped, instead of buffering whatever it sends.
+const MAX_FRAME_LEN: usize = 1 << 28;
+
+/// A writer stuck for this long is considered gone
+const WRITE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// A connection to another node of the mesh
+#[derive(Debug)]
+struct GossipPeer {
+    stream: TcpStream,
+    /// The seed address, if we connected to this peer ourselves
+    seed: Option<SocketAddr>,
+    /// Bytes received, but not yet parsed into frames
+    buf: Vec<u8>,
+    /// Set once reading from, or writing to, the peer failed
+    dead: bool,
+}
+
+impl GossipPeer {
+    fn new(stream: TcpStream, seed: Option<SocketAddr>) -> Result<Self, Error> {
+        stream.set_nodelay(true)?;
+        stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+        Ok(Self {
+            stream,
+            seed,
+            buf: Vec::new(),
+            dead: false,
+        })
+    }
+
+    /// Reads everything available, and returns the complete frames
+    fn read_frames(&mut self) -> Result<Vec<Vec<u8>>, Error> {
+        self.stream.set_nonblocking(true)?;
+        let mut chunk = [0_u8; 4096];
+        let res = loop {
+            match self.stream.read(&mut chunk) {
+                Ok(0) => {
+                    break Err(Error::os_error(
+                        ErrorKind::UnexpectedEof.into(),
+                        "Peer left",
+                    ));
+                }
+                Ok(n_read) => self.buf.extend_from_slice(&chunk[..n_read]),
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break Ok(()),
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => break Err(Error::os_error(e, "Could not read from peer")),
+            }
+        };
+        self.stream.set_nonblocking(false)?;
+
+        let mut frames = Vec::new();
+        let mut pos = 0;
+        while self.buf.len() - pos >= 4 {
+            let len = u32::from_le_bytes(self.buf[pos..pos + 4].try_into().unwrap()) as usize;
+            if len > MAX_FRAME_LEN {
+                log::warn!(
+                    "Dropping peer {:?}: announced a gossip frame of {len} bytes",
+                    self.stream.peer_addr()
+                );
+                self.dead = true;
+                self.buf.clear();
+                return Ok(frames);
+            }
+            if self.buf.len() - pos - 4 < len {
+                break;
+            }
+            frames.push(self.buf[pos..pos + 4 + len].to_vec());
+            pos += 4 + len;
+        }
+        self.buf.drain(..pos);
+
+        // Even if the peer left, hand out what it sent before
+        res.map(|()| frames)
+    }
+
+    fn write_frame(&mut self, frame: &[u8]) {
+        if self.dead {
+            return;
+        }
+        if let Err(e) = self.stream.write_all(frame) {
+            log::info!("Lost peer {:?}: {e}", self.stream.peer_addr());
+            self.dead = true;
+        }
+    }
+}
+
+/// An event manager sharing events with its peers in a gossip mesh, see the [module docs](self).
+///
+/// [`Event::NewTestcase`], [`Event::Objective`], [`Event::UpdateUserStats`], [`Event::Heartbeat`]
+/// and [`Event::Stop`] are gossiped. If the node has a monitor, it displays the stats of the whole mesh.
+pub struct GossipEventManager<EMH, I, MT, S> {
+    hooks: EMH,
+    /// Our id, unique in the mesh
+    node_id: ClientId,
+    listener: TcpListener,
+    peers: Vec<GossipPeer>,
+    /// The peers we connect to, and reconnect to when they are lost
+    seeds: Vec<SocketAddr>,
+    reconnect_interval: Duration,
+    last_reconnect: Duration,
+    fanout: usize,
+    ttl: u8,
+    seen: HashSet<u64>,
+    seen_order: VecDeque<u64>,
+    /// Received events, waiting to be handed to the fuzzer
+    received: VecDeque<(EventWithStats<I>, bool)>,
+    rand: StdRand,
+    monitor: Option<MT>,
+    client_stats_manager: ClientStatsManager,
+    /// The configuration defines this specific fuzzer.
+    /// A node will not re-use the observer values sent over the mesh
+    /// from nodes with other configurations.
+    configuration: EventConfig,
+    phantom: PhantomData<S>,
+}
+
+impl<EMH, I, MT, S> core::fmt::Debug for GossipEventManager<EMH, I, MT, S> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("GossipEventManager")
+            .field("node_id", &self.node_id)
+            .field("listener", &self.listener)
+            .field("peers", &self.peers)
+            .field("seeds", &self.seeds)
+            .field("fanout", &self.fanout)
+            .field("ttl", &self.ttl)
+            .field("configuration", &self.configuration)
+            .finish_non_exhaustive()
+    }
+}
+
+impl GossipEventManager<(), (), NopMonitor, ()> {
+    /// Create a builder for [`GossipEventManager`]
+    #[must_use]
+    pub fn builder() -> GossipEventManagerBuilder<(), NopMonitor> {
+        GossipEventManagerBuilder::new()
+    }
+}
+
+/// Builder for [`GossipEventManager`]
+#[derive(Debug, Clone)]
+pub struct GossipEventManagerBuilder<EMH, MT> {
+    hooks: EMH,
+    monitor: Option<MT>,
+    node_id: Option<ClientId>,
+    seeds: Vec<SocketAddr>,
+    fanout: usize,
+    ttl: u8,
+    reconnect_interval: Duration,
+}
+
+impl Default for GossipEventManagerBuilder<(), NopMonitor> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GossipEventManagerBuilder<(), NopMonitor> {
+    /// The constructor
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            hooks: (),
+            monitor: None,
+            node_id: None,
+            seeds: Vec::new(),
+            fanout: 3,
+            ttl: 4,
+            reconnect_interval: Duration::from_secs(5),
+        }
+    }
+}
+
+impl<EMH, MT> GossipEventManagerBuilder<EMH, MT> {
+    /// Set the hooks
+    #[must_use]
+    pub fn hooks<EMH2>(self, hooks: EMH2) -> GossipEventManagerBuilder<EMH2, MT> {
+        GossipEventManagerBuilder {
+            hooks,
+            monitor: self.monitor,
+            node_id: self.node_id,
+            seeds: self.seeds,
+            fanout: self.fanout,
+            ttl: self.ttl,
+            reconnect_interval: self.reconnect_interval,
+        }
+    }
+
+    /// Display the stats of the whole mesh with this monitor, if any.
+    /// Usually, only one node of the mesh should have a monitor.
+    #[must_use]
+    pub fn monitor<MT2>(self, monitor: Option<MT2>) -> GossipEventManagerBuilder<EMH, MT2> {
+        GossipEventManagerBuilder {
+            hooks: self.hooks,
+            monitor,
+            node_id: self.node_id,
+            seeds: self.seeds,
+            fanout: self.fanout,
+            ttl: self.ttl,
+            reconnect_interval: self.reconnect_interval,
+        }
+    }
+
+    /// Set the id of this node, which has to be unique in the mesh. Random by default.
+    #[must_use]
+    pub fn node_id(mut self, node_id: ClientId) -> Self {
+        self.node_id = Some(node_id);
+        self
+    }
+
+    /// Set the peers to connect to
+    #[must_use]
+    pub fn seeds(mut self, seeds: Vec<SocketAddr>) -> Self {
+        self.seeds = seeds;
+        self
+    }
+
+    /// Set the number of peers each event is sent to. Defaults to 3.
+    #[must_use]
+    pub fn fanout(mut self, fanout: usize) -> Self {
+        self.fanout = fanout;
+        self
+    }
+
+    /// Set how many times an event gets relayed, at most. Defaults to 4.
+    #[must_use]
+    pub fn ttl(mut self, ttl: u8) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Set how often lost seed peers are reconnected to. Defaults to 5 seconds.
+    #[must_use]
+    pub fn reconnect_interval(mut self, reconnect_interval: Duration) -> Self {
+        self.reconnect_interval = reconnect_interval;
+        self
+    }
+
+    /// Create a [`GossipEventManager`] listening for peers on `listen_addr`,
+    /// and connect to the seed peers that are reachable.
+    pub fn build<A, I, S>(
+        self,
+        listen_addr: A,
+        configuration: EventConfig,
+    ) -> Result<GossipEventManager<EMH, I, MT, S>, Error>
+    where
+        A: ToSocketAddrs,
+    {
+        let listener = TcpListener::bind(listen_addr)?;
+        listener.set_nonblocking(true)?;
+
+        let mut rand = StdRand::new();
+        let node_id = self.node_id.unwrap_or_else(|| ClientId(rand.next() as u32));
+
+        let mut mgr = GossipEventManager {
+            hooks: self.hooks,
+            node_id,
+            listener,
+            peers: Vec::new(),
+            seeds: self.seeds,
+            reconnect_interval: self.reconnect_interval,
+            last_reconnect: current_time(),
+            fanout: self.fanout,
+            ttl: self.ttl,
+            seen: HashSet::new(),
+            seen_order: VecDeque::new(),
+            received: VecDeque::new(),
+            rand,
+            monitor: self.monitor,
+            client_stats_manager: ClientStatsManager::default(),
+            configuration,
+            phantom: PhantomData,
+        };
+        mgr.connect_to_seeds();
+        log::info!(
+            "Gossip node {node_id:?} on {:?}, connected to {} peer(s)",
+            mgr.listener.local_addr(),
+            mgr.peers.len()
+        );
+        Ok(mgr)
+    }
+}
+
+impl<EMH, I, MT, S> GossipEventManager<EMH, I, MT, S> {
+    /// The address this node listens on for peers
+    pub fn local_addr(&self) -> Result<SocketAddr, Error> {
+        Ok(self.listener.local_addr()?)
+    }
+
+    /// The number of connected peers
+    #[must_use]
+    pub fn nb_peers(&self) -> usize {
+        self.peers.len()
+    }
+
+    /// Connects to all seeds we are not connected to
+    fn connect_to_seeds(&mut self) {
+        for seed in &self.seeds {
+            if self.peers.iter().any(|peer| peer.seed == Some(*seed)) {
+                continue;
+            }
+            match TcpStream::connect_timeout(seed, WRITE_TIMEOUT)
+                .map_err(Error::from)
+                .and_then(|stream| GossipPeer::new(stream, Some(*seed)))
+            {
+                Ok(peer) => {
+                    log::info!("Connected to peer {seed}");
+                    self.peers.push(peer);
+                }
+                Err(e) => log::debug!("Could not connect to peer {seed}: {e}"),
+            }
+        }
+    }
+
+    /// Accepts new peers, and reconnects to lost seeds from time to time
+    fn maintain_peers(&mut self) -> Result<(), Error> {
+        loop {
+            match self.listener.accept() {
+                Ok((stream, addr)) => {
+                    stream.set_nonblocking(false)?;
+                    log::info!("Peer {addr} joined");
+                    self.peers.push(GossipPeer::new(stream, None)?);
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) => return Err(Error::os_error(e, "Could not accept peer")),
+            }
+        }
+
+        let now = current_time();
+        if now >= self.last_reconnect + self.reconnect_interval {
+            self.last_reconnect = now;
+            self.connect_to_seeds();
+        }
+        Ok(())
+    }
+
+    /// Remembers the id, returns `false` if it was already seen
+    fn mark_seen(&mut self, id: u64) -> bool {
+        if !self.seen.insert(id) {
+            return false;
+        }
+        self.seen_order.push_back(id);
+        if self.seen_order.len() > SEEN_CAPACITY {
+            let oldest = self.seen_order.pop_front().unwrap();
+            self.seen.remove(&oldest);
+        }
+        true
+    }
+
+    /// Sends the frame to `fanout` random live peers, except `except`
+    fn gossip(&mut self, frame: &[u8], except: Option<usize>) {
+        let mut candidates: Vec<usize> = (0..self.peers.len())
+            .filter(|idx| Some(*idx) != except && !self.peers[*idx].dead)
+            .collect();
+        // Partial Fisher-Yates shuffle, to pick `fanout` peers
+        let nb_targets = candidates.len().min(self.fanout);
+        for i in 0..nb_targets {
+            let remaining = NonZeroUsize::new(candidates.len() - i).unwrap();
+            let j = i + self.rand.below(remaining);
+            candidates.swap(i, j);
+        }
+        for idx in &candidates[..nb_targets] {
+            self.peers[*idx].write_frame(frame);
+        }
+    }
+
+    /// Creates the frame for a new event
+    fn new_frame(&self, payload: &[u8]) -> Result<Vec<u8>, Error> {
+        let len = FRAME_HEADER_LEN - 4 + payload.len();
+        if len > MAX_FRAME_LEN {
+            return Err(Error::illegal_argument(format!(
+                "The event is too large to gossip: {len} bytes, at most {MAX_FRAME_LEN}"
+            )));
+        }
+        let len = u32::try_from(len)?;
+        let origin = self.node_id.0.to_le_bytes();
+        let id = hash_std(&[&origin[..], payload].concat());
+
+        let mut frame = Vec::with_capacity(FRAME_HEADER_LEN + payload.len());
+        frame.extend_from_slice(&len.to_le_bytes());
+        frame.extend_from_slice(&id.to_le_bytes());
+        frame.extend_from_slice(&origin);
+        frame.push(self.ttl);
+        frame.extend_from_slice(payload);
+        Ok(frame)
+    }
+}
+
+impl<EMH, I, MT, S> GossipEventManager<EMH, I, MT, S>
+where
+    MT: Monitor,
+{
+    /// Updates the stats of the mesh, and displays them, if we have a monitor
+    fn handle_in_monitor(
+        &mut self,
+        origin: ClientId,
+        event: &EventWithStats<I>,
+    ) -> Result<(), Error> {
+        match &mut self.monitor {
+            Some(monitor) => {
+                StdLlmpEventHook::<I, MT>::handle_in_broker(
+                    monitor,
+                    &mut self.client_stats_manager,
+                    origin,
+                    event,
+                )?;
+            }
+            None => {
+                if let Event::Log {
+                    severity_level,
+                    message,
+                    ..
+                } = event.event()
+                {
+                    log::log!((*severity_level).into(), "{message}");
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Whether the event is shared with the other nodes
+fn is_gossiped<I>(event: &Event<I>) -> bool {
+    matches!(
+        event,
+        Event::NewTestcase { .. }
+            | Event::Objective { .. }
+            | Event::UpdateUserStats { .. }
+            | Event::Heartbeat
+            | Event::Stop
+    )
+}
+
+impl<EMH, I, MT, S> EventFirer<I, S> for GossipEventManager<EMH, I, MT, S>
+where
+    EMH: EventManagerHooksTuple<I, S>,
+    I: Serialize,
+    MT: Monitor,
+{
+    fn should_send(&self) -> bool {
+        true
+    }
+
+    fn fire(&mut self, state: &mut S, event: EventWithStats<I>) -> Result<(), Error> {
+        self.hooks.on_fire_all(state, self.node_id, &event)?;
+        self.handle_in_monitor(self.node_id, &event)?;
+        if !is_gossiped(event.event()) {
+            return Ok(());
+        }
+
+        let payload = postcard::to_allocvec(&event)?;
+        let frame = self.new_frame(&payload)?;
+        let id = u64::from_le_bytes(frame[4..12].try_into().unwrap());
+        self.mark_seen(id);
+        self.gossip(&frame, None);
+        self.peers.retain(|peer| !peer.dead);
+        Ok(())
+    }
+
+    fn configuration(&self) -> EventConfig {
+        self.configuration
+    }
+}
+
+impl<EMH, I, MT, S> EventRestarter<S> for GossipEventManager<EMH, I, MT, S>
+where
+    S: HasCurrentStageId,
+{
+    fn on_restart(&mut self, state: &mut S) -> Result<(), Error> {
+        std_on_restart(self, state)
+    }
+}
+
+impl<EMH, I, MT, S> EventReceiver<I, S> for GossipEventManager<EMH, I, MT, S>
+where
+    EMH: EventManagerHooksTuple<I, S>,
+    I: DeserializeOwned,
+    MT: Monitor,
+    S: Stoppable,
+{
+    fn try_receive(&mut self, state: &mut S) -> Result<Option<(EventWithStats<I>, bool)>, Error> {
+        if let Some(received) = self.received.pop_front() {
+            return Ok(Some(received));
+        }
+
+        self.maintain_peers()?;
+
+        let mut frames = Vec::new();
+        for (idx, peer) in self.peers.iter_mut().enumerate() {
+            match peer.read_frames() {
+                Ok(new_frames) => frames.extend(new_frames.into_iter().map(|frame| (idx, frame))),
+                Err(e) => {
+                    log::info!("Lost peer {:?}: {e}", peer.stream.peer_addr());
+                    peer.dead = true;
+                }
+            }
+        }
+
+        for (from, mut frame) in frames {
+            // A peer sending garbage is dropped, the frames of the others are still handled
+            if frame.len() < FRAME_HEADER_LEN {
+                let peer = &mut self.peers[from];
+                log::warn!(
+                    "Dropping peer {:?}: sent a truncated gossip frame",
+                    peer.stream.peer_addr()
+                );
+                peer.dead = true;
+                continue;
+            }
+            let id = u64::from_le_bytes(frame[4..12].try_into().unwrap());
+            if !self.mark_seen(id) {
+                continue;
+            }
+            let origin = ClientId(u32::from_le_bytes(frame[12..16].try_into().unwrap()));
+
+            // Only relay events we could decode, so that garbage does not spread through the mesh
+            let event: EventWithStats<I> = match postcard::from_bytes(&frame[FRAME_HEADER_LEN..]) {
+                Ok(event) => event,
+                Err(e) => {
+                    let peer = &mut self.peers[from];
+                    log::warn!(
+                        "Dropping peer {:?}: sent an undecodable gossip frame: {e}",
+                        peer.stream.peer_addr()
+                    );
+                    peer.dead = true;
+                    continue;
+                }
+            };
+            let ttl = frame[16];
+            if ttl > 1 {
+                frame[16] = ttl - 1;
+                self.gossip(&frame, Some(from));
+            }
+
+            self.handle_in_monitor(origin, &event)?;
+            if !self.hooks.pre_receive_all(state, origin, &event)? {
+                continue;
+            }
+            match event.event() {
+                Event::NewTestcase {
+                    client_config,
+                    observers_buf,
+                    ..
+                } => {
+                    log::debug!("Received new Testcase from {origin:?} ({client_config:?})");
+                    let reuse_observers =
+                        client_config.match_with(&self.configuration) && observers_buf.is_some();
+                    self.received.push_back((event, reuse_observers));
+                }
+                Event::Objective { .. } => {
+                    log::debug!("Received new Objective from {origin:?}");
+                    self.received.push_back((event, false));
+                }
+                Event::Stop => {
+                    state.request_stop();
+                }
+                _ => {}
+            }
+        }
+
+        self.peers.retain(|peer| !peer.dead);
+        Ok(self.received.pop_front())
+    }
+
+    fn on_interesting(&mut self, _state: &mut S, _event: EventWithStats<I>) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl<EMH, I, MT, S> AwaitRestartSafe for GossipEventManager<EMH, I, MT, S> {
+    /// Nothing is shared with other processes, we can always restart
+    fn await_restart_safe(&mut self) {}
+}
+
+impl<EMH, I, MT, S> SendExiting for GossipEventManager<EMH, I, MT, S> {
+    /// The peers notice on their own when we leave
+    fn send_exiting(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn on_shutdown(&mut self) -> Result<(), Error> {
+        self.send_exiting()
+    }
+}
+
+impl<EMH, I, MT, S> ProgressReporter<S> for GossipEventManager<EMH, I, MT, S>
+where
+    EMH: EventManagerHooksTuple<I, S>,
+    I: Serialize,
+    MT: Monitor,
+    S: HasExecutions + HasMetadata + HasLastReportTime + MaybeHasClientPerfMonitor,
+{
+    fn maybe_report_progress(
+        &mut self,
+        state: &mut S,
+        monitor_timeout: Duration,
+    ) -> Result<(), Error> {
+        std_maybe_report_progress(self, state, monitor_timeout)
+    }
+
+    fn report_progress(&mut self, state: &mut S) -> Result<(), Error> {
+        std_report_progress(self, state)
+    }
+}
+
+impl<EMH, I, MT, S> HasEventManagerId for GossipEventManager<EMH, I, MT, S> {
+    fn mgr_id(&self) -> EventManagerId {
+        EventManagerId(self.node_id.0 as usize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+    use core::time::Duration;
+    use std::{io::Write, net::TcpStream, thread};
+
+    use libafl_bolts::ClientId;
+
+    use super::GossipEventManager;
+    use crate::{
+        events::{Event, EventConfig, EventFirer, EventReceiver, EventWithStats},
+        executors::ExitKind,
+        inputs::BytesInput,
+        monitors::NopMonitor,
+        state::{NopState, Stoppable},
+    };
+
+    type Mgr = GossipEventManager<(), BytesInput, NopMonitor, NopState<BytesInput>>;
+
+    fn node(id: u32, seeds: &[&Mgr]) -> Mgr {
+        GossipEventManager::builder()
+            .node_id(ClientId(id))
+            .seeds(seeds.iter().map(|mgr| mgr.local_addr().unwrap()).collect())
+            .build("127.0.0.1:0", EventConfig::AlwaysUnique)
+            .unwrap()
+    }
+
+    fn testcase(input: &[u8]) -> EventWithStats<BytesInput> {
+        EventWithStats::with_current_time(
+            Event::NewTestcase {
+                input: BytesInput::new(input.to_vec()),
+                observers_buf: None,
+                exit_kind: ExitKind::Ok,
+                corpus_size: 1,
+                client_config: EventConfig::AlwaysUnique,
+                forward_id: None,
+                #[cfg(all(unix, feature = "std", feature = "multi_machine"))]
+                node_id: None,
+            },
+            0,
+        )
+    }
+
+    /// Polls all nodes for a while, and returns the testcases each of them received
+    fn poll(nodes: &mut [&mut Mgr], state: &mut NopState<BytesInput>) -> Vec<Vec<Vec<u8>>> {
+        let mut received = vec![Vec::new(); nodes.len()];
+        for _ in 0..50 {
+            for (node, received) in nodes.iter_mut().zip(&mut received) {
+                while let Some((event, _)) = node.try_receive(state).unwrap() {
+                    if let Event::NewTestcase { input, .. } = event.event() {
+                        received.push(input.as_ref().clone());
+                    }
+                }
+            }
+            thread::sleep(Duration::from_millis(5));
+        }
+        received
+    }
+
+    #[test]
+    fn test_gossip_relay_and_leave() {
+        let mut state = NopState::new();
+        // A chain: a <- b <- c, so events from a only reach c through b
+        let mut a = node(1, &[]);
+        let mut b = node(2, &[&a]);
+        let mut c = node(3, &[&b]);
+        poll(&mut [&mut a, &mut b, &mut c], &mut state);
+        assert_eq!((a.nb_peers(), b.nb_peers(), c.nb_peers()), (1, 2, 1));
+
+        a.fire(&mut state, testcase(b"from a")).unwrap();
+        let received = poll(&mut [&mut a, &mut b, &mut c], &mut state);
+        assert_eq!(received[0], Vec::<Vec<u8>>::new());
+        assert_eq!(received[1], [b"from a".to_vec()]);
+        assert_eq!(received[2], [b"from a".to_vec()]);
+
+        // b leaves, the others keep going
+        drop(b);
+        a.fire(&mut state, testcase(b"again")).unwrap();
+        c.fire(&mut state, testcase(b"from c")).unwrap();
+        let received = poll(&mut [&mut a, &mut c], &mut state);
+        assert_eq!(received, [Vec::<Vec<u8>>::new(), Vec::new()]);
+        assert_eq!((a.nb_peers(), c.nb_peers()), (0, 0));
+
+        // Stop events reach everybody
+        let mut d = node(4, &[&a, &c]);
+        poll(&mut [&mut a, &mut c, &mut d], &mut state);
+        d.fire(
+            &mut state,
+            EventWithStats::with_current_time(Event::Stop, 0),
+        )
+        .unwrap();
+        poll(&mut [&mut a], &mut state);
+        assert!(state.stop_requested());
+    }
+
+    #[test]
+    fn test_gossip_bad_frames() {
+        let mut state = NopState::new();
+        let mut a = node(1, &[]);
+        let mut b = node(2, &[&a]);
+        let mut bad = [
+            TcpStream::connect(a.local_addr().unwrap()).unwrap(),
+            TcpStream::connect(a.local_addr().unwrap()).unwrap(),
+        ];
+        poll(&mut [&mut a, &mut b], &mut state);
+        assert_eq!(a.nb_peers(), 3);
+
+        // A truncated frame, and a frame with a payload that does not decode
+        bad[0].write_all(&[1, 0, 0, 0, 0]).unwrap();
+        let mut garbage = vec![17, 0, 0, 0];
+        garbage.extend_from_slice(&[0x42; 13]);
+        garbage.extend_from_slice(&[0xff; 4]);
+        bad[1].write_all(&garbage).unwrap();
+        b.fire(&mut state, testcase(b"from b")).unwrap();
+
+        let received = poll(&mut [&mut a, &mut b], &mut state);
+        assert_eq!(received[0], [b"from b".to_vec()]);
+        assert_eq!(a.nb_peers(), 1);
+    }
+
+    #[test]
+    fn test_gossip_oversized_frame() {
+        let mut state = NopState::new();
+        let mut a = node(1, &[]);
+        let mut b = node(2, &[&a]);
+        let mut bad = TcpStream::connect(a.local_addr().unwrap()).unwrap();
+        poll(&mut [&mut a, &mut b], &mut state);
+        assert_eq!(a.nb_peers(), 2);
+
+        // Only the length of the frame is sent, the peer is dropped without waiting for the rest
+        bad.write_all(&u32::MAX.to_le_bytes()).unwrap();
+        b.fire(&mut state, testcase(b"from b")).unwrap();
+
+        let received = poll(&mut [&mut a, &mut b], &mut state);
+        assert_eq!(received[0], [b"from b".to_vec()]);
+        assert_eq!(a.nb_peers(), 1);
+    }
+}
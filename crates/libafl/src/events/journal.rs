@@ -0,0 +1,539 @@
+//! Journaling of the events an event manager fires and receives, and their deterministic replay.
+//!
+//! The [`EventJournalHook`] appends every event that passes through an event manager to a journal
+//! on disk, together with the time and the id of the client that sent it.
+//! Later, a [`ReplayEventManager`] feeds the recorded journal back into a single, local client,
+//! so that the same sequence of imported testcases and stats can be reproduced offline.
+//!
+//! Like the [`MessageFileWriter`](crate::observers::concolic::serialization_format::MessageFileWriter)
+//! of concolic traces, the journal is a plain stream of length-prefixed messages.
+//! Each record is `len: u32 | postcard(JournalEntry)`, and is written in a single call.
+//! Since the journal is only ever appended to, a client crashing mid-write leaves at most one truncated record
+//! at the very end, which the [`EventJournalReader`] ignores.
+
+use alloc::vec::Vec;
+use core::{fmt::Debug, marker::PhantomData, time::Duration};
+use std::{
+    fs::{File, OpenOptions},
+    io::{BufReader, ErrorKind, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use libafl_bolts::{ClientId, current_time};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+
+use crate::{
+    Error, HasMetadata,
+    events::{
+        AwaitRestartSafe, Event, EventConfig, EventFirer, EventManagerHook, EventManagerId,
+        EventReceiver, EventRestarter, EventWithStats, HasEventManagerId, ProgressReporter,
+        SendExiting, StdLlmpEventHook, std_maybe_report_progress, std_on_restart,
+        std_report_progress,
+    },
+    monitors::{Monitor, stats::ClientStatsManager},
+    state::{
+        HasCurrentStageId, HasExecutions, HasLastReportTime, MaybeHasClientPerfMonitor, Stoppable,
+    },
+};
+
+/// The length of the size prefix of each journal record
+const RECORD_HEADER_LEN: usize = 4;
+
+/// Whether a journaled event was fired by this client, or received from another one
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum JournalDirection {
+    /// The event was fired by the journaling client
+    Fired,
+    /// The event was received from another client
+    Received,
+}
+
+/// A single record of the journal
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct JournalEntry<I> {
+    /// The time the event passed through the event manager
+    pub time: Duration,
+    /// If the event was fired or received
+    pub direction: JournalDirection,
+    /// The client that sent the event. For fired events, this is the journaling client itself.
+    pub client_id: ClientId,
+    /// The event
+    pub event: EventWithStats<I>,
+}
+
+/// The borrowed version of [`JournalEntry`], serialized to the same bytes
+#[derive(Serialize)]
+struct JournalEntryRef<'a, I> {
+    time: Duration,
+    direction: JournalDirection,
+    client_id: ClientId,
+    event: &'a EventWithStats<I>,
+}
+
+/// Appends [`JournalEntry`]s to a journal file
+#[derive(Debug)]
+pub struct EventJournalWriter {
+    file: File,
+    path: PathBuf,
+}
+
+impl EventJournalWriter {
+    /// Opens the journal at the given path, creating it if it does not exist.
+    /// Records are appended, so a restarted client continues the journal of its previous run.
+    pub fn create<P>(path: P) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref().to_path_buf();
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        Ok(Self { file, path })
+    }
+
+    /// The path of the journal
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Appends the event to the journal, timestamped with the current time.
+    pub fn record<I>(
+        &mut self,
+        direction: JournalDirection,
+        client_id: ClientId,
+        event: &EventWithStats<I>,
+    ) -> Result<(), Error>
+    where
+        I: Serialize,
+    {
+        let entry = JournalEntryRef {
+            time: current_time(),
+            direction,
+            client_id,
+            event,
+        };
+        let payload = postcard::to_allocvec(&entry)?;
+        let len = u32::try_from(payload.len())
+            .map_err(|_| Error::illegal_argument("Event too large for the journal"))?;
+        let mut record = Vec::with_capacity(RECORD_HEADER_LEN + payload.len());
+        record.extend_from_slice(&len.to_le_bytes());
+        record.extend_from_slice(&payload);
+        self.file.write_all(&record)?;
+        Ok(())
+    }
+}
+
+/// Reads the [`JournalEntry`]s of a journal file, in the order they were recorded
+#[derive(Debug)]
+pub struct EventJournalReader {
+    reader: BufReader<File>,
+}
+
+impl EventJournalReader {
+    /// Opens the journal at the given path
+    pub fn open<P>(path: P) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        Ok(Self {
+            reader: BufReader::new(File::open(path)?),
+        })
+    }
+
+    /// Reads the next entry of the journal, or `None` at its end.
+    pub fn next_entry<I>(&mut self) -> Result<Option<JournalEntry<I>>, Error>
+    where
+        I: DeserializeOwned,
+    {
+        let mut len_buf = [0; RECORD_HEADER_LEN];
+        if !self.read_or_eof(&mut len_buf)? {
+            return Ok(None);
+        }
+        let mut record = vec![0; u32::from_le_bytes(len_buf) as usize];
+        if !self.read_or_eof(&mut record)? {
+            return Ok(None);
+        }
+        Ok(Some(postcard::from_bytes(&record)?))
+    }
+
+    /// Fills the buffer, returns `false` if the journal ends before.
+    fn read_or_eof(&mut self, buf: &mut [u8]) -> Result<bool, Error> {
+        match self.reader.read_exact(buf) {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+/// An [`EventManagerHook`] that appends every event fired and received by the event manager
+/// to an [`EventJournalWriter`].
+///
+/// It works with any event manager that supports hooks, such as the LLMP and TCP managers.
+/// For the [`CentralizedEventManager`](crate::events::CentralizedEventManager), use
+/// [`CentralizedEventManagerBuilder::journal`](crate::events::CentralizedEventManagerBuilder::journal).
+#[derive(Debug)]
+pub struct EventJournalHook {
+    writer: EventJournalWriter,
+}
+
+impl EventJournalHook {
+    /// Creates a hook journaling to the file at the given path
+    pub fn new<P>(path: P) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        Ok(Self::with_writer(EventJournalWriter::create(path)?))
+    }
+
+    /// Creates a hook journaling to the given writer
+    #[must_use]
+    pub fn with_writer(writer: EventJournalWriter) -> Self {
+        Self { writer }
+    }
+}
+
+impl<I, S> EventManagerHook<I, S> for EventJournalHook
+where
+    I: Serialize,
+{
+    fn pre_receive(
+        &mut self,
+        _state: &mut S,
+        client_id: ClientId,
+        event: &EventWithStats<I>,
+    ) -> Result<bool, Error> {
+        self.writer
+            .record(JournalDirection::Received, client_id, event)?;
+        Ok(true)
+    }
+
+    fn on_fire(
+        &mut self,
+        _state: &mut S,
+        client_id: ClientId,
+        event: &EventWithStats<I>,
+    ) -> Result<(), Error> {
+        self.writer
+            .record(JournalDirection::Fired, client_id, event)
+    }
+}
+
+/// An event manager replaying the events received in a journal, recorded by an [`EventJournalHook`].
+///
+/// The recorded testcases and objectives are handed to the fuzzer in their original order,
+/// the recorded stats of the other clients are shown in the monitor, next to the stats of this client.
+/// Events fired by this client are not shared with anyone.
+pub struct ReplayEventManager<I, MT, S> {
+    reader: EventJournalReader,
+    /// The next entry to replay, if it is not due yet
+    pending: Option<JournalEntry<I>>,
+    finished: bool,
+    /// The journal time and the wall time of the first replayed entry
+    replay_start: Option<(Duration, Duration)>,
+    realtime: bool,
+    monitor: MT,
+    client_stats_manager: ClientStatsManager,
+    configuration: EventConfig,
+    phantom: PhantomData<S>,
+}
+
+impl<I, MT, S> Debug for ReplayEventManager<I, MT, S>
+where
+    MT: Debug,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("ReplayEventManager")
+            .field("reader", &self.reader)
+            .field("finished", &self.finished)
+            .field("realtime", &self.realtime)
+            .field("monitor", &self.monitor)
+            .field("configuration", &self.configuration)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<I, MT, S> ReplayEventManager<I, MT, S>
+where
+    MT: Monitor,
+{
+    /// Creates a new [`ReplayEventManager`], replaying the given journal.
+    /// Recorded testcases from clients with a matching `configuration` reuse their recorded observers.
+    pub fn new(monitor: MT, reader: EventJournalReader, configuration: EventConfig) -> Self {
+        Self {
+            reader,
+            pending: None,
+            finished: false,
+            replay_start: None,
+            realtime: false,
+            monitor,
+            client_stats_manager: ClientStatsManager::default(),
+            configuration,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Creates a new [`ReplayEventManager`], replaying the journal at the given path
+    pub fn from_path<P>(monitor: MT, path: P, configuration: EventConfig) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        Ok(Self::new(
+            monitor,
+            EventJournalReader::open(path)?,
+            configuration,
+        ))
+    }
+
+    /// Only hand out events once as much time passed since the start of the replay as in the recording.
+    /// By default, events are replayed as fast as the fuzzer asks for them.
+    #[must_use]
+    pub fn realtime(mut self, realtime: bool) -> Self {
+        self.realtime = realtime;
+        self
+    }
+
+    /// If all events of the journal were replayed
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.finished && self.pending.is_none()
+    }
+
+    /// The next recorded entry that is due, skipping the events this client fired itself
+    fn next_due_entry(&mut self) -> Result<Option<JournalEntry<I>>, Error>
+    where
+        I: DeserializeOwned,
+    {
+        loop {
+            let entry = match self.pending.take() {
+                Some(entry) => entry,
+                None if self.finished => return Ok(None),
+                None => {
+                    let Some(entry) = self.reader.next_entry()? else {
+                        self.finished = true;
+                        return Ok(None);
+                    };
+                    entry
+                }
+            };
+            if entry.direction == JournalDirection::Fired {
+                continue;
+            }
+
+            if self.realtime {
+                let now = current_time();
+                let (journal_start, replay_start) =
+                    *self.replay_start.get_or_insert((entry.time, now));
+                let due = replay_start + entry.time.saturating_sub(journal_start);
+                if due > now {
+                    self.pending = Some(entry);
+                    return Ok(None);
+                }
+            }
+            return Ok(Some(entry));
+        }
+    }
+}
+
+impl<I, MT, S> EventFirer<I, S> for ReplayEventManager<I, MT, S>
+where
+    MT: Monitor,
+{
+    fn should_send(&self) -> bool {
+        true
+    }
+
+    fn fire(&mut self, _state: &mut S, event: EventWithStats<I>) -> Result<(), Error> {
+        StdLlmpEventHook::<I, MT>::handle_in_broker(
+            &mut self.monitor,
+            &mut self.client_stats_manager,
+            ClientId(0),
+            &event,
+        )?;
+        Ok(())
+    }
+
+    fn configuration(&self) -> EventConfig {
+        self.configuration
+    }
+}
+
+impl<I, MT, S> EventRestarter<S> for ReplayEventManager<I, MT, S>
+where
+    S: HasCurrentStageId,
+{
+    fn on_restart(&mut self, state: &mut S) -> Result<(), Error> {
+        std_on_restart(self, state)
+    }
+}
+
+impl<I, MT, S> EventReceiver<I, S> for ReplayEventManager<I, MT, S>
+where
+    I: DeserializeOwned,
+    MT: Monitor,
+    S: Stoppable,
+{
+    fn try_receive(&mut self, state: &mut S) -> Result<Option<(EventWithStats<I>, bool)>, Error> {
+        while let Some(entry) = self.next_due_entry()? {
+            let event = entry.event;
+            // The recorded clients are shown next to us, with their recorded stats
+            let client_id = ClientId(entry.client_id.0.saturating_add(1));
+            StdLlmpEventHook::<I, MT>::handle_in_broker(
+                &mut self.monitor,
+                &mut self.client_stats_manager,
+                client_id,
+                &event,
+            )?;
+            match event.event() {
+                Event::NewTestcase {
+                    client_config,
+                    observers_buf,
+                    ..
+                } => {
+                    let reuse_observers =
+                        client_config.match_with(&self.configuration) && observers_buf.is_some();
+                    return Ok(Some((event, reuse_observers)));
+                }
+                Event::Objective { .. } => return Ok(Some((event, false))),
+                Event::Stop => state.request_stop(),
+                _ => {}
+            }
+        }
+        Ok(None)
+    }
+
+    fn on_interesting(&mut self, _state: &mut S, _event: EventWithStats<I>) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl<I, MT, S> AwaitRestartSafe for ReplayEventManager<I, MT, S> {
+    fn await_restart_safe(&mut self) {}
+}
+
+impl<I, MT, S> SendExiting for ReplayEventManager<I, MT, S> {
+    fn send_exiting(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn on_shutdown(&mut self) -> Result<(), Error> {
+        self.send_exiting()
+    }
+}
+
+impl<I, MT, S> ProgressReporter<S> for ReplayEventManager<I, MT, S>
+where
+    MT: Monitor,
+    S: HasExecutions + HasMetadata + HasLastReportTime + MaybeHasClientPerfMonitor,
+{
+    fn maybe_report_progress(
+        &mut self,
+        state: &mut S,
+        monitor_timeout: Duration,
+    ) -> Result<(), Error> {
+        std_maybe_report_progress(self, state, monitor_timeout)
+    }
+
+    fn report_progress(&mut self, state: &mut S) -> Result<(), Error> {
+        std_report_progress(self, state)
+    }
+}
+
+impl<I, MT, S> HasEventManagerId for ReplayEventManager<I, MT, S> {
+    fn mgr_id(&self) -> EventManagerId {
+        EventManagerId(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+    use std::{env, fs, process};
+
+    use libafl_bolts::ClientId;
+
+    use super::{EventJournalHook, EventJournalReader, JournalDirection, ReplayEventManager};
+    use crate::{
+        events::{Event, EventConfig, EventManagerHook, EventReceiver, EventWithStats, ExecStats},
+        executors::ExitKind,
+        inputs::BytesInput,
+        monitors::NopMonitor,
+        state::{NopState, Stoppable},
+    };
+
+    fn testcase(input: &[u8], client_config: EventConfig) -> EventWithStats<BytesInput> {
+        EventWithStats::new(
+            Event::NewTestcase {
+                input: BytesInput::new(input.to_vec()),
+                observers_buf: Some(Vec::new()),
+                exit_kind: ExitKind::Ok,
+                corpus_size: 1,
+                client_config,
+                forward_id: None,
+                #[cfg(all(unix, feature = "std", feature = "multi_machine"))]
+                node_id: None,
+            },
+            ExecStats::new(core::time::Duration::ZERO, 1),
+        )
+    }
+
+    #[test]
+    fn test_journal_replay() {
+        let path = env::temp_dir().join(format!("libafl_journal_test_{}", process::id()));
+        let _ = fs::remove_file(&path);
+        let config = EventConfig::from_name("journal");
+        let mut state = NopState::<BytesInput>::new();
+
+        {
+            let mut hook = EventJournalHook::new(&path).unwrap();
+            let fired = testcase(b"mine", config);
+            EventManagerHook::<_, NopState<BytesInput>>::on_fire(
+                &mut hook,
+                &mut state,
+                ClientId(0),
+                &fired,
+            )
+            .unwrap();
+            for (id, input) in [(1, &b"first"[..]), (2, &b"second"[..])] {
+                assert!(
+                    hook.pre_receive(&mut state, ClientId(id), &testcase(input, config))
+                        .unwrap()
+                );
+            }
+            let foreign = testcase(b"foreign", EventConfig::from_name("other"));
+            hook.pre_receive(&mut state, ClientId(3), &foreign).unwrap();
+            hook.pre_receive(
+                &mut state,
+                ClientId(1),
+                &EventWithStats::<BytesInput>::with_current_time(Event::Stop, 0),
+            )
+            .unwrap();
+        }
+
+        let mut reader = EventJournalReader::open(&path).unwrap();
+        let first = reader.next_entry::<BytesInput>().unwrap().unwrap();
+        assert_eq!(first.direction, JournalDirection::Fired);
+        assert_eq!(first.client_id, ClientId(0));
+
+        let mut mgr: ReplayEventManager<BytesInput, NopMonitor, NopState<BytesInput>> =
+            ReplayEventManager::from_path(NopMonitor::new(), &path, config).unwrap();
+        let mut replayed = Vec::new();
+        while let Some((event, reuse_observers)) = mgr.try_receive(&mut state).unwrap() {
+            let Event::NewTestcase { input, .. } = event.event() else {
+                panic!("Unexpected event {:?}", event.event().name());
+            };
+            replayed.push((input.clone(), reuse_observers));
+        }
+        assert_eq!(
+            replayed,
+            [
+                (BytesInput::new(b"first".to_vec()), true),
+                (BytesInput::new(b"second".to_vec()), true),
+                (BytesInput::new(b"foreign".to_vec()), false),
+            ]
+        );
+        assert!(mgr.is_finished());
+        assert!(state.stop_requested());
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -9,16 +9,20 @@
 //! To connect multiple nodes together via TCP, we can use the `remote_broker_addr`.
 //! (this requires the `llmp_bind_public` compile-time feature for `LibAFL`).
 //!
+//! With the `gossip_manager` feature, `Launcher::launch_gossip` connects the clients in a gossip mesh instead,
+//! without any broker.
+//!
 //! On `Unix` systems, the [`Launcher`] will use `fork` if the `fork` feature is used for `LibAFL`.
 //! Else, it will start subsequent nodes with the same commandline, and will set special `env` variables accordingly.
 
-use alloc::string::String;
+use alloc::{string::String, vec::Vec};
 use core::{
     fmt::{self, Debug, Formatter},
     net::SocketAddr,
     num::NonZeroUsize,
     time::Duration,
 };
+use std::path::PathBuf;
 
 use libafl_bolts::{
     core_affinity::{CoreId, Cores},
@@ -27,19 +31,26 @@ use libafl_bolts::{
 };
 use serde::{Deserialize, Serialize, de::DeserializeOwned};
 use typed_builder::TypedBuilder;
+#[cfg(all(unix, feature = "fork", feature = "gossip_manager"))]
+use {
+    crate::events::gossip::GossipEventManager,
+    libafl_bolts::{ClientId, os::CTRL_C_EXIT},
+};
 #[cfg(all(unix, feature = "fork"))]
 use {
-    crate::{
-        events::{CentralizedLlmpHook, StdLlmpEventHook, centralized::CentralizedEventManager},
-        inputs::Input,
+    crate::events::{
+        CentralizedLlmpHook, ControlFile, GlobalCoverageLlmpHook, LlmpControlHook,
+        ObjectiveLlmpHook, StdLlmpEventHook, centralized::CentralizedEventManager,
     },
     alloc::boxed::Box,
     alloc::string::ToString,
+    core::sync::atomic::{AtomicBool, Ordering},
     libafl_bolts::{
         core_affinity::get_core_ids,
         llmp::{Broker, Brokers, LlmpBroker},
         os::{ForkResult, fork},
     },
+    std::{collections::HashMap, fs, io::ErrorKind, path::Path},
 };
 #[cfg(unix)]
 use {
@@ -54,9 +65,10 @@ use crate::events::multi_machine::{NodeDescriptor, TcpMultiMachineHooks};
 use crate::{
     Error,
     events::{
-        EventConfig, EventManagerHooksTuple,
+        EventConfig, EventManagerHooksTuple, GlobalCoverageMap, ObjectiveAction,
         llmp::{LlmpRestartingEventManager, LlmpShouldSaveState, ManagerKind, RestartingMgr},
     },
+    inputs::Input,
     monitors::Monitor,
 };
 
@@ -67,6 +79,68 @@ const _AFL_LAUNCHER_CLIENT: &str = "AFL_LAUNCHER_CLIENT";
 #[cfg(all(feature = "fork", unix))]
 const LIBAFL_DEBUG_OUTPUT: &str = "LIBAFL_DEBUG_OUTPUT";
 
+/// Creates the [`GlobalCoverageMap`] for the clients and the broker spawned from now on, if it has a size.
+/// It lives as long as the returned value, so keep it around until the campaign ends.
+fn create_global_coverage_map<SP>(
+    shmem_provider: &mut SP,
+    map_size: Option<usize>,
+) -> Result<Option<GlobalCoverageMap<SP::ShMem>>, Error>
+where
+    SP: ShMemProvider,
+{
+    let Some(map_size) = map_size else {
+        return Ok(None);
+    };
+    let global_map = GlobalCoverageMap::new(shmem_provider, map_size)?;
+    // # Safety
+    // The launcher sets up the env before spawning anything, from a single thread.
+    unsafe {
+        global_map.write_to_env()?;
+    }
+    Ok(Some(global_map))
+}
+
+/// How often the launcher looks at its [`Launcher::scale_file`]
+#[cfg(all(unix, feature = "fork"))]
+const SCALE_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Set once a scaling launcher got interrupted
+#[cfg(all(unix, feature = "fork"))]
+static SCALING_INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+#[cfg(all(unix, feature = "fork"))]
+extern "C" fn handle_scaling_interrupt(_signal: libc::c_int) {
+    SCALING_INTERRUPTED.store(true, Ordering::Relaxed);
+}
+
+/// Compares the running clients to the cores they should run on.
+/// Returns the `(core, overcommit id)` slots that need a new client, and the pids of the clients to retire.
+#[cfg(all(unix, feature = "fork"))]
+fn plan_scaling(
+    running: &HashMap<libc::pid_t, ClientDescription>,
+    cores: &Cores,
+    overcommit: usize,
+) -> (Vec<(CoreId, usize)>, Vec<libc::pid_t>) {
+    let mut spawn = vec![];
+    for core_id in &cores.ids {
+        for overcommit_id in 0..overcommit {
+            if !running
+                .values()
+                .any(|client| client.core_id == *core_id && client.overcommit_id == overcommit_id)
+            {
+                spawn.push((*core_id, overcommit_id));
+            }
+        }
+    }
+    let mut retire: Vec<_> = running
+        .iter()
+        .filter(|(_, client)| !cores.ids.contains(&client.core_id))
+        .map(|(pid, _)| *pid)
+        .collect();
+    retire.sort_unstable();
+    (spawn, retire)
+}
+
 /// Information about this client from the launcher
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ClientDescription {
@@ -180,6 +254,43 @@ pub struct Launcher<'a, CF, MT, SP> {
     /// Tell the manager to serialize or not the state on restart
     #[builder(default = LlmpShouldSaveState::OnRestart)]
     serialize_state: LlmpShouldSaveState,
+    /// A control file for the broker to watch, to pause, resume, stop or checkpoint all clients.
+    /// See [`crate::events::control`].
+    #[builder(default = None)]
+    control_file: Option<PathBuf>,
+    /// The directory to write campaign checkpoints to. See [`crate::events::checkpoint`].
+    #[builder(default = None)]
+    checkpoint_dir: Option<PathBuf>,
+    /// Restore the campaign from [`Self::checkpoint_dir`]: all clients start from their checkpointed state.
+    #[builder(default = false)]
+    restore_checkpoint: bool,
+    /// The size of the coverage map, to share a [`GlobalCoverageMap`] of it between all clients.
+    /// The clients attach to it with [`GlobalCoverageMap::from_env`], for their
+    /// [`GlobalCoverageFeedback`](crate::feedbacks::GlobalCoverageFeedback),
+    /// and the broker drops the testcases that add no coverage to it.
+    /// See [`crate::events::global_coverage`].
+    #[builder(default = None)]
+    global_coverage_map_size: Option<usize>,
+    /// What the broker does with each new objective, such as running a triage command.
+    /// See [`crate::events::broker_hooks::objective`].
+    #[builder(default = vec![])]
+    objective_actions: Vec<ObjectiveAction>,
+    /// The directory the broker writes the objectives the clients share to, for the [`Self::objective_actions`]
+    #[builder(default = None)]
+    objectives_dir: Option<PathBuf>,
+    /// Log a warning, and set the `plateau` user stat, once the coverage did not grow for this long.
+    /// See [`crate::monitors::stats::PlateauDetector`].
+    #[builder(default = None)]
+    plateau_timeout: Option<Duration>,
+    /// A file with the cores to run on, in the format of [`Cores::from_cmdline`], such as `0-3,8`.
+    /// If set, the launcher keeps watching the file, and spawns or retires clients whenever it changes,
+    /// without restarting the campaign. Until the file exists, the clients run on [`Self::cores`].
+    ///
+    /// New clients catch up on the corpus of the campaign, as the broker keeps all messages.
+    /// Retired clients get interrupted, and detach from the broker.
+    #[cfg(all(unix, feature = "fork"))]
+    #[builder(default = None)]
+    scale_file: Option<PathBuf>,
 }
 
 impl<CF, MT, SP> Debug for Launcher<'_, CF, MT, SP> {
@@ -215,7 +326,7 @@ where
             LlmpRestartingEventManager<(), I, S, SP::ShMem, SP>,
             ClientDescription,
         ) -> Result<(), Error>,
-        I: DeserializeOwned,
+        I: DeserializeOwned + Input,
         S: DeserializeOwned + Serialize,
         SP: ShMemProvider,
     {
@@ -233,7 +344,7 @@ where
     pub fn launch_with_hooks<EMH, I, S>(&mut self, hooks: EMH) -> Result<(), Error>
     where
         S: DeserializeOwned + Serialize,
-        I: DeserializeOwned,
+        I: DeserializeOwned + Input,
         EMH: EventManagerHooksTuple<I, S> + Clone + Copy,
         CF: FnOnce(
             Option<S>,
@@ -253,6 +364,13 @@ where
             ));
         }
 
+        let _global_map =
+            create_global_coverage_map(&mut self.shmem_provider, self.global_coverage_map_size)?;
+
+        if let Some(scale_file) = self.scale_file.clone() {
+            return self.launch_scaling(hooks, &scale_file);
+        }
+
         let core_ids = get_core_ids()?;
         let mut handles = vec![];
 
@@ -324,6 +442,8 @@ where
                                 })
                                 .configuration(self.configuration)
                                 .serialize_state(self.serialize_state)
+                                .checkpoint_dir(self.checkpoint_dir.clone())
+                                .restore_checkpoint(self.restore_checkpoint)
                                 .hooks(hooks);
                             let (state, mgr) = builder.build().launch()?;
 
@@ -351,6 +471,12 @@ where
                 .exit_cleanly_after(Some(NonZeroUsize::try_from(self.cores.ids.len()).unwrap()))
                 .configuration(self.configuration)
                 .serialize_state(self.serialize_state)
+                .checkpoint_dir(self.checkpoint_dir.clone())
+                .restore_checkpoint(self.restore_checkpoint)
+                .objective_actions(self.objective_actions.clone())
+                .objectives_dir(self.objectives_dir.clone())
+                .plateau_timeout(self.plateau_timeout)
+                .control_file(self.control_file.clone())
                 .hooks(hooks);
 
             builder.build().launch()?;
@@ -392,13 +518,19 @@ where
             ClientDescription,
         ) -> Result<(), Error>,
         EMH: EventManagerHooksTuple<I, S> + Clone + Copy,
-        I: DeserializeOwned,
+        I: DeserializeOwned + Input,
         S: DeserializeOwned + Serialize,
     {
         use libafl_bolts::core_affinity::get_core_ids;
 
         let is_client = std::env::var(_AFL_LAUNCHER_CLIENT);
 
+        let _global_map = if is_client.is_err() {
+            create_global_coverage_map(&mut self.shmem_provider, self.global_coverage_map_size)?
+        } else {
+            None
+        };
+
         let mut handles = match is_client {
             Ok(core_conf) => {
                 let client_description = ClientDescription::from_safe_string(&core_conf);
@@ -412,6 +544,8 @@ where
                     })
                     .configuration(self.configuration)
                     .serialize_state(self.serialize_state)
+                    .checkpoint_dir(self.checkpoint_dir.clone())
+                    .restore_checkpoint(self.restore_checkpoint)
                     .hooks(hooks);
 
                 let (state, mgr) = builder.build().launch()?;
@@ -520,6 +654,12 @@ where
                 .exit_cleanly_after(Some(NonZeroUsize::try_from(self.cores.ids.len()).unwrap()))
                 .configuration(self.configuration)
                 .serialize_state(self.serialize_state)
+                .checkpoint_dir(self.checkpoint_dir.clone())
+                .restore_checkpoint(self.restore_checkpoint)
+                .objective_actions(self.objective_actions.clone())
+                .objectives_dir(self.objectives_dir.clone())
+                .plateau_timeout(self.plateau_timeout)
+                .control_file(self.control_file.clone())
                 .hooks(hooks);
 
             builder.build().launch()?;
@@ -544,6 +684,461 @@ where
     }
 }
 
+#[cfg(all(unix, feature = "fork"))]
+impl<CF, MT, SP> Launcher<'_, CF, MT, SP>
+where
+    MT: Monitor + Clone,
+    SP: ShMemProvider,
+{
+    /// Launch the broker and the clients, and keep matching the clients to the cores in the scale file.
+    /// See [`Self::scale_file`].
+    ///
+    /// The broker runs in its own process, and each client in its own process group, so that it can be retired alone.
+    fn launch_scaling<EMH, I, S>(&mut self, hooks: EMH, scale_file: &Path) -> Result<(), Error>
+    where
+        S: DeserializeOwned + Serialize,
+        I: DeserializeOwned + Input,
+        EMH: EventManagerHooksTuple<I, S> + Clone + Copy,
+        CF: FnOnce(
+            Option<S>,
+            LlmpRestartingEventManager<EMH, I, S, SP::ShMem, SP>,
+            ClientDescription,
+        ) -> Result<(), Error>,
+    {
+        let available_cores = get_core_ids()?;
+
+        self.opened_stdout_file = self
+            .stdout_file
+            .map(|filename| File::create(filename).unwrap());
+        self.opened_stderr_file = self
+            .stderr_file
+            .map(|filename| File::create(filename).unwrap());
+
+        let mut broker = None;
+        if self.spawn_broker {
+            self.shmem_provider.pre_fork()?;
+            // # Safety
+            // Fork is safe in general, apart from potential side effects to the OS and other threads
+            match unsafe { fork() }? {
+                ForkResult::Parent(child) => {
+                    self.shmem_provider.post_fork(false)?;
+                    broker = Some(child.pid);
+                }
+                ForkResult::Child => {
+                    self.shmem_provider.post_fork(true)?;
+                    log::info!("I am broker!!.");
+
+                    // Clients come and go, so the broker runs until the launcher stops it.
+                    let builder = RestartingMgr::<EMH, I, MT, S, SP>::builder()
+                        .shmem_provider(self.shmem_provider.clone())
+                        .monitor(Some(self.monitor.clone()))
+                        .broker_port(self.broker_port)
+                        .kind(ManagerKind::Broker)
+                        .remote_broker_addr(self.remote_broker_addr)
+                        .configuration(self.configuration)
+                        .serialize_state(self.serialize_state)
+                        .checkpoint_dir(self.checkpoint_dir.clone())
+                        .restore_checkpoint(self.restore_checkpoint)
+                        .objective_actions(self.objective_actions.clone())
+                        .objectives_dir(self.objectives_dir.clone())
+                        .plateau_timeout(self.plateau_timeout)
+                        .control_file(self.control_file.clone())
+                        .hooks(hooks);
+                    return builder.build().launch().map(|_| ());
+                }
+            }
+        }
+
+        SCALING_INTERRUPTED.store(false, Ordering::Relaxed);
+        // # Safety
+        // The handler only stores to an atomic
+        unsafe {
+            libc::signal(
+                libc::SIGINT,
+                handle_scaling_interrupt as *const () as libc::sighandler_t,
+            );
+        }
+
+        let mut cores = self.cores.clone();
+        let mut last_spec = None;
+        let mut rescale = true;
+        let mut next_id = 1;
+        let mut running: HashMap<libc::pid_t, ClientDescription> = HashMap::new();
+        let mut retiring: HashMap<libc::pid_t, ClientDescription> = HashMap::new();
+
+        'supervise: while !SCALING_INTERRUPTED.load(Ordering::Relaxed) {
+            loop {
+                let mut status = 0;
+                // # Safety
+                // Normal libc call, no dereferences whatsoever
+                let pid = unsafe { libc::waitpid(-1, &raw mut status, libc::WNOHANG) };
+                if pid <= 0 {
+                    break;
+                }
+                if Some(pid) == broker {
+                    log::info!("The broker exited (status {status}), stopping all clients");
+                    broker = None;
+                    break 'supervise;
+                }
+                if let Some(client) = running.remove(&pid) {
+                    log::warn!("Client {} exited (status {status})", client.id());
+                } else if let Some(client) = retiring.remove(&pid) {
+                    log::info!("Client {} retired", client.id());
+                }
+            }
+
+            match fs::read_to_string(scale_file) {
+                Ok(spec) if last_spec.as_ref() != Some(&spec) => {
+                    match Cores::from_cmdline(spec.trim()) {
+                        Ok(new_cores) => {
+                            log::info!("Scaling to cores {new_cores:?}");
+                            cores = new_cores;
+                            rescale = true;
+                        }
+                        Err(e) => log::error!("Ignoring scale file {}: {e}", scale_file.display()),
+                    }
+                    last_spec = Some(spec);
+                }
+                Ok(_) => {}
+                Err(e) if e.kind() == ErrorKind::NotFound => {}
+                Err(e) => log::error!("Could not read scale file {}: {e}", scale_file.display()),
+            }
+
+            if rescale {
+                rescale = false;
+                let (spawn, retire) = plan_scaling(&running, &cores, self.overcommit);
+                for pid in retire {
+                    let client = running.remove(&pid).unwrap();
+                    log::info!(
+                        "Retiring client {} on core {:?}",
+                        client.id(),
+                        client.core_id()
+                    );
+                    // # Safety
+                    // Normal libc call, no dereferences whatsoever
+                    unsafe {
+                        libc::kill(-pid, libc::SIGINT);
+                    }
+                    retiring.insert(pid, client);
+                }
+                for (core_id, overcommit_id) in spawn {
+                    if !available_cores.contains(&core_id) {
+                        log::error!("Core {core_id:?} is not available, not spawning on it");
+                        continue;
+                    }
+                    let client_description =
+                        ClientDescription::new(next_id, overcommit_id, core_id);
+                    next_id += 1;
+                    match self.fork_scaling_client(hooks, &client_description)? {
+                        Some(pid) => {
+                            running.insert(pid, client_description);
+                        }
+                        // We are the client, and it is done fuzzing
+                        None => return Ok(()),
+                    }
+                    std::thread::sleep(Duration::from_millis(self.launch_delay));
+                }
+            }
+
+            std::thread::sleep(SCALE_POLL_INTERVAL);
+        }
+
+        // Interrupt all clients, and the broker once they detached
+        for pid in running.keys().chain(retiring.keys()) {
+            // # Safety
+            // Normal libc call, no dereferences whatsoever
+            unsafe {
+                libc::kill(-*pid, libc::SIGINT);
+            }
+        }
+        for pid in running.keys().chain(retiring.keys()).chain(&broker) {
+            let mut status = 0;
+            // # Safety
+            // Normal libc calls, no dereferences whatsoever
+            unsafe {
+                if Some(*pid) == broker {
+                    libc::kill(*pid, libc::SIGINT);
+                }
+                libc::waitpid(*pid, &raw mut status, 0);
+            }
+        }
+
+        // # Safety
+        // Restores the default handler
+        unsafe {
+            libc::signal(libc::SIGINT, libc::SIG_DFL);
+        }
+        Ok(())
+    }
+
+    /// Forks a client, in its own process group.
+    /// Returns its pid in the parent, and `None` in the child, once it is done.
+    fn fork_scaling_client<EMH, I, S>(
+        &mut self,
+        hooks: EMH,
+        client_description: &ClientDescription,
+    ) -> Result<Option<libc::pid_t>, Error>
+    where
+        S: DeserializeOwned + Serialize,
+        I: DeserializeOwned + Input,
+        EMH: EventManagerHooksTuple<I, S> + Clone + Copy,
+        CF: FnOnce(
+            Option<S>,
+            LlmpRestartingEventManager<EMH, I, S, SP::ShMem, SP>,
+            ClientDescription,
+        ) -> Result<(), Error>,
+    {
+        self.shmem_provider.pre_fork()?;
+        // # Safety
+        // Fork is safe in general, apart from potential side effects to the OS and other threads
+        match unsafe { fork() }? {
+            ForkResult::Parent(child) => {
+                self.shmem_provider.post_fork(false)?;
+                // # Safety
+                // Normal libc call, no dereferences whatsoever.
+                // The child does the same, whoever comes first wins.
+                unsafe {
+                    libc::setpgid(child.pid, child.pid);
+                }
+                log::info!(
+                    "child spawned with id {} and bound to core {:?}",
+                    client_description.id(),
+                    client_description.core_id()
+                );
+                Ok(Some(child.pid))
+            }
+            ForkResult::Child => {
+                // # Safety
+                // Normal libc calls, no dereferences whatsoever
+                unsafe {
+                    libc::setpgid(0, 0);
+                    libc::signal(libc::SIGINT, libc::SIG_DFL);
+                }
+                self.shmem_provider.post_fork(true)?;
+
+                if std::env::var(LIBAFL_DEBUG_OUTPUT).is_err() {
+                    if let Some(file) = &self.opened_stdout_file {
+                        // # Safety
+                        // We assume the file descriptors are valid here
+                        unsafe {
+                            dup2(file.as_raw_fd(), libc::STDOUT_FILENO)?;
+                            match &self.opened_stderr_file {
+                                Some(stderr) => {
+                                    dup2(stderr.as_raw_fd(), libc::STDERR_FILENO)?;
+                                }
+                                _ => {
+                                    dup2(file.as_raw_fd(), libc::STDERR_FILENO)?;
+                                }
+                            }
+                        }
+                    }
+                }
+
+                let builder = RestartingMgr::<EMH, I, MT, S, SP>::builder()
+                    .shmem_provider(self.shmem_provider.clone())
+                    .broker_port(self.broker_port)
+                    .kind(ManagerKind::Client {
+                        client_description: client_description.clone(),
+                    })
+                    .configuration(self.configuration)
+                    .serialize_state(self.serialize_state)
+                    .checkpoint_dir(self.checkpoint_dir.clone())
+                    .restore_checkpoint(self.restore_checkpoint)
+                    .hooks(hooks);
+                let (state, mgr) = builder.build().launch()?;
+
+                (self.run_client.take().unwrap())(state, mgr, client_description.clone())?;
+                Ok(None)
+            }
+        }
+    }
+}
+
+#[cfg(all(unix, feature = "fork", feature = "gossip_manager"))]
+impl<CF, MT, SP> Launcher<'_, CF, MT, SP>
+where
+    MT: Monitor + Clone,
+    SP: ShMemProvider,
+{
+    /// Launch the clients in a gossip mesh, without any broker, see [`GossipEventManager`].
+    ///
+    /// Client `n` listens for peers on `broker_port + n`, and connects to all clients launched before it,
+    /// as well as to `remote_broker_addr`, if set, to join the mesh of another machine.
+    /// Only the first client displays the stats of the mesh with the monitor.
+    ///
+    /// There is no state restoring: the launcher restarts crashed clients with a fresh state,
+    /// and returns once all clients exited.
+    pub fn launch_gossip<EMH, I, S>(&mut self, hooks: EMH) -> Result<(), Error>
+    where
+        EMH: Clone,
+        CF: FnOnce(GossipEventManager<EMH, I, MT, S>, ClientDescription) -> Result<(), Error>,
+    {
+        if self.cores.ids.is_empty() {
+            return Err(Error::illegal_argument(
+                "No cores to spawn on given, cannot launch anything.",
+            ));
+        }
+
+        if self.run_client.is_none() {
+            return Err(Error::illegal_argument(
+                "No client callback provided".to_string(),
+            ));
+        }
+
+        log::info!("spawning gossip clients on cores: {:?}", self.cores);
+
+        self.opened_stdout_file = self
+            .stdout_file
+            .map(|filename| File::create(filename).unwrap());
+        self.opened_stderr_file = self
+            .stderr_file
+            .map(|filename| File::create(filename).unwrap());
+
+        let mut descriptions = vec![];
+        for bind_to in get_core_ids()? {
+            if self.cores.ids.contains(&bind_to) {
+                for overcommit_id in 0..self.overcommit {
+                    descriptions.push(ClientDescription::new(
+                        descriptions.len() + 1,
+                        overcommit_id,
+                        bind_to,
+                    ));
+                }
+            }
+        }
+
+        let mut clients = HashMap::new();
+        for client_description in descriptions {
+            match self.fork_gossip_client(&hooks, &client_description)? {
+                Some(pid) => {
+                    clients.insert(pid, client_description);
+                }
+                // We are the client, and it is done fuzzing
+                None => return Ok(()),
+            }
+        }
+
+        // Restart the clients that crash, until all of them exited
+        while !clients.is_empty() {
+            let mut status = 0;
+            // # Safety
+            // Normal libc call, no dereferences whatsoever
+            let pid = unsafe { libc::waitpid(-1, &raw mut status, 0) };
+            if pid < 0 {
+                return Err(Error::last_os_error(
+                    "Waiting for the gossip clients failed",
+                ));
+            }
+            let Some(client_description) = clients.remove(&pid) else {
+                continue;
+            };
+            let crashed = if libc::WIFSIGNALED(status) {
+                libc::WTERMSIG(status) != libc::SIGINT
+            } else {
+                let code = libc::WEXITSTATUS(status);
+                code != 0 && code != CTRL_C_EXIT
+            };
+            if !crashed {
+                log::info!("Client {} exited", client_description.id());
+                continue;
+            }
+
+            log::info!(
+                "Client {} crashed (status {status}), restarting it",
+                client_description.id()
+            );
+            match self.fork_gossip_client(&hooks, &client_description)? {
+                Some(pid) => {
+                    clients.insert(pid, client_description);
+                }
+                None => return Ok(()),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Forks a gossip client. Returns its pid in the parent, and `None` in the child, once it is done.
+    fn fork_gossip_client<EMH, I, S>(
+        &mut self,
+        hooks: &EMH,
+        client_description: &ClientDescription,
+    ) -> Result<Option<libc::pid_t>, Error>
+    where
+        EMH: Clone,
+        CF: FnOnce(GossipEventManager<EMH, I, MT, S>, ClientDescription) -> Result<(), Error>,
+    {
+        let id = client_description.id();
+        self.shmem_provider.pre_fork()?;
+        // # Safety
+        // Fork is safe in general, apart from potential side effects to the OS and other threads
+        match unsafe { fork() }? {
+            ForkResult::Parent(child) => {
+                self.shmem_provider.post_fork(false)?;
+                log::info!(
+                    "gossip client spawned with id {id} and bound to core {:?}",
+                    client_description.core_id()
+                );
+                Ok(Some(child.pid))
+            }
+            ForkResult::Child => {
+                self.shmem_provider.post_fork(true)?;
+
+                std::thread::sleep(Duration::from_millis(id as u64 * self.launch_delay));
+
+                if std::env::var(LIBAFL_DEBUG_OUTPUT).is_err() {
+                    if let Some(file) = &self.opened_stdout_file {
+                        // # Safety
+                        // We assume the file descriptors are valid here
+                        unsafe {
+                            dup2(file.as_raw_fd(), libc::STDOUT_FILENO)?;
+                            match &self.opened_stderr_file {
+                                Some(stderr) => {
+                                    dup2(stderr.as_raw_fd(), libc::STDERR_FILENO)?;
+                                }
+                                _ => {
+                                    dup2(file.as_raw_fd(), libc::STDERR_FILENO)?;
+                                }
+                            }
+                        }
+                    }
+                }
+
+                let mut seeds: Vec<SocketAddr> = (1..id)
+                    .map(|peer| SocketAddr::from(([127, 0, 0, 1], self.gossip_port(peer))))
+                    .collect();
+                seeds.extend(self.remote_broker_addr);
+                // Only listen publicly if this mesh spans multiple machines
+                let listen_ip = if self.remote_broker_addr.is_some() {
+                    [0, 0, 0, 0]
+                } else {
+                    [127, 0, 0, 1]
+                };
+
+                let mgr = GossipEventManager::builder()
+                    .hooks(hooks.clone())
+                    .monitor((id == 1).then(|| self.monitor.clone()))
+                    .node_id(ClientId(id as u32))
+                    .seeds(seeds)
+                    .build(
+                        SocketAddr::from((listen_ip, self.gossip_port(id))),
+                        self.configuration,
+                    )?;
+
+                (self.run_client.take().unwrap())(mgr, client_description.clone())?;
+                Ok(None)
+            }
+        }
+    }
+
+    /// The port the gossip client with the given id listens on
+    fn gossip_port(&self, id: usize) -> u16 {
+        self.broker_port
+            .checked_add(u16::try_from(id).unwrap())
+            .expect("Gossip client port out of range")
+    }
+}
+
 /// A Launcher that minimizes re-execution of shared testcases.
 ///
 /// Provides a Launcher, which can be used to launch a fuzzing run on a specified list of cores with a single main and multiple secondary nodes
@@ -608,6 +1203,21 @@ pub struct CentralizedLauncher<'a, CF, MF, MT, SP> {
     /// Tell the manager to serialize or not the state on restart
     #[builder(default = LlmpShouldSaveState::OnRestart)]
     serialize_state: LlmpShouldSaveState,
+    /// A control file for the broker to watch, to pause, resume, stop or checkpoint all clients.
+    /// See [`crate::events::control`].
+    #[builder(default = None)]
+    control_file: Option<PathBuf>,
+    /// The size of the coverage map, to share a [`GlobalCoverageMap`] of it between all clients.
+    /// The centralized broker drops the testcases of secondary clients that add no coverage to it.
+    /// See [`Launcher::global_coverage_map_size`].
+    #[builder(default = None)]
+    global_coverage_map_size: Option<usize>,
+    /// What the broker does with each new objective, see [`Launcher::objective_actions`].
+    #[builder(default = vec![])]
+    objective_actions: Vec<ObjectiveAction>,
+    /// The directory the broker writes the objectives the clients share to, for the [`Self::objective_actions`]
+    #[builder(default = None)]
+    objectives_dir: Option<PathBuf>,
 }
 
 #[cfg(all(unix, feature = "fork"))]
@@ -724,6 +1334,9 @@ where
             ));
         }
 
+        let mut global_map =
+            create_global_coverage_map(&mut self.shmem_provider, self.global_coverage_map_size)?;
+
         let core_ids = get_core_ids().unwrap();
         let mut handles = vec![];
 
@@ -860,14 +1473,18 @@ where
 
         // Add centralized broker
         brokers.add(Box::new({
+            // Only the secondary clients send their testcases here, the main node broadcasts them all
+            let global_hook = GlobalCoverageLlmpHook::<I, SP::ShMem>::new(global_map.take());
+
             #[cfg(feature = "multi_machine")]
             let centralized_hooks = tuple_list!(
                 CentralizedLlmpHook::<I>::new()?,
                 multi_machine_receiver_hook,
+                global_hook,
             );
 
             #[cfg(not(feature = "multi_machine"))]
-            let centralized_hooks = tuple_list!(CentralizedLlmpHook::<I>::new()?);
+            let centralized_hooks = tuple_list!(CentralizedLlmpHook::<I>::new()?, global_hook);
 
             // TODO switch to false after solving the bug
             let mut broker = LlmpBroker::with_keep_pages_attach_to_tcp(
@@ -890,11 +1507,24 @@ where
         if self.spawn_broker {
             log::info!("I am broker!!.");
 
+            let control_hook =
+                LlmpControlHook::<I>::new(self.control_file.as_ref().map(ControlFile::new));
+            let objective_hook = ObjectiveLlmpHook::<I>::new(
+                self.objective_actions.clone(),
+                self.objectives_dir.clone(),
+            );
+
             #[cfg(not(feature = "multi_machine"))]
-            let llmp_hook = tuple_list!(StdLlmpEventHook::<I, MT>::new(self.monitor.clone())?);
+            let llmp_hook = tuple_list!(
+                control_hook,
+                objective_hook,
+                StdLlmpEventHook::<I, MT>::new(self.monitor.clone())?
+            );
 
             #[cfg(feature = "multi_machine")]
             let llmp_hook = tuple_list!(
+                control_hook,
+                objective_hook,
                 StdLlmpEventHook::<I, MT>::new(self.monitor.clone())?,
                 multi_machine_sender_hook,
             );
@@ -932,3 +1562,28 @@ where
         Err(Error::shutting_down())
     }
 }
+
+#[cfg(all(test, unix, feature = "fork"))]
+mod tests {
+    use std::collections::HashMap;
+
+    use libafl_bolts::core_affinity::Cores;
+
+    use super::{ClientDescription, plan_scaling};
+
+    #[test]
+    fn test_plan_scaling() {
+        let mut running = HashMap::new();
+        running.insert(10, ClientDescription::new(1, 0, 0.into()));
+        running.insert(11, ClientDescription::new(2, 1, 0.into()));
+        running.insert(12, ClientDescription::new(3, 0, 1.into()));
+
+        let (spawn, retire) = plan_scaling(&running, &Cores::from_cmdline("0,2").unwrap(), 2);
+        assert_eq!(spawn, [(2.into(), 0), (2.into(), 1)]);
+        assert_eq!(retire, [12]);
+
+        let (spawn, retire) = plan_scaling(&running, &Cores::from_cmdline("0-1").unwrap(), 1);
+        assert!(spawn.is_empty());
+        assert!(retire.is_empty());
+    }
+}
@@ -305,7 +305,7 @@ where
                 }
                 Ok(())
             }
-            Event::Stop => Ok(()),
+            Event::Stop | Event::Pause | Event::Resume | Event::Checkpoint => Ok(()),
             _ => Err(Error::unknown(format!(
                 "Received illegal message that message should not have arrived: {:?}.",
                 event.name()
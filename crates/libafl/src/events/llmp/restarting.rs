@@ -14,7 +14,7 @@ use core::{
     time::Duration,
 };
 #[cfg(feature = "std")]
-use std::net::TcpStream;
+use std::{net::TcpStream, path::PathBuf};
 
 #[cfg(any(windows, not(feature = "fork")))]
 use libafl_bolts::os::startable_self;
@@ -54,10 +54,12 @@ use crate::{
     Error,
     common::HasMetadata,
     events::{
-        _LLMP_TAG_EVENT_TO_BROKER, AwaitRestartSafe, Event, EventConfig, EventFirer,
-        EventManagerHooksTuple, EventManagerId, EventReceiver, EventRestarter, EventWithStats,
-        HasEventManagerId, LLMP_TAG_EVENT_TO_BOTH, LlmpShouldSaveState, ProgressReporter,
-        SendExiting, StdLlmpEventHook, launcher::ClientDescription, std_maybe_report_progress,
+        _LLMP_TAG_EVENT_TO_BROKER, AwaitRestartSafe, CampaignCheckpoint, ClientControl,
+        ControlCommand, ControlFile, Event, EventConfig, EventFirer, EventManagerHooksTuple,
+        EventManagerId, EventReceiver, EventRestarter, EventWithStats, GlobalCoverageLlmpHook,
+        GlobalCoverageMap, HasEventManagerId, LLMP_TAG_EVENT_TO_BOTH, LlmpControlHook,
+        LlmpShouldSaveState, ObjectiveAction, ObjectiveLlmpHook, ProgressReporter, SendExiting,
+        StdLlmpEventHook, launcher::ClientDescription, std_maybe_report_progress,
         std_report_progress,
     },
     inputs::Input,
@@ -91,11 +93,16 @@ pub struct LlmpRestartingEventManager<EMH, I, S, SHM, SP> {
     staterestorer: Option<StateRestorer<SHM, SP>>,
     /// Decide if the state restorer must save the serialized state
     save_state: LlmpShouldSaveState,
+    /// Pauses and resumes this client on commands of the broker
+    control: ClientControl<I>,
+    /// Where to save the state to on control commands, and the launcher id of this client
+    checkpoint: Option<(CampaignCheckpoint, usize)>,
     phantom: PhantomData<(I, S)>,
 }
 
 impl<EMH, I, S, SHM, SP> ProgressReporter<S> for LlmpRestartingEventManager<EMH, I, S, SHM, SP>
 where
+    EMH: EventManagerHooksTuple<I, S>,
     I: Serialize,
     S: HasExecutions + HasLastReportTime + HasMetadata + Serialize + MaybeHasClientPerfMonitor,
     SHM: ShMem,
@@ -116,12 +123,16 @@ where
 
 impl<EMH, I, S, SHM, SP> EventFirer<I, S> for LlmpRestartingEventManager<EMH, I, S, SHM, SP>
 where
+    EMH: EventManagerHooksTuple<I, S>,
     I: Serialize,
     S: Serialize,
     SHM: ShMem,
     SP: ShMemProvider<ShMem = SHM>,
 {
-    fn fire(&mut self, _state: &mut S, event: EventWithStats<I>) -> Result<(), Error> {
+    fn fire(&mut self, state: &mut S, event: EventWithStats<I>) -> Result<(), Error> {
+        self.hooks
+            .on_fire_all(state, self.llmp.sender().id(), &event)?;
+
         // Check if we are going to crash in the event, in which case we store our current state for the next runner
         #[cfg(feature = "llmp_compression")]
         let flags = LLMP_FLAG_INITIALIZED;
@@ -251,14 +262,35 @@ impl<EMH, I, S, SHM, SP> EventReceiver<I, S> for LlmpRestartingEventManager<EMH,
 where
     EMH: EventManagerHooksTuple<I, S>,
     I: DeserializeOwned + Input,
-    S: HasImported + HasCurrentTestcase<I> + HasSolutions<I> + Stoppable + Serialize,
+    S: HasImported
+        + HasCurrentTestcase<I>
+        + HasSolutions<I>
+        + HasExecutions
+        + Stoppable
+        + Serialize,
     SHM: ShMem,
     SP: ShMemProvider<ShMem = SHM>,
 {
     fn try_receive(&mut self, state: &mut S) -> Result<Option<(EventWithStats<I>, bool)>, Error> {
+        if let Some(deferred) = self.control.next_deferred() {
+            return Ok(Some(deferred));
+        }
+
         // TODO: Get around local event copy by moving handle_in_client
         let self_id = self.llmp.sender().id();
-        while let Some((client_id, tag, flags, msg)) = self.llmp.recv_buf_with_flags()? {
+        loop {
+            let Some((client_id, tag, flags, msg)) = self.llmp.recv_buf_with_flags()? else {
+                if !self.control.is_paused() {
+                    break;
+                }
+                // Paused at this stage boundary, until the broker tells us otherwise
+                if self.control.wait_paused() {
+                    let heartbeat =
+                        EventWithStats::with_current_time(Event::Heartbeat, *state.executions());
+                    self.fire(state, heartbeat)?;
+                }
+                continue;
+            };
             assert_ne!(
                 tag, _LLMP_TAG_EVENT_TO_BROKER,
                 "EVENT_TO_BROKER parcel should not have arrived in the client!"
@@ -300,6 +332,10 @@ where
             if !self.hooks.pre_receive_all(state, client_id, &event)? {
                 continue;
             }
+            if let Some(command) = ControlCommand::from_event(event.event()) {
+                self.on_control(state, command)?;
+                continue;
+            }
             let evt_name = event.event().name_detailed();
             match event.event() {
                 Event::NewTestcase {
@@ -315,20 +351,19 @@ where
                         std::process::id()
                     );
 
-                    if client_config.match_with(&self.configuration) && observers_buf.is_some() {
-                        return Ok(Some((event, true)));
+                    let reuse_observers =
+                        client_config.match_with(&self.configuration) && observers_buf.is_some();
+                    if let Some(received) = self.control.pass((event, reuse_observers)) {
+                        return Ok(Some(received));
                     }
-
-                    return Ok(Some((event, false)));
                 }
                 Event::Objective { .. } => {
                     #[cfg(feature = "std")]
                     log::debug!("[{}] Received new Objective", std::process::id());
 
-                    return Ok(Some((event, false)));
-                }
-                Event::Stop => {
-                    state.request_stop();
+                    if let Some(received) = self.control.pass((event, false)) {
+                        return Ok(Some(received));
+                    }
                 }
                 _ => {
                     return Err(Error::unknown(format!(
@@ -438,6 +473,8 @@ impl<EMH> LlmpEventManagerBuilder<EMH> {
             event_buffer: Vec::with_capacity(INITIAL_EVENT_BUFFER_SIZE),
             staterestorer,
             save_state: LlmpShouldSaveState::OnRestart,
+            control: ClientControl::new(),
+            checkpoint: None,
             phantom: PhantomData,
         })
     }
@@ -495,6 +532,41 @@ impl<EMH> LlmpEventManagerBuilder<EMH> {
     }
 }
 
+impl<EMH, I, S, SHM, SP> LlmpRestartingEventManager<EMH, I, S, SHM, SP>
+where
+    EMH: EventManagerHooksTuple<I, S>,
+    I: Serialize,
+    S: HasExecutions + Stoppable + Serialize,
+    SHM: ShMem,
+    SP: ShMemProvider<ShMem = SHM>,
+{
+    /// Carries out a command of the broker, saves the state if the command asks for it,
+    /// and acknowledges the command.
+    /// In the `OOMSafe` modes, the saved state is overwritten by the next message we send.
+    fn on_control(&mut self, state: &mut S, command: ControlCommand) -> Result<(), Error> {
+        self.control.apply(state, command);
+        if command.saves_state() {
+            if let Some(sr) = &mut self.staterestorer {
+                sr.reset();
+                sr.save(&(
+                    if self.save_state.on_restart() {
+                        Some(&*state)
+                    } else {
+                        None
+                    },
+                    &self.llmp.describe()?,
+                ))?;
+            }
+            if let Some((checkpoint, client_id)) = &self.checkpoint {
+                checkpoint.save_client(*client_id, &*state)?;
+            }
+        }
+        let ack =
+            EventWithStats::with_current_time(Event::ControlAck { command }, *state.executions());
+        self.fire(state, ack)
+    }
+}
+
 impl<EMH, I, S, SHM, SP> LlmpRestartingEventManager<EMH, I, S, SHM, SP>
 where
     S: Serialize,
@@ -513,6 +585,12 @@ where
         }
     }
 
+    /// Save the state to the [`CampaignCheckpoint`] on control commands, under the given launcher id.
+    /// See [`crate::events::checkpoint`].
+    pub fn set_checkpoint(&mut self, checkpoint: CampaignCheckpoint, client_id: usize) {
+        self.checkpoint = Some((checkpoint, client_id));
+    }
+
     /// Get the staterestorer
     pub fn staterestorer(&self) -> &Option<StateRestorer<SHM, SP>> {
         &self.staterestorer
@@ -610,7 +688,7 @@ pub fn setup_restarting_mgr_std<I, MT, S>(
     Error,
 >
 where
-    I: DeserializeOwned,
+    I: DeserializeOwned + Input,
     MT: Monitor + Clone,
     S: Serialize + DeserializeOwned,
 {
@@ -644,7 +722,7 @@ pub fn setup_restarting_mgr_std_adaptive<I, MT, S>(
 where
     MT: Monitor + Clone,
     S: Serialize + DeserializeOwned,
-    I: DeserializeOwned,
+    I: DeserializeOwned + Input,
 {
     RestartingMgr::builder()
         .shmem_provider(StdShMemProvider::new()?)
@@ -656,6 +734,54 @@ where
         .launch()
 }
 
+/// The hooks of a broker: the campaign control and objective actions, followed by the standard event handling
+#[expect(clippy::type_complexity, clippy::too_many_arguments)]
+fn broker_hooks<I, MT, SP>(
+    shmem_provider: &mut SP,
+    monitor: MT,
+    control_file: Option<&PathBuf>,
+    checkpoint_dir: Option<&PathBuf>,
+    restore_checkpoint: bool,
+    objective_actions: &[ObjectiveAction],
+    objectives_dir: Option<&PathBuf>,
+    plateau_timeout: Option<Duration>,
+) -> Result<
+    (
+        LlmpControlHook<I>,
+        ObjectiveLlmpHook<I>,
+        StdLlmpEventHook<I, MT>,
+        GlobalCoverageLlmpHook<I, SP::ShMem>,
+    ),
+    Error,
+>
+where
+    MT: Monitor,
+    SP: ShMemProvider,
+{
+    let mut llmp_hook = StdLlmpEventHook::new(monitor)?;
+    let control_hook = LlmpControlHook::new(control_file.map(ControlFile::new));
+    if let Some(checkpoint_dir) = checkpoint_dir {
+        let checkpoint = CampaignCheckpoint::new(checkpoint_dir);
+        if restore_checkpoint {
+            if let Some(broker) = checkpoint.load_broker()? {
+                log::info!("Restoring the campaign from {}", checkpoint_dir.display());
+                // Continue the run time and the stats of all clients where the checkpoint left off
+                llmp_hook.client_stats_manager_mut().restore(broker.stats);
+            }
+        }
+        llmp_hook.set_checkpoint(checkpoint);
+    }
+    if let Some(plateau_timeout) = plateau_timeout {
+        llmp_hook
+            .client_stats_manager_mut()
+            .set_plateau_timeout(plateau_timeout);
+    }
+    let global_hook = GlobalCoverageLlmpHook::new(GlobalCoverageMap::from_env(shmem_provider)?);
+    let objective_hook =
+        ObjectiveLlmpHook::new(objective_actions.to_vec(), objectives_dir.cloned());
+    Ok((control_hook, objective_hook, llmp_hook, global_hook))
+}
+
 /// Provides a `builder` which can be used to build a [`RestartingMgr`].
 ///
 /// The [`RestartingMgr`] is is a combination of a
@@ -691,6 +817,26 @@ pub struct RestartingMgr<EMH, I, MT, S, SP> {
     /// Tell the manager to serialize or not the state on restart
     #[builder(default = LlmpShouldSaveState::OnRestart)]
     serialize_state: LlmpShouldSaveState,
+    /// A control file for the broker to watch, to pause, resume, stop or checkpoint all clients.
+    /// See [`crate::events::control`].
+    #[builder(default = None)]
+    control_file: Option<PathBuf>,
+    /// The directory to write campaign checkpoints to. See [`crate::events::checkpoint`].
+    #[builder(default = None)]
+    checkpoint_dir: Option<PathBuf>,
+    /// Start the clients from their states in [`Self::checkpoint_dir`], if there are any
+    #[builder(default = false)]
+    restore_checkpoint: bool,
+    /// What the broker does with each new objective. See [`crate::events::broker_hooks::objective`].
+    #[builder(default = vec![])]
+    objective_actions: Vec<ObjectiveAction>,
+    /// The directory the broker writes the objectives the clients share to, for the [`Self::objective_actions`]
+    #[builder(default = None)]
+    objectives_dir: Option<PathBuf>,
+    /// Log a warning, and set the `plateau` user stat, once the coverage did not grow for this long.
+    /// See [`crate::monitors::stats::PlateauDetector`].
+    #[builder(default = None)]
+    plateau_timeout: Option<Duration>,
     /// The hooks passed to event manager:
     hooks: EMH,
     #[builder(setter(skip), default = PhantomData)]
@@ -701,7 +847,7 @@ pub struct RestartingMgr<EMH, I, MT, S, SP> {
 impl<EMH, I, MT, S, SP> RestartingMgr<EMH, I, MT, S, SP>
 where
     EMH: EventManagerHooksTuple<I, S> + Copy + Clone,
-    I: DeserializeOwned,
+    I: DeserializeOwned + Input,
     MT: Monitor + Clone,
     S: Serialize + DeserializeOwned,
     SP: ShMemProvider,
@@ -744,8 +890,17 @@ where
                         LlmpConnection::on_port(self.shmem_provider.clone(), self.broker_port)?;
                     match connection {
                         LlmpConnection::IsBroker { broker } => {
-                            let llmp_hook =
-                                StdLlmpEventHook::<I, MT>::new(self.monitor.take().unwrap())?;
+                            let (control_hook, objective_hook, llmp_hook, global_hook) =
+                                broker_hooks::<I, MT, SP>(
+                                    &mut self.shmem_provider,
+                                    self.monitor.take().unwrap(),
+                                    self.control_file.as_ref(),
+                                    self.checkpoint_dir.as_ref(),
+                                    self.restore_checkpoint,
+                                    &self.objective_actions,
+                                    self.objectives_dir.as_ref(),
+                                    self.plateau_timeout,
+                                )?;
 
                             // Yep, broker. Just loop here.
                             log::info!(
@@ -753,7 +908,12 @@ where
                             );
 
                             broker_things(
-                                broker.add_hooks(tuple_list!(llmp_hook)),
+                                broker.add_hooks(tuple_list!(
+                                    control_hook,
+                                    objective_hook,
+                                    llmp_hook,
+                                    global_hook
+                                )),
                                 self.remote_broker_addr,
                             )?;
 
@@ -769,11 +929,21 @@ where
                     }
                 }
                 ManagerKind::Broker => {
-                    let llmp_hook = StdLlmpEventHook::new(self.monitor.take().unwrap())?;
+                    let (control_hook, objective_hook, llmp_hook, global_hook) =
+                        broker_hooks::<I, MT, SP>(
+                            &mut self.shmem_provider,
+                            self.monitor.take().unwrap(),
+                            self.control_file.as_ref(),
+                            self.checkpoint_dir.as_ref(),
+                            self.restore_checkpoint,
+                            &self.objective_actions,
+                            self.objectives_dir.as_ref(),
+                            self.plateau_timeout,
+                        )?;
 
                     let broker = LlmpBroker::create_attach_to_tcp(
                         self.shmem_provider.clone(),
-                        tuple_list!(llmp_hook),
+                        tuple_list!(control_hook, objective_hook, llmp_hook, global_hook),
                         self.broker_port,
                     )?;
 
@@ -926,7 +1096,7 @@ where
         }
 
         // If we're restarting, deserialize the old state.
-        let (state, mut mgr) =
+        let (mut state, mut mgr, first_run) =
             if let Some((state_opt, mgr_description)) = staterestorer.restore()? {
                 (
                     state_opt,
@@ -939,6 +1109,7 @@ where
                             self.configuration,
                             Some(staterestorer),
                         )?,
+                    false,
                 )
             } else {
                 log::info!("First run. Let's set it all up");
@@ -954,8 +1125,28 @@ where
                             self.configuration,
                             Some(staterestorer),
                         )?,
+                    true,
                 )
             };
+
+        if let Some(checkpoint_dir) = &self.checkpoint_dir {
+            let checkpoint = CampaignCheckpoint::new(checkpoint_dir);
+            // Match clients to their checkpoint by their launcher id, if they have one
+            let client_id = match &self.kind {
+                ManagerKind::Client { client_description } => client_description.id(),
+                _ => mgr.mgr_id().0,
+            };
+            if first_run && self.restore_checkpoint {
+                state = checkpoint.load_client(client_id)?;
+                if state.is_some() {
+                    log::info!(
+                        "Restored the state of client {client_id} from {}",
+                        checkpoint_dir.display()
+                    );
+                }
+            }
+            mgr.set_checkpoint(checkpoint, client_id);
+        }
         // We reset the staterestorer, the next staterestorer and receiver (after crash) will reuse the page from the initial message.
         if self.serialize_state.oom_safe() {
             mgr.intermediate_save()?;
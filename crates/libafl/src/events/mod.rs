@@ -11,10 +11,31 @@ pub mod centralized;
 #[cfg(all(unix, feature = "std"))]
 pub use centralized::*;
 #[cfg(feature = "std")]
+pub mod checkpoint;
+#[cfg(feature = "std")]
+pub use checkpoint::*;
+#[cfg(feature = "std")]
+pub mod control;
+#[cfg(feature = "std")]
+pub use control::*;
+#[cfg(feature = "std")]
+pub mod global_coverage;
+#[cfg(feature = "std")]
+pub use global_coverage::*;
+#[cfg(feature = "std")]
+pub mod journal;
+#[cfg(feature = "std")]
 pub mod launcher;
 
 pub mod llmp;
 pub use llmp::*;
+#[cfg(any(
+    feature = "tcp_manager",
+    all(unix, feature = "std", feature = "multi_machine")
+))]
+mod async_stream;
+#[cfg(feature = "gossip_manager")]
+pub mod gossip;
 #[cfg(feature = "tcp_manager")]
 pub mod tcp;
 
@@ -26,6 +47,7 @@ use core::{
     fmt,
     hash::{BuildHasher, Hasher},
     marker::PhantomData,
+    str::FromStr,
     time::Duration,
 };
 
@@ -296,6 +318,78 @@ impl<I> EventWithStats<I> {
     }
 }
 
+/// A command to control all clients of a campaign, see [`Event::Pause`], [`Event::Resume`],
+/// [`Event::Stop`] and [`Event::Checkpoint`]
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ControlCommand {
+    /// Pause fuzzing
+    Pause,
+    /// Resume fuzzing
+    Resume,
+    /// Stop fuzzing, and exit
+    Stop,
+    /// Save the state
+    Checkpoint,
+}
+
+impl ControlCommand {
+    /// The event broadcasting this command to the clients
+    #[must_use]
+    pub fn to_event<I>(self) -> Event<I> {
+        match self {
+            ControlCommand::Pause => Event::Pause,
+            ControlCommand::Resume => Event::Resume,
+            ControlCommand::Stop => Event::Stop,
+            ControlCommand::Checkpoint => Event::Checkpoint,
+        }
+    }
+
+    /// The command carried by this event, if it is a control event
+    #[must_use]
+    pub fn from_event<I>(event: &Event<I>) -> Option<Self> {
+        match event {
+            Event::Pause => Some(ControlCommand::Pause),
+            Event::Resume => Some(ControlCommand::Resume),
+            Event::Stop => Some(ControlCommand::Stop),
+            Event::Checkpoint => Some(ControlCommand::Checkpoint),
+            _ => None,
+        }
+    }
+
+    /// If the clients save their state before acknowledging this command
+    #[must_use]
+    pub fn saves_state(self) -> bool {
+        !matches!(self, ControlCommand::Resume)
+    }
+}
+
+impl fmt::Display for ControlCommand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ControlCommand::Pause => "pause",
+            ControlCommand::Resume => "resume",
+            ControlCommand::Stop => "stop",
+            ControlCommand::Checkpoint => "checkpoint",
+        })
+    }
+}
+
+impl FromStr for ControlCommand {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "pause" => Ok(ControlCommand::Pause),
+            "resume" => Ok(ControlCommand::Resume),
+            "stop" => Ok(ControlCommand::Stop),
+            "checkpoint" => Ok(ControlCommand::Checkpoint),
+            _ => Err(Error::illegal_argument(format!(
+                "Unknown control command {s:?}, expected pause, resume, stop or checkpoint"
+            ))),
+        }
+    }
+}
+
 // TODO remove forward_id as not anymore needed for centralized
 /// Events sent around in the library
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -358,6 +452,17 @@ pub enum Event<I> {
     },
     /// Exit gracefully
     Stop,
+    /// Pause fuzzing at the next stage boundary, until [`Event::Resume`] or [`Event::Stop`] arrives
+    Pause,
+    /// Continue fuzzing after an [`Event::Pause`]
+    Resume,
+    /// Save the state at the next stage boundary, so that the campaign can be restarted from there
+    Checkpoint,
+    /// A client acknowledges that it carried out a [`ControlCommand`]
+    ControlAck {
+        /// The acknowledged command
+        command: ControlCommand,
+    },
     /*/// A custom type
     Custom {
         // TODO: Allow custom events
@@ -380,6 +485,10 @@ impl<I> Event<I> {
                 sender_id: _, /*custom_event} => custom_event.name()*/
             } => "todo",*/
             Event::Stop => "Stop",
+            Event::Pause => "Pause",
+            Event::Resume => "Resume",
+            Event::Checkpoint => "Checkpoint",
+            Event::ControlAck { .. } => "Control Ack",
         }
     }
 
@@ -399,6 +508,10 @@ impl<I> Event<I> {
             Event::Objective { .. } => Cow::Borrowed("Objective"),
             Event::Log { .. } => Cow::Borrowed("Log"),
             Event::Stop => Cow::Borrowed("Stop"),
+            Event::Pause => Cow::Borrowed("Pause"),
+            Event::Resume => Cow::Borrowed("Resume"),
+            Event::Checkpoint => Cow::Borrowed("Checkpoint"),
+            Event::ControlAck { command } => Cow::Owned(format!("Control Ack {command}")),
             /*Event::Custom {
                 sender_id: _, /*custom_event} => custom_event.name()*/
             } => "todo",*/
@@ -1,15 +1,28 @@
-use alloc::{boxed::Box, sync::Arc, vec::Vec};
+use alloc::{
+    boxed::Box,
+    collections::VecDeque,
+    string::{String, ToString},
+    sync::Arc,
+    vec::Vec,
+};
 use core::{
-    fmt::Display,
+    fmt::{self, Debug, Display, Formatter},
     sync::atomic::{AtomicU64, Ordering},
     time::Duration,
 };
-use std::{collections::HashMap, io::ErrorKind, process, sync::OnceLock};
+use std::{
+    collections::{HashMap, HashSet},
+    io::{self, ErrorKind},
+    process,
+    sync::OnceLock,
+};
 
 use enumflags2::{BitFlags, bitflags};
 #[cfg(feature = "llmp_compression")]
 use libafl_bolts::compress::GzipCompressor;
-use libafl_bolts::{Error, current_time, ownedref::OwnedRef};
+#[cfg(feature = "tls")]
+use libafl_bolts::tls::TlsConfig;
+use libafl_bolts::{Error, current_time, hash_std, ownedref::OwnedRef};
 use serde::{Deserialize, Serialize};
 use tokio::{
     io::{AsyncReadExt, AsyncWriteExt},
@@ -22,7 +35,10 @@ use tokio::{
 use typed_builder::TypedBuilder;
 
 use crate::{
-    events::{EventWithStats, TcpMultiMachineLlmpReceiverHook, TcpMultiMachineLlmpSenderHook},
+    events::{
+        EventWithStats, TcpMultiMachineLlmpReceiverHook, TcpMultiMachineLlmpSenderHook,
+        async_stream::{self, BoxedAsyncStream},
+    },
     inputs::{Input, NopInput},
 };
 
@@ -40,6 +56,422 @@ pub enum NodePolicy {
 }
 
 const DUMMY_BYTE: u8 = 0x14;
+/// Sent instead of the [`DUMMY_BYTE`] when a node leaves the tree.
+const LEAVING_BYTE: u8 = 0x15;
+/// Starts a frame containing several messages, see [`SharingPolicy::batch_window`].
+const BATCH_BYTE: u8 = 0x16;
+/// Starts a frame containing the coverage summary of the sending node, see [`NoveltyFilter`].
+const SUMMARY_BYTE: u8 = 0x17;
+
+/// How often queued messages get flushed, if the [`SharingPolicy`] queues messages.
+const MAINTENANCE_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Returns the coverage map indices a message exercises, or `None` if the message carries
+/// no coverage (for example, if it is not a testcase).
+///
+/// The message is the raw llmp message, as shared between nodes.
+pub type CoverageFn = Arc<dyn Fn(&[u8]) -> Option<Vec<usize>> + Send + Sync>;
+
+/// Only forwards testcases to a link if they bring coverage new to the nodes behind it.
+///
+/// Each node summarizes the coverage it knows about in a bitmap, and periodically sends it to its
+/// parent and children. Before forwarding a testcase over a link, its coverage is checked against
+/// the last summary received over that link.
+#[derive(Clone, TypedBuilder)]
+pub struct NoveltyFilter {
+    /// Extracts the coverage of a message
+    pub coverage: CoverageFn,
+
+    /// The number of bits of the summary bitmap. Coverage indices are folded onto it.
+    #[builder(default = 1 << 16)]
+    pub summary_bits: usize,
+
+    /// How often the summary is sent to the parent and the children
+    #[builder(default = Duration::from_secs(10))]
+    pub summary_interval: Duration,
+}
+
+impl Debug for NoveltyFilter {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NoveltyFilter")
+            .field("summary_bits", &self.summary_bits)
+            .field("summary_interval", &self.summary_interval)
+            .finish_non_exhaustive()
+    }
+}
+
+/// How the testcases of a node get shared with its parent and children.
+/// Every limit applies to each link separately.
+///
+/// The default policy forwards every message as soon as it arrives.
+#[derive(Debug, Clone, Default, TypedBuilder)]
+pub struct SharingPolicy {
+    /// The maximum number of messages sent over a link per second.
+    /// Messages over the limit are queued.
+    #[builder(default)]
+    pub max_msgs_per_sec: Option<u32>,
+
+    /// The maximum number of messages queued for a link.
+    /// New messages are dropped once the queue is full.
+    #[builder(default)]
+    pub max_queued_msgs: Option<usize>,
+
+    /// Queue messages for this long, and send them as a single (compressed) batch.
+    #[builder(default)]
+    pub batch_window: Option<Duration>,
+
+    /// Messages bigger than this are never forwarded
+    #[builder(default)]
+    pub max_msg_size: Option<usize>,
+
+    /// Only forward messages bringing new coverage
+    #[builder(default)]
+    pub novelty_filter: Option<NoveltyFilter>,
+
+    /// How often the per-link [`LinkStats`] are reported to the monitor.
+    /// `None` disables reporting.
+    #[builder(default = Some(Duration::from_secs(15)))]
+    pub report_interval: Option<Duration>,
+}
+
+impl SharingPolicy {
+    /// The coverage of a message, according to the [`NoveltyFilter`]
+    fn coverage(&self, msg: &[u8]) -> Option<Vec<usize>> {
+        self.novelty_filter
+            .as_ref()
+            .and_then(|filter| (filter.coverage)(msg))
+    }
+
+    /// Whether messages may stay queued, and need to be flushed in the background
+    fn needs_maintenance(&self) -> bool {
+        self.max_msgs_per_sec.is_some()
+            || self.batch_window.is_some()
+            || self.novelty_filter.is_some()
+    }
+}
+
+/// Statistics of a link between two nodes
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LinkStats {
+    /// Messages sent over the link
+    pub sent_msgs: u64,
+    /// Bytes sent over the link, after batching and compression
+    pub sent_bytes: u64,
+    /// Messages received over the link
+    pub received_msgs: u64,
+    /// Bytes received over the link
+    pub received_bytes: u64,
+    /// Batches sent over the link
+    pub batches: u64,
+    /// Messages not sent because of [`SharingPolicy::max_msg_size`]
+    pub dropped_oversized: u64,
+    /// Messages not sent because of [`SharingPolicy::max_queued_msgs`]
+    pub dropped_queue_full: u64,
+    /// Messages not sent because of the [`NoveltyFilter`]
+    pub skipped_not_novel: u64,
+}
+
+impl LinkStats {
+    /// The statistics, with their names
+    #[must_use]
+    pub fn entries(&self) -> [(&'static str, u64); 8] {
+        [
+            ("sent_msgs", self.sent_msgs),
+            ("sent_bytes", self.sent_bytes),
+            ("received_msgs", self.received_msgs),
+            ("received_bytes", self.received_bytes),
+            ("batches", self.batches),
+            ("dropped_oversized", self.dropped_oversized),
+            ("dropped_queue_full", self.dropped_queue_full),
+            ("skipped_not_novel", self.skipped_not_novel),
+        ]
+    }
+}
+
+/// Whether all `indices` are set in the `summary` bitmap
+fn summary_covers(summary: &[u8], indices: &[usize]) -> bool {
+    let bits = summary.len() * 8;
+    bits > 0
+        && indices.iter().all(|idx| {
+            let bit = idx % bits;
+            summary[bit / 8] & (1 << (bit % 8)) != 0
+        })
+}
+
+/// Sets all `indices` in the `summary` bitmap
+fn summary_mark(summary: &mut [u8], indices: &[usize]) {
+    let bits = summary.len() * 8;
+    if bits == 0 {
+        return;
+    }
+    for idx in indices {
+        let bit = idx % bits;
+        summary[bit / 8] |= 1 << (bit % 8);
+    }
+}
+
+/// Encodes and decodes batches of messages, compressing them if possible
+#[derive(Debug)]
+struct BatchCodec {
+    #[cfg(feature = "llmp_compression")]
+    compressor: GzipCompressor,
+}
+
+impl BatchCodec {
+    fn new() -> Self {
+        Self {
+            #[cfg(feature = "llmp_compression")]
+            compressor: GzipCompressor::new(),
+        }
+    }
+
+    /// Serializes the messages, prefixed with a byte telling if they are compressed
+    fn encode(&self, msgs: &[Vec<u8>]) -> Result<Vec<u8>, Error> {
+        let serialized = postcard::to_allocvec(msgs)?;
+        #[cfg(feature = "llmp_compression")]
+        if let Some(compressed) = self.compressor.maybe_compress(&serialized) {
+            let mut payload = Vec::with_capacity(compressed.len() + 1);
+            payload.push(1);
+            payload.extend(compressed);
+            return Ok(payload);
+        }
+        let mut payload = Vec::with_capacity(serialized.len() + 1);
+        payload.push(0);
+        payload.extend(serialized);
+        Ok(payload)
+    }
+
+    /// Reverses [`BatchCodec::encode`]
+    fn decode(&self, payload: &[u8]) -> Result<Vec<Vec<u8>>, Error> {
+        match payload.split_first() {
+            Some((0, serialized)) => Ok(postcard::from_bytes(serialized)?),
+            #[cfg(feature = "llmp_compression")]
+            Some((1, compressed)) => Ok(postcard::from_bytes(
+                &self.compressor.decompress(compressed)?,
+            )?),
+            _ => Err(Error::illegal_state("Received an invalid batch")),
+        }
+    }
+}
+
+/// A connection to the parent or to a child, with its outgoing queue
+#[derive(Debug)]
+struct Link {
+    stream: BoxedAsyncStream,
+    queue: VecDeque<Vec<u8>>,
+    last_flush: Duration,
+    rate_window_start: Duration,
+    sent_in_rate_window: u32,
+    /// The coverage the nodes behind this link know about, see [`NoveltyFilter`]
+    peer_summary: Option<Vec<u8>>,
+    stats: LinkStats,
+}
+
+impl Link {
+    fn new(stream: BoxedAsyncStream) -> Self {
+        Self {
+            stream,
+            queue: VecDeque::new(),
+            last_flush: current_time(),
+            rate_window_start: Duration::ZERO,
+            sent_in_rate_window: 0,
+            peer_summary: None,
+            stats: LinkStats::default(),
+        }
+    }
+
+    /// Queues a message to be sent over this link, if the policy allows it.
+    /// If `replay` is set, the message is queued even if the queue is full.
+    fn queue_msg(
+        &mut self,
+        policy: &SharingPolicy,
+        msg: &[u8],
+        coverage: Option<&[usize]>,
+        replay: bool,
+    ) {
+        if policy.max_msg_size.is_some_and(|max| msg.len() > max) {
+            self.stats.dropped_oversized += 1;
+            return;
+        }
+        if let (Some(coverage), Some(peer_summary)) = (coverage, &mut self.peer_summary) {
+            if summary_covers(peer_summary, coverage) {
+                self.stats.skipped_not_novel += 1;
+                return;
+            }
+            // The peer will know about it once it gets the message
+            summary_mark(peer_summary, coverage);
+        }
+        if !replay
+            && policy
+                .max_queued_msgs
+                .is_some_and(|max| self.queue.len() >= max)
+        {
+            self.stats.dropped_queue_full += 1;
+            return;
+        }
+        self.queue.push_back(msg.to_vec());
+    }
+
+    /// Sends as many queued messages as the policy allows right now
+    async fn flush(&mut self, policy: &SharingPolicy, codec: &BatchCodec) -> Result<(), Error> {
+        let now = current_time();
+        if self.queue.is_empty()
+            || policy
+                .batch_window
+                .is_some_and(|window| now < self.last_flush + window)
+        {
+            return Ok(());
+        }
+
+        if now >= self.rate_window_start + Duration::from_secs(1) {
+            self.rate_window_start = now;
+            self.sent_in_rate_window = 0;
+        }
+        let budget = policy.max_msgs_per_sec.map_or(usize::MAX, |max| {
+            max.saturating_sub(self.sent_in_rate_window) as usize
+        });
+        let nb_msgs = self.queue.len().min(budget);
+        if nb_msgs == 0 {
+            return Ok(());
+        }
+
+        let msgs: Vec<Vec<u8>> = self.queue.drain(..nb_msgs).collect();
+        self.sent_in_rate_window += nb_msgs as u32;
+        self.last_flush = now;
+
+        if policy.batch_window.is_some() {
+            let payload = codec.encode(&msgs)?;
+            write_frame(&mut self.stream, BATCH_BYTE, &payload).await?;
+            self.stats.batches += 1;
+            self.stats.sent_bytes += payload.len() as u64;
+        } else {
+            for msg in &msgs {
+                write_frame(&mut self.stream, DUMMY_BYTE, msg).await?;
+                self.stats.sent_bytes += msg.len() as u64;
+            }
+        }
+        self.stats.sent_msgs += nb_msgs as u64;
+
+        Ok(())
+    }
+
+    /// Reads everything the peer sent so far, and appends the received messages to `msgs`.
+    async fn receive(&mut self, codec: &BatchCodec, msgs: &mut Vec<Vec<u8>>) -> Result<(), Error> {
+        while let Some((kind, payload)) = read_frame(&mut self.stream).await? {
+            self.stats.received_bytes += payload.len() as u64;
+            match kind {
+                DUMMY_BYTE => {
+                    self.stats.received_msgs += 1;
+                    msgs.push(payload);
+                }
+                BATCH_BYTE => {
+                    let batch = codec.decode(&payload)?;
+                    self.stats.received_msgs += batch.len() as u64;
+                    msgs.extend(batch);
+                }
+                SUMMARY_BYTE => match &mut self.peer_summary {
+                    Some(summary) if summary.len() == payload.len() => {
+                        // Keep the coverage we sent since the peer made its summary
+                        for (known, new) in summary.iter_mut().zip(payload) {
+                            *known |= new;
+                        }
+                    }
+                    _ => self.peer_summary = Some(payload),
+                },
+                _ => unreachable!("read_frame only returns known frames"),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Read a frame, written by [`write_frame`], from a stream.
+/// If there is nothing to read from the stream, return asap with Ok(None).
+#[expect(clippy::uninit_vec)]
+async fn read_frame(stream: &mut BoxedAsyncStream) -> Result<Option<(u8, Vec<u8>)>, Error> {
+    // 0. Check if we should try to fetch something from the stream
+    let mut kind: [u8; 1] = [0u8];
+    log::debug!("Starting read msg...");
+
+    // Only poll the read once, TLS streams have no `try_read`
+    let n_read = match time::timeout(Duration::ZERO, stream.read(&mut kind)).await {
+        Ok(Ok(n)) => n,
+        Err(_elapsed) => {
+            return Ok(None);
+        }
+        Ok(Err(e)) => return Err(Error::os_error(e, "try read failed")),
+    };
+
+    log::debug!("msg read.");
+
+    if n_read == 0 {
+        return Err(Error::os_error(
+            io::Error::from(ErrorKind::UnexpectedEof),
+            "The node disconnected",
+        ));
+    }
+
+    match kind[0] {
+        LEAVING_BYTE => {
+            return Err(Error::os_error(
+                io::Error::from(ErrorKind::ConnectionAborted),
+                "The node left the tree",
+            ));
+        }
+        DUMMY_BYTE | BATCH_BYTE | SUMMARY_BYTE => {}
+        _ => {
+            return Err(Error::os_error(
+                io::Error::from(ErrorKind::InvalidData),
+                "Received an unknown frame",
+            ));
+        }
+    }
+
+    log::debug!("Received frame kind!");
+
+    // 1. Read msg size
+    let mut node_msg_len: [u8; 4] = [0; 4];
+    log::debug!("Receiving msg len...");
+    stream.read_exact(&mut node_msg_len).await?;
+    log::debug!("msg len received.");
+    let node_msg_len = u32::from_le_bytes(node_msg_len) as usize;
+
+    // 2. Read msg
+    // do not store msg on the stack to avoid overflow issues
+    // TODO: optimize with less allocations...
+    let mut node_msg: Vec<u8> = Vec::with_capacity(node_msg_len);
+    unsafe {
+        node_msg.set_len(node_msg_len);
+    }
+    log::debug!("Receiving msg...");
+    stream.read_exact(node_msg.as_mut_slice()).await?;
+    log::debug!("msg received.");
+
+    Ok(Some((kind[0], node_msg)))
+}
+
+/// Write a frame to a stream.
+/// Can be read back using [`read_frame`].
+async fn write_frame(stream: &mut BoxedAsyncStream, kind: u8, payload: &[u8]) -> Result<(), Error> {
+    let msg_len = u32::to_le_bytes(payload.len() as u32);
+
+    // 0. Write the frame kind
+    log::debug!("Sending frame kind...");
+    stream.write_all(&[kind]).await?;
+    log::debug!("frame kind sent.");
+
+    // 1. Write msg size
+    log::debug!("Sending msg len...");
+    stream.write_all(&msg_len).await?;
+    log::debug!("msg len sent.");
+
+    // 2. Write msg
+    log::debug!("Sending msg...");
+    stream.write_all(payload).await?;
+    log::debug!("msg sent.");
+
+    Ok(())
+}
 
 /// Use `OwnedRef` as much as possible here to avoid useless copies.
 /// An owned TCP message for multi machine
@@ -113,12 +545,20 @@ impl NodeId {
 pub struct TcpMultiMachineState<A> {
     node_descriptor: NodeDescriptor<A>,
     /// the parent to which the testcases should be forwarded when deemed interesting
-    parent: Option<TcpStream>,
+    parent: Option<Link>,
     /// The children who connected during the fuzzing session.
-    children: HashMap<NodeId, TcpStream>, // The children who connected during the fuzzing session.
+    children: HashMap<NodeId, Link>, // The children who connected during the fuzzing session.
     old_msgs: Vec<Vec<u8>>,
-    #[cfg(feature = "llmp_compression")]
-    compressor: GzipCompressor,
+    /// The hashes of all messages in `old_msgs`, or received from other nodes.
+    /// Messages get replayed on reconnect, so other nodes may send them more than once.
+    known_msgs: HashSet<u64>,
+    /// Set once this node left the tree, see [`TcpMultiMachineState::leave`]
+    left: bool,
+    /// The coverage this node knows about, see [`NoveltyFilter`]
+    summary: Vec<u8>,
+    last_summary_sent: Duration,
+    last_stats_report: Duration,
+    codec: BatchCodec,
 }
 
 /// The tree descriptor for the
@@ -127,17 +567,47 @@ pub struct NodeDescriptor<A> {
     /// The parent address, if there is one.
     pub parent_addr: Option<A>,
 
+    /// The addresses to fall back to, in order, if the parent is unreachable.
+    #[builder(default)]
+    pub fallback_parents: Vec<A>,
+
     /// The node listening port. Defaults to 50000
     #[builder(default = Some(50000))]
     pub node_listening_port: Option<u16>,
 
     #[builder(default = Duration::from_secs(60))]
-    /// The timeout for connecting to parent
+    /// The timeout for the initial connection to a parent
     pub timeout: Duration,
 
+    /// The delay before trying to reconnect to a lost parent.
+    /// Doubles after each failed attempt, up to `max_reconnect_backoff`.
+    #[builder(default = Duration::from_secs(1))]
+    pub reconnect_backoff: Duration,
+
+    /// The maximum delay between two attempts to reconnect to a lost parent.
+    #[builder(default = Duration::from_secs(60))]
+    pub max_reconnect_backoff: Duration,
+
     /// Node flags
     #[builder(default_code = "BitFlags::default()")]
     pub flags: BitFlags<NodePolicy>, // The policy for shared messages between nodes.
+
+    /// How testcases are shared with the parent and the children
+    #[builder(default)]
+    pub sharing_policy: SharingPolicy,
+
+    /// Secures the connections to the parent and the children with TLS.
+    /// All nodes of the tree have to use TLS, and pin each other's certificates.
+    #[cfg(feature = "tls")]
+    #[builder(default = None)]
+    pub tls: Option<TlsConfig>,
+}
+
+impl<A> NodeDescriptor<A> {
+    /// The parent, followed by the fallback parents
+    pub fn parents(&self) -> impl Iterator<Item = &A> {
+        self.parent_addr.iter().chain(&self.fallback_parents)
+    }
 }
 
 /// A set of multi-machine `broker_hooks`.
@@ -219,20 +689,13 @@ where
 
             // Create the state of the hook. This will be shared with the background server, so we wrap
             // it with concurrent-safe objects
-            let state = Arc::new(RwLock::new(TcpMultiMachineState {
-                node_descriptor,
-                parent: None,
-                children: HashMap::default(),
-                old_msgs: Vec::new(),
-                #[cfg(feature = "llmp_compression")]
-                compressor: GzipCompressor::new(),
-            }));
+            let state = Arc::new(RwLock::new(TcpMultiMachineState::new(node_descriptor)));
 
             let rt = Arc::new(
                 Runtime::new().map_err(|_| Error::unknown("Tokio runtime spawning failed"))?,
             );
 
-            TcpMultiMachineState::init::<I>(&state.clone(), &rt.clone())?;
+            TcpMultiMachineState::init(&state.clone(), &rt.clone())?;
 
             Ok(TcpMultiMachineHooks {
                 sender: TcpMultiMachineLlmpSenderHook::new(state.clone(), rt.clone()),
@@ -252,10 +715,7 @@ where
     ///
     /// This should be run **only once**, in the same process as the llmp hooks, and before the hooks
     /// are effectively used.
-    unsafe fn init<I: Input>(
-        self_mutex: &Arc<RwLock<Self>>,
-        rt: &Arc<Runtime>,
-    ) -> Result<(), Error> {
+    unsafe fn init(self_mutex: &Arc<RwLock<Self>>, rt: &Arc<Runtime>) -> Result<(), Error> {
         let node_descriptor =
             rt.block_on(async { self_mutex.read().await.node_descriptor.clone() });
 
@@ -264,20 +724,15 @@ where
             let parent_mutex = self_mutex.clone();
             let mut parent_lock = parent_mutex.write().await;
 
-            if let Some(parent_addr) = &parent_lock.node_descriptor.parent_addr {
-                let timeout = current_time() + parent_lock.node_descriptor.timeout;
+            if node_descriptor.parents().next().is_some() {
+                let timeout = current_time() + node_descriptor.timeout;
 
                 parent_lock.parent = loop {
-                    log::debug!("Trying to connect to parent @ {parent_addr}..");
-                    match TcpStream::connect(parent_addr).await {
-                        Ok(stream) => {
-                            log::debug!("Connected to parent @ {parent_addr}");
-
-                            break Some(stream);
-                        }
+                    match Self::connect_to_parent(&node_descriptor).await {
+                        Ok(stream) => break Some(Link::new(stream)),
                         Err(e) => {
                             if current_time() > timeout {
-                                return Err(Error::os_error(e, "Unable to connect to parent"));
+                                return Err(e);
                             }
                         }
                     }
@@ -289,6 +744,51 @@ where
             Ok(())
         })?;
 
+        // Reconnect in the background whenever we lose our parent
+        if node_descriptor.parents().next().is_some() {
+            let bg_state = self_mutex.clone();
+            let node_descriptor = node_descriptor.clone();
+            let _handle: JoinHandle<()> = rt.spawn(async move {
+                let mut backoff = node_descriptor.reconnect_backoff;
+                loop {
+                    time::sleep(backoff).await;
+
+                    {
+                        let state = bg_state.read().await;
+                        if state.left {
+                            return;
+                        }
+                        if state.parent.is_some() {
+                            backoff = node_descriptor.reconnect_backoff;
+                            continue;
+                        }
+                    }
+
+                    match Self::connect_to_parent(&node_descriptor).await {
+                        Ok(stream) => {
+                            let mut state = bg_state.write().await;
+                            let mut link = Link::new(stream);
+                            // Our parent may have missed messages while we were disconnected
+                            if let Err(e) = state.send_old_events_to_link(&mut link).await {
+                                log::error!("Error while replaying old messages to parent: {e:?}");
+                                continue;
+                            }
+                            state.parent = Some(link);
+                            log::info!("[pid {}] Reconnected to a parent.", process::id());
+                        }
+                        Err(e) => {
+                            log::warn!(
+                                "Could not reconnect to any parent, retrying in {backoff:?}: {e:?}"
+                            );
+                            backoff = backoff
+                                .saturating_mul(2)
+                                .min(node_descriptor.max_reconnect_backoff);
+                        }
+                    }
+                }
+            });
+        }
+
         // Now, setup the background tasks for the children to connect to
         if let Some(listening_port) = node_descriptor.node_listening_port {
             let bg_state = self_mutex.clone();
@@ -304,20 +804,37 @@ where
                 'listening: loop {
                     log::debug!("listening for children on {listener:?}...");
                     match listener.accept().await {
-                        Ok((mut stream, addr)) => {
+                        Ok((stream, addr)) => {
                             log::debug!("{addr} joined the children.");
+                            if state.read().await.left {
+                                log::debug!("This node left the tree, rejecting {addr}.");
+                                continue 'listening;
+                            }
+                            #[cfg(feature = "tls")]
+                            let stream = match &node_descriptor.tls {
+                                Some(tls_config) => {
+                                    match async_stream::tls_accept(tls_config, stream).await {
+                                        Ok(stream) => stream,
+                                        Err(e) => {
+                                            log::error!("Rejected child {addr}: {e}");
+                                            continue 'listening;
+                                        }
+                                    }
+                                }
+                                None => async_stream::plain(stream),
+                            };
+                            #[cfg(not(feature = "tls"))]
+                            let stream = async_stream::plain(stream);
                             let mut state_guard = state.write().await;
+                            let mut link = Link::new(stream);
 
-                            if let Err(e) = state_guard
-                                .send_old_events_to_stream::<I>(&mut stream)
-                                .await
-                            {
+                            if let Err(e) = state_guard.send_old_events_to_link(&mut link).await {
                                 log::error!("Error while send old messages: {e:?}.");
                                 log::error!("The loop will resume");
                                 continue 'listening;
                             }
 
-                            state_guard.children.insert(NodeId::new(), stream);
+                            state_guard.children.insert(NodeId::new(), link);
                             log::debug!(
                                 "[pid {}]{addr} added the child. nb children: {}",
                                 process::id(),
@@ -332,115 +849,83 @@ where
             });
         }
 
+        // Flush the queues and share our coverage summary in the background
+        if node_descriptor.sharing_policy.needs_maintenance() {
+            let bg_state = self_mutex.clone();
+            let _handle: JoinHandle<()> = rt.spawn(async move {
+                loop {
+                    time::sleep(MAINTENANCE_INTERVAL).await;
+                    let mut state = bg_state.write().await;
+                    if state.left {
+                        return;
+                    }
+                    state.maintain().await;
+                }
+            });
+        }
+
         Ok(())
     }
 
-    /// Add an event as past event.
-    pub fn add_past_msg(&mut self, msg: &[u8]) {
-        self.old_msgs.push(msg.to_vec());
-    }
+    /// Tries to connect to the parent, then to the fallback parents, in order.
+    /// Returns the error of the last attempt if all of them fail.
+    async fn connect_to_parent(
+        node_descriptor: &NodeDescriptor<A>,
+    ) -> Result<BoxedAsyncStream, Error> {
+        let mut last_err = Error::illegal_state("This node has no parent");
+        for parent_addr in node_descriptor.parents() {
+            log::debug!("Trying to connect to parent @ {parent_addr}..");
+            let stream = match TcpStream::connect(parent_addr).await {
+                Ok(stream) => stream,
+                Err(e) => {
+                    last_err =
+                        Error::os_error(e, format!("Unable to connect to parent {parent_addr}"));
+                    continue;
+                }
+            };
+            log::debug!("Connected to parent @ {parent_addr}");
 
-    /// The compressor
-    #[cfg(feature = "llmp_compression")]
-    pub fn compressor(&mut self) -> &GzipCompressor {
-        &self.compressor
-    }
-
-    /// Read a [`TcpMultiMachineMsg`] from a stream.
-    /// Expects a message written by [`TcpMultiMachineState::write_msg`].
-    /// If there is nothing to read from the stream, return asap with Ok(None).
-    #[expect(clippy::uninit_vec)]
-    async fn read_msg<'a, I: Input + 'a>(
-        stream: &mut TcpStream,
-    ) -> Result<Option<MultiMachineMsg<'a, I>>, Error> {
-        // 0. Check if we should try to fetch something from the stream
-        let mut dummy_byte: [u8; 1] = [0u8];
-        log::debug!("Starting read msg...");
-
-        let n_read = match stream.try_read(&mut dummy_byte) {
-            Ok(n) => n,
-            Err(e) if e.kind() == ErrorKind::WouldBlock => {
-                return Ok(None);
+            #[cfg(feature = "tls")]
+            if let Some(tls_config) = &node_descriptor.tls {
+                match async_stream::tls_connect(tls_config, stream).await {
+                    Ok(stream) => return Ok(stream),
+                    Err(e) => {
+                        last_err = e;
+                        continue;
+                    }
+                }
             }
-            Err(e) => return Err(Error::os_error(e, "try read failed")),
-        };
-
-        log::debug!("msg read.");
-
-        if n_read == 0 {
-            log::debug!("No dummy byte received...");
-            return Ok(None); // Nothing to read from this stream
+            return Ok(async_stream::plain(stream));
         }
+        Err(last_err)
+    }
 
-        log::debug!("Received dummy byte!");
-
-        // we should always read the dummy byte at this point.
-        assert_eq!(u8::from_le_bytes(dummy_byte), DUMMY_BYTE);
-
-        // 1. Read msg size
-        let mut node_msg_len: [u8; 4] = [0; 4];
-        log::debug!("Receiving msg len...");
-        stream.read_exact(&mut node_msg_len).await?;
-        log::debug!("msg len received.");
-        let node_msg_len = u32::from_le_bytes(node_msg_len) as usize;
-
-        // 2. Read msg
-        // do not store msg on the stack to avoid overflow issues
-        // TODO: optimize with less allocations...
-        let mut node_msg: Vec<u8> = Vec::with_capacity(node_msg_len);
-        unsafe {
-            node_msg.set_len(node_msg_len);
+    /// Add an event as past event.
+    /// Messages that are already known are not stored twice.
+    pub fn add_past_msg(&mut self, msg: &[u8]) {
+        if self.known_msgs.insert(hash_std(msg)) {
+            self.old_msgs.push(msg.to_vec());
         }
-        log::debug!("Receiving msg...");
-        stream.read_exact(node_msg.as_mut_slice()).await?;
-        log::debug!("msg received.");
-        let node_msg = node_msg.into_boxed_slice();
-
-        Ok(Some(MultiMachineMsg::from_llmp_msg(node_msg)))
     }
 
-    /// Write an [`OwnedTcpMultiMachineMsg`] to a stream.
-    /// Can be read back using [`TcpMultiMachineState::read_msg`].
-    async fn write_msg<I: Input>(
-        stream: &mut TcpStream,
-        msg: &MultiMachineMsg<'_, I>,
-    ) -> Result<(), Error> {
-        let serialized_msg = msg.serialize_as_ref();
-        let msg_len = u32::to_le_bytes(serialized_msg.len() as u32);
-
-        // 0. Write the dummy byte
-        log::debug!("Sending dummy byte...");
-        stream.write_all(&[DUMMY_BYTE]).await?;
-        log::debug!("dummy byte sent.");
-
-        // 1. Write msg size
-        log::debug!("Sending msg len...");
-        stream.write_all(&msg_len).await?;
-        log::debug!("msg len sent.");
-
-        // 2. Write msg
-        log::debug!("Sending msg...");
-        stream.write_all(serialized_msg).await?;
-        log::debug!("msg sent.");
-
-        Ok(())
+    /// The compressor
+    #[cfg(feature = "llmp_compression")]
+    pub fn compressor(&mut self) -> &GzipCompressor {
+        &self.codec.compressor
     }
 
-    pub(crate) async fn send_old_events_to_stream<I: Input>(
-        &mut self,
-        stream: &mut TcpStream,
-    ) -> Result<(), Error> {
-        log::debug!("Send old events to new child...");
+    /// Queues all old messages for a new link, and sends what the policy allows.
+    async fn send_old_events_to_link(&mut self, link: &mut Link) -> Result<(), Error> {
+        log::debug!("Send old events to new node...");
 
+        let policy = &self.node_descriptor.sharing_policy;
         for old_msg in &self.old_msgs {
-            let event_ref: MultiMachineMsg<I> =
-                MultiMachineMsg::llmp_msg(OwnedRef::Ref(old_msg.as_slice()));
-            log::debug!("Sending an old message...");
-            Self::write_msg(stream, &event_ref).await?;
-            log::debug!("Old message sent.");
+            let coverage = policy.coverage(old_msg);
+            link.queue_msg(policy, old_msg, coverage.as_deref(), true);
         }
+        link.flush(policy, &self.codec).await?;
 
-        log::debug!("Sent {} old messages.", self.old_msgs.len());
+        log::debug!("Queued {} old messages.", self.old_msgs.len());
 
         Ok(())
     }
@@ -451,6 +936,13 @@ where
     ) -> Result<(), Error> {
         log::debug!("Sending interesting events to nodes...");
 
+        let msg = msg.serialize_as_ref();
+        let policy = &self.node_descriptor.sharing_policy;
+        let coverage = policy.coverage(msg);
+        if let Some(coverage) = &coverage {
+            summary_mark(&mut self.summary, coverage);
+        }
+
         if self
             .node_descriptor
             .flags
@@ -458,10 +950,9 @@ where
         {
             if let Some(parent) = &mut self.parent {
                 log::debug!("Sending to parent...");
-                if let Err(e) = Self::write_msg(parent, msg).await {
-                    log::error!(
-                        "The parent disconnected. We won't try to communicate with it again."
-                    );
+                parent.queue_msg(policy, msg, coverage.as_deref(), false);
+                if let Err(e) = parent.flush(policy, &self.codec).await {
+                    log::error!("The parent disconnected. Trying to reconnect.");
                     log::error!("Error: {e:?}");
                     self.parent.take();
                 }
@@ -474,9 +965,10 @@ where
             .intersects(NodePolicy::SendToChildren)
         {
             let mut ids_to_remove: Vec<NodeId> = Vec::new();
-            for (child_id, child_stream) in &mut self.children {
+            for (child_id, child) in &mut self.children {
                 log::debug!("Sending to child {child_id:?}...");
-                if let Err(err) = Self::write_msg(child_stream, msg).await {
+                child.queue_msg(policy, msg, coverage.as_deref(), false);
+                if let Err(err) = child.flush(policy, &self.codec).await {
                     // most likely the child disconnected. drop the connection later on and continue.
                     log::debug!(
                         "The child disconnected. We won't try to communicate with it again. Error: {err:?}"
@@ -502,48 +994,25 @@ where
         msgs: &mut Vec<MultiMachineMsg<'_, I>>,
     ) -> Result<(), Error> {
         log::debug!("Checking for new events from other nodes...");
-        // let mut nb_received = 0usize;
 
         // Our (potential) parent could have something for us
         if let Some(parent) = &mut self.parent {
-            loop {
-                // Exit if received a lot of inputs at once.
-                // TODO: this causes problems in some cases, it could freeze all fuzzer instances.
-                // if nb_received > MAX_NB_RECEIVED_AT_ONCE {
-                //     log::debug!("hitting MAX_NB_RECEIVED_AT_ONCE limit...");
-                //     return Ok(());
-                // }
-
-                log::debug!("Receiving from parent...");
-                match Self::read_msg(parent).await {
-                    Ok(Some(msg)) => {
-                        log::debug!("Received event from parent");
-                        // The parent has something for us, we store it
-                        msgs.push(msg);
-                        // nb_received += 1;
-                    }
-
-                    Ok(None) => {
-                        // nothing from the parent, we continue
-                        log::debug!("Nothing from parent");
-                        break;
-                    }
-
-                    Err(Error::OsError(_, _, _)) => {
-                        // most likely the parent disconnected. drop the connection
-                        log::debug!(
-                            "The parent disconnected. We won't try to communicate with it again."
-                        );
-                        self.parent.take();
-                        break;
-                    }
-
-                    Err(e) => {
-                        log::debug!("An error occurred and was not expected.");
-                        return Err(e);
-                    }
+            log::debug!("Receiving from parent...");
+            let mut received = Vec::new();
+            match parent.receive(&self.codec, &mut received).await {
+                Ok(()) => {}
+                Err(Error::OsError(_, _, _)) => {
+                    // most likely the parent disconnected. drop the connection, we will try to reconnect in the background.
+                    log::info!("The parent disconnected. Trying to reconnect.");
+                    self.parent.take();
+                }
+                Err(e) => {
+                    log::debug!("An error occurred and was not expected.");
+                    return Err(e);
                 }
             }
+            // The parent has something for us, we store it, unless we already know it
+            self.learn_received(received, ParentOrChild::Parent, msgs);
         }
 
         // What about the (potential) children?
@@ -553,45 +1022,31 @@ where
             process::id(),
             self.children.len()
         );
-        for (child_id, child_stream) in &mut self.children {
-            loop {
-                // Exit if received a lot of inputs at once.
-                // if nb_received > MAX_NB_RECEIVED_AT_ONCE {
-                //    return Ok(());
-                //}
-
-                log::debug!("Receiving from child {child_id:?}...");
-                match Self::read_msg(child_stream).await {
-                    Ok(Some(msg)) => {
-                        // The parent has something for us, we store it
-                        log::debug!("Received event from child!");
-                        msgs.push(msg);
-                        // nb_received += 1;
-                    }
-
-                    Ok(None) => {
-                        // nothing from the parent, we continue
-                        log::debug!("Nothing from child");
-                        break;
-                    }
-
-                    Err(Error::OsError(e, _, _)) => {
-                        // most likely the parent disconnected. drop the connection
-                        log::error!(
-                            "The child disconnected. We won't try to communicate with it again."
-                        );
-                        log::error!("Error: {e:?}");
-                        ids_to_remove.push(*child_id);
-                        break;
-                    }
-
-                    Err(e) => {
-                        // Other error
-                        log::debug!("An error occurred and was not expected.");
-                        return Err(e);
-                    }
+        let child_ids: Vec<NodeId> = self.children.keys().copied().collect();
+        for child_id in child_ids {
+            log::debug!("Receiving from child {child_id:?}...");
+            let mut received = Vec::new();
+            let Some(child) = self.children.get_mut(&child_id) else {
+                continue;
+            };
+            match child.receive(&self.codec, &mut received).await {
+                Ok(()) => {}
+                Err(Error::OsError(e, _, _)) => {
+                    // most likely the child disconnected. drop the connection
+                    log::error!(
+                        "The child disconnected. We won't try to communicate with it again."
+                    );
+                    log::error!("Error: {e:?}");
+                    ids_to_remove.push(child_id);
+                }
+                Err(e) => {
+                    // Other error
+                    log::debug!("An error occurred and was not expected.");
+                    return Err(e);
                 }
             }
+            // The child has something for us, we store it, unless we already know it
+            self.learn_received(received, ParentOrChild::Child(child_id), msgs);
         }
 
         // Garbage collect disconnected children
@@ -603,3 +1058,343 @@ where
         Ok(())
     }
 }
+
+/// The link a message was received from
+#[derive(Debug, Copy, Clone)]
+enum ParentOrChild {
+    Parent,
+    Child(NodeId),
+}
+
+impl<A> TcpMultiMachineState<A> {
+    fn new(node_descriptor: NodeDescriptor<A>) -> Self {
+        let summary_bits = node_descriptor
+            .sharing_policy
+            .novelty_filter
+            .as_ref()
+            .map_or(0, |filter| filter.summary_bits);
+        Self {
+            node_descriptor,
+            parent: None,
+            children: HashMap::default(),
+            old_msgs: Vec::new(),
+            known_msgs: HashSet::new(),
+            left: false,
+            summary: vec![0; summary_bits.div_ceil(8)],
+            last_summary_sent: Duration::ZERO,
+            last_stats_report: current_time(),
+            codec: BatchCodec::new(),
+        }
+    }
+
+    /// The statistics of the link to the parent, if any, and of the links to the children
+    pub fn link_stats(&self) -> Vec<(String, LinkStats)> {
+        let mut children: Vec<(&NodeId, &Link)> = self.children.iter().collect();
+        children.sort_by_key(|(id, _)| id.0);
+        self.parent
+            .iter()
+            .map(|parent| ("parent".to_string(), parent.stats))
+            .chain(
+                children
+                    .into_iter()
+                    .map(|(id, child)| (format!("child {}", id.0), child.stats)),
+            )
+            .collect()
+    }
+
+    /// Returns the [`LinkStats`] if they should be reported to the monitor now,
+    /// see [`SharingPolicy::report_interval`].
+    pub(crate) fn stats_to_report(&mut self) -> Option<Vec<(String, LinkStats)>> {
+        let interval = self.node_descriptor.sharing_policy.report_interval?;
+        let now = current_time();
+        if now < self.last_stats_report + interval {
+            return None;
+        }
+        self.last_stats_report = now;
+        Some(self.link_stats())
+    }
+
+    /// Deduplicates the messages received over a link, and learns about their coverage
+    fn learn_received<I>(
+        &mut self,
+        received: Vec<Vec<u8>>,
+        from: ParentOrChild,
+        msgs: &mut Vec<MultiMachineMsg<'_, I>>,
+    ) {
+        for msg in received {
+            if !self.known_msgs.insert(hash_std(&msg)) {
+                continue;
+            }
+            if let Some(coverage) = self.node_descriptor.sharing_policy.coverage(&msg) {
+                summary_mark(&mut self.summary, &coverage);
+                // The nodes behind the link obviously know about it
+                let link = match from {
+                    ParentOrChild::Parent => self.parent.as_mut(),
+                    ParentOrChild::Child(id) => self.children.get_mut(&id),
+                };
+                if let Some(peer_summary) = link.and_then(|link| link.peer_summary.as_mut()) {
+                    summary_mark(peer_summary, &coverage);
+                }
+            }
+            msgs.push(MultiMachineMsg::from_llmp_msg(msg.into_boxed_slice()));
+        }
+    }
+
+    /// Flushes the queued messages, and sends the coverage summary if it is time to.
+    /// Drops the links that fail.
+    async fn maintain(&mut self) {
+        let policy = &self.node_descriptor.sharing_policy;
+        let now = current_time();
+        let send_summary = policy
+            .novelty_filter
+            .as_ref()
+            .is_some_and(|filter| now >= self.last_summary_sent + filter.summary_interval);
+        if send_summary {
+            self.last_summary_sent = now;
+        }
+
+        if let Some(parent) = &mut self.parent {
+            let mut res = parent.flush(policy, &self.codec).await;
+            if res.is_ok() && send_summary {
+                res = write_frame(&mut parent.stream, SUMMARY_BYTE, &self.summary).await;
+            }
+            if let Err(e) = res {
+                log::error!("The parent disconnected. Trying to reconnect. Error: {e:?}");
+                self.parent.take();
+            }
+        }
+
+        let mut ids_to_remove: Vec<NodeId> = Vec::new();
+        for (child_id, child) in &mut self.children {
+            let mut res = child.flush(policy, &self.codec).await;
+            if res.is_ok() && send_summary {
+                res = write_frame(&mut child.stream, SUMMARY_BYTE, &self.summary).await;
+            }
+            if let Err(e) = res {
+                log::debug!("The child disconnected. Error: {e:?}");
+                ids_to_remove.push(*child_id);
+            }
+        }
+        for id_to_remove in &ids_to_remove {
+            self.children.remove(id_to_remove);
+        }
+    }
+
+    /// Tells the parent and the children that this node leaves the tree, and drops all connections.
+    /// The children will then reconnect to one of their fallback parents.
+    pub async fn leave(&mut self) {
+        self.left = true;
+        let children = self.children.drain().map(|(_, child)| child);
+        for Link { mut stream, .. } in self.parent.take().into_iter().chain(children) {
+            if let Err(e) = stream.write_all(&[LEAVING_BYTE]).await {
+                log::debug!("Could not notify a node that we leave: {e:?}");
+            }
+            drop(stream.shutdown().await);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{string::String, sync::Arc, vec::Vec};
+    use core::time::Duration;
+    use std::{net::TcpListener, thread};
+
+    use libafl_bolts::ownedref::OwnedRef;
+    use tokio::{runtime::Runtime, sync::RwLock};
+
+    use super::{
+        MultiMachineMsg, NodeDescriptor, NoveltyFilter, SharingPolicy, TcpMultiMachineState,
+    };
+    use crate::inputs::NopInput;
+
+    type Node = (Arc<RwLock<TcpMultiMachineState<String>>>, Arc<Runtime>);
+
+    fn free_port() -> u16 {
+        TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port()
+    }
+
+    fn start_node(node_descriptor: NodeDescriptor<String>) -> Node {
+        let state = Arc::new(RwLock::new(TcpMultiMachineState::new(node_descriptor)));
+        let rt = Arc::new(Runtime::new().unwrap());
+        unsafe {
+            TcpMultiMachineState::init(&state, &rt).unwrap();
+        }
+        (state, rt)
+    }
+
+    fn receive(node: &Node) -> Vec<Vec<u8>> {
+        node.1.block_on(async {
+            let mut msgs: Vec<MultiMachineMsg<NopInput>> = Vec::new();
+            node.0
+                .write()
+                .await
+                .receive_new_messages_from_nodes(&mut msgs)
+                .await
+                .unwrap();
+            msgs.iter()
+                .map(|msg| msg.serialize_as_ref().to_vec())
+                .collect()
+        })
+    }
+
+    fn send(node: &Node, msg: &[u8]) {
+        node.1.block_on(async {
+            let mut state = node.0.write().await;
+            state.add_past_msg(msg);
+            state
+                .send_interesting_event_to_nodes(&MultiMachineMsg::<NopInput>::llmp_msg(
+                    OwnedRef::Ref(msg),
+                ))
+                .await
+                .unwrap();
+        });
+    }
+
+    /// Polls `f` until it returns `true`, for up to 10 seconds
+    fn wait_for<F: FnMut() -> bool>(mut f: F) {
+        for _ in 0..1000 {
+            if f() {
+                return;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        panic!("Timed out");
+    }
+
+    fn has_children(node: &Node) -> bool {
+        node.1
+            .block_on(async { !node.0.read().await.children.is_empty() })
+    }
+
+    #[test]
+    fn test_parent_failover() {
+        let (port_a, port_b) = (free_port(), free_port());
+        let parent = |port| {
+            NodeDescriptor::builder()
+                .parent_addr(None)
+                .node_listening_port(Some(port))
+                .build()
+        };
+        let node_a = start_node(parent(port_a));
+        let node_b = start_node(parent(port_b));
+        let child = start_node(
+            NodeDescriptor::builder()
+                .parent_addr(Some(format!("127.0.0.1:{port_a}")))
+                .fallback_parents(vec![format!("127.0.0.1:{port_b}")])
+                .node_listening_port(None)
+                .reconnect_backoff(Duration::from_millis(20))
+                .build(),
+        );
+
+        wait_for(|| has_children(&node_a));
+        send(&child, b"testcase");
+        let mut received = Vec::new();
+        wait_for(|| {
+            received.extend(receive(&node_a));
+            !received.is_empty()
+        });
+        assert_eq!(received, [b"testcase".to_vec()]);
+
+        // Kill the parent, the child has to fail over to node b, and replay its messages
+        node_a
+            .1
+            .block_on(async { node_a.0.write().await.leave().await });
+        drop(node_a);
+        wait_for(|| {
+            receive(&child);
+            has_children(&node_b)
+        });
+        let mut received = Vec::new();
+        wait_for(|| {
+            received.extend(receive(&node_b));
+            !received.is_empty()
+        });
+        assert_eq!(received, [b"testcase".to_vec()]);
+
+        // Replayed messages are deduplicated
+        send(&child, b"testcase");
+        send(&child, b"another testcase");
+        wait_for(|| {
+            received.extend(receive(&node_b));
+            received.len() > 1
+        });
+        assert_eq!(
+            received,
+            [b"testcase".to_vec(), b"another testcase".to_vec()]
+        );
+    }
+
+    #[test]
+    fn test_sharing_policy() {
+        // Each byte of a message is a coverage index
+        let novelty_filter = NoveltyFilter::builder()
+            .coverage(Arc::new(|msg: &[u8]| {
+                Some(msg.iter().map(|b| usize::from(*b)).collect())
+            }))
+            .summary_interval(Duration::from_millis(20))
+            .build();
+        let port = free_port();
+        let parent = start_node(
+            NodeDescriptor::builder()
+                .parent_addr(None)
+                .node_listening_port(Some(port))
+                .sharing_policy(
+                    SharingPolicy::builder()
+                        .novelty_filter(Some(novelty_filter.clone()))
+                        .build(),
+                )
+                .build(),
+        );
+        let child = start_node(
+            NodeDescriptor::builder()
+                .parent_addr(Some(format!("127.0.0.1:{port}")))
+                .node_listening_port(None)
+                .sharing_policy(
+                    SharingPolicy::builder()
+                        .batch_window(Some(Duration::from_millis(50)))
+                        .max_msg_size(Some(16))
+                        .novelty_filter(Some(novelty_filter))
+                        .build(),
+                )
+                .build(),
+        );
+
+        // Wait for the first summary of the parent
+        wait_for(|| has_children(&parent));
+        wait_for(|| {
+            receive(&child);
+            child.1.block_on(async {
+                let state = child.0.read().await;
+                state.parent.as_ref().unwrap().peer_summary.is_some()
+            })
+        });
+
+        send(&child, &[0x42; 32]);
+        send(&child, b"ab");
+        send(&child, b"ba");
+        send(&child, b"abc");
+
+        let mut received = Vec::new();
+        wait_for(|| {
+            received.extend(receive(&parent));
+            received.len() > 1
+        });
+        assert_eq!(received, [b"ab".to_vec(), b"abc".to_vec()]);
+
+        let stats = child
+            .1
+            .block_on(async { child.0.read().await.link_stats() });
+        assert_eq!(stats.len(), 1);
+        let (link, stats) = &stats[0];
+        assert_eq!(link, "parent");
+        assert_eq!(stats.sent_msgs, 2);
+        assert_eq!(stats.dropped_oversized, 1);
+        assert_eq!(stats.skipped_not_novel, 1);
+        assert!(stats.batches >= 1);
+    }
+}
@@ -1,6 +1,6 @@
 //! A very simple event manager, that just supports log outputs, but no multiprocessing
 
-use alloc::vec::Vec;
+use alloc::{string::ToString, vec::Vec};
 #[cfg(feature = "std")]
 use core::sync::atomic::{Ordering, compiler_fence};
 use core::{fmt::Debug, marker::PhantomData, time::Duration};
@@ -32,7 +32,8 @@ use crate::{
     Error, HasMetadata,
     events::{
         BrokerEventResult, Event, EventFirer, EventManagerId, EventReceiver, EventRestarter,
-        HasEventManagerId, SendExiting, std_maybe_report_progress, std_report_progress,
+        HasEventManagerId, LogSeverity, SendExiting, std_maybe_report_progress,
+        std_report_progress,
     },
     monitors::{Monitor, stats::ClientStatsManager},
     state::{
@@ -213,6 +214,17 @@ where
         client_stats_manager.update_client_stats_for(ClientId(0), |client_stat| {
             client_stat.update_executions(stats.executions, stats.time);
         })?;
+        if let Some(change) = client_stats_manager.update_history() {
+            let log = EventWithStats::new(
+                Event::Log {
+                    severity_level: LogSeverity::Warn,
+                    message: change.to_string(),
+                    phantom: PhantomData,
+                },
+                stats.clone(),
+            );
+            Self::handle_in_broker(monitor, client_stats_manager, &log)?;
+        }
 
         let event = event.event();
         match event {
@@ -267,6 +279,10 @@ where
                 Ok(BrokerEventResult::Handled)
             }
             Event::Stop => Ok(BrokerEventResult::Forward),
+            // There are no other clients to control
+            Event::Pause | Event::Resume | Event::Checkpoint | Event::ControlAck { .. } => {
+                Ok(BrokerEventResult::Handled)
+            }
         }
     }
 }
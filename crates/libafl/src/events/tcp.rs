@@ -1,6 +1,6 @@
 //! TCP-backed event manager for scalable multi-processed fuzzing
 
-use alloc::{sync::Arc, vec::Vec};
+use alloc::{string::ToString, sync::Arc, vec::Vec};
 use core::{
     marker::PhantomData,
     net::SocketAddr,
@@ -22,12 +22,15 @@ use libafl_bolts::os::startable_self;
 use libafl_bolts::os::unix_signals::setup_signal_handler;
 #[cfg(all(feature = "fork", unix))]
 use libafl_bolts::os::{ForkResult, fork};
+#[cfg(feature = "tls")]
+use libafl_bolts::tls::TlsConfig;
 use libafl_bolts::{
     ClientId,
     core_affinity::CoreId,
     os::CTRL_C_EXIT,
     shmem::{ShMem, ShMemProvider, StdShMem, StdShMemProvider},
     staterestore::StateRestorer,
+    tls::MaybeTlsStream,
     tuples::tuple_list,
 };
 use serde::{Serialize, de::DeserializeOwned};
@@ -44,9 +47,10 @@ use crate::events::EVENTMGR_SIGHANDLER_STATE;
 use crate::{
     Error, HasMetadata,
     events::{
-        BrokerEventResult, Event, EventConfig, EventFirer, EventManagerHooksTuple, EventManagerId,
-        EventReceiver, EventRestarter, EventWithStats, HasEventManagerId, ProgressReporter,
-        std_on_restart,
+        BrokerEventResult, ClientControl, ControlCommand, ControlFile, Event, EventConfig,
+        EventFirer, EventManagerHooksTuple, EventManagerId, EventReceiver, EventRestarter,
+        EventWithStats, HasEventManagerId, LogSeverity, ProgressReporter, async_stream,
+        control::CONTROL_POLL_INTERVAL, std_on_restart,
     },
     inputs::Input,
     monitors::{Monitor, stats::ClientStatsManager},
@@ -78,6 +82,11 @@ where
     /// Amount of all clients ever, after which (when all are disconnected) this broker should quit.
     exit_cleanly_after: Option<NonZeroUsize>,
     client_stats_manager: ClientStatsManager,
+    /// Authenticates clients and secures their connections, if set
+    #[cfg(feature = "tls")]
+    tls_config: Option<TlsConfig>,
+    /// Broadcasts control commands to the clients, if set
+    control: Option<ControlFile>,
     phantom: PhantomData<I>,
 }
 
@@ -102,6 +111,9 @@ where
             listener: Some(listener),
             monitor,
             client_stats_manager: ClientStatsManager::default(),
+            #[cfg(feature = "tls")]
+            tls_config: None,
+            control: None,
             phantom: PhantomData,
             exit_cleanly_after: None,
         }
@@ -112,6 +124,39 @@ where
         self.exit_cleanly_after = Some(n_clients);
     }
 
+    /// Only accept clients over TLS, presenting one of the pinned certificates.
+    /// Clients need the same setting, see [`TcpEventManagerBuilder::tls`].
+    #[cfg(feature = "tls")]
+    pub fn set_tls_config(&mut self, tls_config: TlsConfig) {
+        self.tls_config = Some(tls_config);
+    }
+
+    /// Broadcast the commands written to the control file to all clients, see [`crate::events::control`]
+    pub fn set_control_file(&mut self, control: ControlFile) {
+        self.control = Some(control);
+    }
+
+    /// Sends the new commands of the control file, if any, to all clients
+    fn broadcast_control(&mut self, tx_bc: &broadcast::Sender<Vec<u8>>) -> Result<(), Error> {
+        let Some(control) = &mut self.control else {
+            return Ok(());
+        };
+        for command in control.poll()? {
+            let event = EventWithStats::<I>::with_current_time(command.to_event(), 0);
+            let serialized = postcard::to_allocvec(&event)?;
+            #[cfg(feature = "tcp_compression")]
+            let serialized = GzipCompressor::new().compress(&serialized);
+
+            // Sent by the broker itself, so it reaches all clients
+            let mut buf = UNDEFINED_CLIENT_ID.0.to_le_bytes().to_vec();
+            buf.extend_from_slice(&serialized);
+            if tx_bc.send(buf).is_err() {
+                log::warn!("Control: no clients to send {command} to");
+            }
+        }
+        Ok(())
+    }
+
     /// Run in the broker until all clients exit
     // TODO: remove expect(clippy::needless_return) when clippy is fixed
     #[tokio::main(flavor = "current_thread")]
@@ -127,6 +172,8 @@ where
             .take()
             .ok_or_else(|| Error::illegal_state("Listener has already been used / was none"))?;
         let listener = tokio::net::TcpListener::from_std(listener)?;
+        #[cfg(feature = "tls")]
+        let tls_config = self.tls_config.clone();
 
         let tokio_broker = spawn(async move {
             let mut recv_handles: Vec<JoinHandle<_>> = vec![];
@@ -142,7 +189,21 @@ where
                 }
 
                 // Asynchronously wait for an inbound socket.
-                let (socket, _) = listener.accept().await.expect("Accept failed");
+                let (socket, addr) = listener.accept().await.expect("Accept failed");
+                #[cfg(feature = "tls")]
+                let socket = match &tls_config {
+                    Some(tls_config) => match async_stream::tls_accept(tls_config, socket).await {
+                        Ok(socket) => socket,
+                        Err(e) => {
+                            log::error!("Rejected client {addr}: {e}");
+                            continue;
+                        }
+                    },
+                    None => async_stream::plain(socket),
+                };
+                #[cfg(not(feature = "tls"))]
+                let socket = async_stream::plain(socket);
+                log::debug!("TCP Manager - New client {addr}");
                 let (mut read, mut write) = tokio::io::split(socket);
 
                 // Protocol: the new client communicate its old ClientId or -1 if new
@@ -278,8 +339,15 @@ where
             }*/
         });
 
+        let mut control_interval = tokio::time::interval(CONTROL_POLL_INTERVAL);
         loop {
-            let buf = rx_mpsc.recv().await.expect("Could not receive");
+            let buf = tokio::select! {
+                buf = rx_mpsc.recv() => buf.expect("Could not receive"),
+                _ = control_interval.tick(), if self.control.is_some() => {
+                    self.broadcast_control(&tx_bc)?;
+                    continue;
+                }
+            };
 
             // read client ID.
             let mut client_id_buf = [0_u8; 4];
@@ -294,12 +362,19 @@ where
             let event_bytes = &GzipCompressor::new().decompress(event_bytes)?;
 
             let event: EventWithStats<I> = postcard::from_bytes(event_bytes)?;
-            match Self::handle_in_broker(
-                &mut self.monitor,
-                &mut self.client_stats_manager,
-                client_id,
-                &event,
-            )? {
+            let result = match (event.event(), &mut self.control) {
+                (Event::ControlAck { command }, Some(control)) => {
+                    control.acknowledge(client_id, *command)?;
+                    BrokerEventResult::Handled
+                }
+                _ => Self::handle_in_broker(
+                    &mut self.monitor,
+                    &mut self.client_stats_manager,
+                    client_id,
+                    &event,
+                )?,
+            };
+            match result {
                 BrokerEventResult::Forward => {
                     tx_bc.send(buf).expect("Could not send");
                 }
@@ -329,6 +404,17 @@ where
         client_stats_manager.update_client_stats_for(client_id, |client_stat| {
             client_stat.update_executions(stats.executions, stats.time);
         })?;
+        if let Some(change) = client_stats_manager.update_history() {
+            let log = EventWithStats::new(
+                Event::Log {
+                    severity_level: LogSeverity::Warn,
+                    message: change.to_string(),
+                    phantom: PhantomData,
+                },
+                stats.clone(),
+            );
+            Self::handle_in_broker(monitor, client_stats_manager, client_id, &log)?;
+        }
 
         let event = event.event();
         match event {
@@ -401,8 +487,13 @@ where
                 log::log!((*severity_level).into(), "{message}");
                 Ok(BrokerEventResult::Handled)
             }
-            Event::Stop => Ok(BrokerEventResult::Forward),
-            //_ => Ok(BrokerEventResult::Forward),
+            Event::Stop | Event::Pause | Event::Resume | Event::Checkpoint => {
+                Ok(BrokerEventResult::Forward)
+            }
+            Event::ControlAck { command } => {
+                log::info!("Client {client_id:?} acknowledged {command}");
+                Ok(BrokerEventResult::Handled)
+            } //_ => Ok(BrokerEventResult::Forward),
         }
     }
 }
@@ -415,7 +506,7 @@ pub struct TcpEventManager<EMH, I, S> {
     last_sent: Duration,
     hooks: EMH,
     /// The TCP stream for inter process communication
-    tcp: TcpStream,
+    tcp: MaybeTlsStream,
     /// Our `CientId`
     client_id: ClientId,
     #[cfg(feature = "tcp_compression")]
@@ -424,6 +515,8 @@ pub struct TcpEventManager<EMH, I, S> {
     /// A node will not re-use the observer values sent over TCP
     /// from nodes with other configurations.
     configuration: EventConfig,
+    /// Pauses and resumes this client on commands of the broker
+    control: ClientControl<I>,
     phantom: PhantomData<(I, S)>,
 }
 
@@ -436,10 +529,13 @@ impl<I, S> TcpEventManager<(), I, S> {
 }
 
 /// Builder for `TcpEventManager`
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Clone)]
+#[cfg_attr(not(feature = "tls"), derive(Copy))]
 pub struct TcpEventManagerBuilder<EMH, I, S> {
     throttle: Option<Duration>,
     hooks: EMH,
+    #[cfg(feature = "tls")]
+    tls_config: Option<TlsConfig>,
     phantom: PhantomData<(I, S)>,
 }
 
@@ -456,6 +552,8 @@ impl<I, S> TcpEventManagerBuilder<(), I, S> {
         Self {
             throttle: None,
             hooks: (),
+            #[cfg(feature = "tls")]
+            tls_config: None,
             phantom: PhantomData,
         }
     }
@@ -466,6 +564,8 @@ impl<I, S> TcpEventManagerBuilder<(), I, S> {
         TcpEventManagerBuilder {
             throttle: self.throttle,
             hooks,
+            #[cfg(feature = "tls")]
+            tls_config: self.tls_config,
             phantom: PhantomData,
         }
     }
@@ -479,6 +579,14 @@ impl<EMH, I, S> TcpEventManagerBuilder<EMH, I, S> {
         self
     }
 
+    /// Connect to the broker over TLS, see [`TcpEventBroker::set_tls_config`]
+    #[cfg(feature = "tls")]
+    #[must_use]
+    pub fn tls(mut self, tls_config: TlsConfig) -> Self {
+        self.tls_config = Some(tls_config);
+        self
+    }
+
     /// Create a manager from a raw TCP client with hooks
     pub fn build_from_client<A: ToSocketAddrs>(
         self,
@@ -486,7 +594,14 @@ impl<EMH, I, S> TcpEventManagerBuilder<EMH, I, S> {
         client_id: ClientId,
         configuration: EventConfig,
     ) -> Result<TcpEventManager<EMH, I, S>, Error> {
-        let mut tcp = TcpStream::connect(addr)?;
+        let tcp = TcpStream::connect(addr)?;
+        #[cfg(feature = "tls")]
+        let mut tcp = match &self.tls_config {
+            Some(tls_config) => tls_config.connect(tcp)?,
+            None => MaybeTlsStream::from(tcp),
+        };
+        #[cfg(not(feature = "tls"))]
+        let mut tcp = MaybeTlsStream::from(tcp);
 
         let mut our_client_id_buf = client_id.0.to_le_bytes();
         tcp.write_all(&our_client_id_buf)
@@ -507,6 +622,7 @@ impl<EMH, I, S> TcpEventManagerBuilder<EMH, I, S> {
             #[cfg(feature = "tcp_compression")]
             compressor: GzipCompressor::new(),
             configuration,
+            control: ClientControl::new(),
             phantom: PhantomData,
         })
     }
@@ -600,7 +716,9 @@ where
         }
     }
 
-    fn fire(&mut self, _state: &mut S, event: EventWithStats<I>) -> Result<(), Error> {
+    fn fire(&mut self, state: &mut S, event: EventWithStats<I>) -> Result<(), Error> {
+        self.hooks.on_fire_all(state, self.client_id, &event)?;
+
         let serialized = postcard::to_allocvec(&event)?;
 
         #[cfg(feature = "tcp_compression")]
@@ -629,7 +747,7 @@ where
     }
 }
 
-impl<EMH, I, S> EventReceiver<I, S> for TcpEventManager<EMH, I, S>
+impl<EMH, I, S> TcpEventManager<EMH, I, S>
 where
     EMH: EventManagerHooksTuple<I, S>,
     S: HasExecutions
@@ -638,18 +756,37 @@ where
         + HasSolutions<I>
         + HasCurrentTestcase<I>
         + Stoppable,
-    I: DeserializeOwned,
+    I: DeserializeOwned + Serialize,
 {
-    fn try_receive(&mut self, state: &mut S) -> Result<Option<(EventWithStats<I>, bool)>, Error> {
+    /// Receives the next event, carrying out the control commands sent by the broker on the way.
+    /// `checkpoint` is called to save the state, before a command is acknowledged.
+    pub(crate) fn receive_with_checkpoint<F>(
+        &mut self,
+        state: &mut S,
+        mut checkpoint: F,
+    ) -> Result<Option<(EventWithStats<I>, bool)>, Error>
+    where
+        F: FnMut(&mut S) -> Result<(), Error>,
+    {
+        if let Some(deferred) = self.control.next_deferred() {
+            return Ok(Some(deferred));
+        }
+
         // TODO: Get around local event copy by moving handle_in_client
         let self_id = self.client_id;
         let mut len_buf = [0_u8; 4];
-        self.tcp.set_nonblocking(true).expect("set to non-blocking");
+        self.tcp
+            .tcp()
+            .set_nonblocking(true)
+            .expect("set to non-blocking");
         // read all pending messages
         loop {
             match self.tcp.read_exact(&mut len_buf) {
                 Ok(()) => {
-                    self.tcp.set_nonblocking(false).expect("set to blocking");
+                    self.tcp
+                        .tcp()
+                        .set_nonblocking(false)
+                        .expect("set to blocking");
                     let len = u32::from_le_bytes(len_buf);
                     let mut buf = vec![0_u8; 4_usize + len as usize];
                     self.tcp.read_exact(&mut buf)?;
@@ -659,7 +796,10 @@ where
 
                     let other_client_id = ClientId(u32::from_le_bytes(client_id_buf));
 
-                    self.tcp.set_nonblocking(true).expect("set to non-blocking");
+                    self.tcp
+                        .tcp()
+                        .set_nonblocking(true)
+                        .expect("set to non-blocking");
                     if self_id == other_client_id {
                         panic!("Own ID should never have been sent by the broker");
                     } else {
@@ -675,7 +815,22 @@ where
                         if !self.hooks.pre_receive_all(state, other_client_id, &event)? {
                             continue;
                         }
-                        match event.event() {
+                        if let Some(command) = ControlCommand::from_event(event.event()) {
+                            self.control.apply(state, command);
+                            if command.saves_state() {
+                                checkpoint(state)?;
+                            }
+                            let executions = *state.executions();
+                            self.fire_blocking(
+                                state,
+                                EventWithStats::with_current_time(
+                                    Event::ControlAck { command },
+                                    executions,
+                                ),
+                            )?;
+                            continue;
+                        }
+                        let received = match event.event() {
                             Event::NewTestcase {
                                 client_config,
                                 observers_buf,
@@ -685,19 +840,13 @@ where
                                 log::info!(
                                     "Received new Testcase from {other_client_id:?} ({client_config:?}, forward {forward_id:?})"
                                 );
-                                if client_config.match_with(&self.configuration)
-                                    && observers_buf.is_some()
-                                {
-                                    return Ok(Some((event, true)));
-                                }
-                                return Ok(Some((event, false)));
+                                let reuse = client_config.match_with(&self.configuration)
+                                    && observers_buf.is_some();
+                                (event, reuse)
                             }
                             Event::Objective { .. } => {
                                 log::info!("Received new Objective");
-                                return Ok(Some((event, false)));
-                            }
-                            Event::Stop => {
-                                state.request_stop();
+                                (event, false)
                             }
                             _ => {
                                 return Err(Error::unknown(format!(
@@ -705,22 +854,67 @@ where
                                     event.event().name()
                                 )));
                             }
+                        };
+                        if let Some(received) = self.control.pass(received) {
+                            return Ok(Some(received));
                         }
                     }
                 }
                 Err(e) if e.kind() == ErrorKind::WouldBlock => {
                     // no new data on the socket
-                    break;
+                    if !self.control.is_paused() {
+                        break;
+                    }
+                    if self.control.wait_paused() {
+                        let executions = *state.executions();
+                        self.fire_blocking(
+                            state,
+                            EventWithStats::with_current_time(Event::Heartbeat, executions),
+                        )?;
+                    }
                 }
                 Err(e) => {
                     panic!("Unexpected error {e:?}");
                 }
             }
         }
-        self.tcp.set_nonblocking(false).expect("set to blocking");
+        self.tcp
+            .tcp()
+            .set_nonblocking(false)
+            .expect("set to blocking");
         Ok(None)
     }
 
+    /// Fires an event while the socket is in non-blocking mode
+    fn fire_blocking(&mut self, state: &mut S, event: EventWithStats<I>) -> Result<(), Error> {
+        self.tcp
+            .tcp()
+            .set_nonblocking(false)
+            .expect("set to blocking");
+        self.fire(state, event)?;
+        self.tcp
+            .tcp()
+            .set_nonblocking(true)
+            .expect("set to non-blocking");
+        Ok(())
+    }
+}
+
+impl<EMH, I, S> EventReceiver<I, S> for TcpEventManager<EMH, I, S>
+where
+    EMH: EventManagerHooksTuple<I, S>,
+    S: HasExecutions
+        + HasMetadata
+        + HasImported
+        + HasSolutions<I>
+        + HasCurrentTestcase<I>
+        + Stoppable,
+    I: DeserializeOwned + Serialize,
+{
+    fn try_receive(&mut self, state: &mut S) -> Result<Option<(EventWithStats<I>, bool)>, Error> {
+        self.receive_with_checkpoint(state, |_| Ok(()))
+    }
+
     fn on_interesting(&mut self, _state: &mut S, _event: EventWithStats<I>) -> Result<(), Error> {
         Ok(())
     }
@@ -878,18 +1072,30 @@ where
 impl<EMH, I, S, SHM, SP> EventReceiver<I, S> for TcpRestartingEventManager<EMH, I, S, SHM, SP>
 where
     EMH: EventManagerHooksTuple<I, S>,
-    I: DeserializeOwned,
+    I: DeserializeOwned + Serialize,
     S: HasExecutions
         + HasMetadata
         + HasImported
         + HasSolutions<I>
         + HasCurrentTestcase<I>
-        + Stoppable,
+        + Stoppable
+        + Serialize,
     SHM: ShMem,
     SP: ShMemProvider<ShMem = SHM>,
 {
     fn try_receive(&mut self, state: &mut S) -> Result<Option<(EventWithStats<I>, bool)>, Error> {
-        self.tcp_mgr.try_receive(state)
+        // Save the state on control commands, so that the campaign can be restarted from there
+        let staterestorer = &mut self.staterestorer;
+        let save_state = self.save_state;
+        let client_id = self.tcp_mgr.client_id;
+        self.tcp_mgr.receive_with_checkpoint(state, |state| {
+            staterestorer.reset();
+            staterestorer.save(&if save_state {
+                Some((&*state, client_id))
+            } else {
+                None
+            })
+        })
     }
 
     fn on_interesting(&mut self, state: &mut S, event: EventWithStats<I>) -> Result<(), Error> {
@@ -0,0 +1,461 @@
+//! A [`BatchedForkserverExecutor`] drives several forkservers at once, to run batches of inputs concurrently.
+//!
+//! This is useful for slow, I/O-bound targets: while one child blocks or sleeps, the others keep running.
+
+use alloc::{collections::VecDeque, string::ToString, vec::Vec};
+use core::{
+    fmt::{self, Debug, Formatter},
+    time::Duration,
+};
+use std::time::Instant;
+
+use libafl_bolts::{
+    AsSlice, AsSliceMut, StdTargetArgs,
+    shmem::{ShMem, ShMemProvider, UnixShMemProvider},
+    tuples::{Handle, MatchNameRef, RefIndexable},
+};
+use nix::sys::time::TimeSpec;
+
+use super::{
+    BatchExecutor, HasTimeout,
+    forkserver::{ForkserverExecutor, ForkserverExecutorBuilder, SHM_ENV_VAR},
+};
+use crate::{
+    Error,
+    executors::{Executor, ExitKind, HasObservers},
+    inputs::ToTargetBytes,
+    observers::ObserversTuple,
+    state::HasExecutions,
+};
+
+/// A forkserver, together with the coverage map its children write to
+struct ForkserverSlot<I, S, SHM, CM> {
+    executor: ForkserverExecutor<I, (), S, SHM>,
+    coverage_map: CM,
+}
+
+/// The result of a dispatched run, waiting to be picked up by [`Executor::run_target`]
+#[derive(Debug)]
+struct PendingRun {
+    target_bytes: Vec<u8>,
+    slot: usize,
+    exit_kind: ExitKind,
+}
+
+/// An [`Executor`] managing `N` forkservers, each with its own input shared memory and coverage map.
+///
+/// Batches of inputs handed to [`BatchExecutor::dispatch_batch`] run concurrently, one per forkserver.
+/// The results are merged back into the observers in order, when the fuzzer evaluates the same inputs with
+/// [`Executor::run_target`] afterwards, e.g., in a [`crate::stages::BatchedMultiMutationalStage`].
+/// Inputs that were not dispatched beforehand simply run on the first forkserver.
+/// `SHM` is the input shared memory of the forkservers, `CM` their coverage maps.
+pub struct BatchedForkserverExecutor<I, O, OT, S, SHM, CM> {
+    slots: Vec<ForkserverSlot<I, S, SHM, CM>>,
+    observers: OT,
+    map_observer: Handle<O>,
+    pending: VecDeque<PendingRun>,
+}
+
+impl<I, O, OT, S, SHM, CM> Debug for BatchedForkserverExecutor<I, O, OT, S, SHM, CM>
+where
+    OT: Debug,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BatchedForkserverExecutor")
+            .field("batch_size", &self.slots.len())
+            .field("observers", &self.observers)
+            .field("map_observer", &self.map_observer)
+            .field("pending", &self.pending)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<I, O, OT, S, SHM, CM> BatchedForkserverExecutor<I, O, OT, S, SHM, CM>
+where
+    SHM: ShMem,
+    CM: ShMem,
+{
+    /// Creates a new [`BatchedForkserverExecutor`] running up to `batch_size` inputs at once.
+    ///
+    /// For each forkserver, a coverage map of `map_size` bytes is allocated with the `shmem_provider`,
+    /// then `build_slot` gets called with a [`ForkserverExecutorBuilder`] passing this map as `__AFL_SHM_ID`
+    /// in the env of the target. It should configure the target and build the [`ForkserverExecutor`], without any observers.
+    /// Use [`ForkserverExecutorBuilder::shmem_provider`] to give each forkserver its own input shared memory.
+    /// After each run, the coverage is copied into the `map_observer`, which has to be part of the `observers`.
+    pub fn new<F, SP>(
+        shmem_provider: &mut SP,
+        observers: OT,
+        map_observer: Handle<O>,
+        batch_size: usize,
+        map_size: usize,
+        mut build_slot: F,
+    ) -> Result<Self, Error>
+    where
+        F: FnMut(
+            ForkserverExecutorBuilder<'static, UnixShMemProvider>,
+        ) -> Result<ForkserverExecutor<I, (), S, SHM>, Error>,
+        SP: ShMemProvider<ShMem = CM>,
+    {
+        if batch_size == 0 {
+            return Err(Error::illegal_argument(
+                "BatchedForkserverExecutor needs at least one forkserver",
+            ));
+        }
+
+        let mut slots = Vec::with_capacity(batch_size);
+        for _ in 0..batch_size {
+            let coverage_map = shmem_provider.new_shmem(map_size)?;
+            // Each forkserver gets its own map in its env, ours stays untouched
+            let builder = ForkserverExecutorBuilder::new()
+                .coverage_map_size(map_size)
+                .env(SHM_ENV_VAR, coverage_map.id().to_string())
+                .env(
+                    format!("{SHM_ENV_VAR}_SIZE"),
+                    coverage_map.len().to_string(),
+                );
+            slots.push(ForkserverSlot {
+                executor: build_slot(builder)?,
+                coverage_map,
+            });
+        }
+
+        Ok(Self {
+            slots,
+            observers,
+            map_observer,
+            pending: VecDeque::new(),
+        })
+    }
+
+    /// The number of forkservers managed by this executor
+    #[must_use]
+    pub fn batch_size(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Get a reference to the [`ForkserverExecutor`] of the given slot.
+    #[must_use]
+    pub fn forkserver_executor(&self, slot: usize) -> &ForkserverExecutor<I, (), S, SHM> {
+        &self.slots[slot].executor
+    }
+}
+
+impl<I, O, OT, S, SHM, CM> BatchedForkserverExecutor<I, O, OT, S, SHM, CM>
+where
+    CM: ShMem,
+    O: for<'a> AsSliceMut<'a, Entry = u8>,
+    OT: ObserversTuple<I, S>,
+    SHM: ShMem,
+{
+    /// Copies the coverage of the given slot into the map observer
+    fn merge_coverage(&mut self, slot: usize) {
+        let coverage_map = self.slots[slot].coverage_map.as_slice();
+        let observer = self.observers.get_mut(&self.map_observer).unwrap();
+        let mut map = observer.as_slice_mut();
+        let len = map.len().min(coverage_map.len());
+        map[..len].copy_from_slice(&coverage_map[..len]);
+    }
+}
+
+impl<I, O, OT, S, SHM, CM, Z> BatchExecutor<I, S, Z>
+    for BatchedForkserverExecutor<I, O, OT, S, SHM, CM>
+where
+    CM: ShMem,
+    SHM: ShMem,
+    Z: ToTargetBytes<I>,
+{
+    fn max_batch_size(&self) -> usize {
+        self.slots.len()
+    }
+
+    fn dispatch_batch(
+        &mut self,
+        fuzzer: &mut Z,
+        _state: &mut S,
+        inputs: &[I],
+    ) -> Result<(), Error> {
+        if inputs.len() > self.slots.len() {
+            return Err(Error::illegal_argument(format!(
+                "Batch of {} inputs exceeds the {} forkservers of this executor",
+                inputs.len(),
+                self.slots.len()
+            )));
+        }
+        // Results nobody picked up are stale now
+        self.pending.clear();
+
+        let mut started = Vec::with_capacity(inputs.len());
+        for (slot, input) in self.slots.iter_mut().zip(inputs) {
+            let target_bytes = fuzzer.to_target_bytes(input).as_slice().to_vec();
+            slot.coverage_map.fill(0);
+            slot.executor.start_run(&target_bytes)?;
+            started.push((Instant::now(), target_bytes));
+        }
+
+        for (slot, (start_time, target_bytes)) in started.into_iter().enumerate() {
+            let timeout = self.slots[slot].executor.timeout();
+            let remaining = timeout.saturating_sub(start_time.elapsed());
+            let exit_kind = self.slots[slot]
+                .executor
+                .finish_run(&TimeSpec::from_duration(remaining))?;
+            self.pending.push_back(PendingRun {
+                target_bytes,
+                slot,
+                exit_kind,
+            });
+        }
+        Ok(())
+    }
+}
+
+impl<EM, I, O, OT, S, SHM, CM, Z> Executor<EM, I, S, Z>
+    for BatchedForkserverExecutor<I, O, OT, S, SHM, CM>
+where
+    CM: ShMem,
+    O: for<'a> AsSliceMut<'a, Entry = u8>,
+    OT: ObserversTuple<I, S>,
+    S: HasExecutions,
+    SHM: ShMem,
+    Z: ToTargetBytes<I>,
+{
+    fn run_target(
+        &mut self,
+        fuzzer: &mut Z,
+        state: &mut S,
+        _mgr: &mut EM,
+        input: &I,
+    ) -> Result<ExitKind, Error> {
+        let target_bytes = fuzzer.to_target_bytes(input);
+        self.observers_mut().pre_exec_child_all(state, input)?;
+        *state.executions_mut() += 1;
+
+        // Skip over dispatched inputs the fuzzer did not evaluate, e.g. because they were filtered
+        while self
+            .pending
+            .front()
+            .is_some_and(|run| run.target_bytes.as_slice() != target_bytes.as_slice())
+        {
+            self.pending.pop_front();
+        }
+
+        let (slot, exit_kind) = if let Some(run) = self.pending.pop_front() {
+            (run.slot, run.exit_kind)
+        } else {
+            // Not dispatched as part of a batch, run it on the first forkserver
+            self.pending.clear();
+            let first = &mut self.slots[0];
+            first.coverage_map.fill(0);
+            first.executor.start_run(target_bytes.as_slice())?;
+            let timeout = first.executor.timeout();
+            (
+                0,
+                first
+                    .executor
+                    .finish_run(&TimeSpec::from_duration(timeout))?,
+            )
+        };
+
+        self.merge_coverage(slot);
+        self.observers_mut()
+            .post_exec_child_all(state, input, &exit_kind)?;
+        Ok(exit_kind)
+    }
+}
+
+impl<I, O, OT, S, SHM, CM> HasTimeout for BatchedForkserverExecutor<I, O, OT, S, SHM, CM> {
+    #[inline]
+    fn timeout(&self) -> Duration {
+        self.slots[0].executor.timeout()
+    }
+
+    #[inline]
+    fn set_timeout(&mut self, timeout: Duration) {
+        for slot in &mut self.slots {
+            slot.executor.set_timeout(timeout);
+        }
+    }
+}
+
+impl<I, O, OT, S, SHM, CM> HasObservers for BatchedForkserverExecutor<I, O, OT, S, SHM, CM>
+where
+    OT: ObserversTuple<I, S>,
+{
+    type Observers = OT;
+
+    #[inline]
+    fn observers(&self) -> RefIndexable<&Self::Observers, Self::Observers> {
+        RefIndexable::from(&self.observers)
+    }
+
+    #[inline]
+    fn observers_mut(&mut self) -> RefIndexable<&mut Self::Observers, Self::Observers> {
+        RefIndexable::from(&mut self.observers)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{vec, vec::Vec};
+    use core::time::Duration;
+    use std::{env, fs, process::Command, time::Instant};
+
+    use libafl_bolts::{
+        AsSlice, StdTargetArgs,
+        shmem::{ShMemProvider, UnixShMem, UnixShMemProvider},
+        tuples::{Handle, Handled, tuple_list},
+    };
+    use serial_test::serial;
+
+    use super::BatchedForkserverExecutor;
+    use crate::{
+        executors::{
+            BatchExecutor, Executor, ExitKind, HasObservers, StdChildArgs,
+            forkserver::{ForkserverExecutor, ForkserverExecutorBuilder, SHM_ENV_VAR},
+        },
+        inputs::{BytesInput, NopToTargetBytes},
+        observers::StdMapObserver,
+        state::NopState,
+    };
+
+    /// A minimal target speaking the old forkserver protocol.
+    /// Each input starts with the map index the child marks, followed by what it should do.
+    const TARGET: &str = r#"
+#include <signal.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/shm.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+int main(int argc, char **argv) {
+    int hello = 0;
+    if (write(199, &hello, 4) != 4) return 1;
+    unsigned char *map = shmat(atoi(getenv("__AFL_SHM_ID")), NULL, 0);
+    for (;;) {
+        int was_killed, status;
+        if (read(198, &was_killed, 4) != 4) return 0;
+        pid_t pid = fork();
+        if (pid == 0) {
+            char buf[64] = {0};
+            FILE *f = fopen(argv[1], "rb");
+            fread(buf, 1, sizeof(buf) - 1, f);
+            fclose(f);
+            map[buf[0] - '0'] = 1;
+            usleep(300000);
+            if (!strcmp(buf + 1, "crash")) abort();
+            if (!strcmp(buf + 1, "hang")) pause();
+            _exit(0);
+        }
+        write(199, &pid, 4);
+        waitpid(pid, &status, 0);
+        write(199, &status, 4);
+    }
+}
+"#;
+
+    #[test]
+    fn test_empty_batch() {
+        let map_observer: Handle<StdMapObserver<'static, u8, false>> = Handle::new("edges".into());
+        let executor = BatchedForkserverExecutor::<BytesInput, _, (), (), UnixShMem, _>::new(
+            &mut UnixShMemProvider::new().unwrap(),
+            (),
+            map_observer,
+            0,
+            65536,
+            |_: ForkserverExecutorBuilder<'static, UnixShMemProvider>| -> Result<
+                ForkserverExecutor<BytesInput, (), (), UnixShMem>,
+                _,
+            > { unreachable!() },
+        );
+        assert!(executor.is_err());
+    }
+
+    #[test]
+    #[serial]
+    #[cfg_attr(miri, ignore)]
+    fn test_batch() {
+        const MAP_SIZE: usize = 64;
+
+        let dir = env::temp_dir().join(format!("libafl_batched_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let source = dir.join("target.c");
+        let target = dir.join("target");
+        fs::write(&source, TARGET).unwrap();
+        let compiled = Command::new("cc")
+            .arg(&source)
+            .arg("-o")
+            .arg(&target)
+            .status()
+            .is_ok_and(|status| status.success());
+        if !compiled {
+            log::warn!("No working C compiler, skipping test_batch");
+            return;
+        }
+
+        let edges = StdMapObserver::owned("edges", vec![0_u8; MAP_SIZE]);
+        let edges_handle = edges.handle();
+        let shm_env = env::var(SHM_ENV_VAR).ok();
+        let mut executor = BatchedForkserverExecutor::new(
+            &mut UnixShMemProvider::new().unwrap(),
+            tuple_list!(edges),
+            edges_handle.clone(),
+            4,
+            MAP_SIZE,
+            |builder: ForkserverExecutorBuilder<'static, UnixShMemProvider>| {
+                builder
+                    .program(&target)
+                    .arg_input_file_std()
+                    .timeout(Duration::from_secs(2))
+                    .build(())
+            },
+        )
+        .unwrap();
+        assert_eq!(executor.batch_size(), 4);
+        // The maps are passed to the forkservers in their own env
+        assert_eq!(env::var(SHM_ENV_VAR).ok(), shm_env);
+
+        let inputs = ["0ok", "1crash", "2hang", "3ok"]
+            .map(|input| BytesInput::new(input.as_bytes().to_vec()));
+        let mut fuzzer = NopToTargetBytes::new();
+        let mut state = NopState::<BytesInput>::new();
+
+        // The children sleep, then the third one hangs until the timeout: run concurrently,
+        // the whole batch takes about one timeout instead of their sum.
+        let start = Instant::now();
+        executor
+            .dispatch_batch(&mut fuzzer, &mut state, &inputs)
+            .unwrap();
+        assert!(start.elapsed() < Duration::from_secs(4));
+
+        let mut run = |executor: &mut BatchedForkserverExecutor<_, _, _, _, _, _>, input| {
+            let exit_kind = Executor::<(), _, _, _>::run_target(
+                executor,
+                &mut fuzzer,
+                &mut state,
+                &mut (),
+                input,
+            )
+            .unwrap();
+            let edges = executor.observers()[&edges_handle]
+                .as_slice()
+                .iter()
+                .enumerate()
+                .filter(|(_, hit)| **hit != 0)
+                .map(|(idx, _)| idx)
+                .collect::<Vec<_>>();
+            (exit_kind, edges)
+        };
+
+        // The results come back in input order, each with the coverage of its own child
+        assert_eq!(run(&mut executor, &inputs[0]), (ExitKind::Ok, vec![0]));
+        assert_eq!(run(&mut executor, &inputs[1]), (ExitKind::Crash, vec![1]));
+        // Skipping an input drops its result, the next one still matches
+        assert_eq!(run(&mut executor, &inputs[3]), (ExitKind::Ok, vec![3]));
+        // Inputs that were not dispatched run on their own
+        assert_eq!(run(&mut executor, &inputs[2]), (ExitKind::Timeout, vec![2]));
+
+        drop(executor);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
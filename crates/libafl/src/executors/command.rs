@@ -1,9 +1,15 @@
 //! The command executor executes a sub program for each run
+//!
+//! On unix, inputs can optionally be delivered via shared memory (see `CommandExecutorBuilder::build_with_shmem_provider`),
+//! and a long-lived child can be reused for many runs in persistent mode (see `PersistentChild`).
 #[cfg(all(feature = "intel_pt", target_os = "linux"))]
 use alloc::ffi::CString;
 #[cfg(not(unix))]
 use alloc::string::{String, ToString};
-#[cfg(all(feature = "intel_pt", target_os = "linux"))]
+#[cfg(any(
+    all(unix, feature = "fork"),
+    all(feature = "intel_pt", target_os = "linux")
+))]
 use alloc::vec::Vec;
 #[cfg(all(feature = "intel_pt", target_os = "linux"))]
 use core::ffi::CStr;
@@ -17,29 +23,55 @@ use core::{
 use std::ffi::OsStr;
 #[cfg(not(unix))]
 use std::ffi::OsString;
-#[cfg(all(feature = "intel_pt", target_os = "linux"))]
+#[cfg(any(
+    all(unix, feature = "fork"),
+    all(feature = "intel_pt", target_os = "linux")
+))]
 use std::os::fd::AsRawFd;
+#[cfg(all(unix, feature = "fork"))]
+use std::os::fd::BorrowedFd;
 #[cfg(unix)]
 use std::os::{fd::RawFd, unix::ffi::OsStrExt};
+#[cfg(all(unix, feature = "fork"))]
+use std::time::Instant;
 use std::{
     io::{Read, Write},
     process::{Child, Command, Stdio},
 };
 
+#[cfg(any(
+    all(unix, feature = "fork"),
+    all(feature = "intel_pt", target_os = "linux")
+))]
+use libafl_bolts::core_affinity::CoreId;
+#[cfg(all(feature = "intel_pt", target_os = "linux"))]
+use libafl_bolts::os::dup2;
 #[cfg(unix)]
 use libafl_bolts::{AsSlice, tuples::MatchNameRef};
 use libafl_bolts::{
     InputLocation, StdTargetArgs, StdTargetArgsInner,
     ownedref::OwnedSlice,
+    shmem::{ShMem, StdShMem},
     tuples::{Handle, MatchName, RefIndexable},
 };
-#[cfg(all(feature = "intel_pt", target_os = "linux"))]
-use libafl_bolts::{core_affinity::CoreId, os::dup2};
+#[cfg(all(unix, feature = "fork"))]
+use libafl_bolts::{os::pipes::Pipe, shmem::ShMemProvider};
 #[cfg(all(feature = "intel_pt", target_os = "linux"))]
 use libc::STDIN_FILENO;
+#[cfg(any(target_os = "linux", all(unix, feature = "fork")))]
+use nix::errno::Errno;
+#[cfg(all(unix, feature = "fork"))]
+use nix::{
+    fcntl::{FcntlArg, OFlag, fcntl},
+    sys::{
+        select::{FdSet, pselect},
+        signal::SigSet,
+        time::TimeSpec,
+    },
+    unistd::write,
+};
 #[cfg(target_os = "linux")]
 use nix::{
-    errno::Errno,
     sys::{
         ptrace,
         signal::Signal,
@@ -55,11 +87,20 @@ use nix::{
 #[cfg(all(feature = "intel_pt", target_os = "linux"))]
 use typed_builder::TypedBuilder;
 
+#[cfg(target_os = "linux")]
+use super::crash_context::CrashContextCapture;
 #[cfg(all(target_family = "unix", feature = "fork"))]
-use super::forkserver::ConfigTarget;
+use super::forkserver::{
+    ConfigTarget, FORKSRV_FD, MAX_INPUT_SIZE_DEFAULT, SHM_FUZZ_ENV_VAR, SHM_FUZZ_MAP_SIZE_ENV_VAR,
+    SHMEM_FUZZ_HDR_SIZE,
+};
+#[cfg(unix)]
+use super::resource_limits::{ResourceLimiter, ResourceLimits};
 use super::{HasTimeout, StdChildArgs, StdChildArgsInner};
 #[cfg(target_os = "linux")]
 use crate::executors::hooks::ExecutorHooksTuple;
+#[cfg(unix)]
+use crate::observers::{ProcessExit, ProcessExitObserver};
 use crate::{
     Error,
     executors::{Executor, ExitKind, HasObservers},
@@ -114,10 +155,10 @@ impl StdCommandCaptureMethod {
 }
 
 /// A simple Configurator that takes the most common parameters
-/// Writes the input either to stdio or to a file
+/// Writes the input either to stdio, to a file, or to a shared memory map
 /// Use [`CommandExecutor::builder()`] to use this configurator.
 #[derive(Debug)]
-pub struct StdCommandConfigurator {
+pub struct StdCommandConfigurator<SHM = StdShMem> {
     /// If set to true, the child output will remain visible
     /// By default, the child output is hidden to increase execution speed
     debug_child: bool,
@@ -126,35 +167,93 @@ pub struct StdCommandConfigurator {
     timeout: Duration,
     /// true: input gets delivered via stdin
     input_location: InputLocation,
+    /// If set, the input gets delivered via this shared memory map instead of the `input_location`
+    input_shmem: Option<SHM>,
+    /// Core to bind freshly spawned persistent children to
+    #[cfg(all(unix, feature = "fork"))]
+    core: Option<CoreId>,
+    /// If set, the target is kept alive and fed with inputs using the [`PersistentChild`] protocol
+    #[cfg(all(unix, feature = "fork"))]
+    persistent: bool,
+    /// The currently running persistent child, if any
+    #[cfg(all(unix, feature = "fork"))]
+    persistent_child: Option<PersistentChild>,
+    /// The exit status of the persistent child that died during the last run, if any
+    #[cfg(all(unix, feature = "fork"))]
+    persistent_exit: Option<std::process::ExitStatus>,
+    /// Enforces the resource limits on each child
+    #[cfg(unix)]
+    resource_limiter: Option<ResourceLimiter>,
+    /// Collects the context of crashed children
+    #[cfg(target_os = "linux")]
+    crash_context: Option<CrashContextCapture>,
     /// The Command to execute
     command: Command,
 }
 
-impl CommandConfigurator<Child> for StdCommandConfigurator {
-    fn spawn_child(&mut self, target_bytes: OwnedSlice<'_, u8>) -> Result<Child, Error> {
+impl<SHM> StdCommandConfigurator<SHM> {
+    /// Creates a fresh [`Command`] from the configured program, environment, working directory and output capture.
+    /// The caller is responsible for adding the program arguments.
+    fn fresh_command(&self) -> Command {
         let mut cmd = Command::new(self.command.get_program());
+
+        if self.debug_child {
+            cmd.stdout(Stdio::inherit());
+        } else if let Some(cap) = &self.stdout_cap {
+            cap.pre_capture(&mut cmd, true);
+        } else {
+            cmd.stdout(Stdio::null());
+        }
+
+        if self.debug_child {
+            cmd.stderr(Stdio::inherit());
+        } else if let Some(cap) = &self.stderr_cap {
+            cap.pre_capture(&mut cmd, false);
+        } else {
+            cmd.stderr(Stdio::null());
+        }
+
+        cmd.envs(
+            self.command
+                .get_envs()
+                .filter_map(|(key, value)| value.map(|value| (key, value))),
+        );
+        if let Some(cwd) = self.command.get_current_dir() {
+            cmd.current_dir(cwd);
+        }
+        #[cfg(unix)]
+        if let Some(resource_limiter) = &self.resource_limiter {
+            resource_limiter.configure_command(&mut cmd);
+        }
+        #[cfg(target_os = "linux")]
+        if let Some(crash_context) = &self.crash_context {
+            crash_context.configure_command(&mut cmd);
+        }
+        cmd
+    }
+}
+
+impl<SHM> CommandConfigurator<Child> for StdCommandConfigurator<SHM>
+where
+    SHM: ShMem,
+{
+    fn spawn_child(&mut self, target_bytes: OwnedSlice<'_, u8>) -> Result<Child, Error> {
+        if let Some(shmem) = &mut self.input_shmem {
+            #[cfg(all(unix, feature = "fork"))]
+            write_input_to_shmem(shmem, &target_bytes);
+            #[cfg(not(all(unix, feature = "fork")))]
+            let _ = shmem;
+            return Ok(self.command.spawn()?);
+        }
+
         match &mut self.input_location {
             InputLocation::Arg { argnum } => {
+                let argnum = *argnum;
+                let mut cmd = self.fresh_command();
                 let args = self.command.get_args();
 
-                if self.debug_child {
-                    cmd.stdout(Stdio::inherit());
-                } else if let Some(cap) = &self.stdout_cap {
-                    cap.pre_capture(&mut cmd, true);
-                } else {
-                    cmd.stdout(Stdio::null());
-                }
-
-                if self.debug_child {
-                    cmd.stderr(Stdio::inherit());
-                } else if let Some(cap) = &self.stderr_cap {
-                    cap.pre_capture(&mut cmd, false);
-                } else {
-                    cmd.stderr(Stdio::null());
-                }
-
                 for (i, arg) in args.enumerate() {
-                    if i == *argnum {
+                    if i == argnum {
                         debug_assert_eq!(arg, "PLACEHOLDER");
                         #[cfg(unix)]
                         cmd.arg(OsStr::from_bytes(target_bytes.as_slice()));
@@ -168,14 +267,6 @@ impl CommandConfigurator<Child> for StdCommandConfigurator {
                         cmd.arg(arg);
                     }
                 }
-                cmd.envs(
-                    self.command
-                        .get_envs()
-                        .filter_map(|(key, value)| value.map(|value| (key, value))),
-                );
-                if let Some(cwd) = self.command.get_current_dir() {
-                    cmd.current_dir(cwd);
-                }
                 Ok(cmd.spawn()?)
             }
             InputLocation::StdIn { input_file: _ } => {
@@ -205,6 +296,62 @@ impl CommandConfigurator<Child> for StdCommandConfigurator {
         }
     }
 
+    #[cfg(all(unix, feature = "fork"))]
+    fn run_persistent(&mut self, target_bytes: &[u8]) -> Result<Option<ExitKind>, Error> {
+        if !self.persistent {
+            return Ok(None);
+        }
+
+        if self.persistent_child.is_none() {
+            let mut cmd = self.fresh_command();
+            cmd.args(self.command.get_args()).stdin(Stdio::null());
+            if let Some(core) = self.core {
+                cmd.bind(core);
+            }
+            self.persistent_child = Some(PersistentChild::spawn(cmd)?);
+        }
+        // # Safety
+        // We just made sure a child is running.
+        let child = unsafe { self.persistent_child.as_mut().unwrap_unchecked() };
+
+        let status = if let Some(shmem) = &mut self.input_shmem {
+            let input_len = write_input_to_shmem(shmem, target_bytes);
+            child.run(input_len, None, self.timeout)?
+        } else {
+            child.run(target_bytes.len(), Some(target_bytes), self.timeout)?
+        };
+
+        let exit_kind = match status {
+            PersistentStatus::Reported(0) => ExitKind::Ok,
+            PersistentStatus::Reported(_) => ExitKind::Crash,
+            PersistentStatus::Exited(status) => {
+                self.persistent_child = None;
+                self.persistent_exit = Some(status);
+                self.exit_kind_from_status(&status)
+            }
+            PersistentStatus::Timeout => {
+                self.persistent_child = None;
+                ExitKind::Timeout
+            }
+        };
+        Ok(Some(exit_kind))
+    }
+
+    #[cfg(all(unix, feature = "fork"))]
+    fn take_persistent_exit(&mut self) -> Option<std::process::ExitStatus> {
+        self.persistent_exit.take()
+    }
+
+    #[cfg(unix)]
+    fn resource_limiter_mut(&mut self) -> Option<&mut ResourceLimiter> {
+        self.resource_limiter.as_mut()
+    }
+
+    #[cfg(target_os = "linux")]
+    fn crash_context(&self) -> Option<&CrashContextCapture> {
+        self.crash_context.as_ref()
+    }
+
     fn exec_timeout(&self) -> Duration {
         self.timeout
     }
@@ -213,6 +360,243 @@ impl CommandConfigurator<Child> for StdCommandConfigurator {
     }
 }
 
+/// Writes the input to the shared memory map, prefixed by its length as native-endian `u32`, like `AFL++` does.
+///
+/// Inputs that do not fit into the map get truncated. Returns the number of input bytes written.
+#[cfg(all(unix, feature = "fork"))]
+fn write_input_to_shmem<SHM>(shmem: &mut SHM, input: &[u8]) -> usize
+where
+    SHM: ShMem,
+{
+    let input_len = input.len().min(shmem.len() - SHMEM_FUZZ_HDR_SIZE);
+    shmem[..SHMEM_FUZZ_HDR_SIZE].copy_from_slice(&(input_len as u32).to_ne_bytes());
+    shmem[SHMEM_FUZZ_HDR_SIZE..SHMEM_FUZZ_HDR_SIZE + input_len]
+        .copy_from_slice(&input[..input_len]);
+    input_len
+}
+
+/// The environment variable set for targets spawned in persistent mode
+#[cfg(all(unix, feature = "fork"))]
+pub const PERSISTENT_ENV_VAR: &str = "__LIBAFL_PERSISTENT";
+/// The fd a [`PersistentChild`] reads length-prefixed inputs from
+#[cfg(all(unix, feature = "fork"))]
+pub const PERSISTENT_CTL_FD: i32 = FORKSRV_FD;
+/// The fd a [`PersistentChild`] reports its hello message and the status of each run to
+#[cfg(all(unix, feature = "fork"))]
+pub const PERSISTENT_ST_FD: i32 = FORKSRV_FD + 1;
+/// How long we wait for a freshly spawned [`PersistentChild`] to say hello
+#[cfg(all(unix, feature = "fork"))]
+const PERSISTENT_HELLO_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The outcome of a single run in a [`PersistentChild`]
+#[cfg(all(unix, feature = "fork"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PersistentStatus {
+    /// The child reported this status for the run and is ready for the next input
+    Reported(i32),
+    /// The child exited during the run
+    Exited(std::process::ExitStatus),
+    /// The child did not report back in time and got killed
+    Timeout,
+}
+
+/// A long-lived child that executes many inputs, for targets that cannot link the `AFL++` forkserver runtime.
+///
+/// This is typically a small driver script around an interpreter, or a harness with its own loop.
+/// The protocol is a simple length-prefixed message exchange over two pipes:
+/// - The child finds [`PERSISTENT_ENV_VAR`] set to `1` in its environment, reads from [`PERSISTENT_CTL_FD`],
+///   and writes to [`PERSISTENT_ST_FD`].
+/// - On startup, the child writes a 4 byte hello message (with arbitrary content) to [`PERSISTENT_ST_FD`].
+/// - For each run, the fuzzer writes the input length as native-endian `u32` to [`PERSISTENT_CTL_FD`],
+///   followed by the input bytes, unless inputs are delivered via shared memory.
+///   In that case, the input is placed in the map announced in [`SHM_FUZZ_ENV_VAR`] beforehand,
+///   prefixed with its length, like `AFL++`'s shared memory fuzzing does.
+/// - After the run, the child writes a native-endian `i32` status to [`PERSISTENT_ST_FD`].
+///   `0` means the run went fine, any other value is treated as a crash.
+///
+/// If the child dies or does not report back within the timeout, a new child gets spawned for the next run.
+#[cfg(all(unix, feature = "fork"))]
+#[derive(Debug)]
+pub struct PersistentChild {
+    child: Child,
+    /// Control pipe, we write inputs here
+    ctl_pipe: Pipe,
+    /// Status pipe, the child reports back here
+    st_pipe: Pipe,
+}
+
+#[cfg(all(unix, feature = "fork"))]
+impl PersistentChild {
+    /// Spawns the given [`Command`] as a persistent child and waits for its hello message.
+    pub fn spawn(mut command: Command) -> Result<Self, Error> {
+        let mut st_pipe = Pipe::new()?;
+        let mut ctl_pipe = Pipe::new()?;
+
+        command.env(PERSISTENT_ENV_VAR, "1");
+        // # Safety
+        // The pipe file descriptors used for `setpipe` are valid at this point.
+        let child = unsafe {
+            command.setpipe(
+                st_pipe.read_end().unwrap(),
+                st_pipe.write_end().unwrap(),
+                ctl_pipe.read_end().unwrap(),
+                ctl_pipe.write_end().unwrap(),
+            )
+        }
+        .spawn()
+        .map_err(|err| {
+            Error::illegal_state(format!("Could not spawn the persistent child: {err:#?}"))
+        })?;
+
+        // Ctl_pipe.read_end and st_pipe.write_end are unnecessary for the parent, so we'll close them
+        ctl_pipe.close_read_end();
+        st_pipe.close_write_end();
+        // Writes must not block past the timeout of a run, if the child stops reading inputs.
+        // # Safety
+        // The write end of the control pipe is open.
+        fcntl(
+            unsafe { BorrowedFd::borrow_raw(ctl_pipe.write_end().unwrap()) },
+            FcntlArg::F_SETFL(OFlag::O_NONBLOCK),
+        )?;
+
+        let mut persistent_child = Self {
+            child,
+            ctl_pipe,
+            st_pipe,
+        };
+        match persistent_child.read_st_timed(PERSISTENT_HELLO_TIMEOUT)? {
+            PersistentStatus::Reported(_) => Ok(persistent_child),
+            status => Err(Error::illegal_state(format!(
+                "Persistent child did not say hello ({status:?}). Does the target speak the persistent protocol?"
+            ))),
+        }
+    }
+
+    /// The process id of the child
+    #[must_use]
+    pub fn id(&self) -> u32 {
+        self.child.id()
+    }
+
+    /// Hands the next input to the child and waits up to `timeout` for it to report back.
+    ///
+    /// If `input` is `None`, only the length is sent, as the input has been placed in shared memory already.
+    /// A child that timed out gets killed.
+    pub fn run(
+        &mut self,
+        input_len: usize,
+        input: Option<&[u8]>,
+        timeout: Duration,
+    ) -> Result<PersistentStatus, Error> {
+        let deadline = Instant::now() + timeout;
+        let mut msg = Vec::with_capacity(4 + input.map_or(0, <[u8]>::len));
+        msg.extend_from_slice(&(input_len as u32).to_ne_bytes());
+        if let Some(input) = input {
+            msg.extend_from_slice(input);
+        }
+
+        let status = match self.write_ctl_timed(&msg, deadline)? {
+            Some(status) => status,
+            None => self.read_st_timed(deadline.saturating_duration_since(Instant::now()))?,
+        };
+        if status == PersistentStatus::Timeout {
+            // if this fails, there is not much we can do. let's hope it failed because the process finished
+            // in the meantime.
+            drop(self.child.kill());
+            drop(self.child.wait());
+        }
+        Ok(status)
+    }
+
+    /// Writes `msg` to the non-blocking control pipe, waiting until `deadline` at most for the child to drain it.
+    ///
+    /// Returns `None` once everything got written, or the status of a child that is gone or stuck.
+    fn write_ctl_timed(
+        &mut self,
+        msg: &[u8],
+        deadline: Instant,
+    ) -> Result<Option<PersistentStatus>, Error> {
+        let Some(ctl_write) = self.ctl_pipe.write_end() else {
+            return Err(Error::illegal_state("Control pipe was already closed"));
+        };
+        // # Safety
+        // The FDs are valid as this point in time.
+        let ctl_write = unsafe { BorrowedFd::borrow_raw(ctl_write) };
+
+        let mut written = 0;
+        while written < msg.len() {
+            match write(ctl_write, &msg[written..]) {
+                Ok(len) => written += len,
+                Err(Errno::EINTR) => {}
+                Err(Errno::EAGAIN) => {
+                    // The pipe is full, wait for the child to read from it.
+                    let remaining = deadline.saturating_duration_since(Instant::now());
+                    let mut writefds = FdSet::new();
+                    writefds.insert(ctl_write);
+                    if remaining.is_zero()
+                        || pselect(
+                            Some(ctl_write.as_raw_fd() + 1),
+                            None,
+                            &mut writefds,
+                            None,
+                            Some(&TimeSpec::from_duration(remaining)),
+                            Some(&SigSet::empty()),
+                        )? <= 0
+                    {
+                        return Ok(Some(PersistentStatus::Timeout));
+                    }
+                }
+                // The child is gone already, collect its exit status.
+                Err(Errno::EPIPE) => return Ok(Some(PersistentStatus::Exited(self.child.wait()?))),
+                Err(err) => return Err(err.into()),
+            }
+        }
+        Ok(None)
+    }
+
+    /// Waits up to `timeout` for the child to report a status.
+    fn read_st_timed(&mut self, timeout: Duration) -> Result<PersistentStatus, Error> {
+        let Some(st_read) = self.st_pipe.read_end() else {
+            return Err(Error::illegal_state("Status pipe was already closed"));
+        };
+        // # Safety
+        // The FDs are valid as this point in time.
+        let st_read = unsafe { BorrowedFd::borrow_raw(st_read) };
+
+        let mut readfds = FdSet::new();
+        readfds.insert(st_read);
+        let sret = pselect(
+            Some(st_read.as_raw_fd() + 1),
+            &mut readfds,
+            None,
+            None,
+            Some(&TimeSpec::from_duration(timeout)),
+            Some(&SigSet::empty()),
+        )?;
+        if sret <= 0 {
+            return Ok(PersistentStatus::Timeout);
+        }
+
+        let mut buf = [0_u8; 4];
+        match self.st_pipe.read_exact(&mut buf) {
+            Ok(()) => Ok(PersistentStatus::Reported(i32::from_ne_bytes(buf))),
+            // The write end got closed, the child is gone.
+            Err(err) if err.kind() == std::io::ErrorKind::UnexpectedEof => {
+                Ok(PersistentStatus::Exited(self.child.wait()?))
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+#[cfg(all(unix, feature = "fork"))]
+impl Drop for PersistentChild {
+    fn drop(&mut self) {
+        drop(self.child.kill());
+        drop(self.child.wait());
+    }
+}
+
 /// Linux specific [`CommandConfigurator`] that leverages `ptrace`
 ///
 /// This configurator was primarly developed to be used in conjunction with
@@ -322,6 +706,8 @@ pub struct CommandExecutor<C, HT, I, OT, S, T> {
     observers: OT,
     stdout_observer: Option<Handle<StdOutObserver>>,
     stderr_observer: Option<Handle<StdErrObserver>>,
+    #[cfg(unix)]
+    process_exit_observer: Option<Handle<ProcessExitObserver>>,
     hooks: HT,
     phantom: PhantomData<(C, I, S)>,
 }
@@ -351,13 +737,16 @@ where
     HT: Debug,
 {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        f.debug_struct("CommandExecutor")
+        let mut debug = f.debug_struct("CommandExecutor");
+        debug
             .field("inner", &self.configurator)
             .field("observers", &self.observers)
             .field("hooks", &self.hooks)
             .field("stdout_observer", &self.stdout_observer)
-            .field("stderr_observer", &self.stderr_observer)
-            .finish()
+            .field("stderr_observer", &self.stderr_observer);
+        #[cfg(unix)]
+        debug.field("process_exit_observer", &self.process_exit_observer);
+        debug.finish()
     }
 }
 
@@ -366,6 +755,29 @@ impl<C, HT, I, OT, S, T> CommandExecutor<C, HT, I, OT, S, T> {
     pub fn inner(&mut self) -> &mut T {
         &mut self.configurator
     }
+
+    /// Sets the observer recording how the children exited
+    #[cfg(unix)]
+    pub fn set_process_exit_observer(&mut self, process_exit: Handle<ProcessExitObserver>) {
+        self.process_exit_observer = Some(process_exit);
+    }
+}
+
+#[cfg(unix)]
+impl<C, HT, I, OT, S, T> CommandExecutor<C, HT, I, OT, S, T>
+where
+    OT: MatchName,
+{
+    /// Records the exit of the last child in the [`ProcessExitObserver`], if one is set
+    fn observe_process_exit(&mut self, exit: ProcessExit) {
+        if let Some(observer) = self
+            .process_exit_observer
+            .as_ref()
+            .and_then(|handle| self.observers.get_mut(handle))
+        {
+            observer.observe(exit);
+        }
+    }
 }
 
 // this only works on unix because of the reliance on checking the process signal for detecting OOM
@@ -375,6 +787,17 @@ where
     T: CommandConfigurator<Child> + Debug,
     OT: ObserversTuple<I, S>,
 {
+    /// Maps resource limit violations of the last run to [`ExitKind::Oom`], see [`ResourceLimiter::check_exit`].
+    #[cfg(unix)]
+    fn check_resource_limits(&mut self, signal: Option<i32>, exit_kind: ExitKind) -> ExitKind {
+        match self.configurator.resource_limiter_mut() {
+            Some(resource_limiter) => {
+                resource_limiter.check_exit(&mut self.observers, signal, exit_kind)
+            }
+            None => exit_kind,
+        }
+    }
+
     fn execute_input_with_command<TB: ToTargetBytes<I>>(
         &mut self,
         target_bytes_converter: &mut TB,
@@ -385,22 +808,51 @@ where
 
         self.observers_mut().pre_exec_all(state, input)?;
         *state.executions_mut() += 1;
-        let mut child = self
-            .configurator
-            .spawn_child(target_bytes_converter.to_target_bytes(input))?;
+        let target_bytes = target_bytes_converter.to_target_bytes(input);
+
+        if let Some(exit_kind) = self.configurator.run_persistent(&target_bytes)? {
+            #[cfg(unix)]
+            let exit_kind = {
+                use std::os::unix::process::ExitStatusExt;
+                let status = self.configurator.take_persistent_exit();
+                if let Some(status) = &status {
+                    self.observe_process_exit(ProcessExit::from_exit_status(status));
+                }
+                self.check_resource_limits(status.and_then(|status| status.signal()), exit_kind)
+            };
+            self.observers_mut()
+                .post_exec_child_all(state, input, &exit_kind)?;
+            return Ok(exit_kind);
+        }
+
+        let mut child = self.configurator.spawn_child(target_bytes)?;
 
-        let exit_kind = child
+        let status = child
             .wait_timeout(self.configurator.exec_timeout())
-            .expect("waiting on child failed")
-            .map(|status| self.configurator.exit_kind_from_status(&status))
-            .unwrap_or_else(|| {
-                // if this fails, there is not much we can do. let's hope it failed because the process finished
-                // in the meantime.
-                drop(child.kill());
-                // finally, try to wait to properly clean up system resources.
-                drop(child.wait());
-                ExitKind::Timeout
-            });
+            .expect("waiting on child failed");
+        let exit_kind = if let Some(status) = status {
+            self.configurator.exit_kind_from_status(&status)
+        } else {
+            // if this fails, there is not much we can do. let's hope it failed because the process finished
+            // in the meantime.
+            drop(child.kill());
+            // finally, try to wait to properly clean up system resources.
+            drop(child.wait());
+            ExitKind::Timeout
+        };
+        #[cfg(unix)]
+        let exit_kind = {
+            use std::os::unix::process::ExitStatusExt;
+            if let Some(status) = &status {
+                let exit = ProcessExit::from_exit_status(status);
+                #[cfg(target_os = "linux")]
+                if let Some(crash_context) = self.configurator.crash_context() {
+                    crash_context.capture(&mut self.observers, child.id().cast_signed(), &exit);
+                }
+                self.observe_process_exit(exit);
+            }
+            self.check_resource_limits(status.and_then(|status| status.signal()), exit_kind)
+        };
 
         // Manually update stdout/stderr here if we use piped implementation.
         // Reason of not putting into state and pass by post_exec_all is that
@@ -518,7 +970,11 @@ where
         // todo: it might be better to keep the target ptraced in case the target handles sigalarm,
         // breaking the libafl timeout
         ptrace::detach(child, None)?;
-        let res = match waitpid(child, None)? {
+        let wait_status = waitpid(child, None)?;
+        if let Some(exit) = ProcessExit::from_wait_status(&wait_status) {
+            self.observe_process_exit(exit);
+        }
+        let res = match wait_status {
             Exited(pid, 0) if pid == child => ExitKind::Ok,
             Exited(pid, _) if pid == child => ExitKind::Crash,
             Signaled(pid, Signal::SIGALRM, _has_coredump) if pid == child => ExitKind::Timeout,
@@ -561,6 +1017,10 @@ where
 pub struct CommandExecutorBuilder {
     target_inner: StdTargetArgsInner,
     child_env_inner: StdChildArgsInner,
+    #[cfg(all(unix, feature = "fork"))]
+    persistent: bool,
+    #[cfg(all(unix, feature = "fork"))]
+    max_input_size: usize,
 }
 
 impl StdTargetArgs for CommandExecutorBuilder {
@@ -596,9 +1056,31 @@ impl CommandExecutorBuilder {
         CommandExecutorBuilder {
             target_inner: StdTargetArgsInner::default(),
             child_env_inner: StdChildArgsInner::default(),
+            #[cfg(all(unix, feature = "fork"))]
+            persistent: false,
+            #[cfg(all(unix, feature = "fork"))]
+            max_input_size: MAX_INPUT_SIZE_DEFAULT,
         }
     }
 
+    /// Call this to keep the target alive and feed it many inputs, see [`PersistentChild`] for the protocol.
+    /// Default is false.
+    #[cfg(all(unix, feature = "fork"))]
+    #[must_use]
+    pub fn persistent(mut self, persistent: bool) -> Self {
+        self.persistent = persistent;
+        self
+    }
+
+    /// Set the max input size for inputs delivered via shared memory.
+    /// Larger inputs get truncated.
+    #[cfg(all(unix, feature = "fork"))]
+    #[must_use]
+    pub fn max_input_size(mut self, size: usize) -> Self {
+        self.max_input_size = size;
+        self
+    }
+
     /// Builds the `CommandExecutor`
     pub fn build<I, OT, S>(
         &self,
@@ -607,6 +1089,40 @@ impl CommandExecutorBuilder {
     where
         I: HasTargetBytes,
         OT: MatchName + ObserversTuple<I, S>,
+    {
+        self.build_helper(observers, None)
+    }
+
+    /// Builds the `CommandExecutor`, delivering inputs via a shared memory map from the given [`ShMemProvider`].
+    ///
+    /// The id and size of the map are passed to the target in [`SHM_FUZZ_ENV_VAR`] and [`SHM_FUZZ_MAP_SIZE_ENV_VAR`],
+    /// the map starts with the input length as native-endian `u32`, followed by the input, like `AFL++` does.
+    #[cfg(all(unix, feature = "fork"))]
+    #[expect(clippy::type_complexity)]
+    pub fn build_with_shmem_provider<I, OT, S, SP>(
+        &self,
+        observers: OT,
+        shmem_provider: &mut SP,
+    ) -> Result<CommandExecutor<Child, (), I, OT, S, StdCommandConfigurator<SP::ShMem>>, Error>
+    where
+        I: HasTargetBytes,
+        OT: MatchName + ObserversTuple<I, S>,
+        SP: ShMemProvider,
+    {
+        let shmem = shmem_provider.new_shmem(self.max_input_size + SHMEM_FUZZ_HDR_SIZE)?;
+        self.build_helper(observers, Some(shmem))
+    }
+
+    #[expect(clippy::type_complexity)]
+    fn build_helper<I, OT, S, SHM>(
+        &self,
+        observers: OT,
+        input_shmem: Option<SHM>,
+    ) -> Result<CommandExecutor<Child, (), I, OT, S, StdCommandConfigurator<SHM>>, Error>
+    where
+        I: HasTargetBytes,
+        OT: MatchName + ObserversTuple<I, S>,
+        SHM: ShMem,
     {
         let Some(program) = &self.target_inner.program else {
             return Err(Error::illegal_argument(
@@ -614,6 +1130,11 @@ impl CommandExecutorBuilder {
             ));
         };
 
+        #[cfg(all(unix, feature = "fork"))]
+        let custom_input_delivery = self.persistent || input_shmem.is_some();
+        #[cfg(not(all(unix, feature = "fork")))]
+        let custom_input_delivery = input_shmem.is_some();
+
         let mut command = Command::new(program);
         match &self.target_inner.input_location {
             InputLocation::StdIn {
@@ -624,9 +1145,18 @@ impl CommandExecutorBuilder {
                         "Setting filename for CommandExecutor is not supported!",
                     ));
                 }
-                command.stdin(Stdio::piped());
+                if custom_input_delivery {
+                    command.stdin(Stdio::null());
+                } else {
+                    command.stdin(Stdio::piped());
+                }
             }
             InputLocation::File { .. } | InputLocation::Arg { .. } => {
+                if custom_input_delivery {
+                    return Err(Error::illegal_argument(
+                        "Inputs are delivered via shared memory or the persistent control pipe, do not set an input location",
+                    ));
+                }
                 command.stdin(Stdio::null());
             }
         }
@@ -712,20 +1242,81 @@ impl CommandExecutorBuilder {
             )));
         }
 
+        #[cfg(all(unix, feature = "fork"))]
+        if self.persistent
+            && [&stdout_cap, &stderr_cap]
+                .iter()
+                .any(|cap| matches!(cap, Some(StdCommandCaptureMethod::Pipe)))
+        {
+            return Err(Error::illegal_argument(
+                "Persistent mode cannot capture stdout/stderr via pipes, use fd-backed StdOut and StdErr observers",
+            ));
+        }
+
+        #[cfg(all(unix, feature = "fork"))]
+        if let Some(shmem) = &input_shmem {
+            command.env(SHM_FUZZ_ENV_VAR, shmem.id().as_str());
+            command.env(SHM_FUZZ_MAP_SIZE_ENV_VAR, format!("{}", shmem.len()));
+        }
+
+        #[cfg(unix)]
+        let resource_limiter = self
+            .child_env_inner
+            .resource_limits
+            .as_ref()
+            .map(ResourceLimits::build)
+            .transpose()?;
+        #[cfg(unix)]
+        if let Some(resource_limiter) = &resource_limiter {
+            resource_limiter.configure_command(&mut command);
+        }
+
+        #[cfg(target_os = "linux")]
+        let crash_context = self
+            .child_env_inner
+            .crash_context
+            .clone()
+            .map(|crash_context| {
+                crash_context.default_core_dir(self.child_env_inner.current_directory.as_deref())
+            });
+        #[cfg(target_os = "linux")]
+        if let Some(crash_context) = &crash_context {
+            crash_context.configure_command(&mut command);
+        }
+
         let configurator = StdCommandConfigurator {
             debug_child: self.child_env_inner.debug_child,
             stdout_cap,
             stderr_cap,
             input_location: self.target_inner.input_location.clone(),
+            input_shmem,
+            #[cfg(all(unix, feature = "fork"))]
+            core: self.child_env_inner.core,
+            #[cfg(all(unix, feature = "fork"))]
+            persistent: self.persistent,
+            #[cfg(all(unix, feature = "fork"))]
+            persistent_child: None,
+            #[cfg(all(unix, feature = "fork"))]
+            persistent_exit: None,
+            #[cfg(unix)]
+            resource_limiter,
+            #[cfg(target_os = "linux")]
+            crash_context,
             timeout: self.child_env_inner.timeout,
             command,
         };
 
-        Ok(configurator.into_executor::<I, OT, S>(
+        #[cfg_attr(not(unix), expect(unused_mut))]
+        let mut executor = configurator.into_executor::<I, OT, S>(
             observers,
             self.child_env_inner.stdout_observer.clone(),
             self.child_env_inner.stderr_observer.clone(),
-        ))
+        );
+        #[cfg(unix)]
+        if let Some(process_exit) = &self.child_env_inner.process_exit_observer {
+            executor.set_process_exit_observer(process_exit.clone());
+        }
+        Ok(executor)
     }
 }
 
@@ -793,6 +1384,34 @@ pub trait CommandConfigurator<C>: Sized {
     /// Spawns a new process with the given configuration.
     fn spawn_child(&mut self, target_bytes: OwnedSlice<'_, u8>) -> Result<C, Error>;
 
+    /// Runs the input in a long-lived child, for configurators supporting persistent mode (see `PersistentChild`).
+    ///
+    /// Returns `Ok(None)` if this configurator does not run in persistent mode, which is the default.
+    /// A new child gets spawned via [`CommandConfigurator::spawn_child`] for each run, then.
+    fn run_persistent(&mut self, _target_bytes: &[u8]) -> Result<Option<ExitKind>, Error> {
+        Ok(None)
+    }
+
+    /// The exit status of the persistent child, if it died during the last [`CommandConfigurator::run_persistent`].
+    ///
+    /// A child that is still alive, reported a status, or got killed after a timeout has no exit status.
+    #[cfg(unix)]
+    fn take_persistent_exit(&mut self) -> Option<std::process::ExitStatus> {
+        None
+    }
+
+    /// The [`ResourceLimiter`] enforcing resource limits on the children, if any.
+    #[cfg(unix)]
+    fn resource_limiter_mut(&mut self) -> Option<&mut ResourceLimiter> {
+        None
+    }
+
+    /// The [`CrashContextCapture`] collecting the context of crashed children, if any.
+    #[cfg(target_os = "linux")]
+    fn crash_context(&self) -> Option<&CrashContextCapture> {
+        None
+    }
+
     /// Provides timeout duration for execution of the child process.
     fn exec_timeout(&self) -> Duration;
     /// Set the timeout duration for execution of the child process.
@@ -835,6 +1454,8 @@ pub trait CommandConfigurator<C>: Sized {
             hooks: (),
             stderr_observer,
             stdout_observer,
+            #[cfg(unix)]
+            process_exit_observer: None,
             phantom: PhantomData,
         }
     }
@@ -853,6 +1474,8 @@ pub trait CommandConfigurator<C>: Sized {
             hooks,
             stderr_observer,
             stdout_observer,
+            #[cfg(unix)]
+            process_exit_observer: None,
             phantom: PhantomData,
         }
     }
@@ -876,12 +1499,19 @@ fn waitpid_filtered(pid: Pid, options: Option<WaitPidFlag>) -> Result<WaitStatus
 
 #[cfg(test)]
 mod tests {
+    #[cfg(all(unix, feature = "fork"))]
+    use alloc::vec;
+    #[cfg(all(unix, feature = "fork"))]
+    use core::time::Duration;
+
     use libafl_bolts::StdTargetArgs;
     #[cfg(unix)]
     use libafl_bolts::tuples::Handled;
     #[cfg(unix)]
     use tuple_list::tuple_list;
 
+    #[cfg(unix)]
+    use crate::executors::{ExitKind, HasObservers, resource_limits::ResourceLimits};
     use crate::{
         events::SimpleEventManager,
         executors::{
@@ -894,7 +1524,12 @@ mod tests {
         state::NopState,
     };
     #[cfg(unix)]
-    use crate::{executors::StdChildArgs, observers::StdOutObserver};
+    use crate::{
+        executors::StdChildArgs,
+        observers::{LimitedResource, ProcessExitObserver, ResourceLimitObserver, StdOutObserver},
+    };
+    #[cfg(target_os = "linux")]
+    use crate::{executors::crash_context::CrashContextCapture, observers::CrashContextObserver};
 
     #[test]
     #[cfg_attr(miri, ignore)]
@@ -920,6 +1555,193 @@ mod tests {
             .unwrap();
     }
 
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    #[cfg(unix)]
+    fn test_resource_limits() {
+        let mut mgr: SimpleEventManager<NopInput, _, NopState<NopInput>> =
+            SimpleEventManager::new(SimpleMonitor::new(|status| {
+                log::info!("{status}");
+            }));
+
+        let out_file = std::env::temp_dir().join(format!("libafl_fsize_{}", std::process::id()));
+        let observer = ResourceLimitObserver::new("resource_limits");
+        let handle = observer.handle();
+        let mut executor = CommandExecutor::builder()
+            .program("sh")
+            .args(["-c", "exec head -c 4096 /dev/zero > \"$0\""])
+            .arg(&out_file)
+            .resource_limits(
+                ResourceLimits::new()
+                    .file_size(1024)
+                    .observer(handle.clone()),
+            )
+            .build(tuple_list!(observer))
+            .unwrap();
+
+        let exit_kind = executor
+            .run_target(
+                &mut NopFuzzer::new(),
+                &mut NopState::<NopInput>::new(),
+                &mut mgr,
+                &BytesInput::new(b"test".to_vec()),
+            )
+            .unwrap();
+        drop(std::fs::remove_file(&out_file));
+
+        assert_eq!(exit_kind, ExitKind::Oom);
+        assert_eq!(
+            executor.observers()[&handle].violation(),
+            Some(LimitedResource::FileSize)
+        );
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    #[cfg(unix)]
+    fn test_address_space_limit() {
+        // A runaway allocation, the failed `malloc` returns NULL and the write segfaults.
+        const TARGET: &str = r"
+#include <stdlib.h>
+#include <string.h>
+int main(void) {
+    size_t len = (size_t)1 << 32;
+    char *buf = malloc(len);
+    memset(buf, 1, len);
+    return buf[len - 1] != 1;
+}
+";
+        let dir = std::env::temp_dir().join(format!("libafl_rlimit_as_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let source = dir.join("alloc.c");
+        let target = dir.join("alloc");
+        std::fs::write(&source, TARGET).unwrap();
+        let compiled = std::process::Command::new("cc")
+            .arg(&source)
+            .arg("-o")
+            .arg(&target)
+            .status()
+            .is_ok_and(|status| status.success());
+        if !compiled {
+            log::warn!("No working C compiler, skipping test_address_space_limit");
+            return;
+        }
+
+        let mut mgr: SimpleEventManager<NopInput, _, NopState<NopInput>> =
+            SimpleEventManager::new(SimpleMonitor::new(|status| {
+                log::info!("{status}");
+            }));
+        let observer = ResourceLimitObserver::new("resource_limits");
+        let handle = observer.handle();
+        let mut executor = CommandExecutor::builder()
+            .program(&target)
+            .resource_limits(
+                ResourceLimits::new()
+                    .address_space(256 << 20)
+                    .observer(handle.clone()),
+            )
+            .build(tuple_list!(observer))
+            .unwrap();
+
+        let exit_kind = executor
+            .run_target(
+                &mut NopFuzzer::new(),
+                &mut NopState::<NopInput>::new(),
+                &mut mgr,
+                &BytesInput::new(b"test".to_vec()),
+            )
+            .unwrap();
+        drop(std::fs::remove_dir_all(&dir));
+
+        // A segfault is no evidence of a failed allocation, so it stays a crash
+        assert_eq!(exit_kind, ExitKind::Crash);
+        assert_eq!(executor.observers()[&handle].violation(), None);
+        assert_eq!(
+            executor.observers()[&handle].suspected(),
+            Some(LimitedResource::MemoryRlimit)
+        );
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    #[cfg(unix)]
+    fn test_rlimit_segfault_is_crash() {
+        let mut mgr: SimpleEventManager<NopInput, _, NopState<NopInput>> =
+            SimpleEventManager::new(SimpleMonitor::new(|status| {
+                log::info!("{status}");
+            }));
+        let observer = ResourceLimitObserver::new("resource_limits");
+        let handle = observer.handle();
+        let mut executor = CommandExecutor::builder()
+            .program("sh")
+            .args(["-c", "kill -SEGV $$"])
+            .resource_limits(
+                ResourceLimits::new()
+                    .address_space(1 << 30)
+                    .observer(handle.clone()),
+            )
+            .build(tuple_list!(observer))
+            .unwrap();
+
+        let exit_kind = executor
+            .run_target(
+                &mut NopFuzzer::new(),
+                &mut NopState::<NopInput>::new(),
+                &mut mgr,
+                &BytesInput::new(b"test".to_vec()),
+            )
+            .unwrap();
+
+        assert_eq!(exit_kind, ExitKind::Crash);
+        assert_eq!(executor.observers()[&handle].violation(), None);
+        assert_eq!(
+            executor.observers()[&handle].suspected(),
+            Some(LimitedResource::MemoryRlimit)
+        );
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    #[cfg(target_os = "linux")]
+    fn test_crash_context() {
+        let mut mgr: SimpleEventManager<NopInput, _, NopState<NopInput>> =
+            SimpleEventManager::new(SimpleMonitor::new(|status| {
+                log::info!("{status}");
+            }));
+
+        let dir = std::env::temp_dir().join(format!("libafl_cores_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let observer = CrashContextObserver::new("crash_context");
+        let handle = observer.handle();
+        let mut executor = CommandExecutor::builder()
+            .program("sh")
+            .args(["-c", "kill -SEGV $$"])
+            .current_dir(dir.clone())
+            .crash_context(CrashContextCapture::new(handle.clone()))
+            .build(tuple_list!(observer))
+            .unwrap();
+
+        let exit_kind = executor
+            .run_target(
+                &mut NopFuzzer::new(),
+                &mut NopState::<NopInput>::new(),
+                &mut mgr,
+                &BytesInput::new(b"test".to_vec()),
+            )
+            .unwrap();
+        drop(std::fs::remove_dir_all(&dir));
+
+        assert_eq!(exit_kind, ExitKind::Crash);
+        let observers = executor.observers();
+        let context = observers[&handle].last_context().unwrap();
+        assert_eq!(context.signal, libc::SIGSEGV);
+        // Whether there is a core dump depends on the core limit and pattern of the system
+        if !context.registers.is_empty() {
+            assert!(!context.maps.is_empty());
+            assert!(!context.backtrace.is_empty());
+        }
+    }
+
     #[test]
     #[cfg_attr(miri, ignore)]
     #[cfg(unix)]
@@ -949,4 +1771,165 @@ mod tests {
 
         assert!(executor.observers.0.output.is_some());
     }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    #[cfg(all(unix, feature = "fork"))]
+    fn test_persistent() {
+        // A tiny driver speaking the persistent protocol, crashing on the input `crash`.
+        // This needs bash, as plain sh cannot address fds above 9.
+        const DRIVER: &str = r#"
+            printf 'LAFL' >&199
+            while true; do
+                len=$(dd bs=1 count=4 <&198 2>/dev/null | od -An -tu4 | tr -d ' ')
+                [ -z "$len" ] && exit 0
+                input=$(dd bs=1 count="$len" <&198 2>/dev/null)
+                [ "$input" = crash ] && kill -SEGV $$
+                printf '\000\000\000\000' >&199
+            done
+        "#;
+
+        let mut mgr: SimpleEventManager<NopInput, _, NopState<NopInput>> =
+            SimpleEventManager::new(SimpleMonitor::new(|status| {
+                log::info!("{status}");
+            }));
+        let mut fuzzer = NopFuzzer::new();
+        let mut state = NopState::<NopInput>::new();
+
+        let process_exit = ProcessExitObserver::new("process_exit");
+        let process_exit_handle = process_exit.handle();
+        let mut executor = CommandExecutor::builder()
+            .program("bash")
+            .args(["-c", DRIVER])
+            .persistent(true)
+            .process_exit_observer(process_exit_handle.clone())
+            .build(tuple_list!(process_exit))
+            .unwrap();
+
+        let mut run = |executor: &mut CommandExecutor<_, _, _, _, _, _>, input: &[u8]| {
+            executor
+                .run_target(
+                    &mut fuzzer,
+                    &mut state,
+                    &mut mgr,
+                    &BytesInput::new(input.to_vec()),
+                )
+                .unwrap()
+        };
+
+        assert_eq!(run(&mut executor, b"hello"), ExitKind::Ok);
+        let pid = executor.inner().persistent_child.as_ref().unwrap().id();
+        assert_eq!(run(&mut executor, b"world"), ExitKind::Ok);
+        assert_eq!(
+            executor.inner().persistent_child.as_ref().unwrap().id(),
+            pid
+        );
+
+        // The child stays alive, so there is no exit to record
+        assert!(
+            executor.observers()[&process_exit_handle]
+                .last_exit()
+                .is_none()
+        );
+
+        assert_eq!(run(&mut executor, b"crash"), ExitKind::Crash);
+        assert!(executor.inner().persistent_child.is_none());
+        assert_eq!(
+            executor.observers()[&process_exit_handle]
+                .last_exit()
+                .and_then(|exit| exit.signal),
+            Some(libc::SIGSEGV)
+        );
+        assert_eq!(run(&mut executor, b"again"), ExitKind::Ok);
+        assert!(
+            executor.observers()[&process_exit_handle]
+                .last_exit()
+                .is_none()
+        );
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    #[cfg(all(target_os = "linux", feature = "fork"))]
+    fn test_persistent_shmem() {
+        use libafl_bolts::shmem::{MmapShMemProvider, ShMemProvider};
+
+        // Only the length comes in over the control pipe, the input is read from the mapped file.
+        const DRIVER: &str = r#"
+            printf 'LAFL' >&199
+            while true; do
+                len=$(dd bs=1 count=4 <&198 2>/dev/null | od -An -tu4 | tr -d ' ')
+                [ -z "$len" ] && exit 0
+                input=$(dd if="/dev/shm$__AFL_SHM_FUZZ_ID" bs=1 skip=4 count="$len" 2>/dev/null)
+                [ "$input" = crash ] && kill -SEGV $$
+                printf '\000\000\000\000' >&199
+            done
+        "#;
+
+        let mut mgr: SimpleEventManager<NopInput, _, NopState<NopInput>> =
+            SimpleEventManager::new(SimpleMonitor::new(|status| {
+                log::info!("{status}");
+            }));
+        let mut fuzzer = NopFuzzer::new();
+        let mut state = NopState::<NopInput>::new();
+
+        let mut shmem_provider = MmapShMemProvider::new().unwrap();
+        let mut executor = CommandExecutor::builder()
+            .program("bash")
+            .args(["-c", DRIVER])
+            .persistent(true)
+            .build_with_shmem_provider((), &mut shmem_provider)
+            .unwrap();
+
+        let mut run = |executor: &mut CommandExecutor<_, _, _, _, _, _>, input: &[u8]| {
+            executor
+                .run_target(
+                    &mut fuzzer,
+                    &mut state,
+                    &mut mgr,
+                    &BytesInput::new(input.to_vec()),
+                )
+                .unwrap()
+        };
+
+        assert_eq!(run(&mut executor, b"crash-not"), ExitKind::Ok);
+        assert_eq!(run(&mut executor, b"crash"), ExitKind::Crash);
+        assert_eq!(run(&mut executor, b"fine"), ExitKind::Ok);
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    #[cfg(all(unix, feature = "fork"))]
+    fn test_persistent_stuck_child() {
+        // Says hello, then never reads, so a large input fills the control pipe.
+        const DRIVER: &str = r"
+            printf 'LAFL' >&199
+            sleep 60
+        ";
+
+        let mut mgr: SimpleEventManager<NopInput, _, NopState<NopInput>> =
+            SimpleEventManager::new(SimpleMonitor::new(|status| {
+                log::info!("{status}");
+            }));
+        let mut executor = CommandExecutor::builder()
+            .program("bash")
+            .args(["-c", DRIVER])
+            .persistent(true)
+            .timeout(Duration::from_millis(500))
+            .build(())
+            .unwrap();
+
+        let start = std::time::Instant::now();
+        let exit_kind = executor
+            .run_target(
+                &mut NopFuzzer::new(),
+                &mut NopState::<NopInput>::new(),
+                &mut mgr,
+                &BytesInput::new(vec![0x41; 1 << 20]),
+            )
+            .unwrap();
+        assert_eq!(exit_kind, ExitKind::Timeout);
+        assert!(start.elapsed() < Duration::from_secs(10));
+        assert!(executor.inner().persistent_child.is_none());
+    }
 }
@@ -0,0 +1,806 @@
+//! Crash context capture for the children of process-based executors.
+//!
+//! Configure a [`CrashContextCapture`] and hand it to the [`crate::executors::ForkserverExecutor`] or
+//! [`crate::executors::CommandExecutor`] builders (via [`crate::executors::StdChildArgs::crash_context`]).
+//! The children then run with core dumps enabled. Whenever one gets terminated by a signal, its core dump is
+//! parsed for the registers, the file-backed memory mappings and a backtrace, symbolized with `addr2line`.
+//! The result is stored in a [`CrashContextObserver`], use a
+//! [`crate::feedbacks::crash_context::CrashContextToMetadataFeedback`] to attach it to the objectives.
+//!
+//! The backtrace is collected by walking the frame pointer chain, so compile the target with
+//! `-fno-omit-frame-pointer` for complete backtraces.
+//! Sanitizers disable core dumps by default, set `disable_coredump=0` in e.g. `ASAN_OPTIONS` to get them.
+
+use alloc::{
+    collections::BTreeMap,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::ffi::c_int;
+use std::{
+    fs::{self, File},
+    io,
+    os::unix::{fs::FileExt, process::CommandExt},
+    path::{Path, PathBuf},
+    process::Command,
+    time::SystemTime,
+};
+
+use libafl_bolts::tuples::{Handle, MatchNameRef};
+
+use crate::{
+    Error,
+    observers::{CrashContext, CrashContextObserver, MemoryMapping, ProcessExit, StackFrame},
+};
+
+/// The default maximum number of frames in a backtrace
+pub const DEFAULT_MAX_FRAMES: usize = 64;
+
+/// The kernel setting deciding where core dumps are written to
+const CORE_PATTERN_PATH: &str = "/proc/sys/kernel/core_pattern";
+/// The kernel setting deciding if the pid gets appended to core dumps
+const CORE_USES_PID_PATH: &str = "/proc/sys/kernel/core_uses_pid";
+
+const PT_LOAD: u32 = 1;
+const PT_NOTE: u32 = 4;
+const NT_PRSTATUS: u32 = 1;
+const NT_FILE: u32 = 0x4649_4c45;
+const EM_X86_64: u16 = 62;
+const EM_AARCH64: u16 = 183;
+/// The offset of `pr_reg` in the `elf_prstatus` of 64 bit platforms
+const PRSTATUS_REGS_OFFSET: usize = 112;
+
+const X86_64_REGISTERS: [&str; 27] = [
+    "r15", "r14", "r13", "r12", "rbp", "rbx", "r11", "r10", "r9", "r8", "rax", "rcx", "rdx", "rsi",
+    "rdi", "orig_rax", "rip", "cs", "eflags", "rsp", "ss", "fs_base", "gs_base", "ds", "es", "fs",
+    "gs",
+];
+const AARCH64_REGISTERS: [&str; 34] = [
+    "x0", "x1", "x2", "x3", "x4", "x5", "x6", "x7", "x8", "x9", "x10", "x11", "x12", "x13", "x14",
+    "x15", "x16", "x17", "x18", "x19", "x20", "x21", "x22", "x23", "x24", "x25", "x26", "x27",
+    "x28", "x29", "x30", "sp", "pc", "pstate",
+];
+
+/// Configuration for the crash context capture of fork-based executors, see the [module docs](self).
+#[derive(Debug, Clone)]
+pub struct CrashContextCapture {
+    observer: Handle<CrashContextObserver>,
+    core_dir: Option<PathBuf>,
+    keep_cores: Option<PathBuf>,
+    addr2line: PathBuf,
+    max_frames: usize,
+}
+
+impl CrashContextCapture {
+    /// Creates a new [`CrashContextCapture`], storing the context of each crash in the given observer.
+    #[must_use]
+    pub fn new(observer: Handle<CrashContextObserver>) -> Self {
+        Self {
+            observer,
+            core_dir: None,
+            keep_cores: None,
+            addr2line: PathBuf::from("addr2line"),
+            max_frames: DEFAULT_MAX_FRAMES,
+        }
+    }
+
+    /// The working directory of the target, where the kernel writes relative core dumps to.
+    /// Defaults to the current directory.
+    #[must_use]
+    pub fn core_dir<P>(mut self, core_dir: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        self.core_dir = Some(core_dir.into());
+        self
+    }
+
+    /// Sets the `core_dir` to the working directory of the target, unless set explicitly
+    pub(crate) fn default_core_dir(mut self, current_dir: Option<&Path>) -> Self {
+        if self.core_dir.is_none() {
+            self.core_dir = current_dir.map(Path::to_path_buf);
+        }
+        self
+    }
+
+    /// Moves the core dumps to the given directory instead of deleting them once they are parsed.
+    /// Their new path is part of the [`CrashContext`].
+    #[must_use]
+    pub fn keep_cores<P>(mut self, dir: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        self.keep_cores = Some(dir.into());
+        self
+    }
+
+    /// The `addr2line` binary used to symbolize the backtraces, defaults to the one in `PATH`
+    #[must_use]
+    pub fn addr2line<P>(mut self, addr2line: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        self.addr2line = addr2line.into();
+        self
+    }
+
+    /// The maximum number of frames in a backtrace, defaults to [`DEFAULT_MAX_FRAMES`]
+    #[must_use]
+    pub fn max_frames(mut self, max_frames: usize) -> Self {
+        self.max_frames = max_frames;
+        self
+    }
+
+    /// Enables core dumps for the process spawned by this [`Command`], its children inherit the setting.
+    ///
+    /// The core size gets raised to the hard limit, which has to be raised beforehand if it is `0`.
+    pub fn configure_command(&self, command: &mut Command) {
+        let func = || {
+            let mut limit = libc::rlimit {
+                rlim_cur: 0,
+                rlim_max: 0,
+            };
+            // # Safety
+            // Raw libc calls with valid pointers.
+            unsafe {
+                if libc::getrlimit(libc::RLIMIT_CORE, &raw mut limit) != 0 {
+                    return Err(io::Error::last_os_error());
+                }
+                limit.rlim_cur = limit.rlim_max;
+                if libc::setrlimit(libc::RLIMIT_CORE, &raw const limit) != 0 {
+                    return Err(io::Error::last_os_error());
+                }
+            }
+            Ok(())
+        };
+        // # Safety
+        // The closure only does raw libc calls.
+        unsafe {
+            command.pre_exec(func);
+        }
+    }
+
+    /// Collects the context of the child with the given `pid`, if it crashed, and stores it in the observer.
+    ///
+    /// Failures to collect parts of the context are logged, the remaining context is still stored.
+    pub fn capture<OT>(&self, observers: &mut OT, pid: i32, exit: &ProcessExit)
+    where
+        OT: MatchNameRef,
+    {
+        let Some(signal) = exit.signal else {
+            return;
+        };
+        let mut context = CrashContext {
+            pid,
+            signal,
+            ..CrashContext::default()
+        };
+        if exit.core_dumped {
+            match self.find_core(pid, signal) {
+                Ok(Some(core_path)) => {
+                    if let Err(err) = self.parse_core(&core_path, &mut context) {
+                        log::warn!("Could not parse core dump {}: {err}", core_path.display());
+                    }
+                    match self.dispose_core(&core_path, pid) {
+                        Ok(kept) => context.core_path = kept,
+                        Err(err) => {
+                            log::warn!(
+                                "Could not dispose core dump {}: {err}",
+                                core_path.display()
+                            );
+                        }
+                    }
+                }
+                Ok(None) => log::warn!("Could not find the core dump of crashed child {pid}"),
+                Err(err) => {
+                    log::warn!("Could not find the core dump of crashed child {pid}: {err}");
+                }
+            }
+        }
+
+        if let Some(observer) = observers.get_mut(&self.observer) {
+            observer.observe(context);
+        }
+    }
+
+    /// Finds the core dump of the given child, following the kernel `core_pattern`
+    fn find_core(&self, pid: i32, signal: c_int) -> Result<Option<PathBuf>, Error> {
+        let pattern = fs::read_to_string(CORE_PATTERN_PATH)?;
+        let mut pattern = pattern.trim().to_string();
+        if pattern.starts_with('|') {
+            return Err(Error::illegal_state(format!(
+                "Core dumps are piped to a helper ({pattern}), set {CORE_PATTERN_PATH} to a file name"
+            )));
+        }
+        if !pattern.contains("%p")
+            && fs::read_to_string(CORE_USES_PID_PATH).is_ok_and(|uses_pid| uses_pid.trim() == "1")
+        {
+            pattern.push_str(".%p");
+        }
+
+        let pattern = Path::new(&pattern);
+        let dir = match pattern.parent() {
+            Some(parent) if pattern.is_absolute() => parent.to_path_buf(),
+            _ => self.core_dir.clone().unwrap_or_else(|| PathBuf::from(".")),
+        };
+        let file_name = pattern
+            .file_name()
+            .ok_or_else(|| Error::illegal_state("Empty core pattern"))?
+            .to_string_lossy();
+        let parts = core_pattern_parts(&file_name, pid, signal);
+
+        let mut newest: Option<(SystemTime, PathBuf)> = None;
+        for entry in fs::read_dir(&dir)? {
+            let entry = entry?;
+            if !matches_parts(&entry.file_name().to_string_lossy(), &parts) {
+                continue;
+            }
+            let modified = entry.metadata()?.modified()?;
+            if newest.as_ref().is_none_or(|(time, _)| modified > *time) {
+                newest = Some((modified, entry.path()));
+            }
+        }
+        Ok(newest.map(|(_, path)| path))
+    }
+
+    /// Fills the context with the registers, maps and backtrace from the core dump
+    fn parse_core(&self, core_path: &Path, context: &mut CrashContext) -> Result<(), Error> {
+        let core = ElfFile::open(core_path)?;
+
+        let mut prstatus = None;
+        for note in core.notes()? {
+            match note.kind {
+                // The first `NT_PRSTATUS` belongs to the thread that crashed
+                NT_PRSTATUS if prstatus.is_none() => prstatus = Some(note.desc),
+                NT_FILE => context.maps = parse_file_note(&note.desc)?,
+                _ => {}
+            }
+        }
+        let prstatus =
+            prstatus.ok_or_else(|| Error::illegal_state("Core dump has no NT_PRSTATUS note"))?;
+
+        let names: &[&str] = match core.machine {
+            EM_X86_64 => &X86_64_REGISTERS,
+            EM_AARCH64 => &AARCH64_REGISTERS,
+            machine => {
+                return Err(Error::unsupported(format!(
+                    "Registers of machine type {machine} are not supported"
+                )));
+            }
+        };
+        let regs = prstatus
+            .get(PRSTATUS_REGS_OFFSET..PRSTATUS_REGS_OFFSET + names.len() * 8)
+            .ok_or_else(|| Error::illegal_state("NT_PRSTATUS note is too short"))?;
+        context.registers = names
+            .iter()
+            .zip(regs.chunks_exact(8))
+            .map(|(name, value)| ((*name).to_string(), read_u64(value, 0)))
+            .collect();
+
+        let register = |name: &str| {
+            context
+                .registers
+                .iter()
+                .find(|(reg, _)| reg == name)
+                .map_or(0, |(_, value)| *value)
+        };
+        let (pc, fp) = if core.machine == EM_X86_64 {
+            (register("rip"), register("rbp"))
+        } else {
+            (register("pc"), register("x29"))
+        };
+        let addresses = self.unwind(&core, pc, fp);
+        context.backtrace = self.symbolize(&addresses, &context.maps);
+        Ok(())
+    }
+
+    /// Walks the frame pointer chain in the memory of the core dump
+    fn unwind(&self, core: &ElfFile, pc: u64, mut fp: u64) -> Vec<u64> {
+        let mut addresses = vec![pc];
+        while addresses.len() < self.max_frames && fp != 0 {
+            // On both x86_64 and aarch64, the frame record is the saved frame pointer, followed by the return address
+            let (Some(next_fp), Some(ret)) = (
+                core.read_memory(fp),
+                fp.checked_add(8)
+                    .and_then(|ret_addr| core.read_memory(ret_addr)),
+            ) else {
+                break;
+            };
+            if ret == 0 {
+                break;
+            }
+            addresses.push(ret);
+            // The stack grows down, so a sane chain moves up
+            if next_fp <= fp {
+                break;
+            }
+            fp = next_fp;
+        }
+        addresses
+    }
+
+    /// Symbolizes the addresses with `addr2line`, one invocation per module
+    fn symbolize(&self, addresses: &[u64], maps: &[MemoryMapping]) -> Vec<StackFrame> {
+        let mut frames: Vec<StackFrame> = addresses
+            .iter()
+            .map(|address| StackFrame {
+                address: *address,
+                module: None,
+                function: None,
+                location: None,
+            })
+            .collect();
+
+        // module path -> (frame index, address in the module)
+        let mut lookups: BTreeMap<&str, Vec<(usize, u64)>> = BTreeMap::new();
+        for (i, frame) in frames.iter_mut().enumerate() {
+            let Some(map) = maps
+                .iter()
+                .find(|map| map.start <= frame.address && frame.address < map.end)
+            else {
+                continue;
+            };
+            frame.module = Some(map.path.clone());
+            // Return addresses point after the call, look up the call itself
+            let address = if i == 0 {
+                frame.address
+            } else {
+                frame.address - 1
+            };
+            let file_offset = address - map.start + map.file_offset;
+            if let Some(vaddr) = ElfFile::open(Path::new(&map.path))
+                .ok()
+                .and_then(|module| module.file_offset_to_vaddr(file_offset))
+            {
+                lookups.entry(&map.path).or_default().push((i, vaddr));
+            }
+        }
+
+        let mut symbols = Vec::new();
+        for (module, lookups) in &lookups {
+            match self.run_addr2line(module, lookups.iter().map(|(_, vaddr)| *vaddr)) {
+                Ok(results) => symbols.extend(lookups.iter().map(|(i, _)| *i).zip(results)),
+                Err(err) => log::warn!("Could not symbolize addresses in {module}: {err}"),
+            }
+        }
+        for (i, (function, location)) in symbols {
+            frames[i].function = function;
+            frames[i].location = location;
+        }
+        frames
+    }
+
+    /// Runs `addr2line`, returning the function and source location for each address
+    fn run_addr2line<A>(&self, module: &str, addresses: A) -> Result<Vec<Symbol>, Error>
+    where
+        A: Iterator<Item = u64>,
+    {
+        let output = Command::new(&self.addr2line)
+            .args(["-f", "-C", "-e", module])
+            .args(addresses.map(|address| format!("{address:#x}")))
+            .output()?;
+        if !output.status.success() {
+            return Err(Error::unknown(format!(
+                "addr2line failed: {}",
+                String::from_utf8_lossy(&output.stderr)
+            )));
+        }
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let known = |line: &str| (!line.starts_with("??")).then(|| line.to_string());
+        let lines: Vec<&str> = stdout.lines().collect();
+        Ok(lines
+            .chunks_exact(2)
+            .map(|pair| (known(pair[0]), known(pair[1])))
+            .collect())
+    }
+
+    /// Moves the core dump to the `keep_cores` directory, or deletes it
+    fn dispose_core(&self, core_path: &Path, pid: i32) -> Result<Option<PathBuf>, Error> {
+        let Some(dir) = &self.keep_cores else {
+            fs::remove_file(core_path)?;
+            return Ok(None);
+        };
+        fs::create_dir_all(dir)?;
+        let timestamp = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+        let kept = dir.join(format!("core-{timestamp}-{pid}"));
+        if fs::rename(core_path, &kept).is_err() {
+            // Probably on a different file system
+            fs::copy(core_path, &kept)?;
+            fs::remove_file(core_path)?;
+        }
+        Ok(Some(kept))
+    }
+}
+
+/// The function name and source location of an address, as far as `addr2line` knows them
+type Symbol = (Option<String>, Option<String>);
+
+/// A part of a core dump file name, following the `core_pattern` syntax
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum CorePatternPart {
+    Literal(String),
+    /// A specifier we cannot expand, e.g. the time of the dump
+    Wildcard,
+}
+
+/// Expands the specifiers of the `core_pattern` we know, see `man 5 core`
+fn core_pattern_parts(pattern: &str, pid: i32, signal: c_int) -> Vec<CorePatternPart> {
+    let mut parts = Vec::new();
+    let mut literal = String::new();
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            literal.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('%') => literal.push('%'),
+            Some('p' | 'P' | 'i' | 'I') => literal.push_str(&pid.to_string()),
+            Some('s') => literal.push_str(&signal.to_string()),
+            Some(_) => {
+                if !literal.is_empty() {
+                    parts.push(CorePatternPart::Literal(core::mem::take(&mut literal)));
+                }
+                if parts.last() != Some(&CorePatternPart::Wildcard) {
+                    parts.push(CorePatternPart::Wildcard);
+                }
+            }
+            None => {}
+        }
+    }
+    if !literal.is_empty() {
+        parts.push(CorePatternPart::Literal(literal));
+    }
+    parts
+}
+
+/// Checks if the file name matches the expanded `core_pattern`
+fn matches_parts(name: &str, parts: &[CorePatternPart]) -> bool {
+    match parts.split_first() {
+        None => name.is_empty(),
+        Some((CorePatternPart::Literal(literal), rest)) => name
+            .strip_prefix(literal.as_str())
+            .is_some_and(|name| matches_parts(name, rest)),
+        Some((CorePatternPart::Wildcard, rest)) => name
+            .char_indices()
+            .skip(1)
+            .map(|(i, _)| i)
+            .chain([name.len()])
+            .any(|i| !name.is_empty() && matches_parts(&name[i..], rest)),
+    }
+}
+
+/// Parses an `NT_FILE` note, listing the file-backed memory mappings
+fn parse_file_note(desc: &[u8]) -> Result<Vec<MemoryMapping>, Error> {
+    let too_short = || Error::illegal_state("NT_FILE note is too short");
+    let count = read_u64_checked(desc, 0).ok_or_else(too_short)?;
+    let page_size = read_u64_checked(desc, 8).ok_or_else(too_short)?;
+    // A corrupt count must not overflow the offset of the names
+    let count = usize::try_from(count).map_err(|_| too_short())?;
+    let names_offset = count
+        .checked_mul(24)
+        .and_then(|entries_len| entries_len.checked_add(16))
+        .ok_or_else(too_short)?;
+    let mut names = desc
+        .get(names_offset..)
+        .ok_or_else(too_short)?
+        .split(|b| *b == 0);
+
+    let mut maps = Vec::with_capacity(count.min(desc.len() / 24));
+    for i in 0..count {
+        let entry = 16 + i * 24;
+        let (Some(start), Some(end), Some(page_offset), Some(path)) = (
+            read_u64_checked(desc, entry),
+            read_u64_checked(desc, entry + 8),
+            read_u64_checked(desc, entry + 16),
+            names.next(),
+        ) else {
+            return Err(too_short());
+        };
+        maps.push(MemoryMapping {
+            start,
+            end,
+            file_offset: page_offset.wrapping_mul(page_size),
+            path: String::from_utf8_lossy(path).into_owned(),
+        });
+    }
+    Ok(maps)
+}
+
+/// Reads a little-endian `u64` at the given offset, which has to be in bounds
+fn read_u64(buf: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(buf[offset..offset + 8].try_into().unwrap())
+}
+
+/// Reads a little-endian `u64` at the given offset, if in bounds
+fn read_u64_checked(buf: &[u8], offset: usize) -> Option<u64> {
+    buf.get(offset..offset + 8).map(|_| read_u64(buf, offset))
+}
+
+/// A loadable segment of an ELF file
+#[derive(Debug, Clone, Copy)]
+struct Segment {
+    vaddr: u64,
+    offset: u64,
+    filesz: u64,
+}
+
+/// A note of an ELF file
+#[derive(Debug)]
+struct Note {
+    kind: u32,
+    desc: Vec<u8>,
+}
+
+/// Just enough of a 64 bit little-endian ELF parser for core dumps and symbolization
+#[derive(Debug)]
+struct ElfFile {
+    file: File,
+    machine: u16,
+    loads: Vec<Segment>,
+    /// Offset and size of the note segments
+    notes: Vec<(u64, u64)>,
+}
+
+impl ElfFile {
+    fn open(path: &Path) -> Result<Self, Error> {
+        let file = File::open(path)?;
+        let mut header = [0; 64];
+        file.read_exact_at(&mut header, 0)?;
+        if header[..4] != *b"\x7fELF" || header[4] != 2 || header[5] != 1 {
+            return Err(Error::illegal_argument(format!(
+                "{} is not a 64 bit little-endian ELF file",
+                path.display()
+            )));
+        }
+        let machine = u16::from_le_bytes([header[18], header[19]]);
+        let phoff = read_u64(&header, 32);
+        let phentsize = u64::from(u16::from_le_bytes([header[54], header[55]]));
+        let phnum = u64::from(u16::from_le_bytes([header[56], header[57]]));
+
+        let mut loads = Vec::new();
+        let mut notes = Vec::new();
+        let mut phdr = [0; 56];
+        for i in 0..phnum {
+            file.read_exact_at(&mut phdr, phoff + i * phentsize)?;
+            let p_type = u32::from_le_bytes(phdr[..4].try_into().unwrap());
+            let offset = read_u64(&phdr, 8);
+            let filesz = read_u64(&phdr, 32);
+            match p_type {
+                PT_LOAD => loads.push(Segment {
+                    vaddr: read_u64(&phdr, 16),
+                    offset,
+                    filesz,
+                }),
+                PT_NOTE => notes.push((offset, filesz)),
+                _ => {}
+            }
+        }
+        Ok(Self {
+            file,
+            machine,
+            loads,
+            notes,
+        })
+    }
+
+    fn notes(&self) -> Result<Vec<Note>, Error> {
+        let mut notes = Vec::new();
+        for (offset, size) in &self.notes {
+            let mut data = vec![0; *size as usize];
+            self.file.read_exact_at(&mut data, *offset)?;
+            let mut pos = 0;
+            while pos + 12 <= data.len() {
+                let field = |i: usize| {
+                    u32::from_le_bytes(data[pos + i * 4..pos + i * 4 + 4].try_into().unwrap())
+                        as usize
+                };
+                let (namesz, descsz, kind) = (field(0), field(1), field(2) as u32);
+                let desc_start = pos + 12 + namesz.next_multiple_of(4);
+                let Some(desc) = data.get(desc_start..desc_start + descsz) else {
+                    break;
+                };
+                notes.push(Note {
+                    kind,
+                    desc: desc.to_vec(),
+                });
+                pos = desc_start + descsz.next_multiple_of(4);
+            }
+        }
+        Ok(notes)
+    }
+
+    /// Reads a `u64` of the dumped memory at the given address
+    fn read_memory(&self, address: u64) -> Option<u64> {
+        let end = address.checked_add(8)?;
+        let segment = self
+            .loads
+            .iter()
+            .find(|segment| segment.vaddr <= address && end <= segment.vaddr + segment.filesz)?;
+        let mut buf = [0; 8];
+        self.file
+            .read_exact_at(&mut buf, segment.offset + address - segment.vaddr)
+            .ok()?;
+        Some(u64::from_le_bytes(buf))
+    }
+
+    /// Translates an offset in the file to the virtual address it gets loaded at, relative to the load base
+    fn file_offset_to_vaddr(&self, file_offset: u64) -> Option<u64> {
+        self.loads
+            .iter()
+            .find(|segment| {
+                segment.offset <= file_offset && file_offset < segment.offset + segment.filesz
+            })
+            .map(|segment| segment.vaddr + file_offset - segment.offset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{string::ToString, vec, vec::Vec};
+    use std::fs;
+
+    use libafl_bolts::tuples::Handled;
+
+    use super::{
+        CorePatternPart, CrashContextCapture, EM_X86_64, NT_FILE, NT_PRSTATUS,
+        PRSTATUS_REGS_OFFSET, PT_LOAD, PT_NOTE, X86_64_REGISTERS, core_pattern_parts,
+        matches_parts, parse_file_note,
+    };
+    use crate::observers::{CrashContext, CrashContextObserver, MemoryMapping};
+
+    /// Appends an ELF note with the `CORE` owner
+    fn push_note(buf: &mut Vec<u8>, kind: u32, desc: &[u8]) {
+        buf.extend_from_slice(&5u32.to_le_bytes());
+        buf.extend_from_slice(&(desc.len() as u32).to_le_bytes());
+        buf.extend_from_slice(&kind.to_le_bytes());
+        buf.extend_from_slice(b"CORE\0\0\0\0");
+        buf.extend_from_slice(desc);
+        buf.resize(buf.len().next_multiple_of(4), 0);
+    }
+
+    /// Appends a 64 bit program header
+    fn push_phdr(buf: &mut Vec<u8>, p_type: u32, offset: u64, vaddr: u64, size: u64) {
+        buf.extend_from_slice(&p_type.to_le_bytes());
+        buf.extend_from_slice(&0u32.to_le_bytes());
+        for field in [offset, vaddr, vaddr, size, size, 0] {
+            buf.extend_from_slice(&field.to_le_bytes());
+        }
+    }
+
+    /// Builds a minimal `x86_64` core dump of a thread at `pc`, with two frames on its stack at `stack`
+    fn synthetic_core(pc: u64, stack: u64) -> Vec<u8> {
+        let mut prstatus = vec![0; PRSTATUS_REGS_OFFSET + X86_64_REGISTERS.len() * 8];
+        let mut set_register = |name: &str, value: u64| {
+            let i = X86_64_REGISTERS
+                .iter()
+                .position(|reg| *reg == name)
+                .unwrap();
+            let offset = PRSTATUS_REGS_OFFSET + i * 8;
+            prstatus[offset..offset + 8].copy_from_slice(&value.to_le_bytes());
+        };
+        set_register("rip", pc);
+        set_register("rbp", stack);
+        set_register("rsp", stack);
+
+        let mut file_note = Vec::new();
+        for field in [1, 0x1000, 0x1000, 0x2000, 2] {
+            file_note.extend_from_slice(&u64::to_le_bytes(field));
+        }
+        file_note.extend_from_slice(b"/nonexistent/target\0");
+
+        let mut notes = Vec::new();
+        push_note(&mut notes, NT_PRSTATUS, &prstatus);
+        push_note(&mut notes, NT_FILE, &file_note);
+
+        // Frame records: saved frame pointer, then return address
+        let mut memory = Vec::new();
+        for field in [stack + 16, 0x1234, 0, 0x5678] {
+            memory.extend_from_slice(&u64::to_le_bytes(field));
+        }
+
+        let notes_offset = 64 + 2 * 56;
+        let memory_offset = notes_offset + notes.len() as u64;
+        let mut core = vec![0; 64];
+        core[..8].copy_from_slice(b"\x7fELF\x02\x01\x01\0");
+        // ET_CORE
+        core[16..18].copy_from_slice(&4u16.to_le_bytes());
+        core[18..20].copy_from_slice(&EM_X86_64.to_le_bytes());
+        core[32..40].copy_from_slice(&64u64.to_le_bytes());
+        core[52..54].copy_from_slice(&64u16.to_le_bytes());
+        core[54..56].copy_from_slice(&56u16.to_le_bytes());
+        core[56..58].copy_from_slice(&2u16.to_le_bytes());
+        push_phdr(&mut core, PT_NOTE, notes_offset, 0, notes.len() as u64);
+        push_phdr(
+            &mut core,
+            PT_LOAD,
+            memory_offset,
+            stack,
+            memory.len() as u64,
+        );
+        core.extend_from_slice(&notes);
+        core.extend_from_slice(&memory);
+        core
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_parse_core() {
+        let path = std::env::temp_dir().join(format!("libafl_core_{}", std::process::id()));
+        fs::write(&path, synthetic_core(0x1100, 0x7000)).unwrap();
+
+        let capture = CrashContextCapture::new(CrashContextObserver::new("crash_context").handle());
+        let mut context = CrashContext::default();
+        let result = capture.parse_core(&path, &mut context);
+        drop(fs::remove_file(&path));
+        result.unwrap();
+
+        assert_eq!(context.registers.len(), X86_64_REGISTERS.len());
+        assert!(context.registers.contains(&("rip".to_string(), 0x1100)));
+        assert!(context.registers.contains(&("rbp".to_string(), 0x7000)));
+        assert_eq!(
+            context.maps,
+            [MemoryMapping {
+                start: 0x1000,
+                end: 0x2000,
+                file_offset: 0x2000,
+                path: "/nonexistent/target".to_string(),
+            }]
+        );
+
+        let addresses: Vec<u64> = context
+            .backtrace
+            .iter()
+            .map(|frame| frame.address)
+            .collect();
+        assert_eq!(addresses, [0x1100, 0x1234, 0x5678]);
+        let modules: Vec<Option<&str>> = context
+            .backtrace
+            .iter()
+            .map(|frame| frame.module.as_deref())
+            .collect();
+        assert_eq!(
+            modules,
+            [
+                Some("/nonexistent/target"),
+                Some("/nonexistent/target"),
+                None
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_file_note_bad_count() {
+        // Counts whose entries would overflow, or not fit in the note
+        for count in [u64::MAX, 1 << 61, 1 << 40, 2] {
+            let mut desc = Vec::new();
+            desc.extend_from_slice(&count.to_le_bytes());
+            desc.extend_from_slice(&0x1000u64.to_le_bytes());
+            desc.extend_from_slice(&[0; 24]);
+            assert!(parse_file_note(&desc).is_err());
+        }
+    }
+
+    #[test]
+    fn test_core_pattern() {
+        let parts = core_pattern_parts("core.%e.%p", 1337, 11);
+        assert_eq!(
+            parts,
+            [
+                CorePatternPart::Literal("core.".into()),
+                CorePatternPart::Wildcard,
+                CorePatternPart::Literal(".1337".into()),
+            ]
+        );
+        assert!(matches_parts("core.target.1337", &parts));
+        assert!(!matches_parts("core.target.1338", &parts));
+        assert!(!matches_parts("core..1337", &parts));
+
+        let parts = core_pattern_parts("core", 1337, 11);
+        assert!(matches_parts("core", &parts));
+        assert!(!matches_parts("core.1337", &parts));
+    }
+}
@@ -19,14 +19,14 @@ use std::{
 };
 
 #[cfg(feature = "regex")]
-use libafl_bolts::tuples::{Handle, Handled};
+use libafl_bolts::tuples::Handled;
 use libafl_bolts::{
     AsSlice, AsSliceMut, InputLocation, StdTargetArgs, StdTargetArgsInner, Truncate,
     core_affinity::CoreId,
     fs::{InputFile, get_unique_std_input_file},
     os::{dup2, last_error_str, pipes::Pipe},
     shmem::{ShMem, ShMemProvider, UnixShMem, UnixShMemProvider},
-    tuples::{MatchNameRef, Prepend, RefIndexable},
+    tuples::{Handle, MatchNameRef, Prepend, RefIndexable},
 };
 use libc::RLIM_INFINITY;
 use nix::{
@@ -39,7 +39,14 @@ use nix::{
     unistd::Pid,
 };
 
-use super::{HasTimeout, StdChildArgs, StdChildArgsInner};
+#[cfg(target_os = "linux")]
+use super::crash_context::CrashContextCapture;
+use super::{
+    HasTimeout, StdChildArgs, StdChildArgsInner,
+    resource_limits::{ResourceLimiter, ResourceLimits},
+};
+#[cfg(target_os = "linux")]
+use crate::observers::ProcessExit;
 #[cfg(feature = "regex")]
 use crate::observers::{
     AsanBacktraceObserver, get_asan_runtime_flags, get_asan_runtime_flags_with_log_path,
@@ -49,7 +56,7 @@ use crate::{
     executors::{Executor, ExitKind, HasObservers},
     inputs::{Input, ToTargetBytes},
     mutators::Tokens,
-    observers::{MapObserver, Observer, ObserversTuple},
+    observers::{MapObserver, Observer, ObserversTuple, ProcessExitObserver},
     state::HasExecutions,
 };
 
@@ -413,6 +420,8 @@ impl Forkserver {
         stderr_memfd: Option<RawFd>,
         cwd: Option<PathBuf>,
         core: Option<CoreId>,
+        resource_limiter: Option<&ResourceLimiter>,
+        coredumps: bool,
     ) -> Result<Self, Error> {
         let Some(coverage_map_size) = coverage_map_size else {
             return Err(Error::unknown(
@@ -420,7 +429,8 @@ impl Forkserver {
             ));
         };
 
-        if env::var(SHM_ENV_VAR).is_err() {
+        // The map id is in our env, or in the env of the target only
+        if env::var(SHM_ENV_VAR).is_err() && !envs.iter().any(|(key, _)| key == SHM_ENV_VAR) {
             return Err(Error::unknown("__AFL_SHM_ID not set. It is necessary to set this env, otherwise the forkserver cannot communicate with the fuzzer".to_string()));
         }
 
@@ -511,23 +521,27 @@ impl Forkserver {
             command.current_dir(cwd);
         }
 
+        command
+            .env("LD_BIND_NOW", "1")
+            .envs(envs)
+            .setlimit(memlimit)
+            .set_coredump(afl_debug || coredumps);
+        // The forkserver applies the limits to itself, its children inherit them
+        if let Some(resource_limiter) = resource_limiter {
+            resource_limiter.configure_command(&mut command);
+        }
+
         // # Saftey
         // The pipe file descriptors used for `setpipe` are valid at this point.
         let fsrv_handle = unsafe {
-            match ConfigTarget::setsid(
-                command
-                    .env("LD_BIND_NOW", "1")
-                    .envs(envs)
-                    .setlimit(memlimit)
-                    .set_coredump(afl_debug),
-            )
-            .setpipe(
-                st_pipe.read_end().unwrap(),
-                st_pipe.write_end().unwrap(),
-                ctl_pipe.read_end().unwrap(),
-                ctl_pipe.write_end().unwrap(),
-            )
-            .spawn()
+            match ConfigTarget::setsid(&mut command)
+                .setpipe(
+                    st_pipe.read_end().unwrap(),
+                    st_pipe.write_end().unwrap(),
+                    ctl_pipe.read_end().unwrap(),
+                    ctl_pipe.write_end().unwrap(),
+                )
+                .spawn()
             {
                 Ok(fsrv_handle) => fsrv_handle,
                 Err(err) => {
@@ -714,6 +728,10 @@ pub struct ForkserverExecutor<I, OT, S, SHM> {
     asan_obs: Handle<AsanBacktraceObserver>,
     timeout: TimeSpec,
     crash_exitcode: Option<i8>,
+    resource_limiter: Option<ResourceLimiter>,
+    process_exit_obs: Option<Handle<ProcessExitObserver>>,
+    #[cfg(target_os = "linux")]
+    crash_context: Option<CrashContextCapture>,
 }
 
 impl<I, OT, S, SHM> Debug for ForkserverExecutor<I, OT, S, SHM>
@@ -812,8 +830,15 @@ where
     /// Execute input, but side-step the execution counter.
     #[inline]
     fn execute_input_uncounted(&mut self, input: &[u8]) -> Result<ExitKind, Error> {
-        let mut exit_kind = ExitKind::Ok;
+        self.start_run(input)?;
+        let timeout = self.timeout;
+        self.finish_run(&timeout)
+    }
 
+    /// Hand the input to the target and request a new child from the forkserver, without waiting for it to finish.
+    ///
+    /// Must be followed by a call to [`Self::finish_run`] before the next run is started.
+    pub(crate) fn start_run(&mut self, input: &[u8]) -> Result<(), Error> {
         let last_run_timed_out = self.forkserver.last_run_timed_out_raw();
 
         let mut input_size = input.len();
@@ -853,9 +878,34 @@ where
         }
 
         self.forkserver.set_child_pid(Pid::from_raw(pid));
+        Ok(())
+    }
+
+    /// Wait up to `timeout` for the child started by [`Self::start_run`] to finish.
+    pub(crate) fn finish_run(&mut self, timeout: &TimeSpec) -> Result<ExitKind, Error> {
+        let mut exit_kind = ExitKind::Ok;
 
-        if let Some(status) = self.forkserver.read_st_timed(&self.timeout)? {
+        let mut signal = None;
+        if let Some(status) = self.forkserver.read_st_timed(timeout)? {
             self.forkserver.set_status(status);
+            if libc::WIFSIGNALED(status) {
+                signal = Some(libc::WTERMSIG(status));
+            }
+            if let Some(process_exit_observer) = self
+                .process_exit_obs
+                .as_ref()
+                .and_then(|handle| self.observers.get_mut(handle))
+            {
+                process_exit_observer.observe_raw_status(status);
+            }
+            #[cfg(target_os = "linux")]
+            if let Some(crash_context) = &self.crash_context {
+                crash_context.capture(
+                    &mut self.observers,
+                    self.forkserver.child_pid().as_raw(),
+                    &ProcessExit::from_raw_status(status),
+                );
+            }
             let exitcode_is_crash = if let Some(crash_exitcode) = self.crash_exitcode {
                 (libc::WEXITSTATUS(self.forkserver().status()) as i8) == crash_exitcode
             } else {
@@ -865,7 +915,9 @@ where
                 exit_kind = ExitKind::Crash;
                 #[cfg(feature = "regex")]
                 if let Some(asan_observer) = self.observers.get_mut(&self.asan_obs) {
-                    asan_observer.parse_asan_output_from_asan_log_file(pid)?;
+                    asan_observer.parse_asan_output_from_asan_log_file(
+                        self.forkserver.child_pid().as_raw(),
+                    )?;
                 }
             }
         } else {
@@ -885,6 +937,10 @@ where
             self.forkserver.reset_child_pid();
         }
 
+        if let Some(resource_limiter) = &mut self.resource_limiter {
+            exit_kind = resource_limiter.check_exit(&mut self.observers, signal, exit_kind);
+        }
+
         Ok(exit_kind)
     }
 }
@@ -952,7 +1008,14 @@ where
     where
         OT: ObserversTuple<I, S>,
     {
-        let (forkserver, input_file, map) = self.build_helper(&observers)?;
+        let resource_limiter = self
+            .child_env_inner
+            .resource_limits
+            .as_ref()
+            .map(ResourceLimits::build)
+            .transpose()?;
+        let (forkserver, input_file, map) =
+            self.build_helper(&observers, resource_limiter.as_ref())?;
 
         let target = self.target_inner.program.take().unwrap();
         log::info!(
@@ -998,6 +1061,10 @@ where
                 .clone()
                 .unwrap_or(AsanBacktraceObserver::default().handle()),
             crash_exitcode: self.crash_exitcode,
+            resource_limiter,
+            process_exit_obs: self.child_env_inner.process_exit_observer.clone(),
+            #[cfg(target_os = "linux")]
+            crash_context: self.crash_context_capture(),
         })
     }
 
@@ -1014,7 +1081,14 @@ where
         MO: MapObserver + Truncate, // TODO maybe enforce Entry = u8 for the cov map
         OT: ObserversTuple<I, S> + Prepend<MO>,
     {
-        let (forkserver, input_file, map) = self.build_helper(&other_observers)?;
+        let resource_limiter = self
+            .child_env_inner
+            .resource_limits
+            .as_ref()
+            .map(ResourceLimits::build)
+            .transpose()?;
+        let (forkserver, input_file, map) =
+            self.build_helper(&other_observers, resource_limiter.as_ref())?;
 
         let target = self.target_inner.program.take().unwrap();
         log::info!(
@@ -1058,6 +1132,10 @@ where
                 .clone()
                 .unwrap_or(AsanBacktraceObserver::default().handle()),
             crash_exitcode: self.crash_exitcode,
+            resource_limiter,
+            process_exit_obs: self.child_env_inner.process_exit_observer.clone(),
+            #[cfg(target_os = "linux")]
+            crash_context: self.crash_context_capture(),
         })
     }
 
@@ -1065,6 +1143,7 @@ where
     fn build_helper<I, OT, S>(
         &mut self,
         obs: &OT,
+        resource_limiter: Option<&ResourceLimiter>,
     ) -> Result<(Forkserver, InputFile, Option<SHM>), Error>
     where
         OT: ObserversTuple<I, S>,
@@ -1133,6 +1212,8 @@ where
                 }),
                 self.child_env_inner.current_directory.clone(),
                 self.child_env_inner.core,
+                resource_limiter,
+                self.has_crash_context(),
             )?,
             None => {
                 return Err(Error::illegal_argument(
@@ -1448,6 +1529,29 @@ where
     pub fn has_asan_obs(&self) -> bool {
         false
     }
+
+    /// Determine if the context of crashed children gets collected, enabling their core dumps
+    #[cfg(target_os = "linux")]
+    fn has_crash_context(&self) -> bool {
+        self.child_env_inner.crash_context.is_some()
+    }
+
+    /// Determine if the context of crashed children gets collected (always false on other platforms than Linux)
+    #[cfg(not(target_os = "linux"))]
+    fn has_crash_context(&self) -> bool {
+        false
+    }
+
+    /// The crash context capture, with core dumps of the children in their working directory by default
+    #[cfg(target_os = "linux")]
+    fn crash_context_capture(&self) -> Option<CrashContextCapture> {
+        self.child_env_inner
+            .crash_context
+            .clone()
+            .map(|crash_context| {
+                crash_context.default_core_dir(self.child_env_inner.current_directory.as_deref())
+            })
+    }
 }
 
 impl<'a> ForkserverExecutorBuilder<'a, UnixShMemProvider> {
@@ -8,9 +8,8 @@ use core::{
 };
 
 use libafl_bolts::{
-    os::unix_signals::Signal,
     shmem::ShMemProvider,
-    tuples::{Merge, RefIndexable, tuple_list},
+    tuples::{Handle, MatchNameRef, Merge, RefIndexable, tuple_list},
 };
 use nix::{
     sys::wait::{WaitStatus, waitpid},
@@ -27,8 +26,9 @@ use crate::{
             ExecutorHooksTuple,
             inprocess_fork::{FORK_EXECUTOR_GLOBAL_DATA, InChildProcessHooks},
         },
+        resource_limits::ResourceLimiter,
     },
-    observers::ObserversTuple,
+    observers::{ObserversTuple, ProcessExit, ProcessExitObserver},
 };
 
 /// Inner state of GenericInProcessExecutor-like structures.
@@ -40,6 +40,8 @@ pub struct GenericInProcessForkExecutorInner<EM, HT, I, OT, S, SP, Z> {
     pub(super) itimerspec: libc::itimerspec,
     #[cfg(all(unix, not(target_os = "linux")))]
     pub(super) itimerval: Itimerval,
+    pub(super) resource_limiter: Option<ResourceLimiter>,
+    pub(super) process_exit_observer: Option<Handle<ProcessExitObserver>>,
     pub(super) phantom: PhantomData<(EM, I, S, Z)>,
 }
 
@@ -120,6 +122,10 @@ where
         unsafe {
             self.shmem_provider.post_fork(true)?;
 
+            if let Some(resource_limiter) = &self.resource_limiter {
+                resource_limiter.apply_to_current_process()?;
+            }
+
             self.enter_target(fuzzer, state, mgr, input);
             self.hooks.pre_exec_all(state, input);
 
@@ -172,30 +178,37 @@ where
 
         let res = waitpid(child, None)?;
         log::trace!("{res:#?}");
-        match res {
-            WaitStatus::Signaled(_, signal, _) => match signal {
-                nix::sys::signal::Signal::SIGALRM | nix::sys::signal::Signal::SIGUSR2 => {
-                    Ok(ExitKind::Timeout)
-                }
-                _ => Ok(ExitKind::Crash),
+        // The crash and timeout handlers of the child exit with `128 + signal`
+        let exit = match res {
+            WaitStatus::Exited(_, code) if code > 128 && code < 160 => ProcessExit {
+                signal: Some(code - 128),
+                ..ProcessExit::default()
             },
-            WaitStatus::Exited(_, code) => {
-                if code > 128 && code < 160 {
-                    // Signal exit codes
-                    let signal = code - 128;
-                    if signal == Signal::SigAlarm as libc::c_int
-                        || signal == Signal::SigUser2 as libc::c_int
-                    {
-                        Ok(ExitKind::Timeout)
-                    } else {
-                        Ok(ExitKind::Crash)
-                    }
-                } else {
-                    Ok(ExitKind::Ok)
-                }
+            WaitStatus::Exited(..) | WaitStatus::Signaled(..) => {
+                ProcessExit::from_wait_status(&res).unwrap()
             }
             _ => panic!("Unexpected waitpid exit: {res:?}"),
+        };
+        let exit_kind = match exit.signal {
+            Some(libc::SIGALRM | libc::SIGUSR2) => ExitKind::Timeout,
+            Some(_) => ExitKind::Crash,
+            None => ExitKind::Ok,
+        };
+
+        // After a timeout, the exit is the one of the timeout handler
+        if let Some(observer) = self
+            .process_exit_observer
+            .as_ref()
+            .filter(|_| exit_kind != ExitKind::Timeout)
+            .and_then(|handle| self.observers.get_mut(handle))
+        {
+            observer.observe(exit);
+        }
+
+        if let Some(resource_limiter) = &mut self.resource_limiter {
+            return Ok(resource_limiter.check_exit(&mut self.observers, exit.signal, exit_kind));
         }
+        Ok(exit_kind)
     }
 }
 
@@ -257,6 +270,8 @@ where
             observers,
             hooks,
             itimerspec,
+            resource_limiter: None,
+            process_exit_observer: None,
             phantom: PhantomData,
         })
     }
@@ -283,6 +298,8 @@ where
             observers,
             hooks,
             itimerval,
+            resource_limiter: None,
+            process_exit_observer: None,
             phantom: PhantomData,
         })
     }
@@ -7,7 +7,7 @@ use core::{
 use libafl_bolts::{
     os::unix_signals::{Signal, ucontext_t},
     shmem::ShMemProvider,
-    tuples::{RefIndexable, tuple_list},
+    tuples::{Handle, RefIndexable, tuple_list},
 };
 use libc::siginfo_t;
 use nix::unistd::{ForkResult, fork};
@@ -17,9 +17,9 @@ use crate::{
     Error,
     executors::{
         Executor, ExitKind, HasObservers, hooks::inprocess_fork::InProcessForkExecutorGlobalData,
-        inprocess_fork::inner::GenericInProcessForkExecutorInner,
+        inprocess_fork::inner::GenericInProcessForkExecutorInner, resource_limits::ResourceLimits,
     },
-    observers::ObserversTuple,
+    observers::{ObserversTuple, ProcessExitObserver},
     state::HasExecutions,
 };
 
@@ -173,6 +173,21 @@ where {
         })
     }
 
+    /// Enforce the given [`ResourceLimits`] on each forked child.
+    ///
+    /// The children start out with the memory of the whole fuzzer, keep that in mind for memory limits.
+    pub fn with_resource_limits(mut self, resource_limits: &ResourceLimits) -> Result<Self, Error> {
+        self.inner.resource_limiter = Some(resource_limits.build()?);
+        Ok(self)
+    }
+
+    /// Record how each forked child exited in the given [`ProcessExitObserver`]
+    #[must_use]
+    pub fn with_process_exit_observer(mut self, process_exit: Handle<ProcessExitObserver>) -> Self {
+        self.inner.process_exit_observer = Some(process_exit);
+        self
+    }
+
     /// Retrieve the harness function.
     #[inline]
     pub fn harness(&self) -> &H {
@@ -367,6 +382,8 @@ mod tests {
                 shmem_provider: provider,
                 observers: tuple_list!(),
                 itimerspec,
+                resource_limiter: None,
+                process_exit_observer: None,
                 phantom: PhantomData,
             },
         };
@@ -378,6 +395,8 @@ mod tests {
                 shmem_provider: provider,
                 observers: tuple_list!(),
                 itimerval: itimerspec,
+                resource_limiter: None,
+                process_exit_observer: None,
                 phantom: PhantomData,
             },
         };
@@ -390,4 +409,57 @@ mod tests {
             .run_target(&mut fuzzer, &mut state, &mut mgr, &input)
             .unwrap();
     }
+
+    #[test]
+    #[serial]
+    #[cfg_attr(miri, ignore)]
+    fn test_inprocessfork_crash_signal() {
+        use core::time::Duration;
+
+        use libafl_bolts::{
+            shmem::{ShMemProvider, StdShMemProvider},
+            tuples::Handled,
+        };
+
+        use crate::{
+            events::SimpleEventManager,
+            executors::{HasObservers, InProcessForkExecutor},
+            fuzzer::NopFuzzer,
+            observers::ProcessExitObserver,
+            state::NopState,
+        };
+
+        let mut harness = |_buf: &NopInput| {
+            unsafe {
+                libc::raise(libc::SIGSEGV);
+            }
+            ExitKind::Ok
+        };
+        let observer = ProcessExitObserver::new("process_exit");
+        let handle = observer.handle();
+        let mut fuzzer = NopFuzzer::new();
+        let mut state = NopState::<NopInput>::new();
+        let mut mgr: SimpleEventManager<NopInput, _, NopState<NopInput>> =
+            SimpleEventManager::printing();
+        let mut executor = InProcessForkExecutor::new(
+            &mut harness,
+            tuple_list!(observer),
+            &mut fuzzer,
+            &mut state,
+            &mut mgr,
+            Duration::from_secs(5),
+            StdShMemProvider::new().unwrap(),
+        )
+        .unwrap()
+        .with_process_exit_observer(handle.clone());
+
+        let exit_kind = executor
+            .run_target(&mut fuzzer, &mut state, &mut mgr, &NopInput {})
+            .unwrap();
+        assert_eq!(exit_kind, ExitKind::Crash);
+        // The crash handler of the child exits with `128 + signal`, which is decoded back to the signal
+        let exit = *executor.observers()[&handle].last_exit().unwrap();
+        assert_eq!(exit.signal, Some(libc::SIGSEGV));
+        assert_eq!(exit.exit_code, None);
+    }
 }
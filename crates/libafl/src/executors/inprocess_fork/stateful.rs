@@ -9,7 +9,7 @@ use core::{
 
 use libafl_bolts::{
     shmem::ShMemProvider,
-    tuples::{RefIndexable, tuple_list},
+    tuples::{Handle, RefIndexable, tuple_list},
 };
 use nix::unistd::{ForkResult, fork};
 
@@ -17,9 +17,9 @@ use crate::{
     Error,
     executors::{
         Executor, ExitKind, HasObservers, hooks::ExecutorHooksTuple,
-        inprocess_fork::GenericInProcessForkExecutorInner,
+        inprocess_fork::GenericInProcessForkExecutorInner, resource_limits::ResourceLimits,
     },
-    observers::ObserversTuple,
+    observers::{ObserversTuple, ProcessExitObserver},
     state::HasExecutions,
 };
 
@@ -168,6 +168,21 @@ where
         })
     }
 
+    /// Enforce the given [`ResourceLimits`] on each forked child.
+    ///
+    /// The children start out with the memory of the whole fuzzer, keep that in mind for memory limits.
+    pub fn with_resource_limits(mut self, resource_limits: &ResourceLimits) -> Result<Self, Error> {
+        self.inner.resource_limiter = Some(resource_limits.build()?);
+        Ok(self)
+    }
+
+    /// Record how each forked child exited in the given [`ProcessExitObserver`]
+    #[must_use]
+    pub fn with_process_exit_observer(mut self, process_exit: Handle<ProcessExitObserver>) -> Self {
+        self.inner.process_exit_observer = Some(process_exit);
+        self
+    }
+
     /// Retrieve the harness function.
     #[inline]
     pub fn harness(&self) -> &H {
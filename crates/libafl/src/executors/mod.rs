@@ -5,6 +5,8 @@ use core::{fmt::Debug, time::Duration};
 #[cfg(feature = "std")]
 use std::path::PathBuf;
 
+#[cfg(all(feature = "std", feature = "fork", unix))]
+pub use batched_forkserver::BatchedForkserverExecutor;
 pub use combined::CombinedExecutor;
 #[cfg(feature = "std")]
 pub use command::CommandExecutor;
@@ -19,22 +21,36 @@ use libafl_bolts::os::unix_signals::Signal;
 use libafl_bolts::tuples::RefIndexable;
 #[cfg(feature = "std")]
 use libafl_bolts::{core_affinity::CoreId, tuples::Handle};
+#[cfg(feature = "otlp_monitor")]
+pub use otlp::OtlpExecutor;
 use serde::{Deserialize, Serialize};
 pub use shadow::ShadowExecutor;
 pub use with_observers::WithObservers;
 
 use crate::Error;
+#[cfg(all(feature = "std", target_os = "linux"))]
+use crate::executors::crash_context::CrashContextCapture;
 #[cfg(feature = "std")]
 use crate::observers::{StdErrObserver, StdOutObserver};
+#[cfg(all(feature = "std", unix))]
+use crate::{executors::resource_limits::ResourceLimits, observers::ProcessExitObserver};
 
+#[cfg(all(feature = "std", feature = "fork", unix))]
+pub mod batched_forkserver;
 pub mod combined;
 #[cfg(feature = "std")]
 pub mod command;
+#[cfg(all(feature = "std", target_os = "linux"))]
+pub mod crash_context;
 pub mod differential;
 #[cfg(all(feature = "std", feature = "fork", unix))]
 pub mod forkserver;
 pub mod inprocess;
 pub mod nop;
+#[cfg(feature = "otlp_monitor")]
+pub mod otlp;
+#[cfg(all(feature = "std", unix))]
+pub mod resource_limits;
 /// SAND(<https://github.com/wtdcode/sand-aflpp>) implementation
 pub mod sand;
 
@@ -136,6 +152,18 @@ pub trait Executor<EM, I, S, Z> {
     ) -> Result<ExitKind, Error>;
 }
 
+/// An executor that can run several inputs concurrently, ahead of their evaluation.
+///
+/// The results of the last dispatched batch are handed out by [`Executor::run_target`],
+/// when it is called with the same inputs, in the same order.
+pub trait BatchExecutor<I, S, Z> {
+    /// The maximum number of inputs [`BatchExecutor::dispatch_batch`] accepts at once
+    fn max_batch_size(&self) -> usize;
+
+    /// Run all `inputs` concurrently and keep their results until they get picked up
+    fn dispatch_batch(&mut self, fuzzer: &mut Z, state: &mut S, inputs: &[I]) -> Result<(), Error>;
+}
+
 /// A trait that allows to get/set an `Executor`'s timeout thresold
 pub trait HasTimeout {
     /// Get a timeout
@@ -249,6 +277,15 @@ pub struct StdChildArgsInner {
     pub debug_child: bool,
     /// Core to bind for the children
     pub core: Option<CoreId>,
+    /// Resource limits for the children
+    #[cfg(unix)]
+    pub resource_limits: Option<ResourceLimits>,
+    /// The observer recording how the children exited
+    #[cfg(unix)]
+    pub process_exit_observer: Option<Handle<ProcessExitObserver>>,
+    /// Collects the context of crashed children
+    #[cfg(target_os = "linux")]
+    pub crash_context: Option<CrashContextCapture>,
 }
 
 #[cfg(feature = "std")]
@@ -261,6 +298,12 @@ impl Default for StdChildArgsInner {
             current_directory: None,
             debug_child: false,
             core: None,
+            #[cfg(unix)]
+            resource_limits: None,
+            #[cfg(unix)]
+            process_exit_observer: None,
+            #[cfg(target_os = "linux")]
+            crash_context: None,
         }
     }
 }
@@ -322,6 +365,30 @@ pub trait StdChildArgs: Sized {
         self.inner_mut().core = Some(core);
         self
     }
+
+    #[cfg(unix)]
+    #[must_use]
+    /// Set the resource limits for the children
+    fn resource_limits(mut self, resource_limits: ResourceLimits) -> Self {
+        self.inner_mut().resource_limits = Some(resource_limits);
+        self
+    }
+
+    #[cfg(unix)]
+    #[must_use]
+    /// Sets the observer recording how the children exited
+    fn process_exit_observer(mut self, process_exit: Handle<ProcessExitObserver>) -> Self {
+        self.inner_mut().process_exit_observer = Some(process_exit);
+        self
+    }
+
+    #[cfg(target_os = "linux")]
+    #[must_use]
+    /// Collects the context of crashed children from their core dumps
+    fn crash_context(mut self, crash_context: CrashContextCapture) -> Self {
+        self.inner_mut().crash_context = Some(crash_context);
+        self
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,81 @@
+//! A wrapper for any [`Executor`] recording a span for a sample of its runs, for OpenTelemetry.
+//! See [`crate::monitors::otlp`].
+
+use alloc::format;
+
+use libafl_bolts::{current_time, tuples::RefIndexable};
+
+use crate::{
+    Error,
+    executors::{Executor, ExitKind, HasObservers},
+    monitors::otlp::OtlpExporter,
+};
+
+/// Records a `run_target` span for one in every `sample_rate` runs of the inner executor
+/// to the [`OtlpExporter::global`] exporter, with the [`ExitKind`] as attribute.
+#[derive(Debug)]
+pub struct OtlpExecutor<E> {
+    inner: E,
+    sample_rate: u64,
+    runs: u64,
+}
+
+impl<E> OtlpExecutor<E> {
+    /// Wraps the `inner` executor, recording one in every `sample_rate` runs
+    pub fn new(inner: E, sample_rate: u64) -> Self {
+        Self {
+            inner,
+            sample_rate: sample_rate.max(1),
+            runs: 0,
+        }
+    }
+
+    /// The wrapped executor
+    pub fn inner(&mut self) -> &mut E {
+        &mut self.inner
+    }
+}
+
+impl<E, EM, I, S, Z> Executor<EM, I, S, Z> for OtlpExecutor<E>
+where
+    E: Executor<EM, I, S, Z>,
+{
+    fn run_target(
+        &mut self,
+        fuzzer: &mut Z,
+        state: &mut S,
+        mgr: &mut EM,
+        input: &I,
+    ) -> Result<ExitKind, Error> {
+        self.runs += 1;
+        let exporter = match OtlpExporter::global() {
+            Some(exporter) if self.runs.is_multiple_of(self.sample_rate) => exporter,
+            _ => return self.inner.run_target(fuzzer, state, mgr, input),
+        };
+        let start = current_time();
+        let result = self.inner.run_target(fuzzer, state, mgr, input);
+        let mut span = exporter
+            .span("run_target", start)
+            .attribute("executor.sample_rate", self.sample_rate);
+        if let Ok(exit_kind) = &result {
+            span = span.attribute("executor.exit_kind", format!("{exit_kind:?}"));
+        }
+        exporter.record(span);
+        result
+    }
+}
+
+impl<E> HasObservers for OtlpExecutor<E>
+where
+    E: HasObservers,
+{
+    type Observers = E::Observers;
+
+    fn observers(&self) -> RefIndexable<&Self::Observers, Self::Observers> {
+        self.inner.observers()
+    }
+
+    fn observers_mut(&mut self) -> RefIndexable<&mut Self::Observers, Self::Observers> {
+        self.inner.observers_mut()
+    }
+}
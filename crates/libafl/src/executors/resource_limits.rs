@@ -0,0 +1,502 @@
+//! Resource limits (`setrlimit` and cgroup v2) for the children of fork-based executors.
+//!
+//! Configure [`ResourceLimits`] and hand them to the [`crate::executors::ForkserverExecutor`] or
+//! [`crate::executors::CommandExecutor`] builders (via [`crate::executors::StdChildArgs::resource_limits`]),
+//! or to the `InProcessForkExecutor`.
+//! Detected violations are reported as [`ExitKind::Oom`], and the violated resource
+//! is stored in a [`ResourceLimitObserver`], if one is set.
+//!
+//! `RLIMIT_FSIZE` violations are detected by their `SIGXFSZ`, cgroup violations by the cgroup's event counters
+//! (`oom_kill` for the memory).
+//! The kernel does not report `RLIMIT_AS` and `RLIMIT_DATA` violations, a failed allocation usually ends in an
+//! abort or a segfault, but so do genuine crashes. These crashes stay crashes, the limit is only recorded
+//! as suspected in the observer, as is a crash after the cgroup hit `memory.max` without an OOM kill.
+//! `RLIMIT_NOFILE` violations only make syscalls fail with `EMFILE` inside the child, and are not detected.
+
+use alloc::{
+    ffi::CString,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::ffi::c_int;
+use std::{
+    fs, io,
+    os::unix::{ffi::OsStrExt, process::CommandExt},
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use libafl_bolts::tuples::{Handle, MatchNameRef};
+
+use crate::{
+    Error,
+    executors::ExitKind,
+    observers::{LimitedResource, ResourceLimitObserver},
+};
+
+/// The resource type `setrlimit` takes on this platform
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+type RlimitResource = libc::__rlimit_resource_t;
+/// The resource type `setrlimit` takes on this platform
+#[cfg(not(all(target_os = "linux", target_env = "gnu")))]
+type RlimitResource = c_int;
+
+/// The default mount point of the cgroup v2 hierarchy
+pub const DEFAULT_CGROUP_ROOT: &str = "/sys/fs/cgroup";
+
+/// Limits for a cgroup v2 the children get moved to.
+///
+/// The controllers for the limits (`memory`, `pids`) must be enabled in the `cgroup.subtree_control` of
+/// the parent cgroup, and the fuzzer needs write access to it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CgroupLimits {
+    parent: PathBuf,
+    name: String,
+    memory_max: Option<u64>,
+    pids_max: Option<u64>,
+}
+
+impl CgroupLimits {
+    /// Creates new [`CgroupLimits`] for a cgroup with the given `name`, below [`DEFAULT_CGROUP_ROOT`].
+    #[must_use]
+    pub fn new<S>(name: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            parent: PathBuf::from(DEFAULT_CGROUP_ROOT),
+            name: name.into(),
+            memory_max: None,
+            pids_max: None,
+        }
+    }
+
+    /// The parent cgroup to create the new cgroup in
+    #[must_use]
+    pub fn parent<P>(mut self, parent: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        self.parent = parent.into();
+        self
+    }
+
+    /// Limit the memory of all processes in the cgroup to `bytes` (`memory.max`)
+    #[must_use]
+    pub fn memory_max(mut self, bytes: u64) -> Self {
+        self.memory_max = Some(bytes);
+        self
+    }
+
+    /// Limit the number of processes in the cgroup (`pids.max`)
+    #[must_use]
+    pub fn pids_max(mut self, pids: u64) -> Self {
+        self.pids_max = Some(pids);
+        self
+    }
+
+    /// The path of the cgroup
+    #[must_use]
+    pub fn path(&self) -> PathBuf {
+        self.parent.join(&self.name)
+    }
+}
+
+/// Resource limits applied to each child of an executor.
+///
+/// All `setrlimit` values set both the soft and the hard limit.
+/// Note that the in-process fork executor forks the whole fuzzer, so its children start out with the memory
+/// of the fuzzer already mapped.
+#[derive(Debug, Clone, Default)]
+pub struct ResourceLimits {
+    address_space: Option<u64>,
+    data: Option<u64>,
+    open_files: Option<u64>,
+    file_size: Option<u64>,
+    core: Option<u64>,
+    cgroup: Option<CgroupLimits>,
+    observer: Option<Handle<ResourceLimitObserver>>,
+}
+
+impl ResourceLimits {
+    /// Creates new, empty [`ResourceLimits`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limit the virtual address space to `bytes` (`RLIMIT_AS`)
+    #[must_use]
+    pub fn address_space(mut self, bytes: u64) -> Self {
+        self.address_space = Some(bytes);
+        self
+    }
+
+    /// Limit the data segment, including the heap, to `bytes` (`RLIMIT_DATA`)
+    #[must_use]
+    pub fn data(mut self, bytes: u64) -> Self {
+        self.data = Some(bytes);
+        self
+    }
+
+    /// Limit the number of open file descriptors (`RLIMIT_NOFILE`)
+    #[must_use]
+    pub fn open_files(mut self, fds: u64) -> Self {
+        self.open_files = Some(fds);
+        self
+    }
+
+    /// Limit the size of files the child may write to `bytes` (`RLIMIT_FSIZE`)
+    #[must_use]
+    pub fn file_size(mut self, bytes: u64) -> Self {
+        self.file_size = Some(bytes);
+        self
+    }
+
+    /// Limit the size of core dumps to `bytes` (`RLIMIT_CORE`)
+    #[must_use]
+    pub fn core(mut self, bytes: u64) -> Self {
+        self.core = Some(bytes);
+        self
+    }
+
+    /// Move the children to a cgroup v2 with the given limits
+    #[must_use]
+    pub fn cgroup(mut self, cgroup: CgroupLimits) -> Self {
+        self.cgroup = Some(cgroup);
+        self
+    }
+
+    /// Report the violated resource to this observer after each run
+    #[must_use]
+    pub fn observer(mut self, observer: Handle<ResourceLimitObserver>) -> Self {
+        self.observer = Some(observer);
+        self
+    }
+
+    /// Sets up the cgroup, if any, and returns the [`ResourceLimiter`] enforcing these limits.
+    // libc::rlim_t is i64 in freebsd and trivial_numeric_casts check will failed
+    #[cfg_attr(not(target_os = "freebsd"), expect(trivial_numeric_casts))]
+    pub fn build(&self) -> Result<ResourceLimiter, Error> {
+        let rlimits = [
+            (libc::RLIMIT_AS, self.address_space),
+            (libc::RLIMIT_DATA, self.data),
+            (libc::RLIMIT_NOFILE, self.open_files),
+            (libc::RLIMIT_FSIZE, self.file_size),
+            (libc::RLIMIT_CORE, self.core),
+        ]
+        .into_iter()
+        .filter_map(|(resource, limit)| limit.map(|limit| (resource, limit as libc::rlim_t)))
+        .collect();
+
+        let cgroup = self.cgroup.as_ref().map(Cgroup::create).transpose()?;
+
+        Ok(ResourceLimiter {
+            rlimits,
+            limits_file_size: self.file_size.is_some(),
+            limits_memory: self.address_space.is_some() || self.data.is_some(),
+            cgroup,
+            observer: self.observer.clone(),
+        })
+    }
+}
+
+/// A cgroup v2 created for the children of an executor
+#[derive(Debug)]
+struct Cgroup {
+    path: PathBuf,
+    /// `cgroup.procs`, prepared to be opened in the child between `fork` and `exec`
+    procs: CString,
+    oom_kills: u64,
+    memory_max_hits: u64,
+    pids_max_hits: u64,
+}
+
+impl Cgroup {
+    fn create(limits: &CgroupLimits) -> Result<Self, Error> {
+        let path = limits.path();
+        fs::create_dir_all(&path).map_err(|err| {
+            Error::os_error(err, format!("Could not create cgroup {}", path.display()))
+        })?;
+
+        if let Some(memory_max) = limits.memory_max {
+            write_cgroup_file(&path, "memory.max", memory_max)?;
+            // Don't let the children escape to swap, this is best effort
+            drop(write_cgroup_file(&path, "memory.swap.max", 0));
+        }
+        if let Some(pids_max) = limits.pids_max {
+            write_cgroup_file(&path, "pids.max", pids_max)?;
+        }
+
+        let procs = CString::new(path.join("cgroup.procs").as_os_str().as_bytes())
+            .map_err(|_| Error::illegal_argument("Cgroup path must not contain NUL bytes"))?;
+
+        let mut cgroup = Self {
+            path,
+            procs,
+            oom_kills: 0,
+            memory_max_hits: 0,
+            pids_max_hits: 0,
+        };
+        cgroup.oom_kills = cgroup.read_event("memory.events", "oom_kill");
+        cgroup.memory_max_hits = cgroup.read_event("memory.events", "max");
+        cgroup.pids_max_hits = cgroup.read_event("pids.events", "max");
+        Ok(cgroup)
+    }
+
+    /// Reads a counter from one of the `*.events` files, `0` if it's unavailable.
+    fn read_event(&self, file: &str, key: &str) -> u64 {
+        fs::read_to_string(self.path.join(file))
+            .ok()
+            .and_then(|events| {
+                events.lines().find_map(|line| {
+                    let (name, value) = line.split_once(' ')?;
+                    (name == key).then(|| value.trim().parse().ok()).flatten()
+                })
+            })
+            .unwrap_or(0)
+    }
+
+    /// Checks the event counters for new limit violations since the last call.
+    ///
+    /// Hitting `memory.max` without an OOM kill is only suspected if the child got killed by a signal afterwards,
+    /// the kernel reclaims memory on each hit and the child may well survive it, or crash for another reason.
+    fn take_check(&mut self, signaled: bool) -> LimitCheck {
+        let oom_kills = self.read_event("memory.events", "oom_kill");
+        let memory_max_hits = self.read_event("memory.events", "max");
+        let pids_max_hits = self.read_event("pids.events", "max");
+        let violation = if oom_kills > self.oom_kills {
+            Some(LimitedResource::Memory)
+        } else if pids_max_hits > self.pids_max_hits {
+            Some(LimitedResource::Processes)
+        } else {
+            None
+        };
+        let suspected =
+            (signaled && memory_max_hits > self.memory_max_hits).then_some(LimitedResource::Memory);
+        self.oom_kills = oom_kills;
+        self.memory_max_hits = memory_max_hits;
+        self.pids_max_hits = pids_max_hits;
+        LimitCheck {
+            violation,
+            suspected,
+        }
+    }
+}
+
+impl Drop for Cgroup {
+    fn drop(&mut self) {
+        // Only works once all children are gone, the kernel keeps the cgroup around otherwise.
+        if let Err(err) = fs::remove_dir(&self.path) {
+            log::debug!("Could not remove cgroup {}: {err}", self.path.display());
+        }
+    }
+}
+
+fn write_cgroup_file(path: &Path, file: &str, value: u64) -> Result<(), Error> {
+    let file = path.join(file);
+    fs::write(&file, value.to_string()).map_err(|err| {
+        Error::os_error(
+            err,
+            format!("Could not write {value} to {}", file.display()),
+        )
+    })
+}
+
+/// The resource limits a run violated, see [`ResourceLimiter::check`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LimitCheck {
+    /// The limit the run violated, with evidence of it
+    pub violation: Option<LimitedResource>,
+    /// The limit a crash may be caused by, without evidence of a violation
+    pub suspected: Option<LimitedResource>,
+}
+
+/// Enforces [`ResourceLimits`] on the children of an executor and detects violations.
+#[derive(Debug)]
+pub struct ResourceLimiter {
+    rlimits: Vec<(RlimitResource, libc::rlim_t)>,
+    limits_file_size: bool,
+    limits_memory: bool,
+    cgroup: Option<Cgroup>,
+    observer: Option<Handle<ResourceLimitObserver>>,
+}
+
+impl ResourceLimiter {
+    /// Applies the limits to the current process. Meant to be called in the child, right after `fork`.
+    ///
+    /// This only does raw libc calls, so it's safe to call between `fork` and `exec`.
+    pub fn apply_to_current_process(&self) -> io::Result<()> {
+        set_rlimits(&self.rlimits)?;
+        if let Some(cgroup) = &self.cgroup {
+            join_cgroup(&cgroup.procs)?;
+        }
+        Ok(())
+    }
+
+    /// Applies the limits to the process spawned by this [`Command`], after `fork`, before `exec`.
+    pub fn configure_command(&self, command: &mut Command) {
+        let rlimits = self.rlimits.clone();
+        let procs = self.cgroup.as_ref().map(|cgroup| cgroup.procs.clone());
+        let func = move || {
+            set_rlimits(&rlimits)?;
+            if let Some(procs) = &procs {
+                join_cgroup(procs)?;
+            }
+            Ok(())
+        };
+        // # Safety
+        // `set_rlimits` and `join_cgroup` only do raw libc calls.
+        unsafe {
+            command.pre_exec(func);
+        }
+    }
+
+    /// Checks if the last run violated a limit, given the number of the signal that terminated the child, if any.
+    pub fn check(&mut self, signal: Option<c_int>) -> LimitCheck {
+        let mut check = self
+            .cgroup
+            .as_mut()
+            .map(|cgroup| cgroup.take_check(signal.is_some()))
+            .unwrap_or_default();
+        match signal {
+            Some(libc::SIGXFSZ) if self.limits_file_size => {
+                check.violation = Some(LimitedResource::FileSize);
+            }
+            Some(libc::SIGABRT | libc::SIGSEGV | libc::SIGBUS)
+                if self.limits_memory && check.suspected.is_none() =>
+            {
+                check.suspected = Some(LimitedResource::MemoryRlimit);
+            }
+            _ => {}
+        }
+        check
+    }
+
+    /// Checks the last run for a violation, reports it to the observer, and maps it to [`ExitKind::Oom`].
+    ///
+    /// Crashes with a suspected violation stay crashes.
+    pub fn check_exit<OT>(
+        &mut self,
+        observers: &mut OT,
+        signal: Option<c_int>,
+        exit_kind: ExitKind,
+    ) -> ExitKind
+    where
+        OT: MatchNameRef,
+    {
+        let check = self.check(signal);
+        if let Some(observer) = self
+            .observer
+            .as_ref()
+            .and_then(|observer| observers.get_mut(observer))
+        {
+            observer.observe(check.violation, check.suspected);
+        }
+        match (check.violation, exit_kind) {
+            // A timeout stays a timeout, even if the cgroup killed some other process meanwhile.
+            (Some(_), ExitKind::Ok | ExitKind::Crash | ExitKind::Oom) => ExitKind::Oom,
+            _ => exit_kind,
+        }
+    }
+}
+
+/// Sets both the soft and the hard limit for each of the given resources.
+///
+/// This only does raw libc calls, so it's safe to call between `fork` and `exec`.
+fn set_rlimits(rlimits: &[(RlimitResource, libc::rlim_t)]) -> io::Result<()> {
+    for (resource, limit) in rlimits {
+        let rlimit = libc::rlimit {
+            rlim_cur: *limit,
+            rlim_max: *limit,
+        };
+        // # Safety
+        // Raw libc call with a valid pointer.
+        if unsafe { libc::setrlimit(*resource, &raw const rlimit) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+/// Moves the calling process to the cgroup with the given `cgroup.procs` file.
+///
+/// This only does raw libc calls, so it's safe to call between `fork` and `exec`.
+fn join_cgroup(procs: &CString) -> io::Result<()> {
+    // # Safety
+    // Raw libc calls with a valid, NUL-terminated path. Writing `0` moves the calling process.
+    unsafe {
+        let fd = libc::open(procs.as_ptr(), libc::O_WRONLY | libc::O_CLOEXEC);
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let written = libc::write(fd, b"0".as_ptr().cast(), 1);
+        let err = io::Error::last_os_error();
+        libc::close(fd);
+        if written != 1 {
+            return Err(err);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use libafl_bolts::tuples::{Handled, tuple_list};
+
+    use super::{LimitCheck, ResourceLimits};
+    use crate::{
+        executors::ExitKind,
+        observers::{LimitedResource, ResourceLimitObserver},
+    };
+
+    #[test]
+    fn test_file_size_violation() {
+        let mut limiter = ResourceLimits::new().file_size(1024).build().unwrap();
+        assert_eq!(
+            limiter.check(Some(libc::SIGXFSZ)).violation,
+            Some(LimitedResource::FileSize)
+        );
+        assert_eq!(limiter.check(Some(libc::SIGSEGV)), LimitCheck::default());
+
+        let mut limiter = ResourceLimits::new().open_files(64).build().unwrap();
+        assert_eq!(limiter.check(Some(libc::SIGXFSZ)), LimitCheck::default());
+        assert_eq!(limiter.check(Some(libc::SIGSEGV)), LimitCheck::default());
+    }
+
+    #[test]
+    fn test_memory_rlimit_suspected() {
+        let mut limiter = ResourceLimits::new()
+            .address_space(1 << 30)
+            .build()
+            .unwrap();
+        for signal in [libc::SIGABRT, libc::SIGSEGV] {
+            assert_eq!(
+                limiter.check(Some(signal)),
+                LimitCheck {
+                    violation: None,
+                    suspected: Some(LimitedResource::MemoryRlimit),
+                }
+            );
+        }
+        assert_eq!(limiter.check(Some(libc::SIGILL)), LimitCheck::default());
+        assert_eq!(limiter.check(None), LimitCheck::default());
+    }
+
+    #[test]
+    fn test_rlimit_crash_stays_crash() {
+        let observer = ResourceLimitObserver::new("resource_limits");
+        let handle = observer.handle();
+        let mut observers = tuple_list!(observer);
+        let mut limiter = ResourceLimits::new()
+            .address_space(1 << 30)
+            .observer(handle.clone())
+            .build()
+            .unwrap();
+
+        let exit_kind = limiter.check_exit(&mut observers, Some(libc::SIGSEGV), ExitKind::Crash);
+        assert_eq!(exit_kind, ExitKind::Crash);
+        assert_eq!(observers.0.violation(), None);
+        assert_eq!(observers.0.suspected(), Some(LimitedResource::MemoryRlimit));
+    }
+}
@@ -0,0 +1,143 @@
+//! The [`ArbitraryDecodeFeedback`] counts how many of the executed [`ArbitraryInput`]s decode,
+//! and publishes the ratio as user stats.
+
+use alloc::borrow::Cow;
+use core::{marker::PhantomData, time::Duration};
+
+use arbitrary::Arbitrary;
+use libafl_bolts::{Named, current_time};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    Error, HasMetadata,
+    events::{Event, EventFirer, EventWithStats},
+    executors::ExitKind,
+    feedbacks::{Feedback, StateInitializer},
+    inputs::arbitrary::ArbitraryInput,
+    monitors::stats::{AggregatorOps, UserStats, UserStatsValue},
+    state::HasExecutions,
+};
+
+/// The name of the user stat showing the ratio of executed inputs that decode
+pub const ARBITRARY_DECODED_STATS_NAME: &str = "arbitrary_decoded";
+
+/// The default interval at which the [`ArbitraryDecodeFeedback`] publishes the stats
+pub const ARBITRARY_DECODED_STATS_INTERVAL: Duration = Duration::from_secs(15);
+
+/// How many of the executed [`ArbitraryInput`]s decoded
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(
+    any(not(feature = "serdeany_autoreg"), miri),
+    expect(clippy::unsafe_derive_deserialize)
+)] // for SerdeAny
+pub struct ArbitraryDecodeMetadata {
+    /// The inputs that decoded
+    pub decoded: u64,
+    /// All executed inputs
+    pub executions: u64,
+}
+
+libafl_bolts::impl_serdeany!(ArbitraryDecodeMetadata);
+
+/// Counts how many executed [`ArbitraryInput`]s decode into the [`ArbitraryDecodeMetadata`] of the state,
+/// and publishes the ratio as the [`ARBITRARY_DECODED_STATS_NAME`] user stat, once every interval.
+///
+/// It never deems an input interesting, combine it with the other feedbacks using `feedback_or!`.
+#[derive(Debug)]
+pub struct ArbitraryDecodeFeedback<T> {
+    interval: Duration,
+    last_report: Duration,
+    phantom: PhantomData<fn() -> T>,
+}
+
+impl<T> ArbitraryDecodeFeedback<T> {
+    /// Creates a new [`ArbitraryDecodeFeedback`], publishing every [`ARBITRARY_DECODED_STATS_INTERVAL`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_interval(ARBITRARY_DECODED_STATS_INTERVAL)
+    }
+
+    /// Creates a new [`ArbitraryDecodeFeedback`], publishing every `interval`
+    #[must_use]
+    pub fn with_interval(interval: Duration) -> Self {
+        Self {
+            interval,
+            last_report: Duration::ZERO,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<T> Default for ArbitraryDecodeFeedback<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Named for ArbitraryDecodeFeedback<T> {
+    #[inline]
+    fn name(&self) -> &Cow<'static, str> {
+        static NAME: Cow<'static, str> = Cow::Borrowed("ArbitraryDecodeFeedback");
+        &NAME
+    }
+}
+
+impl<S, T> StateInitializer<S> for ArbitraryDecodeFeedback<T>
+where
+    S: HasMetadata,
+{
+    fn init_state(&mut self, state: &mut S) -> Result<(), Error> {
+        state.metadata_or_insert_with(ArbitraryDecodeMetadata::default);
+        Ok(())
+    }
+}
+
+impl<EM, OT, S, T> Feedback<EM, ArbitraryInput<T>, OT, S> for ArbitraryDecodeFeedback<T>
+where
+    EM: EventFirer<ArbitraryInput<T>, S>,
+    S: HasExecutions + HasMetadata,
+    T: for<'a> Arbitrary<'a>,
+{
+    fn is_interesting(
+        &mut self,
+        state: &mut S,
+        manager: &mut EM,
+        input: &ArbitraryInput<T>,
+        _observers: &OT,
+        _exit_kind: &ExitKind,
+    ) -> Result<bool, Error> {
+        let decoded = input.decode().is_ok();
+        let metadata = state.metadata_or_insert_with(ArbitraryDecodeMetadata::default);
+        metadata.executions += 1;
+        if decoded {
+            metadata.decoded += 1;
+        }
+        let stats = *metadata;
+
+        let now = current_time();
+        if now.saturating_sub(self.last_report) >= self.interval {
+            self.last_report = now;
+            let executions = *state.executions();
+            manager.fire(
+                state,
+                EventWithStats::with_current_time(
+                    Event::UpdateUserStats {
+                        name: Cow::Borrowed(ARBITRARY_DECODED_STATS_NAME),
+                        value: UserStats::new(
+                            UserStatsValue::Ratio(stats.decoded, stats.executions),
+                            AggregatorOps::Avg,
+                        ),
+                        phantom: PhantomData,
+                    },
+                    executions,
+                ),
+            )?;
+        }
+        Ok(false)
+    }
+
+    #[cfg(feature = "track_hit_feedbacks")]
+    fn last_result(&self) -> Result<bool, Error> {
+        Ok(false)
+    }
+}
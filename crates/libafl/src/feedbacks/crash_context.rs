@@ -0,0 +1,81 @@
+//! Feedback and metadata for the context of crashed children, see [`CrashContextObserver`].
+
+use alloc::borrow::Cow;
+
+use libafl_bolts::{
+    Named, impl_serdeany,
+    tuples::{Handle, Handled, MatchName, MatchNameRef},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    Error, HasMetadata,
+    corpus::Testcase,
+    feedbacks::{Feedback, StateInitializer},
+    observers::{CrashContext, CrashContextObserver},
+};
+
+/// Metadata for [`CrashContextToMetadataFeedback`].
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CrashContextMetadata {
+    /// The state of the child when it crashed
+    pub context: CrashContext,
+}
+
+impl_serdeany!(CrashContextMetadata);
+
+/// Nop feedback that annotates the crash context, if any, in the new testcase. The testcase
+/// is never interesting (use with an OR in the objective).
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CrashContextToMetadataFeedback {
+    o_ref: Handle<CrashContextObserver>,
+}
+
+impl<S> StateInitializer<S> for CrashContextToMetadataFeedback {}
+
+impl<EM, I, OT, S> Feedback<EM, I, OT, S> for CrashContextToMetadataFeedback
+where
+    OT: MatchName,
+{
+    #[cfg(feature = "track_hit_feedbacks")]
+    fn last_result(&self) -> Result<bool, Error> {
+        Ok(false)
+    }
+
+    /// Append to the testcase the generated metadata in case of a new corpus item.
+    #[inline]
+    fn append_metadata(
+        &mut self,
+        _state: &mut S,
+        _manager: &mut EM,
+        observers: &OT,
+        testcase: &mut Testcase<I>,
+    ) -> Result<(), Error> {
+        let observer = observers
+            .get(&self.o_ref)
+            .ok_or(Error::illegal_state("CrashContextObserver is missing"))?;
+        if let Some(context) = observer.last_context() {
+            testcase.metadata_map_mut().insert(CrashContextMetadata {
+                context: context.clone(),
+            });
+        }
+        Ok(())
+    }
+}
+
+impl Named for CrashContextToMetadataFeedback {
+    #[inline]
+    fn name(&self) -> &Cow<'static, str> {
+        self.o_ref.name()
+    }
+}
+
+impl CrashContextToMetadataFeedback {
+    /// Creates a new [`CrashContextToMetadataFeedback`].
+    #[must_use]
+    pub fn new(observer: &CrashContextObserver) -> Self {
+        Self {
+            o_ref: observer.handle(),
+        }
+    }
+}
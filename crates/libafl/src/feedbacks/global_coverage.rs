@@ -0,0 +1,150 @@
+//! The [`GlobalCoverageFeedback`] merges the coverage of new testcases into the [`GlobalCoverageMap`]
+//! shared by all clients, and marks the testcases that add nothing globally, so that the broker drops them.
+
+use alloc::borrow::Cow;
+use core::{fmt::Debug, marker::PhantomData};
+
+use libafl_bolts::{
+    AsIter, Named,
+    shmem::ShMem,
+    tuples::{Handle, Handled, MatchName, MatchNameRef},
+};
+use serde::Serialize;
+
+use crate::{
+    Error,
+    corpus::Testcase,
+    events::{Event, EventFirer, EventWithStats, GlobalCoverageMap, input_hash},
+    executors::ExitKind,
+    feedbacks::{Feedback, HasObserverHandle, StateInitializer},
+    monitors::stats::{AggregatorOps, UserStats, UserStatsValue},
+    observers::MapObserver,
+    state::HasExecutions,
+};
+
+/// The name of the user stat showing the global coverage
+pub const GLOBAL_COVERAGE_STATS_NAME: &str = "global_coverage";
+
+/// A feedback that keeps the [`GlobalCoverageMap`] of the campaign up to date.
+///
+/// It never deems an input interesting on its own, combine it with the usual map feedback using `feedback_or!`,
+/// after it, so that it sees every testcase added to the corpus.
+#[derive(Debug)]
+pub struct GlobalCoverageFeedback<C, O, SHM> {
+    map_ref: Handle<C>,
+    global_map: GlobalCoverageMap<SHM>,
+    name: Cow<'static, str>,
+    phantom: PhantomData<O>,
+}
+
+impl<C, O, SHM> GlobalCoverageFeedback<C, O, SHM>
+where
+    C: Named,
+{
+    /// Creates a new [`GlobalCoverageFeedback`], merging the coverage of `observer` into `global_map`
+    #[must_use]
+    pub fn new(observer: &C, global_map: GlobalCoverageMap<SHM>) -> Self {
+        Self {
+            map_ref: observer.handle(),
+            global_map,
+            name: Cow::from(format!("global_coverage_{}", observer.name())),
+            phantom: PhantomData,
+        }
+    }
+
+    /// The global map this feedback merges into
+    #[must_use]
+    pub fn global_map(&self) -> &GlobalCoverageMap<SHM> {
+        &self.global_map
+    }
+}
+
+impl<C, O, SHM> Named for GlobalCoverageFeedback<C, O, SHM> {
+    #[inline]
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+impl<C, O, SHM> HasObserverHandle for GlobalCoverageFeedback<C, O, SHM> {
+    type Observer = C;
+
+    #[inline]
+    fn observer_handle(&self) -> &Handle<C> {
+        &self.map_ref
+    }
+}
+
+impl<C, O, S, SHM> StateInitializer<S> for GlobalCoverageFeedback<C, O, SHM> {}
+
+impl<C, EM, I, O, OT, S, SHM> Feedback<EM, I, OT, S> for GlobalCoverageFeedback<C, O, SHM>
+where
+    C: AsRef<O>,
+    EM: EventFirer<I, S>,
+    I: Serialize,
+    O: MapObserver<Entry = u8> + for<'it> AsIter<'it, Item = u8>,
+    OT: MatchName,
+    S: HasExecutions,
+    SHM: ShMem,
+{
+    #[inline]
+    fn is_interesting(
+        &mut self,
+        _state: &mut S,
+        _manager: &mut EM,
+        _input: &I,
+        _observers: &OT,
+        _exit_kind: &ExitKind,
+    ) -> Result<bool, Error> {
+        Ok(false)
+    }
+
+    #[cfg(feature = "track_hit_feedbacks")]
+    fn last_result(&self) -> Result<bool, Error> {
+        Ok(false)
+    }
+
+    fn append_metadata(
+        &mut self,
+        state: &mut S,
+        manager: &mut EM,
+        observers: &OT,
+        testcase: &mut Testcase<I>,
+    ) -> Result<(), Error> {
+        let observer = observers
+            .get(&self.map_ref)
+            .expect("MapObserver not found")
+            .as_ref();
+        let globally_new = self.global_map.merge(
+            observer
+                .as_iter()
+                .map(|x| *x)
+                .enumerate()
+                .filter(|(_, value)| *value != 0),
+        );
+        if !globally_new {
+            if let Some(input) = testcase.input() {
+                self.global_map.mark_redundant(input_hash(input)?);
+            }
+        }
+
+        manager.fire(
+            state,
+            EventWithStats::with_current_time(
+                Event::UpdateUserStats {
+                    name: Cow::Borrowed(GLOBAL_COVERAGE_STATS_NAME),
+                    value: UserStats::new(
+                        UserStatsValue::Ratio(
+                            self.global_map.covered() as u64,
+                            self.global_map.len() as u64,
+                        ),
+                        AggregatorOps::Max,
+                    ),
+                    phantom: PhantomData,
+                },
+                *state.executions(),
+            ),
+        )?;
+        Ok(())
+    }
+}
@@ -0,0 +1,123 @@
+//! A feedback running the `is_interesting` callback of a Lua script, see [`crate::mutators::lua`]
+
+use alloc::{borrow::Cow, format};
+
+use libafl_bolts::Named;
+use serde::Serialize;
+
+use crate::{
+    Error,
+    executors::ExitKind,
+    feedbacks::{Feedback, StateInitializer},
+    mutators::lua::LuaScript,
+    state::HasRand,
+};
+
+/// Decides if an input is interesting with the `is_interesting` callback of a Lua script,
+/// called with the serialized input, see [`crate::mutators::lua::LuaSerdeMutator`], and the [`ExitKind`] as string.
+#[derive(Debug)]
+pub struct LuaFeedback {
+    script: LuaScript,
+    #[cfg(feature = "track_hit_feedbacks")]
+    last_result: Option<bool>,
+}
+
+impl LuaFeedback {
+    /// Creates a new [`LuaFeedback`] from a Lua script with an `is_interesting` callback.
+    /// Will block if the script is an endless loop!
+    pub fn new<S: HasRand>(state: &mut S, feedback_lua_script: &str) -> Result<Self, Error> {
+        let script = LuaScript::new(state, feedback_lua_script)?;
+        if !script.has_callback("is_interesting")? {
+            return Err(Error::illegal_argument(
+                "A Lua feedback needs an is_interesting callback",
+            ));
+        }
+        Ok(Self {
+            script,
+            #[cfg(feature = "track_hit_feedbacks")]
+            last_result: None,
+        })
+    }
+
+    /// The loaded script
+    #[must_use]
+    pub fn script(&self) -> &LuaScript {
+        &self.script
+    }
+}
+
+impl Named for LuaFeedback {
+    fn name(&self) -> &Cow<'static, str> {
+        &Cow::Borrowed("LuaFeedback")
+    }
+}
+
+impl<S> StateInitializer<S> for LuaFeedback {}
+
+impl<EM, I, OT, S> Feedback<EM, I, OT, S> for LuaFeedback
+where
+    I: Serialize,
+{
+    fn is_interesting(
+        &mut self,
+        _state: &mut S,
+        _manager: &mut EM,
+        input: &I,
+        _observers: &OT,
+        exit_kind: &ExitKind,
+    ) -> Result<bool, Error> {
+        let input = self.script.input_to_lua(input)?;
+        let res = self
+            .script
+            .call::<_, bool>("is_interesting", (input, format!("{exit_kind:?}")))?
+            .unwrap_or(false);
+        #[cfg(feature = "track_hit_feedbacks")]
+        {
+            self.last_result = Some(res);
+        }
+        Ok(res)
+    }
+
+    #[cfg(feature = "track_hit_feedbacks")]
+    fn last_result(&self) -> Result<bool, Error> {
+        self.last_result.ok_or_else(|| Error::illegal_state("No last result set in `LuaFeedback`. Either `is_interesting` has never been called or the fuzzer restarted in the meantime."))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        executors::ExitKind,
+        feedbacks::{Feedback, lua::LuaFeedback},
+        inputs::BytesInput,
+        state::NopState,
+    };
+
+    #[test]
+    fn test_lua_feedback() {
+        let mut state: NopState<BytesInput> = NopState::new();
+        let mut feedback = LuaFeedback::new(
+            &mut state,
+            r"{
+              is_interesting = function (bytes, exit_kind)
+                return exit_kind == 'Crash' and bytes[1] == 66
+              end,
+            }",
+        )
+        .unwrap();
+        let input = BytesInput::new(b"BUG".to_vec());
+        let mut is_interesting = |exit_kind| {
+            Feedback::<(), _, (), _>::is_interesting(
+                &mut feedback,
+                &mut state,
+                &mut (),
+                &input,
+                &(),
+                &exit_kind,
+            )
+            .unwrap()
+        };
+        assert!(!is_interesting(ExitKind::Ok));
+        assert!(is_interesting(ExitKind::Crash));
+    }
+}
@@ -11,11 +11,15 @@ use core::{fmt::Debug, marker::PhantomData};
 #[cfg(feature = "std")]
 pub use concolic::ConcolicFeedback;
 pub use differential::DiffFeedback;
+#[cfg(feature = "std")]
+pub use global_coverage::GlobalCoverageFeedback;
 use libafl_bolts::{
     Named,
     tuples::{Handle, Handled, MatchName, MatchNameRef},
 };
 pub use list::*;
+#[cfg(feature = "lua_mutator")]
+pub use lua::LuaFeedback;
 pub use map::*;
 #[cfg(feature = "nautilus")]
 pub use nautilus::*;
@@ -23,10 +27,16 @@ pub use nautilus::*;
 pub use new_hash_feedback::NewHashFeedback;
 #[cfg(feature = "std")]
 pub use new_hash_feedback::NewHashFeedbackMetadata;
+#[cfg(feature = "python")]
+pub use python::PythonFeedback;
 use serde::{Deserialize, Serialize};
 
+#[cfg(feature = "arbitrary_input")]
+pub use self::arbitrary::ArbitraryDecodeFeedback;
 use crate::{Error, corpus::Testcase, executors::ExitKind, observers::TimeObserver};
 
+#[cfg(feature = "arbitrary_input")]
+pub mod arbitrary;
 #[cfg(feature = "std")]
 pub mod capture_feedback;
 
@@ -36,16 +46,27 @@ pub use bool::BoolValueFeedback;
 #[cfg(feature = "std")]
 pub mod concolic;
 #[cfg(feature = "std")]
+pub mod crash_context;
+#[cfg(feature = "std")]
 /// The module for `CustomFilenameToTestcaseFeedback`
 pub mod custom_filename;
 pub mod differential;
+#[cfg(feature = "std")]
+pub mod global_coverage;
 /// The module for list feedback
 pub mod list;
+#[cfg(feature = "lua_mutator")]
+pub mod lua;
 pub mod map;
 #[cfg(feature = "nautilus")]
 pub mod nautilus;
 #[cfg(feature = "std")]
 pub mod new_hash_feedback;
+#[cfg(all(feature = "std", unix))]
+pub mod process_exit;
+#[cfg(feature = "python")]
+pub mod python;
+pub mod resource_limits;
 #[cfg(feature = "simd")]
 pub mod simd;
 #[cfg(feature = "std")]
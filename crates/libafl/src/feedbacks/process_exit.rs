@@ -0,0 +1,228 @@
+//! Feedbacks and metadata filtering on how a child process exited, see [`ProcessExitObserver`].
+
+use alloc::{borrow::Cow, vec::Vec};
+use core::ffi::c_int;
+
+use libafl_bolts::{
+    Named, impl_serdeany,
+    tuples::{Handle, Handled, MatchName, MatchNameRef},
+};
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "track_hit_feedbacks")]
+use crate::feedbacks::premature_last_result_err;
+use crate::{
+    Error, HasMetadata,
+    corpus::Testcase,
+    executors::ExitKind,
+    feedbacks::{Feedback, StateInitializer},
+    observers::{ProcessExit, ProcessExitObserver},
+};
+
+/// Metadata for [`ProcessExitFeedback`], how the child exited when running the testcase.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProcessExitMetadata {
+    /// The exit of the child process
+    pub exit: ProcessExit,
+}
+
+impl_serdeany!(ProcessExitMetadata);
+
+/// A condition on the exit of a child process, checked by the [`ProcessExitFeedback`]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ProcessExitCondition {
+    /// The child got terminated by this signal
+    Signal(c_int),
+    /// The child exited with this exit code
+    ExitCode(c_int),
+    /// The child exited with one of the sanitizer exit codes
+    SanitizerExitCode,
+    /// The child dumped a core
+    CoreDumped,
+}
+
+impl ProcessExitCondition {
+    /// Checks if the exit fulfills this condition
+    #[must_use]
+    pub fn matches(&self, exit: &ProcessExit) -> bool {
+        match self {
+            Self::Signal(signal) => exit.signal == Some(*signal),
+            Self::ExitCode(code) => exit.exit_code == Some(*code),
+            Self::SanitizerExitCode => exit.sanitizer_exit_code.is_some(),
+            Self::CoreDumped => exit.core_dumped,
+        }
+    }
+}
+
+/// A [`ProcessExitFeedback`] filters runs on how the child process exited, as recorded by a [`ProcessExitObserver`].
+///
+/// Created with [`ProcessExitFeedback::matching`], a run is interesting if its exit fulfills any of the conditions,
+/// e.g. to treat exit code `77` as a crash, OR it with a `CrashFeedback` in the objective.
+/// Created with [`ProcessExitFeedback::not_matching`], a run is interesting unless its exit fulfills any of the conditions,
+/// e.g. to ignore `SIGFPE` crashes, AND it with a `CrashFeedback` in the objective.
+/// Runs without a recorded exit (e.g. timeouts) are never interesting.
+///
+/// The exit is added as [`ProcessExitMetadata`] to new testcases.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ProcessExitFeedback {
+    o_ref: Handle<ProcessExitObserver>,
+    conditions: Vec<ProcessExitCondition>,
+    negate: bool,
+    #[cfg(feature = "track_hit_feedbacks")]
+    last_result: Option<bool>,
+}
+
+impl ProcessExitFeedback {
+    /// Creates a new [`ProcessExitFeedback`], interesting if the exit fulfills any of the `conditions`.
+    #[must_use]
+    pub fn matching<C>(observer: &ProcessExitObserver, conditions: C) -> Self
+    where
+        C: IntoIterator<Item = ProcessExitCondition>,
+    {
+        Self {
+            o_ref: observer.handle(),
+            conditions: conditions.into_iter().collect(),
+            negate: false,
+            #[cfg(feature = "track_hit_feedbacks")]
+            last_result: None,
+        }
+    }
+
+    /// Creates a new [`ProcessExitFeedback`], interesting if the exit fulfills none of the `conditions`.
+    #[must_use]
+    pub fn not_matching<C>(observer: &ProcessExitObserver, conditions: C) -> Self
+    where
+        C: IntoIterator<Item = ProcessExitCondition>,
+    {
+        Self {
+            negate: true,
+            ..Self::matching(observer, conditions)
+        }
+    }
+
+    /// Creates a new [`ProcessExitFeedback`], interesting if the child exited with any of the `exit_codes`.
+    #[must_use]
+    pub fn exit_codes<C>(observer: &ProcessExitObserver, exit_codes: C) -> Self
+    where
+        C: IntoIterator<Item = c_int>,
+    {
+        Self::matching(
+            observer,
+            exit_codes.into_iter().map(ProcessExitCondition::ExitCode),
+        )
+    }
+
+    /// Creates a new [`ProcessExitFeedback`], interesting unless the child got terminated by any of the `signals`.
+    #[must_use]
+    pub fn ignoring_signals<C>(observer: &ProcessExitObserver, signals: C) -> Self
+    where
+        C: IntoIterator<Item = c_int>,
+    {
+        Self::not_matching(
+            observer,
+            signals.into_iter().map(ProcessExitCondition::Signal),
+        )
+    }
+}
+
+impl<S> StateInitializer<S> for ProcessExitFeedback {}
+
+impl<EM, I, OT, S> Feedback<EM, I, OT, S> for ProcessExitFeedback
+where
+    OT: MatchName,
+{
+    fn is_interesting(
+        &mut self,
+        _state: &mut S,
+        _manager: &mut EM,
+        _input: &I,
+        observers: &OT,
+        _exit_kind: &ExitKind,
+    ) -> Result<bool, Error> {
+        let observer = observers
+            .get(&self.o_ref)
+            .ok_or(Error::illegal_state("ProcessExitObserver is missing"))?;
+        let res = observer.last_exit().is_some_and(|exit| {
+            self.conditions
+                .iter()
+                .any(|condition| condition.matches(exit))
+                != self.negate
+        });
+        #[cfg(feature = "track_hit_feedbacks")]
+        {
+            self.last_result = Some(res);
+        }
+        Ok(res)
+    }
+
+    #[cfg(feature = "track_hit_feedbacks")]
+    fn last_result(&self) -> Result<bool, Error> {
+        self.last_result.ok_or(premature_last_result_err())
+    }
+
+    /// Append to the testcase the exit of the child process
+    #[inline]
+    fn append_metadata(
+        &mut self,
+        _state: &mut S,
+        _manager: &mut EM,
+        observers: &OT,
+        testcase: &mut Testcase<I>,
+    ) -> Result<(), Error> {
+        let observer = observers
+            .get(&self.o_ref)
+            .ok_or(Error::illegal_state("ProcessExitObserver is missing"))?;
+        if let Some(exit) = observer.last_exit() {
+            testcase
+                .metadata_map_mut()
+                .insert(ProcessExitMetadata { exit: *exit });
+        }
+        Ok(())
+    }
+}
+
+impl Named for ProcessExitFeedback {
+    #[inline]
+    fn name(&self) -> &Cow<'static, str> {
+        self.o_ref.name()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use libafl_bolts::tuples::tuple_list;
+
+    use super::ProcessExitFeedback;
+    use crate::{
+        executors::ExitKind, feedbacks::Feedback, inputs::NopInput, observers::ProcessExitObserver,
+        state::NopState,
+    };
+
+    #[test]
+    fn test_process_exit_feedback() {
+        let mut observer = ProcessExitObserver::new("exit");
+        let mut exit_codes = ProcessExitFeedback::exit_codes(&observer, [77]);
+        let mut ignore_fpe = ProcessExitFeedback::ignoring_signals(&observer, [libc::SIGFPE]);
+        let mut state = NopState::<NopInput>::new();
+        let mut check = |feedback: &mut ProcessExitFeedback, observer: &ProcessExitObserver| {
+            let observers = tuple_list!(observer.clone());
+            Feedback::<(), _, _, _>::is_interesting(
+                feedback,
+                &mut state,
+                &mut (),
+                &NopInput {},
+                &observers,
+                &ExitKind::Ok,
+            )
+            .unwrap()
+        };
+
+        observer.observe_raw_status(77 << 8);
+        assert!(check(&mut exit_codes, &observer));
+        assert!(check(&mut ignore_fpe, &observer));
+
+        observer.observe_raw_status(libc::SIGFPE);
+        assert!(!check(&mut exit_codes, &observer));
+        assert!(!check(&mut ignore_fpe, &observer));
+    }
+}
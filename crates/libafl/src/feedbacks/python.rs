@@ -0,0 +1,163 @@
+//! A feedback running the `is_interesting` function of a Python module, see [`crate::mutators::python`]
+
+use alloc::{borrow::Cow, format, rc::Rc};
+
+use libafl_bolts::Named;
+use pyo3::types::{PyAnyMethods, PyBytes};
+
+use crate::{
+    Error,
+    executors::ExitKind,
+    feedbacks::{Feedback, StateInitializer},
+    inputs::HasTargetBytes,
+    mutators::python::PythonModule,
+};
+
+/// Decides if an input is interesting with the `is_interesting` function of a [`PythonModule`],
+/// called with the target bytes of the input and the [`ExitKind`] as string.
+#[derive(Debug)]
+pub struct PythonFeedback {
+    /// The module, if it has an `is_interesting` function, else no input is interesting
+    module: Option<Rc<PythonModule>>,
+    #[cfg(feature = "track_hit_feedbacks")]
+    last_result: Option<bool>,
+}
+
+impl PythonFeedback {
+    /// Creates a new [`PythonFeedback`], the module must have an `is_interesting` function
+    pub fn new(module: Rc<PythonModule>) -> Result<Self, Error> {
+        if !module.has_function("is_interesting")? {
+            return Err(Error::illegal_argument(format!(
+                "The Python module {} has no is_interesting function",
+                module.name()
+            )));
+        }
+        Self::if_defined(Some(module))
+    }
+
+    /// Creates a new [`PythonFeedback`] for an optional module, deeming no input interesting
+    /// if there is no module or it has no `is_interesting` function.
+    ///
+    /// Use this for modules that may only define the mutator functions.
+    pub fn if_defined(module: Option<Rc<PythonModule>>) -> Result<Self, Error> {
+        let module = match module {
+            Some(module) if module.has_function("is_interesting")? => Some(module),
+            _ => None,
+        };
+        Ok(Self {
+            module,
+            #[cfg(feature = "track_hit_feedbacks")]
+            last_result: None,
+        })
+    }
+}
+
+impl Named for PythonFeedback {
+    fn name(&self) -> &Cow<'static, str> {
+        &Cow::Borrowed("PythonFeedback")
+    }
+}
+
+impl<S> StateInitializer<S> for PythonFeedback {}
+
+impl<EM, I, OT, S> Feedback<EM, I, OT, S> for PythonFeedback
+where
+    I: HasTargetBytes,
+{
+    fn is_interesting(
+        &mut self,
+        _state: &mut S,
+        _manager: &mut EM,
+        input: &I,
+        _observers: &OT,
+        exit_kind: &ExitKind,
+    ) -> Result<bool, Error> {
+        let res = match &self.module {
+            Some(module) => {
+                let bytes = input.target_bytes();
+                module.with_module(|module| {
+                    Ok(module
+                        .call_method1(
+                            "is_interesting",
+                            (PyBytes::new(module.py(), &bytes), format!("{exit_kind:?}")),
+                        )?
+                        .is_truthy()?)
+                })?
+            }
+            None => false,
+        };
+        #[cfg(feature = "track_hit_feedbacks")]
+        {
+            self.last_result = Some(res);
+        }
+        Ok(res)
+    }
+
+    #[cfg(feature = "track_hit_feedbacks")]
+    fn last_result(&self) -> Result<bool, Error> {
+        self.last_result.ok_or_else(|| Error::illegal_state("No last result set in `PythonFeedback`. Either `is_interesting` has never been called or the fuzzer restarted in the meantime."))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::rc::Rc;
+
+    use crate::{
+        executors::ExitKind,
+        feedbacks::{Feedback, python::PythonFeedback},
+        inputs::BytesInput,
+        mutators::python::PythonModule,
+        state::NopState,
+    };
+
+    #[test]
+    fn test_python_feedback() {
+        let mut state: NopState<BytesInput> = NopState::new();
+        let module = PythonModule::from_code(
+            "test_feedback",
+            r"
+def is_interesting(buf, exit_kind):
+    return exit_kind == 'Crash' and buf.startswith(b'B')
+",
+            0,
+        )
+        .unwrap();
+        let mut feedback = PythonFeedback::new(Rc::new(module)).unwrap();
+        let input = BytesInput::new(b"BUG".to_vec());
+        let mut is_interesting = |exit_kind| {
+            Feedback::<(), _, (), _>::is_interesting(
+                &mut feedback,
+                &mut state,
+                &mut (),
+                &input,
+                &(),
+                &exit_kind,
+            )
+            .unwrap()
+        };
+        assert!(!is_interesting(ExitKind::Ok));
+        assert!(is_interesting(ExitKind::Crash));
+
+        let module = PythonModule::from_code(
+            "test_no_feedback",
+            "def fuzz(buf, add_buf, max_size): return buf",
+            0,
+        )
+        .unwrap();
+        let module = Rc::new(module);
+        assert!(PythonFeedback::new(module.clone()).is_err());
+        let mut feedback = PythonFeedback::if_defined(Some(module)).unwrap();
+        assert!(
+            !Feedback::<(), _, (), _>::is_interesting(
+                &mut feedback,
+                &mut state,
+                &mut (),
+                &input,
+                &(),
+                &ExitKind::Crash,
+            )
+            .unwrap()
+        );
+    }
+}
@@ -0,0 +1,92 @@
+//! Feedback and metadata for resource limit violations, see [`ResourceLimitObserver`].
+
+use alloc::borrow::Cow;
+
+use libafl_bolts::{
+    Named, impl_serdeany,
+    tuples::{Handle, Handled, MatchName, MatchNameRef},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    Error, HasMetadata,
+    corpus::Testcase,
+    feedbacks::{Feedback, StateInitializer},
+    observers::{LimitedResource, ResourceLimitObserver},
+};
+
+/// Metadata for [`ResourceLimitToMetadataFeedback`].
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ResourceLimitViolationMetadata {
+    /// The resource limit the testcase violated
+    pub resource: LimitedResource,
+    /// If the violation is only suspected, see [`ResourceLimitObserver::suspected`]
+    pub suspected: bool,
+}
+
+impl_serdeany!(ResourceLimitViolationMetadata);
+
+/// Nop feedback that annotates the violated, or suspected, resource limit, if any, in the new testcase.
+/// The testcase is never interesting (use with an OR).
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ResourceLimitToMetadataFeedback {
+    o_ref: Handle<ResourceLimitObserver>,
+}
+
+impl<S> StateInitializer<S> for ResourceLimitToMetadataFeedback {}
+
+impl<EM, I, OT, S> Feedback<EM, I, OT, S> for ResourceLimitToMetadataFeedback
+where
+    OT: MatchName,
+{
+    #[cfg(feature = "track_hit_feedbacks")]
+    fn last_result(&self) -> Result<bool, Error> {
+        Ok(false)
+    }
+
+    /// Append to the testcase the generated metadata in case of a new corpus item.
+    #[inline]
+    fn append_metadata(
+        &mut self,
+        _state: &mut S,
+        _manager: &mut EM,
+        observers: &OT,
+        testcase: &mut Testcase<I>,
+    ) -> Result<(), Error> {
+        let observer = observers
+            .get(&self.o_ref)
+            .ok_or(Error::illegal_state("ResourceLimitObserver is missing"))?;
+        let metadata = match (observer.violation(), observer.suspected()) {
+            (Some(resource), _) => Some(ResourceLimitViolationMetadata {
+                resource,
+                suspected: false,
+            }),
+            (None, Some(resource)) => Some(ResourceLimitViolationMetadata {
+                resource,
+                suspected: true,
+            }),
+            (None, None) => None,
+        };
+        if let Some(metadata) = metadata {
+            testcase.metadata_map_mut().insert(metadata);
+        }
+        Ok(())
+    }
+}
+
+impl Named for ResourceLimitToMetadataFeedback {
+    #[inline]
+    fn name(&self) -> &Cow<'static, str> {
+        self.o_ref.name()
+    }
+}
+
+impl ResourceLimitToMetadataFeedback {
+    /// Creates a new [`ResourceLimitToMetadataFeedback`].
+    #[must_use]
+    pub fn new(observer: &ResourceLimitObserver) -> Self {
+        Self {
+            o_ref: observer.handle(),
+        }
+    }
+}
@@ -0,0 +1,89 @@
+//! A generator running the `generate` callback of a Lua script, see [`crate::mutators::lua`]
+
+use core::marker::PhantomData;
+
+use mlua::Value as LuaValue;
+use serde::de::DeserializeOwned;
+
+use crate::{
+    Error,
+    generators::Generator,
+    mutators::lua::{LuaScript, lua_to_input},
+    state::HasRand,
+};
+
+/// Generates inputs with the `generate` callback of a Lua script.
+///
+/// The returned Lua value is deserialized into the input, like for the [`crate::mutators::lua::LuaSerdeMutator`].
+#[derive(Debug)]
+pub struct LuaGenerator<I> {
+    script: LuaScript,
+    phantom: PhantomData<fn() -> I>,
+}
+
+impl<I> LuaGenerator<I> {
+    /// Creates a new [`LuaGenerator`] from a Lua script with a `generate` callback.
+    /// Will block if the script is an endless loop!
+    pub fn new<S: HasRand>(state: &mut S, generator_lua_script: &str) -> Result<Self, Error> {
+        let script = LuaScript::new(state, generator_lua_script)?;
+        if !script.has_callback("generate")? {
+            return Err(Error::illegal_argument(
+                "A Lua generator needs a generate callback",
+            ));
+        }
+        Ok(Self {
+            script,
+            phantom: PhantomData,
+        })
+    }
+
+    /// The loaded script
+    #[must_use]
+    pub fn script(&self) -> &LuaScript {
+        &self.script
+    }
+}
+
+impl<I, S> Generator<I, S> for LuaGenerator<I>
+where
+    I: DeserializeOwned,
+{
+    fn generate(&mut self, _state: &mut S) -> Result<I, Error> {
+        match self.script.call::<_, LuaValue>("generate", ())? {
+            Some(generated) => lua_to_input(generated),
+            None => Err(Error::illegal_state("The Lua generate callback is gone")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use crate::{
+        generators::{Generator, lua::LuaGenerator},
+        inputs::BytesInput,
+        state::NopState,
+    };
+
+    #[test]
+    fn test_lua_generator() {
+        let mut state: NopState<BytesInput> = NopState::new();
+        let mut generator = LuaGenerator::<BytesInput>::new(
+            &mut state,
+            r"{
+              generate = function (state)
+                state.count = (state.count or 0) + 1
+                local bytes = {}
+                for i = 1, state.count do bytes[i] = 65 end
+                return bytes
+              end,
+            }",
+        )
+        .unwrap();
+        let lengths = (0..3)
+            .map(|_| generator.generate(&mut state).unwrap().into_inner().len())
+            .collect::<Vec<_>>();
+        assert_eq!(lengths, [1, 2, 3]);
+    }
+}
@@ -12,11 +12,19 @@ use core::cmp::max;
 
 pub use gramatron::*;
 
+#[cfg(feature = "lua_mutator")]
+pub mod lua;
+#[cfg(feature = "lua_mutator")]
+pub use lua::LuaGenerator;
+
 #[cfg(feature = "nautilus")]
 pub mod nautilus;
 #[cfg(feature = "nautilus")]
 pub use nautilus::*;
 
+pub mod structured;
+pub use structured::StructuredGenerator;
+
 /// Generators can generate ranges of bytes.
 pub trait Generator<I, S> {
     /// Generate a new input
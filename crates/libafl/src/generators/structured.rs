@@ -0,0 +1,42 @@
+//! A generator for structured inputs, see [`crate::mutators::structured`]
+
+use core::marker::PhantomData;
+
+use crate::{
+    Error,
+    generators::Generator,
+    mutators::structured::{FieldSpec, StructuredField},
+    state::HasRand,
+};
+
+/// Generates random [`StructuredField`] inputs, honoring the field attributes of their derive
+#[derive(Debug)]
+pub struct StructuredGenerator<I> {
+    phantom: PhantomData<fn() -> I>,
+}
+
+impl<I> StructuredGenerator<I> {
+    /// Creates a new [`StructuredGenerator`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<I> Default for StructuredGenerator<I> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<I, S> Generator<I, S> for StructuredGenerator<I>
+where
+    I: StructuredField,
+    S: HasRand,
+{
+    fn generate(&mut self, state: &mut S) -> Result<I, Error> {
+        Ok(I::generate_field(state.rand_mut(), &FieldSpec::DEFAULT))
+    }
+}
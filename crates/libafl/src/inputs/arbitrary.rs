@@ -0,0 +1,1030 @@
+//! The [`ArbitraryInput`] wraps the raw bytes a [`arbitrary::Arbitrary`] type is decoded from,
+//! as used by `cargo fuzz` harnesses.
+//!
+//! The bytes can be mutated as any other bytes, and the decoded value can be re-encoded to bytes with
+//! [`encode_arbitrary`], which also returns the layout of the value in the bytes for typed mutations,
+//! see [`crate::mutators::arbitrary`].
+//! Values containing enums are encoded with [`encode_arbitrary_with`], given the number of variants of each enum.
+
+use alloc::{
+    borrow::ToOwned,
+    format,
+    string::{String, ToString},
+    vec::{self, Vec},
+};
+use core::{
+    fmt::{self, Debug},
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+    ops::Range,
+};
+
+use arbitrary::{Arbitrary, Unstructured};
+use hashbrown::HashMap;
+use libafl_bolts::{Error, HasLen, ownedref::OwnedSlice};
+use serde::{
+    Deserialize, Serialize, Serializer,
+    ser::{
+        SerializeMap, SerializeSeq, SerializeStruct, SerializeStructVariant, SerializeTuple,
+        SerializeTupleStruct, SerializeTupleVariant,
+    },
+};
+
+use crate::inputs::{HasMutatorBytes, HasTargetBytes, Input, ResizableMutator};
+
+/// An input holding the bytes a `T` is decoded from with [`Arbitrary::arbitrary_take_rest`],
+/// the way `cargo fuzz` harnesses decode their input.
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct ArbitraryInput<T> {
+    bytes: Vec<u8>,
+    #[serde(skip)]
+    phantom: PhantomData<fn() -> T>,
+}
+
+impl<T> ArbitraryInput<T> {
+    /// Creates a new [`ArbitraryInput`] from raw bytes
+    #[must_use]
+    pub fn new(bytes: Vec<u8>) -> Self {
+        Self {
+            bytes,
+            phantom: PhantomData,
+        }
+    }
+
+    /// The raw bytes of this input
+    #[must_use]
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// The raw bytes of this input, mutable
+    pub fn bytes_mut(&mut self) -> &mut Vec<u8> {
+        &mut self.bytes
+    }
+
+    /// Extract the raw bytes
+    #[must_use]
+    pub fn into_inner(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+impl<T> ArbitraryInput<T>
+where
+    T: for<'a> Arbitrary<'a>,
+{
+    /// Decodes the value from the bytes of this input, as the harness would
+    pub fn decode(&self) -> Result<T, Error> {
+        decode_arbitrary(&self.bytes)
+    }
+}
+
+impl<T> ArbitraryInput<T>
+where
+    T: Serialize,
+{
+    /// Creates a new [`ArbitraryInput`] holding the encoding of `value`, see [`encode_arbitrary`]
+    pub fn from_value(value: &T) -> Result<Self, Error> {
+        Ok(Self::new(encode_arbitrary(value)?.0))
+    }
+
+    /// Creates a new [`ArbitraryInput`] holding the encoding of `value` with the given `enums`, see [`encode_arbitrary_with`]
+    pub fn from_value_with(value: &T, enums: &ArbitraryEnums) -> Result<Self, Error> {
+        Ok(Self::new(encode_arbitrary_with(value, enums)?.0))
+    }
+}
+
+/// Decodes a `T` from `bytes` with [`Arbitrary::arbitrary_take_rest`]
+pub fn decode_arbitrary<T>(bytes: &[u8]) -> Result<T, Error>
+where
+    T: for<'a> Arbitrary<'a>,
+{
+    T::arbitrary_take_rest(Unstructured::new(bytes))
+        .map_err(|err| Error::serialize(format!("Could not decode the arbitrary input: {err}")))
+}
+
+impl<T> Clone for ArbitraryInput<T> {
+    fn clone(&self) -> Self {
+        Self::new(self.bytes.clone())
+    }
+}
+
+impl<T> Debug for ArbitraryInput<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ArbitraryInput")
+            .field("bytes", &self.bytes)
+            .finish()
+    }
+}
+
+impl<T> Hash for ArbitraryInput<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.bytes.hash(state);
+    }
+}
+
+impl<T> PartialEq for ArbitraryInput<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.bytes == other.bytes
+    }
+}
+
+impl<T> Eq for ArbitraryInput<T> {}
+
+impl<T> Input for ArbitraryInput<T> {}
+
+impl<T> From<Vec<u8>> for ArbitraryInput<T> {
+    fn from(bytes: Vec<u8>) -> Self {
+        Self::new(bytes)
+    }
+}
+
+impl<T> From<&[u8]> for ArbitraryInput<T> {
+    fn from(bytes: &[u8]) -> Self {
+        Self::new(bytes.to_owned())
+    }
+}
+
+impl<T> HasMutatorBytes for ArbitraryInput<T> {
+    fn mutator_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    fn mutator_bytes_mut(&mut self) -> &mut [u8] {
+        &mut self.bytes
+    }
+}
+
+impl<T> ResizableMutator<u8> for ArbitraryInput<T> {
+    fn resize(&mut self, new_len: usize, value: u8) {
+        self.bytes.resize(new_len, value);
+    }
+
+    fn extend<'a, I: IntoIterator<Item = &'a u8>>(&mut self, iter: I) {
+        <Vec<u8> as Extend<I::Item>>::extend(&mut self.bytes, iter);
+    }
+
+    fn splice<R, I>(&mut self, range: R, replace_with: I) -> vec::Splice<'_, I::IntoIter>
+    where
+        R: core::ops::RangeBounds<usize>,
+        I: IntoIterator<Item = u8>,
+    {
+        self.bytes.splice(range, replace_with)
+    }
+
+    fn drain<R>(&mut self, range: R) -> vec::Drain<'_, u8>
+    where
+        R: core::ops::RangeBounds<usize>,
+    {
+        self.bytes.drain(range)
+    }
+}
+
+impl<T> HasTargetBytes for ArbitraryInput<T> {
+    #[inline]
+    fn target_bytes(&self) -> OwnedSlice<'_, u8> {
+        OwnedSlice::from(&self.bytes)
+    }
+}
+
+impl<T> HasLen for ArbitraryInput<T> {
+    fn len(&self) -> usize {
+        self.bytes.len()
+    }
+}
+
+/// The kind of a value in the encoding of an [`ArbitraryInput`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArbitraryNodeKind {
+    /// A `bool`, one byte whose lowest bit is the value
+    Bool,
+    /// An integer of the given size in bytes, little endian
+    Int(usize),
+    /// A float of the given size in bytes, encoded as its bits
+    Float(usize),
+    /// A `char`, encoded as an `u32`
+    Char,
+    /// The bytes of a string, its length is encoded at the end of the input, see [`encode_arbitrary`]
+    Str,
+    /// The bytes of a byte slice (serialized as bytes, e.g. with `serde_bytes`), its length is encoded
+    /// at the end of the input, see [`encode_arbitrary`]
+    Bytes,
+    /// An enum with the given number of variants, the `u32` choosing the variant followed by its fields
+    Variant(u32),
+    /// An `Option` set to `Some`, the flag byte followed by the value
+    Some,
+    /// An `Option` set to `None`, the flag byte only
+    None,
+    /// An element of a collection, the continuation byte followed by the element
+    Element,
+}
+
+/// A value in the encoding of an [`ArbitraryInput`], see [`encode_arbitrary`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArbitraryNode {
+    /// The kind of the value
+    pub kind: ArbitraryNodeKind,
+    /// The bytes of the value in the encoding
+    pub range: Range<usize>,
+}
+
+/// The number of variants of the enums in a value, by name, to encode them with [`encode_arbitrary_with`].
+///
+/// [`Serialize`] only provides the index of the variant, but the derived [`Arbitrary`] picks the variant
+/// out of their number, so it has to be given here.
+#[derive(Debug, Clone, Default)]
+pub struct ArbitraryEnums {
+    variants: HashMap<&'static str, u32>,
+}
+
+impl ArbitraryEnums {
+    /// Creates a new [`ArbitraryEnums`], without enums
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the enum `name`, as serialized by [`Serialize`] (the name of the type by default), with `variants` variants
+    #[must_use]
+    pub fn with_enum(mut self, name: &'static str, variants: u32) -> Self {
+        self.variants.insert(name, variants);
+        self
+    }
+
+    /// The number of variants of the enum `name`, if known
+    #[must_use]
+    pub fn variants(&self, name: &str) -> Option<u32> {
+        self.variants.get(name).copied()
+    }
+}
+
+/// Encodes `value` to the bytes [`decode_arbitrary`] decodes it from, and returns the layout of all nested values.
+///
+/// This follows the encoding of the `Arbitrary` implementations of the standard types and of the derive:
+/// integers and floats are little endian, options and collection elements are preceded by a flag byte,
+/// collections are terminated by a zero byte and struct fields follow each other.
+/// Strings and byte slices are their bytes, their lengths are taken from the end of the input,
+/// the length of the first one last, as [`Unstructured::arbitrary_len`] does.
+/// The last field of the value, recursively, is decoded by [`Arbitrary::arbitrary_take_rest`]:
+/// a string or byte slice there takes the rest of the input and has no length.
+/// Enums are refused, see [`encode_arbitrary_with`].
+///
+/// Types serialized alike but decoded differently, such as `Box<str>` and `&str`, can't be told apart,
+/// callers should check that the result decodes back to the same bytes.
+pub fn encode_arbitrary<T>(value: &T) -> Result<(Vec<u8>, Vec<ArbitraryNode>), Error>
+where
+    T: Serialize + ?Sized,
+{
+    encode_arbitrary_with(value, &ArbitraryEnums::new())
+}
+
+/// Encodes `value` as [`encode_arbitrary`], with the enums in `enums`.
+///
+/// The variant of an enum is chosen by an `u32` as `(u64::from(u32) * variants) >> 32`, as the derive does,
+/// followed by the fields of the variant.
+pub fn encode_arbitrary_with<T>(
+    value: &T,
+    enums: &ArbitraryEnums,
+) -> Result<(Vec<u8>, Vec<ArbitraryNode>), Error>
+where
+    T: Serialize + ?Sized,
+{
+    let mut encoder = ArbitraryEncoder::new(enums);
+    value
+        .serialize(&mut encoder)
+        .map_err(|err| Error::serialize(err.0))?;
+    Ok(encoder.finish())
+}
+
+#[derive(Debug)]
+struct ArbitraryEncodeError(String);
+
+impl fmt::Display for ArbitraryEncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl core::error::Error for ArbitraryEncodeError {}
+
+impl serde::ser::Error for ArbitraryEncodeError {
+    fn custom<M: fmt::Display>(msg: M) -> Self {
+        Self(msg.to_string())
+    }
+}
+
+#[derive(Debug)]
+struct ArbitraryEncoder<'e> {
+    enums: &'e ArbitraryEnums,
+    bytes: Vec<u8>,
+    nodes: Vec<ArbitraryNode>,
+    /// The offset and length of the strings and byte slices, their lengths are encoded by [`Self::finish`]
+    lengths: Vec<(usize, usize)>,
+    /// The start and number of variants of the enums being encoded
+    variants: Vec<(usize, u32)>,
+    /// Whether the value being encoded is the last one, decoded by `arbitrary_take_rest`
+    tail: bool,
+    /// The fields left in the tuples and structs being encoded, and whether they are the last value
+    fields: Vec<(usize, bool)>,
+}
+
+/// The smallest `u32` such that `(u64::from(u32) * variants) >> 32 == variant_index`,
+/// choosing the variant `variant_index` of a derived [`Arbitrary`] enum
+pub(crate) fn variant_choice(variant_index: u32, variants: u32) -> u32 {
+    (u64::from(variant_index) << 32).div_ceil(u64::from(variants)) as u32
+}
+
+/// Encodes `len` as [`Unstructured::arbitrary_len`] decodes it from the end of the input,
+/// when `rest` bytes remain before the encoded length
+fn encode_len(rest: usize, len: usize) -> Vec<u8> {
+    let rest = rest as u64;
+    // The width of the length depends on the number of remaining bytes, including the length itself
+    let width = [
+        (1, u64::from(u8::MAX) + 1),
+        (2, u64::from(u16::MAX) + 2),
+        (4, u64::from(u32::MAX) + 4),
+    ]
+    .into_iter()
+    .find(|&(width, max)| rest + width <= max)
+    .map_or(8, |(width, _)| width) as usize;
+    // The length is big endian, in as many bytes as needed for `rest`, the maximum length
+    let used = (u64::BITS - rest.leading_zeros()).div_ceil(8) as usize;
+    let mut bytes = vec![0; width];
+    bytes[..used].copy_from_slice(&(len as u64).to_be_bytes()[8 - used..]);
+    bytes
+}
+
+impl<'e> ArbitraryEncoder<'e> {
+    fn new(enums: &'e ArbitraryEnums) -> Self {
+        Self {
+            enums,
+            bytes: vec![],
+            nodes: vec![],
+            lengths: vec![],
+            variants: vec![],
+            tail: true,
+            fields: vec![],
+        }
+    }
+
+    /// Appends the lengths of the strings and byte slices, the first one last
+    fn finish(mut self) -> (Vec<u8>, Vec<ArbitraryNode>) {
+        for &(offset, len) in self.lengths.iter().rev() {
+            let rest = self.bytes.len() - offset;
+            let encoded = encode_len(rest, len);
+            self.bytes.extend_from_slice(&encoded);
+        }
+        (self.bytes, self.nodes)
+    }
+
+    /// Encodes a string or byte slice, its length is encoded by [`Self::finish`],
+    /// unless it is the last value and takes the rest of the input
+    fn push_sized(&mut self, kind: ArbitraryNodeKind, bytes: &[u8]) {
+        if !self.tail {
+            self.lengths.push((self.bytes.len(), bytes.len()));
+        }
+        self.push(kind, bytes);
+    }
+
+    /// Starts a tuple or struct of `len` fields, the last of which is the last value if the tuple is
+    fn start_fields(&mut self, len: usize) {
+        self.fields.push((len, self.tail));
+    }
+
+    /// Moves to the next field of the tuple or struct started last
+    fn next_field(&mut self) {
+        let (left, tail) = self.fields.last_mut().unwrap();
+        *left = left.saturating_sub(1);
+        self.tail = *tail && *left == 0;
+    }
+
+    /// Ends the tuple or struct started last
+    fn end_fields(&mut self) {
+        self.fields.pop();
+    }
+
+    /// Encodes the `u32` choosing the variant `variant_index` of the enum `name`, the fields follow
+    fn start_variant(
+        &mut self,
+        name: &'static str,
+        variant_index: u32,
+    ) -> Result<(), ArbitraryEncodeError> {
+        let Some(variants) = self.enums.variants(name) else {
+            return Err(ArbitraryEncodeError(format!(
+                "The number of variants of the enum {name} is unknown, see `ArbitraryEnums`"
+            )));
+        };
+        if variant_index >= variants {
+            return Err(ArbitraryEncodeError(format!(
+                "The enum {name} has {variants} variants, not {}",
+                variant_index + 1
+            )));
+        }
+        self.variants.push((self.bytes.len(), variants));
+        self.bytes
+            .extend_from_slice(&variant_choice(variant_index, variants).to_le_bytes());
+        Ok(())
+    }
+
+    /// Ends the enum started last, after its fields
+    fn end_variant(&mut self) {
+        let (start, variants) = self.variants.pop().unwrap();
+        self.nodes.push(ArbitraryNode {
+            kind: ArbitraryNodeKind::Variant(variants),
+            range: start..self.bytes.len(),
+        });
+    }
+    fn push(&mut self, kind: ArbitraryNodeKind, bytes: &[u8]) {
+        let start = self.bytes.len();
+        self.bytes.extend_from_slice(bytes);
+        self.nodes.push(ArbitraryNode {
+            kind,
+            range: start..self.bytes.len(),
+        });
+    }
+
+    /// Encodes `value` behind a set flag byte, as done for `Some` and collection elements
+    fn push_flagged<V>(
+        &mut self,
+        kind: ArbitraryNodeKind,
+        value: &V,
+    ) -> Result<(), ArbitraryEncodeError>
+    where
+        V: Serialize + ?Sized,
+    {
+        let start = self.bytes.len();
+        self.bytes.push(1);
+        self.tail = false;
+        value.serialize(&mut *self)?;
+        self.nodes.push(ArbitraryNode {
+            kind,
+            range: start..self.bytes.len(),
+        });
+        Ok(())
+    }
+}
+
+macro_rules! encode_ints {
+    ($($name:ident: $ty:ty),*) => {
+        $(
+            fn $name(self, v: $ty) -> Result<(), ArbitraryEncodeError> {
+                self.push(ArbitraryNodeKind::Int(size_of::<$ty>()), &v.to_le_bytes());
+                Ok(())
+            }
+        )*
+    };
+}
+
+impl<'a, 'e> Serializer for &'a mut ArbitraryEncoder<'e> {
+    type Ok = ();
+    type Error = ArbitraryEncodeError;
+    type SerializeSeq = ArbitraryCollectionEncoder<'a, 'e>;
+    type SerializeTuple = Self;
+    type SerializeTupleStruct = Self;
+    type SerializeTupleVariant = Self;
+    type SerializeMap = ArbitraryCollectionEncoder<'a, 'e>;
+    type SerializeStruct = Self;
+    type SerializeStructVariant = Self;
+
+    encode_ints!(
+        serialize_i8: i8, serialize_i16: i16, serialize_i32: i32, serialize_i64: i64, serialize_i128: i128,
+        serialize_u8: u8, serialize_u16: u16, serialize_u32: u32, serialize_u64: u64, serialize_u128: u128
+    );
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+
+    fn serialize_bool(self, v: bool) -> Result<(), ArbitraryEncodeError> {
+        self.push(ArbitraryNodeKind::Bool, &[u8::from(v)]);
+        Ok(())
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<(), ArbitraryEncodeError> {
+        self.push(ArbitraryNodeKind::Float(4), &v.to_bits().to_le_bytes());
+        Ok(())
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<(), ArbitraryEncodeError> {
+        self.push(ArbitraryNodeKind::Float(8), &v.to_bits().to_le_bytes());
+        Ok(())
+    }
+
+    fn serialize_char(self, v: char) -> Result<(), ArbitraryEncodeError> {
+        self.push(ArbitraryNodeKind::Char, &u32::from(v).to_le_bytes());
+        Ok(())
+    }
+
+    fn serialize_str(self, v: &str) -> Result<(), ArbitraryEncodeError> {
+        self.push_sized(ArbitraryNodeKind::Str, v.as_bytes());
+        Ok(())
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<(), ArbitraryEncodeError> {
+        self.push_sized(ArbitraryNodeKind::Bytes, v);
+        Ok(())
+    }
+
+    fn serialize_none(self) -> Result<(), ArbitraryEncodeError> {
+        self.push(ArbitraryNodeKind::None, &[0]);
+        Ok(())
+    }
+
+    fn serialize_some<V>(self, value: &V) -> Result<(), ArbitraryEncodeError>
+    where
+        V: Serialize + ?Sized,
+    {
+        self.push_flagged(ArbitraryNodeKind::Some, value)
+    }
+
+    fn serialize_unit(self) -> Result<(), ArbitraryEncodeError> {
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<(), ArbitraryEncodeError> {
+        Ok(())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        _variant: &'static str,
+    ) -> Result<(), ArbitraryEncodeError> {
+        self.start_variant(name, variant_index)?;
+        self.end_variant();
+        Ok(())
+    }
+
+    fn serialize_newtype_struct<V>(
+        self,
+        _name: &'static str,
+        value: &V,
+    ) -> Result<(), ArbitraryEncodeError>
+    where
+        V: Serialize + ?Sized,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<V>(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        _variant: &'static str,
+        value: &V,
+    ) -> Result<(), ArbitraryEncodeError>
+    where
+        V: Serialize + ?Sized,
+    {
+        self.start_variant(name, variant_index)?;
+        value.serialize(&mut *self)?;
+        self.end_variant();
+        Ok(())
+    }
+
+    fn serialize_seq(
+        self,
+        _len: Option<usize>,
+    ) -> Result<ArbitraryCollectionEncoder<'a, 'e>, ArbitraryEncodeError> {
+        Ok(ArbitraryCollectionEncoder {
+            encoder: self,
+            key_start: 0,
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self, ArbitraryEncodeError> {
+        self.start_fields(len);
+        Ok(self)
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Self, ArbitraryEncodeError> {
+        self.start_fields(len);
+        Ok(self)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        _variant: &'static str,
+        len: usize,
+    ) -> Result<Self, ArbitraryEncodeError> {
+        self.start_variant(name, variant_index)?;
+        self.start_fields(len);
+        Ok(self)
+    }
+
+    fn serialize_map(
+        self,
+        _len: Option<usize>,
+    ) -> Result<ArbitraryCollectionEncoder<'a, 'e>, ArbitraryEncodeError> {
+        Ok(ArbitraryCollectionEncoder {
+            encoder: self,
+            key_start: 0,
+        })
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Self, ArbitraryEncodeError> {
+        self.start_fields(len);
+        Ok(self)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        _variant: &'static str,
+        len: usize,
+    ) -> Result<Self, ArbitraryEncodeError> {
+        self.start_variant(name, variant_index)?;
+        self.start_fields(len);
+        Ok(self)
+    }
+}
+
+impl SerializeTuple for &mut ArbitraryEncoder<'_> {
+    type Ok = ();
+    type Error = ArbitraryEncodeError;
+
+    fn serialize_element<V>(&mut self, value: &V) -> Result<(), ArbitraryEncodeError>
+    where
+        V: Serialize + ?Sized,
+    {
+        self.next_field();
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<(), ArbitraryEncodeError> {
+        self.end_fields();
+        Ok(())
+    }
+}
+
+impl SerializeTupleStruct for &mut ArbitraryEncoder<'_> {
+    type Ok = ();
+    type Error = ArbitraryEncodeError;
+
+    fn serialize_field<V>(&mut self, value: &V) -> Result<(), ArbitraryEncodeError>
+    where
+        V: Serialize + ?Sized,
+    {
+        self.next_field();
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<(), ArbitraryEncodeError> {
+        self.end_fields();
+        Ok(())
+    }
+}
+
+impl SerializeStruct for &mut ArbitraryEncoder<'_> {
+    type Ok = ();
+    type Error = ArbitraryEncodeError;
+
+    fn serialize_field<V>(
+        &mut self,
+        _key: &'static str,
+        value: &V,
+    ) -> Result<(), ArbitraryEncodeError>
+    where
+        V: Serialize + ?Sized,
+    {
+        self.next_field();
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<(), ArbitraryEncodeError> {
+        self.end_fields();
+        Ok(())
+    }
+}
+
+impl SerializeTupleVariant for &mut ArbitraryEncoder<'_> {
+    type Ok = ();
+    type Error = ArbitraryEncodeError;
+
+    fn serialize_field<V>(&mut self, value: &V) -> Result<(), ArbitraryEncodeError>
+    where
+        V: Serialize + ?Sized,
+    {
+        self.next_field();
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<(), ArbitraryEncodeError> {
+        self.end_fields();
+        self.end_variant();
+        Ok(())
+    }
+}
+
+impl SerializeStructVariant for &mut ArbitraryEncoder<'_> {
+    type Ok = ();
+    type Error = ArbitraryEncodeError;
+
+    fn serialize_field<V>(
+        &mut self,
+        _key: &'static str,
+        value: &V,
+    ) -> Result<(), ArbitraryEncodeError>
+    where
+        V: Serialize + ?Sized,
+    {
+        self.next_field();
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<(), ArbitraryEncodeError> {
+        self.end_fields();
+        self.end_variant();
+        Ok(())
+    }
+}
+
+/// Encodes sequences and maps: each element (or key-value pair) behind a continuation byte, then a zero byte
+#[derive(Debug)]
+struct ArbitraryCollectionEncoder<'a, 'e> {
+    encoder: &'a mut ArbitraryEncoder<'e>,
+    key_start: usize,
+}
+
+impl SerializeSeq for ArbitraryCollectionEncoder<'_, '_> {
+    type Ok = ();
+    type Error = ArbitraryEncodeError;
+
+    fn serialize_element<V>(&mut self, value: &V) -> Result<(), ArbitraryEncodeError>
+    where
+        V: Serialize + ?Sized,
+    {
+        self.encoder.push_flagged(ArbitraryNodeKind::Element, value)
+    }
+
+    fn end(self) -> Result<(), ArbitraryEncodeError> {
+        self.encoder.bytes.push(0);
+        Ok(())
+    }
+}
+
+impl SerializeMap for ArbitraryCollectionEncoder<'_, '_> {
+    type Ok = ();
+    type Error = ArbitraryEncodeError;
+
+    fn serialize_key<K>(&mut self, key: &K) -> Result<(), ArbitraryEncodeError>
+    where
+        K: Serialize + ?Sized,
+    {
+        self.key_start = self.encoder.bytes.len();
+        self.encoder.bytes.push(1);
+        self.encoder.tail = false;
+        key.serialize(&mut *self.encoder)
+    }
+
+    fn serialize_value<V>(&mut self, value: &V) -> Result<(), ArbitraryEncodeError>
+    where
+        V: Serialize + ?Sized,
+    {
+        self.encoder.tail = false;
+        value.serialize(&mut *self.encoder)?;
+        self.encoder.nodes.push(ArbitraryNode {
+            kind: ArbitraryNodeKind::Element,
+            range: self.key_start..self.encoder.bytes.len(),
+        });
+        Ok(())
+    }
+
+    fn end(self) -> Result<(), ArbitraryEncodeError> {
+        self.encoder.bytes.push(0);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{
+        string::{String, ToString},
+        vec,
+        vec::Vec,
+    };
+
+    use arbitrary::{Arbitrary, Unstructured};
+    use serde::{Serialize, Serializer};
+
+    use crate::inputs::arbitrary::{
+        ArbitraryEnums, ArbitraryNodeKind, decode_arbitrary, encode_arbitrary,
+        encode_arbitrary_with,
+    };
+
+    #[derive(Serialize)]
+    struct Packet {
+        kind: u16,
+        ack: bool,
+        payload: Vec<u8>,
+        checksum: Option<u32>,
+    }
+
+    /// A byte slice serialized as bytes, as `serde_bytes` does
+    #[derive(Debug, PartialEq)]
+    struct Raw<'a>(&'a [u8]);
+
+    impl Serialize for Raw<'_> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.serialize_bytes(self.0)
+        }
+    }
+
+    impl<'a> Arbitrary<'a> for Raw<'a> {
+        fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+            Ok(Self(<&[u8]>::arbitrary(u)?))
+        }
+
+        fn arbitrary_take_rest(u: Unstructured<'a>) -> arbitrary::Result<Self> {
+            Ok(Self(<&[u8]>::arbitrary_take_rest(u)?))
+        }
+    }
+
+    #[derive(Debug, PartialEq, Serialize)]
+    enum Command {
+        Nop,
+        Write(u16, String),
+        Seek { offset: i32 },
+    }
+
+    // As generated by `#[derive(Arbitrary)]`
+    impl<'a> Arbitrary<'a> for Command {
+        fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+            Ok(match (u64::from(u32::arbitrary(u)?) * 3) >> 32 {
+                0 => Self::Nop,
+                1 => Self::Write(u16::arbitrary(u)?, String::arbitrary(u)?),
+                2 => Self::Seek {
+                    offset: i32::arbitrary(u)?,
+                },
+                _ => unreachable!(),
+            })
+        }
+
+        fn arbitrary_take_rest(mut u: Unstructured<'a>) -> arbitrary::Result<Self> {
+            Ok(match (u64::from(u32::arbitrary(&mut u)?) * 3) >> 32 {
+                0 => Self::Nop,
+                1 => Self::Write(u16::arbitrary(&mut u)?, String::arbitrary_take_rest(u)?),
+                2 => Self::Seek {
+                    offset: i32::arbitrary_take_rest(u)?,
+                },
+                _ => unreachable!(),
+            })
+        }
+    }
+
+    #[derive(Debug, PartialEq, Serialize)]
+    struct Message<'a> {
+        name: String,
+        payload: Vec<u8>,
+        raw: Raw<'a>,
+        commands: Vec<Command>,
+        id: u32,
+    }
+
+    // As generated by `#[derive(Arbitrary)]`
+    impl<'a> Arbitrary<'a> for Message<'a> {
+        fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+            Ok(Self {
+                name: String::arbitrary(u)?,
+                payload: Vec::arbitrary(u)?,
+                raw: Raw::arbitrary(u)?,
+                commands: Vec::arbitrary(u)?,
+                id: u32::arbitrary(u)?,
+            })
+        }
+    }
+
+    #[test]
+    fn test_encode_arbitrary() {
+        let packet = Packet {
+            kind: 0x0102,
+            ack: true,
+            payload: vec![7, 8],
+            checksum: None,
+        };
+        let (bytes, nodes) = encode_arbitrary(&packet).unwrap();
+        assert_eq!(bytes, [0x02, 0x01, 1, 1, 7, 1, 8, 0, 0]);
+        let kinds = nodes.iter().map(|node| node.kind).collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            [
+                ArbitraryNodeKind::Int(2),
+                ArbitraryNodeKind::Bool,
+                ArbitraryNodeKind::Int(1),
+                ArbitraryNodeKind::Element,
+                ArbitraryNodeKind::Int(1),
+                ArbitraryNodeKind::Element,
+                ArbitraryNodeKind::None,
+            ]
+        );
+        assert_eq!(nodes[3].range, 3..5);
+        // The string is followed by its length
+        let (bytes, nodes) = encode_arbitrary(&("ab", 3u8)).unwrap();
+        assert_eq!(bytes, [b'a', b'b', 3, 2]);
+        assert_eq!(nodes[0].kind, ArbitraryNodeKind::Str);
+        assert_eq!(nodes[0].range, 0..2);
+        assert!(encode_arbitrary(&Command::Nop).is_err());
+    }
+
+    #[test]
+    fn test_encode_arbitrary_take_rest() {
+        // The last string takes the rest of the input and has no length
+        let value = (7u32, "abc".to_string());
+        let (bytes, nodes) = encode_arbitrary(&value).unwrap();
+        assert_eq!(bytes, [7, 0, 0, 0, b'a', b'b', b'c']);
+        assert_eq!(nodes[1].range, 4..7);
+        assert_eq!(decode_arbitrary::<(u32, String)>(&bytes).unwrap(), value);
+
+        // Only the last field, recursively, takes the rest
+        let enums = ArbitraryEnums::new().with_enum("Command", 3);
+        let raw = [1, 2];
+        let values = [
+            ("ab".to_string(), Raw(&raw), Command::Nop),
+            (
+                "löwe".to_string(),
+                Raw(&[]),
+                Command::Write(3, "data".to_string()),
+            ),
+            (
+                "x".repeat(300),
+                Raw(&raw),
+                Command::Write(4, "y".repeat(70_000)),
+            ),
+            (String::new(), Raw(&raw), Command::Seek { offset: -2 }),
+        ];
+        for value in &values {
+            let (bytes, _) = encode_arbitrary_with(value, &enums).unwrap();
+            assert_eq!(
+                &<(String, Raw, Command)>::arbitrary_take_rest(Unstructured::new(&bytes)).unwrap(),
+                value
+            );
+        }
+        let (bytes, _) = encode_arbitrary(&(Raw(&raw), Raw(&raw))).unwrap();
+        assert_eq!(bytes, [1, 2, 1, 2, 2]);
+    }
+
+    #[test]
+    fn test_encode_arbitrary_round_trip() {
+        let enums = ArbitraryEnums::new().with_enum("Command", 3);
+        let raw = [0xff, 0, 0x80];
+        let long_name = "x".repeat(300);
+        let huge_name = "y".repeat(70_000);
+        let messages = [
+            Message {
+                name: String::new(),
+                payload: vec![],
+                raw: Raw(&[]),
+                commands: vec![],
+                id: 0,
+            },
+            Message {
+                name: "löwe 🦁".to_string(),
+                payload: vec![1, 2, 3],
+                raw: Raw(&raw),
+                commands: vec![
+                    Command::Seek { offset: -1 },
+                    Command::Write(0x1234, "data".to_string()),
+                    Command::Nop,
+                ],
+                id: 0xdead_beef,
+            },
+            Message {
+                name: long_name,
+                payload: vec![0; 200],
+                raw: Raw(&raw),
+                commands: vec![Command::Write(1, huge_name)],
+                id: 1,
+            },
+        ];
+        for message in &messages {
+            let (bytes, nodes) = encode_arbitrary_with(message, &enums).unwrap();
+            let mut u = Unstructured::new(&bytes);
+            assert_eq!(&Message::arbitrary(&mut u).unwrap(), message);
+            assert!(u.is_empty());
+            assert_eq!(
+                &Message::arbitrary_take_rest(Unstructured::new(&bytes)).unwrap(),
+                message
+            );
+            assert_eq!(
+                nodes
+                    .iter()
+                    .filter(|node| node.kind == ArbitraryNodeKind::Variant(3))
+                    .count(),
+                message.commands.len()
+            );
+        }
+    }
+}
@@ -30,6 +30,8 @@ pub use list::*;
 #[cfg(feature = "nautilus")]
 pub mod nautilus;
 
+#[cfg(feature = "arbitrary_input")]
+pub mod arbitrary;
 use alloc::{
     boxed::Box,
     string::String,
@@ -56,6 +58,8 @@ use libafl_bolts::{
 pub use nautilus::*;
 use serde::{Deserialize, Serialize};
 
+#[cfg(feature = "arbitrary_input")]
+pub use self::arbitrary::ArbitraryInput;
 use crate::corpus::CorpusId;
 
 /// An input for the target
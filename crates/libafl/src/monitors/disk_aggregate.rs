@@ -9,12 +9,20 @@ use std::{fs::OpenOptions, io::Write, path::PathBuf};
 use libafl_bolts::{ClientId, Error, current_time};
 use serde_json::json;
 
-use crate::monitors::{Monitor, stats::ClientStatsManager};
+use crate::monitors::{
+    Monitor,
+    stats::{ClientStatsManager, StatsSnapshot},
+};
 
 /// A monitor that logs aggregated stats to a JSON file.
+///
+/// With [`OnDiskJsonAggregateMonitor::with_snapshot`], it also keeps a [`StatsSnapshot`] of the campaign,
+/// and restores it when the broker restarts.
 #[derive(Clone)]
 pub struct OnDiskJsonAggregateMonitor {
     json_path: PathBuf,
+    snapshot_path: Option<PathBuf>,
+    restored: bool,
     last_update: Duration,
     update_interval: Duration,
 }
@@ -25,6 +33,7 @@ impl Debug for OnDiskJsonAggregateMonitor {
             .field("last_update", &self.last_update)
             .field("update_interval", &self.update_interval)
             .field("json_path", &self.json_path)
+            .field("snapshot_path", &self.snapshot_path)
             .finish_non_exhaustive()
     }
 }
@@ -36,6 +45,13 @@ impl Monitor for OnDiskJsonAggregateMonitor {
         _event_msg: &str,
         _sender_id: ClientId,
     ) -> Result<(), Error> {
+        if !self.restored {
+            self.restored = true;
+            if let Some(snapshot_path) = self.snapshot_path.as_ref().filter(|path| path.exists()) {
+                client_stats_manager.restore(StatsSnapshot::load(snapshot_path)?);
+            }
+        }
+
         // Write JSON stats if update interval has elapsed
         let cur_time = current_time();
         if cur_time - self.last_update >= self.update_interval {
@@ -68,6 +84,10 @@ impl Monitor for OnDiskJsonAggregateMonitor {
             }
 
             writeln!(&file, "{json_value}").expect("Unable to write JSON to file");
+
+            if let Some(snapshot_path) = &self.snapshot_path {
+                client_stats_manager.snapshot().save(snapshot_path)?;
+            }
         }
         Ok(())
     }
@@ -89,8 +109,23 @@ impl OnDiskJsonAggregateMonitor {
     {
         Self {
             json_path: json_path.into(),
+            snapshot_path: None,
+            restored: false,
             last_update: current_time() - update_interval,
             update_interval,
         }
     }
+
+    /// Keeps a [`StatsSnapshot`] of the campaign at `snapshot_path`, every update interval,
+    /// and restores the stats from it, if it exists, so they survive a restart of the broker.
+    ///
+    /// Snapshots of several campaigns can be combined with [`StatsSnapshot::merge_files`].
+    #[must_use]
+    pub fn with_snapshot<P>(mut self, snapshot_path: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        self.snapshot_path = Some(snapshot_path.into());
+        self
+    }
 }
@@ -0,0 +1,940 @@
+//! OpenTelemetry export of the stats of a campaign, as metrics, and of the fuzzing loop, as spans.
+//!
+//! The [`OtlpExporter`] sends both to an OpenTelemetry collector, using OTLP over HTTP, with the JSON encoding.
+//! The [`OtlpMonitor`] exports the global and per-client stats as gauges, and, with the `introspection` feature,
+//! the [`ClientPerfStats`](crate::monitors::stats::perf_stats::ClientPerfStats) of each client as spans.
+//!
+//! The spans of the fuzzing loop come from wrappers around its parts, recording to the [`OtlpExporter::global`]
+//! exporter of each client process: [`OtlpStage`](crate::stages::OtlpStage) for stages,
+//! [`OtlpExecutor`](crate::executors::OtlpExecutor) for a sample of the executions,
+//! [`OtlpEventManagerHook`](crate::events::OtlpEventManagerHook) for events,
+//! and [`OtlpCorpus`](crate::corpus::OtlpCorpus) for corpus operations.
+//! The spans recorded while a stage runs are children of its span, in its trace.
+//!
+//! ```rust,no_run
+//! use libafl::monitors::{OtlpMonitor, otlp::OtlpExporter};
+//!
+//! // In the broker
+//! let monitor = OtlpMonitor::new(OtlpExporter::new("http://127.0.0.1:4318"));
+//! // In the clients, or set `OTEL_EXPORTER_OTLP_ENDPOINT` instead
+//! OtlpExporter::new("http://127.0.0.1:4318")
+//!     .install()
+//!     .unwrap();
+//! ```
+
+use alloc::{
+    borrow::Cow,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{fmt::Write as _, time::Duration};
+use std::{
+    env,
+    io::{Read, Write},
+    net::TcpStream,
+    process,
+    sync::{
+        Mutex, OnceLock,
+        mpsc::{SyncSender, TrySendError, sync_channel},
+    },
+    thread,
+};
+
+use libafl_bolts::{
+    ClientId, Error, current_nanos, current_time,
+    rands::{Rand, StdRand},
+};
+use serde_json::{Value, json};
+
+#[cfg(feature = "introspection")]
+use crate::monitors::stats::perf_stats::PerfFeature;
+use crate::{
+    events::broker_hooks::objective::parse_http_url,
+    monitors::{
+        Monitor,
+        stats::{ClientStatsManager, EdgeCoverage, user_stats::UserStatsValue},
+    },
+};
+
+/// The env variable with the url of the collector, as for the OpenTelemetry SDKs, such as `http://localhost:4318`
+pub const OTLP_ENDPOINT_ENV: &str = "OTEL_EXPORTER_OTLP_ENDPOINT";
+
+/// The env variable with the name of the service, as for the OpenTelemetry SDKs
+pub const OTLP_SERVICE_NAME_ENV: &str = "OTEL_SERVICE_NAME";
+
+/// The service name, if there is none in the env
+const DEFAULT_SERVICE_NAME: &str = "libafl";
+
+/// How often the exporters send their data by default
+const DEFAULT_EXPORT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How many spans an exporter buffers at most, before sending them
+const DEFAULT_BATCH_SIZE: usize = 512;
+
+/// How long the collector may take to accept a request
+const EXPORT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// How many batches of spans wait for the export thread at most, before new ones get dropped
+const EXPORT_QUEUE_LEN: usize = 4;
+
+/// The exporter of this process, see [`OtlpExporter::global`]
+static GLOBAL_EXPORTER: OnceLock<Option<OtlpExporter>> = OnceLock::new();
+
+/// The value of an attribute or a gauge
+#[derive(Debug, Clone, PartialEq)]
+pub enum OtlpValue {
+    /// An integer
+    Int(i64),
+    /// A float
+    Double(f64),
+    /// A boolean
+    Bool(bool),
+    /// A string
+    String(Cow<'static, str>),
+}
+
+impl OtlpValue {
+    fn to_json(&self) -> Value {
+        match self {
+            // OTLP/JSON encodes 64 bit integers as strings
+            Self::Int(value) => json!({ "intValue": value.to_string() }),
+            Self::Double(value) => json!({ "doubleValue": value }),
+            Self::Bool(value) => json!({ "boolValue": value }),
+            Self::String(value) => json!({ "stringValue": value }),
+        }
+    }
+}
+
+impl From<u64> for OtlpValue {
+    fn from(value: u64) -> Self {
+        Self::Int(i64::try_from(value).unwrap_or(i64::MAX))
+    }
+}
+
+impl From<usize> for OtlpValue {
+    fn from(value: usize) -> Self {
+        Self::Int(i64::try_from(value).unwrap_or(i64::MAX))
+    }
+}
+
+impl From<i64> for OtlpValue {
+    fn from(value: i64) -> Self {
+        Self::Int(value)
+    }
+}
+
+impl From<f64> for OtlpValue {
+    fn from(value: f64) -> Self {
+        Self::Double(value)
+    }
+}
+
+impl From<bool> for OtlpValue {
+    fn from(value: bool) -> Self {
+        Self::Bool(value)
+    }
+}
+
+impl From<&'static str> for OtlpValue {
+    fn from(value: &'static str) -> Self {
+        Self::String(Cow::Borrowed(value))
+    }
+}
+
+impl From<String> for OtlpValue {
+    fn from(value: String) -> Self {
+        Self::String(Cow::Owned(value))
+    }
+}
+
+/// The attributes of a span or a data point
+pub type OtlpAttributes = Vec<(Cow<'static, str>, OtlpValue)>;
+
+fn attributes_json(attributes: &[(Cow<'static, str>, OtlpValue)]) -> Value {
+    attributes
+        .iter()
+        .map(|(key, value)| json!({ "key": key, "value": value.to_json() }))
+        .collect()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    let mut hex = String::with_capacity(bytes.len() * 2);
+    for byte in bytes {
+        write!(hex, "{byte:02x}").unwrap();
+    }
+    hex
+}
+
+/// One span of a trace, see [`OtlpExporter::span`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct OtlpSpan {
+    /// The name of the span, such as the operation it covers
+    pub name: Cow<'static, str>,
+    /// The trace the span belongs to
+    pub trace_id: [u8; 16],
+    /// The id of the span
+    pub span_id: [u8; 8],
+    /// The id of the parent span, if any
+    pub parent_span_id: Option<[u8; 8]>,
+    /// The start, since the epoch
+    pub start: Duration,
+    /// The end, since the epoch
+    pub end: Duration,
+    /// The attributes of the span
+    pub attributes: OtlpAttributes,
+}
+
+impl OtlpSpan {
+    /// Adds an attribute to the span
+    #[must_use]
+    pub fn attribute<K, V>(mut self, key: K, value: V) -> Self
+    where
+        K: Into<Cow<'static, str>>,
+        V: Into<OtlpValue>,
+    {
+        self.attributes.push((key.into(), value.into()));
+        self
+    }
+
+    /// Makes this span a child of `parent`, in the trace of `parent`
+    #[must_use]
+    pub fn child_of(mut self, parent: &OtlpSpan) -> Self {
+        self.trace_id = parent.trace_id;
+        self.parent_span_id = Some(parent.span_id);
+        self
+    }
+
+    fn to_json(&self) -> Value {
+        json!({
+            "traceId": hex(&self.trace_id),
+            "spanId": hex(&self.span_id),
+            "parentSpanId": self.parent_span_id.map_or(String::new(), |id| hex(&id)),
+            "name": self.name,
+            // SPAN_KIND_INTERNAL
+            "kind": 1,
+            "startTimeUnixNano": self.start.as_nanos().to_string(),
+            "endTimeUnixNano": self.end.as_nanos().to_string(),
+            "attributes": attributes_json(&self.attributes),
+        })
+    }
+}
+
+/// One data point of a gauge metric
+#[derive(Debug, Clone, PartialEq)]
+pub struct OtlpGauge {
+    /// The name of the metric
+    pub name: Cow<'static, str>,
+    /// The current value
+    pub value: OtlpValue,
+    /// The attributes of the data point, such as the client it belongs to
+    pub attributes: OtlpAttributes,
+}
+
+impl OtlpGauge {
+    /// Creates a new [`OtlpGauge`] data point
+    #[must_use]
+    pub fn new<N, V>(name: N, value: V) -> Self
+    where
+        N: Into<Cow<'static, str>>,
+        V: Into<OtlpValue>,
+    {
+        Self {
+            name: name.into(),
+            value: value.into(),
+            attributes: vec![],
+        }
+    }
+
+    /// Adds an attribute to the data point
+    #[must_use]
+    pub fn attribute<K, V>(mut self, key: K, value: V) -> Self
+    where
+        K: Into<Cow<'static, str>>,
+        V: Into<OtlpValue>,
+    {
+        self.attributes.push((key.into(), value.into()));
+        self
+    }
+}
+
+/// The spans an [`OtlpExporter`] did not send yet
+#[derive(Debug)]
+struct PendingSpans {
+    spans: Vec<OtlpSpan>,
+    last_export: Duration,
+    rand: StdRand,
+    /// The trace and span id of the span new spans are children of, see [`OtlpExporter::in_span`]
+    parent: Option<([u8; 16], [u8; 8])>,
+}
+
+/// The thread sending the batches of spans of an [`OtlpExporter`], in the process that started it
+#[derive(Debug)]
+struct ExportThread {
+    pid: u32,
+    sender: SyncSender<Vec<OtlpSpan>>,
+}
+
+/// Sends metrics and spans to an OpenTelemetry collector, with OTLP over HTTP, using the JSON encoding.
+///
+/// Spans get buffered, and handed to a background thread in batches, every [`Self::export_interval`],
+/// or once there are [`Self::batch_size`] of them, so that recording never waits for the collector.
+/// Batches get dropped while the thread is still busy with earlier ones.
+/// Failed exports get logged and dropped, so that a missing collector never stops the fuzzer.
+#[derive(Debug)]
+pub struct OtlpExporter {
+    endpoint: String,
+    service_name: String,
+    export_interval: Duration,
+    batch_size: usize,
+    pending: Mutex<PendingSpans>,
+    export_thread: Mutex<Option<ExportThread>>,
+}
+
+impl OtlpExporter {
+    /// Creates a new [`OtlpExporter`] for the collector at the `http://host:port` `endpoint`,
+    /// which receives `/v1/metrics` and `/v1/traces` below it
+    #[must_use]
+    pub fn new<S>(endpoint: S) -> Self
+    where
+        S: Into<String>,
+    {
+        let mut endpoint = endpoint.into();
+        while endpoint.ends_with('/') {
+            endpoint.pop();
+        }
+        Self {
+            endpoint,
+            service_name: DEFAULT_SERVICE_NAME.into(),
+            export_interval: DEFAULT_EXPORT_INTERVAL,
+            batch_size: DEFAULT_BATCH_SIZE,
+            pending: Mutex::new(PendingSpans {
+                spans: vec![],
+                last_export: current_time(),
+                rand: StdRand::with_seed(current_nanos() ^ u64::from(process::id())),
+                parent: None,
+            }),
+            export_thread: Mutex::new(None),
+        }
+    }
+
+    /// Creates an [`OtlpExporter`] for the collector in [`OTLP_ENDPOINT_ENV`], if set,
+    /// with the service name in [`OTLP_SERVICE_NAME_ENV`], if set
+    #[must_use]
+    pub fn from_env() -> Option<Self> {
+        let endpoint = env::var(OTLP_ENDPOINT_ENV).ok()?;
+        let mut exporter = Self::new(endpoint);
+        if let Ok(service_name) = env::var(OTLP_SERVICE_NAME_ENV) {
+            exporter = exporter.service_name(service_name);
+        }
+        Some(exporter)
+    }
+
+    /// Sets the `service.name` the data gets reported for
+    #[must_use]
+    pub fn service_name<S>(mut self, service_name: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.service_name = service_name.into();
+        self
+    }
+
+    /// Sets how often the buffered spans get sent
+    #[must_use]
+    pub fn export_interval(mut self, export_interval: Duration) -> Self {
+        self.export_interval = export_interval;
+        self
+    }
+
+    /// Sets how many spans get buffered at most, before sending them
+    #[must_use]
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// The url of the collector
+    #[must_use]
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
+    /// Makes this the exporter of this process, that the [`OtlpStage`](crate::stages::OtlpStage) and friends record to.
+    ///
+    /// Call it in each client before fuzzing, before anything asks for the [`Self::global`] exporter.
+    pub fn install(self) -> Result<(), Error> {
+        GLOBAL_EXPORTER.set(Some(self)).map_err(|_| {
+            Error::illegal_state(
+                "This process already has an OTLP exporter, see OtlpExporter::global",
+            )
+        })
+    }
+
+    /// The exporter of this process: the one passed to [`Self::install`],
+    /// else the one configured by the env, see [`Self::from_env`], if any.
+    #[must_use]
+    pub fn global() -> Option<&'static Self> {
+        GLOBAL_EXPORTER.get_or_init(Self::from_env).as_ref()
+    }
+
+    /// Creates a span from `start` to now, as a child of the span of the enclosing [`Self::in_span`], if any,
+    /// else in a new trace. Record it with [`Self::record`].
+    pub fn span<N>(&self, name: N, start: Duration) -> OtlpSpan
+    where
+        N: Into<Cow<'static, str>>,
+    {
+        let mut pending = self.pending.lock().unwrap();
+        let (trace_id, parent_span_id) = if let Some((trace_id, span_id)) = pending.parent {
+            (trace_id, Some(span_id))
+        } else {
+            let mut trace_id = [0; 16];
+            trace_id[..8].copy_from_slice(&pending.rand.next().to_le_bytes());
+            trace_id[8..].copy_from_slice(&pending.rand.next().to_le_bytes());
+            (trace_id, None)
+        };
+        let span_id = pending.rand.next().to_le_bytes();
+        OtlpSpan {
+            name: name.into(),
+            trace_id,
+            span_id,
+            parent_span_id,
+            start,
+            end: current_time(),
+            attributes: vec![],
+        }
+    }
+
+    /// Runs `f`, with the spans it creates with [`Self::span`] as children of `span`
+    pub fn in_span<F, R>(&self, span: &OtlpSpan, f: F) -> R
+    where
+        F: FnOnce() -> R,
+    {
+        let previous = self
+            .pending
+            .lock()
+            .unwrap()
+            .parent
+            .replace((span.trace_id, span.span_id));
+        let result = f();
+        self.pending.lock().unwrap().parent = previous;
+        result
+    }
+
+    /// Buffers the span, and hands the buffered spans to the export thread, if it is time to
+    pub fn record(&self, span: OtlpSpan) {
+        let spans = {
+            let mut pending = self.pending.lock().unwrap();
+            pending.spans.push(span);
+            let now = current_time();
+            if pending.spans.len() < self.batch_size
+                && now.saturating_sub(pending.last_export) < self.export_interval
+            {
+                return;
+            }
+            pending.last_export = now;
+            core::mem::take(&mut pending.spans)
+        };
+        self.export_in_background(spans);
+    }
+
+    /// Hands the spans to the export thread of this process, started on first use
+    fn export_in_background(&self, spans: Vec<OtlpSpan>) {
+        let mut export_thread = self.export_thread.lock().unwrap();
+        // A forked child does not inherit the thread of its parent
+        let pid = process::id();
+        if export_thread
+            .as_ref()
+            .is_none_or(|thread| thread.pid != pid)
+        {
+            let (sender, receiver) = sync_channel::<Vec<OtlpSpan>>(EXPORT_QUEUE_LEN);
+            let endpoint = self.endpoint.clone();
+            let service_name = self.service_name.clone();
+            let spawned = thread::Builder::new()
+                .name("otlp-export".into())
+                .spawn(move || {
+                    for spans in receiver {
+                        if let Err(err) =
+                            post(&endpoint, "/v1/traces", &spans_body(&service_name, &spans))
+                        {
+                            log::warn!("Dropping {} spans for {endpoint}: {err}", spans.len());
+                        }
+                    }
+                });
+            if let Err(err) = spawned {
+                log::warn!(
+                    "Dropping {} spans for {}: could not start the export thread: {err}",
+                    spans.len(),
+                    self.endpoint
+                );
+                return;
+            }
+            *export_thread = Some(ExportThread { pid, sender });
+        }
+        match export_thread.as_ref().unwrap().sender.try_send(spans) {
+            Ok(()) => {}
+            Err(TrySendError::Full(spans)) => log::warn!(
+                "Dropping {} spans for {}: the export thread is busy",
+                spans.len(),
+                self.endpoint
+            ),
+            Err(TrySendError::Disconnected(spans)) => log::warn!(
+                "Dropping {} spans for {}: the export thread is gone",
+                spans.len(),
+                self.endpoint
+            ),
+        }
+    }
+
+    /// Sends the buffered spans now, on this thread.
+    /// Batches already handed to the export thread are sent by it.
+    pub fn flush(&self) -> Result<(), Error> {
+        let spans = {
+            let mut pending = self.pending.lock().unwrap();
+            pending.last_export = current_time();
+            core::mem::take(&mut pending.spans)
+        };
+        if spans.is_empty() {
+            return Ok(());
+        }
+        self.export_spans(&spans)
+    }
+
+    /// Sends the spans to the collector, at once
+    pub fn export_spans(&self, spans: &[OtlpSpan]) -> Result<(), Error> {
+        post(
+            &self.endpoint,
+            "/v1/traces",
+            &spans_body(&self.service_name, spans),
+        )
+    }
+
+    /// Sends the gauges to the collector. Data points of the same name become one metric.
+    pub fn export_gauges(&self, gauges: &[OtlpGauge]) -> Result<(), Error> {
+        let now = current_time().as_nanos().to_string();
+        let mut metrics: Vec<(&str, Vec<Value>)> = vec![];
+        for gauge in gauges {
+            let mut data_point = json!({
+                "timeUnixNano": now,
+                "attributes": attributes_json(&gauge.attributes),
+            });
+            match &gauge.value {
+                OtlpValue::Int(value) => data_point["asInt"] = value.to_string().into(),
+                OtlpValue::Double(value) => data_point["asDouble"] = (*value).into(),
+                OtlpValue::Bool(value) => data_point["asInt"] = u8::from(*value).to_string().into(),
+                OtlpValue::String(_) => continue,
+            }
+            if let Some((_, data_points)) = metrics.iter_mut().find(|(name, _)| *name == gauge.name)
+            {
+                data_points.push(data_point);
+            } else {
+                metrics.push((&gauge.name, vec![data_point]));
+            }
+        }
+        let metrics: Vec<Value> = metrics
+            .into_iter()
+            .map(|(name, data_points)| json!({ "name": name, "gauge": { "dataPoints": data_points } }))
+            .collect();
+        let body = json!({
+            "resourceMetrics": [{
+                "resource": resource(&self.service_name),
+                "scopeMetrics": [{ "scope": scope(), "metrics": metrics }],
+            }],
+        });
+        post(&self.endpoint, "/v1/metrics", &body)
+    }
+}
+
+/// The resource everything libafl exports belongs to: the `service_name` and this process
+fn resource(service_name: &str) -> Value {
+    json!({
+        "attributes": attributes_json(&[
+            (Cow::Borrowed("service.name"), service_name.to_string().into()),
+            (Cow::Borrowed("process.pid"), u64::from(process::id()).into()),
+        ]),
+    })
+}
+
+/// The body of a request exporting the `spans`
+fn spans_body(service_name: &str, spans: &[OtlpSpan]) -> Value {
+    json!({
+        "resourceSpans": [{
+            "resource": resource(service_name),
+            "scopeSpans": [{
+                "scope": scope(),
+                "spans": spans.iter().map(OtlpSpan::to_json).collect::<Vec<_>>(),
+            }],
+        }],
+    })
+}
+
+/// Posts the `body` to the `path` below the `endpoint` of the collector
+fn post(endpoint: &str, path: &str, body: &Value) -> Result<(), Error> {
+    let url = format!("{endpoint}{path}");
+    let (addr, host, path) = parse_http_url(&url)?;
+    let body = body.to_string();
+    let mut stream = TcpStream::connect_timeout(&addr, EXPORT_TIMEOUT)?;
+    stream.set_read_timeout(Some(EXPORT_TIMEOUT))?;
+    stream.set_write_timeout(Some(EXPORT_TIMEOUT))?;
+    write!(
+        stream,
+        "POST {path} HTTP/1.1\r\nHost: {host}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )?;
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response)?;
+    let status_line = response.lines().next().unwrap_or_default();
+    if status_line
+        .split(' ')
+        .nth(1)
+        .is_some_and(|code| code.starts_with('2'))
+    {
+        Ok(())
+    } else {
+        Err(Error::illegal_state(format!(
+            "The collector answered {status_line:?} to {path}"
+        )))
+    }
+}
+
+/// The instrumentation scope of everything libafl exports
+fn scope() -> Value {
+    json!({ "name": "libafl", "version": env!("CARGO_PKG_VERSION") })
+}
+
+/// The numeric value of a user stat, if it has one
+#[expect(clippy::cast_precision_loss)]
+fn user_stat_value(value: &UserStatsValue) -> Option<f64> {
+    match value {
+        UserStatsValue::Number(number) => Some(*number as f64),
+        UserStatsValue::Float(float) | UserStatsValue::Percent(float) => Some(*float),
+        UserStatsValue::Ratio(_, 0) => Some(0.0),
+        UserStatsValue::Ratio(numerator, denominator) => {
+            Some(*numerator as f64 / *denominator as f64)
+        }
+        UserStatsValue::String(_) => None,
+    }
+}
+
+/// A monitor exporting the stats of the campaign to an OpenTelemetry collector, see the [module docs](self).
+///
+/// Every [`Self::interval`], it sends the global stats as `libafl.*` gauges, the numeric user stats as
+/// `libafl.user_stats.*` gauges, and the stats of each client as `libafl.client.*` gauges, with a `client.id`.
+/// With the `introspection` feature, it also sends one `client_perf` span per client, covering the time since the last export,
+/// with the share of time spent in each part of the fuzzing loop as attributes.
+#[derive(Debug)]
+pub struct OtlpMonitor {
+    exporter: OtlpExporter,
+    interval: Duration,
+    last_export: Duration,
+}
+
+impl OtlpMonitor {
+    /// Creates a new [`OtlpMonitor`], exporting with the `exporter`
+    #[must_use]
+    pub fn new(exporter: OtlpExporter) -> Self {
+        Self {
+            exporter,
+            interval: DEFAULT_EXPORT_INTERVAL,
+            last_export: Duration::ZERO,
+        }
+    }
+
+    /// Sets how often the stats get exported
+    #[must_use]
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// The exporter of this monitor
+    #[must_use]
+    pub fn exporter(&self) -> &OtlpExporter {
+        &self.exporter
+    }
+
+    fn gauges(
+        client_stats_manager: &mut ClientStatsManager,
+        cur_time: Duration,
+    ) -> Result<Vec<OtlpGauge>, Error> {
+        let edges = client_stats_manager.edges_coverage();
+        let global_stats = client_stats_manager.global_stats();
+        let mut gauges = vec![
+            OtlpGauge::new("libafl.clients", global_stats.client_stats_count),
+            OtlpGauge::new("libafl.run_time", global_stats.run_time.as_secs()),
+            OtlpGauge::new("libafl.executions", global_stats.total_execs),
+            OtlpGauge::new("libafl.execs_per_sec", global_stats.execs_per_sec),
+            OtlpGauge::new("libafl.corpus_size", global_stats.corpus_size),
+            OtlpGauge::new("libafl.objective_size", global_stats.objective_size),
+        ];
+        if let Some(EdgeCoverage {
+            edges_hit,
+            edges_total,
+        }) = edges
+        {
+            gauges.push(OtlpGauge::new("libafl.edges_hit", edges_hit));
+            gauges.push(OtlpGauge::new("libafl.edges_total", edges_total));
+        }
+        for (name, value) in client_stats_manager.aggregated() {
+            if let Some(value) = user_stat_value(value) {
+                gauges.push(OtlpGauge::new(format!("libafl.user_stats.{name}"), value));
+            }
+        }
+
+        let client_ids: Vec<ClientId> = client_stats_manager
+            .client_stats()
+            .iter()
+            .filter(|(_, client)| client.enabled())
+            .map(|(id, _)| *id)
+            .collect();
+        for client_id in client_ids {
+            let execs_per_sec = client_stats_manager
+                .update_client_stats_for(client_id, |client| client.execs_per_sec(cur_time))?;
+            let client = client_stats_manager.client_stats_for(client_id)?;
+            let id = u64::from(client_id.0);
+            gauges.extend([
+                OtlpGauge::new("libafl.client.executions", client.executions())
+                    .attribute("client.id", id),
+                OtlpGauge::new("libafl.client.execs_per_sec", execs_per_sec)
+                    .attribute("client.id", id),
+                OtlpGauge::new("libafl.client.corpus_size", client.corpus_size())
+                    .attribute("client.id", id),
+                OtlpGauge::new("libafl.client.objective_size", client.objective_size())
+                    .attribute("client.id", id),
+            ]);
+        }
+        Ok(gauges)
+    }
+
+    /// Records one span per client, with its [`ClientPerfStats`](crate::monitors::stats::perf_stats::ClientPerfStats)
+    #[cfg(feature = "introspection")]
+    #[expect(clippy::cast_precision_loss)]
+    fn record_perf(&self, client_stats_manager: &ClientStatsManager, since: Duration) {
+        for (client_id, client) in client_stats_manager.client_stats() {
+            let perf = &client.introspection_stats;
+            let elapsed = perf.elapsed_cycles() as f64;
+            if !client.enabled() || elapsed == 0.0 {
+                continue;
+            }
+            let mut span = self
+                .exporter
+                .span("client_perf", since)
+                .attribute("client.id", u64::from(client_id.0))
+                .attribute("perf.scheduler", perf.scheduler_cycles() as f64 / elapsed)
+                .attribute("perf.manager", perf.manager_cycles() as f64 / elapsed);
+            for (stage_index, features) in perf.used_stages() {
+                for (feature_index, cycles) in features.iter().enumerate() {
+                    if *cycles > 0 {
+                        let feature: PerfFeature = feature_index.into();
+                        span = span.attribute(
+                            format!("perf.stage.{stage_index}.{feature:?}"),
+                            *cycles as f64 / elapsed,
+                        );
+                    }
+                }
+            }
+            for (name, cycles) in perf.feedbacks() {
+                span = span.attribute(format!("perf.feedback.{name}"), *cycles as f64 / elapsed);
+            }
+            self.exporter.record(span);
+        }
+    }
+}
+
+impl Monitor for OtlpMonitor {
+    fn display(
+        &mut self,
+        client_stats_manager: &mut ClientStatsManager,
+        _event_msg: &str,
+        _sender_id: ClientId,
+    ) -> Result<(), Error> {
+        let cur_time = current_time();
+        if cur_time.saturating_sub(self.last_export) < self.interval {
+            return Ok(());
+        }
+        #[cfg(feature = "introspection")]
+        {
+            let since = if self.last_export == Duration::ZERO {
+                client_stats_manager.start_time()
+            } else {
+                self.last_export
+            };
+            self.record_perf(client_stats_manager, since);
+            if let Err(err) = self.exporter.flush() {
+                log::warn!(
+                    "Failed to export spans to {}: {err}",
+                    self.exporter.endpoint
+                );
+            }
+        }
+        self.last_export = cur_time;
+
+        let gauges = Self::gauges(client_stats_manager, cur_time)?;
+        if let Err(err) = self.exporter.export_gauges(&gauges) {
+            log::warn!(
+                "Failed to export metrics to {}: {err}",
+                self.exporter.endpoint
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::{String, ToString};
+    use core::time::Duration;
+    use std::{
+        io::{BufRead, BufReader, Read, Write},
+        net::TcpListener,
+        sync::mpsc::{Receiver, channel},
+        thread,
+    };
+
+    use libafl_bolts::{ClientId, current_time};
+    use serde_json::Value;
+
+    use super::{EXPORT_TIMEOUT, OtlpExporter, OtlpMonitor};
+    use crate::monitors::{Monitor, stats::ClientStatsManager};
+
+    /// A stand-in for a collector, handing out the path and the body of each request
+    fn collector() -> (String, Receiver<(String, Value)>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let endpoint = format!("http://{}", listener.local_addr().unwrap());
+        let (sender, receiver) = channel();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut reader = BufReader::new(stream.unwrap());
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                let path = request_line.split(' ').nth(1).unwrap().to_string();
+                let mut content_length = 0;
+                loop {
+                    let mut header = String::new();
+                    reader.read_line(&mut header).unwrap();
+                    if header == "\r\n" {
+                        break;
+                    }
+                    if let Some(len) = header.strip_prefix("Content-Length: ") {
+                        content_length = len.trim().parse().unwrap();
+                    }
+                }
+                let mut body = vec![0; content_length];
+                reader.read_exact(&mut body).unwrap();
+                reader
+                    .get_mut()
+                    .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\n{}")
+                    .unwrap();
+                if sender
+                    .send((path, serde_json::from_slice(&body).unwrap()))
+                    .is_err()
+                {
+                    break;
+                }
+            }
+        });
+        (endpoint, receiver)
+    }
+
+    #[test]
+    fn test_otlp_monitor() {
+        let (endpoint, requests) = collector();
+        let mut monitor = OtlpMonitor::new(OtlpExporter::new(endpoint).service_name("test"));
+        let mut client_stats_manager = ClientStatsManager::default();
+        client_stats_manager
+            .client_stats_insert(ClientId(0))
+            .unwrap();
+        client_stats_manager
+            .update_client_stats_for(ClientId(0), |client| {
+                client.update_executions(100, current_time());
+                client.update_corpus_size(3);
+            })
+            .unwrap();
+        monitor
+            .display(&mut client_stats_manager, "Testcase", ClientId(0))
+            .unwrap();
+
+        let (path, metrics) = requests.recv().unwrap();
+        assert_eq!(path, "/v1/metrics");
+        let resource = &metrics["resourceMetrics"][0];
+        assert_eq!(
+            resource["resource"]["attributes"][0]["value"]["stringValue"],
+            "test"
+        );
+        let metrics = resource["scopeMetrics"][0]["metrics"].as_array().unwrap();
+        let corpus_size = metrics
+            .iter()
+            .find(|metric| metric["name"] == "libafl.client.corpus_size")
+            .unwrap();
+        let data_point = &corpus_size["gauge"]["dataPoints"][0];
+        assert_eq!(data_point["asInt"], "3");
+        assert_eq!(data_point["attributes"][0]["key"], "client.id");
+
+        let exporter = monitor.exporter();
+        let stage = exporter
+            .span(
+                "stage",
+                current_time().saturating_sub(Duration::from_millis(5)),
+            )
+            .attribute("stage.name", "mutational");
+        let run = exporter.span("run_target", current_time()).child_of(&stage);
+        exporter.record(stage);
+        exporter.record(run);
+        exporter.flush().unwrap();
+
+        let (_, traces) = requests
+            .iter()
+            .find(|(path, _)| path == "/v1/traces")
+            .unwrap();
+        let spans = traces["resourceSpans"][0]["scopeSpans"][0]["spans"]
+            .as_array()
+            .unwrap();
+        assert_eq!(spans.len(), 2);
+        let (stage, run) = (&spans[0], &spans[1]);
+        assert_eq!(stage["name"], "stage");
+        assert_eq!(stage["traceId"].as_str().unwrap().len(), 32);
+        assert_eq!(run["traceId"], stage["traceId"]);
+        assert_eq!(run["parentSpanId"], stage["spanId"]);
+        assert_eq!(stage["attributes"][0]["value"]["stringValue"], "mutational");
+    }
+
+    #[test]
+    fn test_otlp_background_export() {
+        let (endpoint, requests) = collector();
+        let exporter = OtlpExporter::new(endpoint).batch_size(2);
+        let stage = exporter.span("stage", current_time());
+        exporter.in_span(&stage, || {
+            exporter.record(exporter.span("run_target", current_time()));
+        });
+        exporter.record(stage);
+
+        // The batch is full, the export thread sends it without a flush
+        let (path, traces) = requests.recv_timeout(Duration::from_secs(10)).unwrap();
+        assert_eq!(path, "/v1/traces");
+        let spans = traces["resourceSpans"][0]["scopeSpans"][0]["spans"]
+            .as_array()
+            .unwrap();
+        let (run, stage) = (&spans[0], &spans[1]);
+        assert_eq!(run["traceId"], stage["traceId"]);
+        assert_eq!(run["parentSpanId"], stage["spanId"]);
+        assert_eq!(stage["parentSpanId"], "");
+        // Outside of the stage, spans start new traces again
+        assert_ne!(
+            exporter.span("run_target", current_time()).trace_id,
+            exporter.span("run_target", current_time()).trace_id
+        );
+    }
+
+    #[test]
+    fn test_otlp_record_does_not_wait() {
+        // A collector that never answers
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let exporter =
+            OtlpExporter::new(format!("http://{}", listener.local_addr().unwrap())).batch_size(1);
+        let start = current_time();
+        for _ in 0..32 {
+            exporter.record(exporter.span("run_target", current_time()));
+        }
+        assert!(current_time().saturating_sub(start) < EXPORT_TIMEOUT);
+    }
+}
@@ -0,0 +1,267 @@
+//! Time series of the global stats of a campaign, and the detection of coverage plateaus on them.
+
+#[cfg(feature = "std")]
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::{fmt, time::Duration};
+#[cfg(feature = "std")]
+use std::io::Write;
+
+#[cfg(feature = "std")]
+use libafl_bolts::Error;
+use libafl_bolts::format_duration;
+use serde::{Deserialize, Serialize};
+
+/// The default time between two samples of a [`StatsHistory`], before any downsampling
+pub const DEFAULT_HISTORY_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The default number of samples a [`StatsHistory`] keeps
+pub const DEFAULT_HISTORY_SAMPLES: usize = 1024;
+
+/// The global stats at one point of the campaign
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct StatsSample {
+    /// The time since the campaign started
+    pub run_time: Duration,
+    /// The edges covered by the best client, if the clients report `edges`
+    pub edges_hit: Option<u64>,
+    /// The size of the coverage map, if the clients report `edges`
+    pub edges_total: Option<u64>,
+    /// The corpus size of all clients
+    pub corpus_size: u64,
+    /// The objectives of all clients
+    pub objective_size: u64,
+    /// The executions per second of all clients
+    pub execs_per_sec: f64,
+}
+
+/// A downsampled time series of [`StatsSample`]s, covering the whole campaign in bounded memory.
+///
+/// It takes a sample every `interval`. Once it holds `max_samples`, it drops every second sample,
+/// and doubles the interval.
+#[derive(Debug, Clone)]
+pub struct StatsHistory {
+    samples: Vec<StatsSample>,
+    interval: Duration,
+    max_samples: usize,
+}
+
+impl Default for StatsHistory {
+    fn default() -> Self {
+        Self::new(DEFAULT_HISTORY_INTERVAL, DEFAULT_HISTORY_SAMPLES)
+    }
+}
+
+impl StatsHistory {
+    /// Creates a new, empty, [`StatsHistory`], sampling every `interval`, and keeping up to `max_samples`
+    #[must_use]
+    pub fn new(interval: Duration, max_samples: usize) -> Self {
+        Self {
+            samples: Vec::new(),
+            interval,
+            max_samples: max_samples.max(2),
+        }
+    }
+
+    /// The samples, oldest first
+    #[must_use]
+    pub fn samples(&self) -> &[StatsSample] {
+        &self.samples
+    }
+
+    /// The current time between two samples
+    #[must_use]
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Whether a sample taken at `run_time` would be recorded
+    #[must_use]
+    pub fn is_due(&self, run_time: Duration) -> bool {
+        self.samples
+            .last()
+            .is_none_or(|last| run_time >= last.run_time + self.interval)
+    }
+
+    /// Records the sample, if it is due. Returns `true` if it was recorded.
+    pub fn record(&mut self, sample: StatsSample) -> bool {
+        if !self.is_due(sample.run_time) {
+            return false;
+        }
+        self.samples.push(sample);
+        if self.samples.len() >= self.max_samples {
+            let mut index = 0;
+            self.samples.retain(|_| {
+                index += 1;
+                index % 2 == 1
+            });
+            self.interval *= 2;
+        }
+        true
+    }
+
+    /// Writes the history as CSV, with a header line
+    #[cfg(feature = "std")]
+    pub fn write_csv<W>(&self, mut writer: W) -> Result<(), Error>
+    where
+        W: Write,
+    {
+        writeln!(
+            writer,
+            "run_time_secs,edges_hit,edges_total,corpus_size,objective_size,execs_per_sec"
+        )?;
+        for sample in &self.samples {
+            writeln!(
+                writer,
+                "{},{},{},{},{},{:.2}",
+                sample.run_time.as_secs(),
+                sample.edges_hit.map_or(String::new(), |x| x.to_string()),
+                sample.edges_total.map_or(String::new(), |x| x.to_string()),
+                sample.corpus_size,
+                sample.objective_size,
+                sample.execs_per_sec
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// A change of the plateau state of a campaign, see [`PlateauDetector`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlateauChange {
+    /// The coverage did not grow for the plateau timeout
+    Reached {
+        /// The time since the campaign started, when the coverage last grew
+        last_growth: Duration,
+    },
+    /// The coverage grew again
+    Left,
+}
+
+impl fmt::Display for PlateauChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Reached { last_growth } => write!(
+                f,
+                "Coverage plateau: no new coverage since {} into the campaign",
+                format_duration(last_growth)
+            ),
+            Self::Left => write!(f, "Coverage plateau left: the coverage grows again"),
+        }
+    }
+}
+
+/// Detects when the coverage of a campaign stops growing for a while
+#[derive(Debug, Clone)]
+pub struct PlateauDetector {
+    timeout: Duration,
+    best: u64,
+    last_growth: Duration,
+    plateaued: bool,
+}
+
+impl PlateauDetector {
+    /// Creates a new [`PlateauDetector`], reporting a plateau once the coverage did not grow for `timeout`
+    #[must_use]
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            timeout,
+            best: 0,
+            last_growth: Duration::ZERO,
+            plateaued: false,
+        }
+    }
+
+    /// The time the coverage must not grow for, to be a plateau
+    #[must_use]
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// If the campaign is on a plateau right now
+    #[must_use]
+    pub fn is_plateaued(&self) -> bool {
+        self.plateaued
+    }
+
+    /// Updates the detector with the coverage at `run_time`. Returns the change of the plateau state, if any.
+    pub fn update(&mut self, run_time: Duration, coverage: u64) -> Option<PlateauChange> {
+        if coverage > self.best {
+            self.best = coverage;
+            self.last_growth = run_time;
+            if self.plateaued {
+                self.plateaued = false;
+                return Some(PlateauChange::Left);
+            }
+        } else if !self.plateaued && run_time >= self.last_growth + self.timeout {
+            self.plateaued = true;
+            return Some(PlateauChange::Reached {
+                last_growth: self.last_growth,
+            });
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{string::String, vec::Vec};
+    use core::time::Duration;
+
+    use super::{PlateauChange, PlateauDetector, StatsHistory, StatsSample};
+
+    fn sample(secs: u64, corpus_size: u64) -> StatsSample {
+        StatsSample {
+            run_time: Duration::from_secs(secs),
+            edges_hit: Some(corpus_size * 10),
+            edges_total: Some(1000),
+            corpus_size,
+            objective_size: 0,
+            execs_per_sec: 1.0,
+        }
+    }
+
+    #[test]
+    fn test_stats_history_downsampling() {
+        let mut history = StatsHistory::new(Duration::from_secs(1), 4);
+        for secs in 0..4 {
+            assert!(history.record(sample(secs, secs)));
+        }
+        // The fourth sample filled the history, so every second one is gone
+        let times: Vec<u64> = history
+            .samples()
+            .iter()
+            .map(|s| s.run_time.as_secs())
+            .collect();
+        assert_eq!(times, [0, 2]);
+        assert_eq!(history.interval(), Duration::from_secs(2));
+        assert!(!history.record(sample(3, 3)));
+        assert!(history.record(sample(4, 4)));
+
+        let mut csv = Vec::new();
+        history.write_csv(&mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        assert_eq!(csv.lines().count(), 4);
+        assert_eq!(csv.lines().nth(3), Some("4,40,1000,4,0,1.00"));
+    }
+
+    #[test]
+    fn test_plateau_detector() {
+        let mut plateau = PlateauDetector::new(Duration::from_secs(10));
+        assert_eq!(plateau.update(Duration::from_secs(1), 5), None);
+        assert_eq!(plateau.update(Duration::from_secs(10), 5), None);
+        assert_eq!(
+            plateau.update(Duration::from_secs(11), 5),
+            Some(PlateauChange::Reached {
+                last_growth: Duration::from_secs(1)
+            })
+        );
+        assert!(plateau.is_plateaued());
+        assert_eq!(plateau.update(Duration::from_secs(20), 5), None);
+        assert_eq!(
+            plateau.update(Duration::from_secs(21), 6),
+            Some(PlateauChange::Left)
+        );
+        assert!(!plateau.is_plateaued());
+    }
+}
@@ -10,13 +10,19 @@ use libafl_bolts::{ClientId, Error, current_time, format_duration};
 #[cfg(feature = "std")]
 use serde_json::Value;
 
-use super::{ClientStats, EdgeCoverage, ProcessTiming, user_stats::UserStatsValue};
+use super::{
+    ClientStats, EdgeCoverage, PlateauChange, PlateauDetector, ProcessTiming, StatsHistory,
+    StatsSample, StatsSnapshot, user_stats::UserStatsValue,
+};
 #[cfg(feature = "std")]
 use super::{
     ItemGeometry,
     user_stats::{AggregatorOps, UserStats},
 };
 
+/// The name of the aggregated user stat that is `1` while the campaign is on a coverage plateau
+pub const PLATEAU_STATS_NAME: &str = "plateau";
+
 /// Manager of all client's statistics
 #[derive(Debug)]
 pub struct ClientStatsManager {
@@ -30,6 +36,8 @@ pub struct ClientStatsManager {
     /// This will be erased to `None` every time a client is updated with crucial stats.
     cached_global_stats: Option<GlobalStats>,
     start_time: Duration,
+    history: StatsHistory,
+    plateau: Option<PlateauDetector>,
 }
 
 impl ClientStatsManager {
@@ -41,6 +49,8 @@ impl ClientStatsManager {
             cached_aggregated_user_stats: HashMap::new(),
             cached_global_stats: None,
             start_time: current_time(),
+            history: StatsHistory::default(),
+            plateau: None,
         }
     }
 
@@ -143,6 +153,94 @@ impl ClientStatsManager {
         self.start_time = time;
     }
 
+    /// The history of the global stats, see [`Self::update_history`]
+    #[must_use]
+    pub fn history(&self) -> &StatsHistory {
+        &self.history
+    }
+
+    /// Replaces the history of the global stats, for example with one of a different sampling interval
+    pub fn set_history(&mut self, history: StatsHistory) {
+        self.history = history;
+    }
+
+    /// Detects coverage plateaus: reports one once the coverage did not grow for `timeout`
+    pub fn set_plateau_timeout(&mut self, timeout: Duration) {
+        self.plateau = Some(PlateauDetector::new(timeout));
+    }
+
+    /// The coverage plateau detector, if there is one
+    #[must_use]
+    pub fn plateau(&self) -> Option<&PlateauDetector> {
+        self.plateau.as_ref()
+    }
+
+    /// Records the global stats in the history, if a sample is due, and checks for a coverage plateau.
+    /// The coverage is the `edges` of the best client, or the corpus size, if the clients report no `edges`.
+    ///
+    /// While plateau detection is on, the [`PLATEAU_STATS_NAME`] aggregated user stat tells if the campaign is on a plateau.
+    /// Returns the change of the plateau state, if any, for the event manager to log.
+    pub fn update_history(&mut self) -> Option<PlateauChange> {
+        let run_time = current_time().saturating_sub(self.start_time);
+        if !self.history.is_due(run_time) {
+            return None;
+        }
+        let edges = self.edges_coverage();
+        let global_stats = self.global_stats();
+        let sample = StatsSample {
+            run_time,
+            edges_hit: edges.as_ref().map(|edges| edges.edges_hit),
+            edges_total: edges.as_ref().map(|edges| edges.edges_total),
+            corpus_size: global_stats.corpus_size,
+            objective_size: global_stats.objective_size,
+            execs_per_sec: global_stats.execs_per_sec,
+        };
+        self.history.record(sample);
+
+        let plateau = self.plateau.as_mut()?;
+        let change = plateau.update(run_time, sample.edges_hit.unwrap_or(sample.corpus_size));
+        self.cached_aggregated_user_stats.insert(
+            Cow::Borrowed(PLATEAU_STATS_NAME),
+            UserStatsValue::Number(u64::from(plateau.is_plateaued())),
+        );
+        change
+    }
+
+    /// Takes a [`StatsSnapshot`] of the campaign, to [`Self::restore`] it later, or merge it with other campaigns
+    pub fn snapshot(&mut self) -> StatsSnapshot {
+        let global_stats = self.global_stats();
+        let (run_time, total_execs, corpus_size, objective_size) = (
+            global_stats.run_time,
+            global_stats.total_execs,
+            global_stats.corpus_size,
+            global_stats.objective_size,
+        );
+        StatsSnapshot {
+            campaigns: 1,
+            run_time,
+            total_execs,
+            corpus_size,
+            objective_size,
+            client_stats: self.client_stats.clone(),
+        }
+    }
+
+    /// Restores a [`StatsSnapshot`] taken before a restart of the broker.
+    ///
+    /// The run time continues from the one of the snapshot, and the stats the clients reported since are carried over,
+    /// see [`ClientStats::resume`].
+    pub fn restore(&mut self, snapshot: StatsSnapshot) {
+        let cur_time = current_time();
+        self.start_time = self.start_time.saturating_sub(snapshot.run_time);
+        for (client_id, mut restored) in snapshot.client_stats {
+            if let Some(current) = self.client_stats.get(&client_id) {
+                restored.resume(current, cur_time);
+            }
+            self.client_stats.insert(client_id, restored);
+        }
+        self.cached_global_stats = None;
+    }
+
     /// Get global stats.
     ///
     /// This global stats will be cached until the underlined client stats are modified.
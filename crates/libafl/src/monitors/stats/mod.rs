@@ -1,8 +1,10 @@
 //! Statistics used for Monitors to display.
 
+pub mod history;
 pub mod manager;
 #[cfg(feature = "introspection")]
 pub mod perf_stats;
+pub mod snapshot;
 pub mod user_stats;
 
 use alloc::{
@@ -12,6 +14,7 @@ use alloc::{
 use core::time::Duration;
 
 use hashbrown::HashMap;
+pub use history::{PlateauChange, PlateauDetector, StatsHistory, StatsSample};
 use libafl_bolts::current_time;
 pub use manager::ClientStatsManager;
 #[cfg(feature = "introspection")]
@@ -19,6 +22,7 @@ pub use perf_stats::{ClientPerfStats, PerfFeature};
 use serde::{Deserialize, Serialize};
 #[cfg(feature = "std")]
 use serde_json::Value;
+pub use snapshot::StatsSnapshot;
 pub use user_stats::{AggregatorOps, UserStats, UserStatsValue};
 
 #[cfg(feature = "afl_exec_sec")]
@@ -230,6 +234,27 @@ impl ClientStats {
         self.stats_status.basic_stats_updated = true;
     }
 
+    /// Carries these stats, restored from a [`StatsSnapshot`], over the `current` stats the client reported since.
+    ///
+    /// Executions are summed up if the client restarted its count, corpus and objective sizes keep the maximum.
+    pub fn resume(&mut self, current: &ClientStats, cur_time: Duration) {
+        if current.executions > 0 {
+            self.update_executions(current.executions, cur_time);
+        }
+        self.enabled |= current.enabled;
+        self.corpus_size = self.corpus_size.max(current.corpus_size);
+        self.last_corpus_time = self.last_corpus_time.max(current.last_corpus_time);
+        self.objective_size = self.objective_size.max(current.objective_size);
+        self.last_objective_time = self.last_objective_time.max(current.last_objective_time);
+        self.user_stats.extend(
+            current
+                .user_stats
+                .iter()
+                .map(|(name, value)| (name.clone(), value.clone())),
+        );
+        self.stats_status.basic_stats_updated = true;
+    }
+
     /// We got new information about corpus size for this client, insert them.
     pub fn update_corpus_size(&mut self, corpus_size: u64) {
         self.corpus_size = corpus_size;
@@ -0,0 +1,161 @@
+//! Snapshots of the stats of a campaign, to restore them after a restart of the broker,
+//! and to merge the stats of several campaigns into one report.
+
+#[cfg(feature = "std")]
+use alloc::format;
+use core::time::Duration;
+#[cfg(feature = "std")]
+use std::{fs, path::Path};
+
+use hashbrown::HashMap;
+use libafl_bolts::ClientId;
+#[cfg(feature = "std")]
+use libafl_bolts::Error;
+use serde::{Deserialize, Serialize};
+
+use super::ClientStats;
+
+/// The stats of one or more campaigns, as taken by [`super::ClientStatsManager::snapshot`]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StatsSnapshot {
+    /// The number of campaigns merged into this snapshot
+    pub campaigns: u64,
+    /// The run time of all campaigns
+    pub run_time: Duration,
+    /// The executions of all clients
+    pub total_execs: u64,
+    /// The corpus size of all clients
+    pub corpus_size: u64,
+    /// The objectives of all clients
+    pub objective_size: u64,
+    /// The stats of each client.
+    ///
+    /// The clients of merged campaigns are numbered after the clients of this one.
+    pub client_stats: HashMap<ClientId, ClientStats>,
+}
+
+impl StatsSnapshot {
+    /// Adds the stats of the `other` campaigns to these
+    pub fn merge(&mut self, other: &Self) {
+        self.campaigns += other.campaigns;
+        self.run_time += other.run_time;
+        self.total_execs += other.total_execs;
+        self.corpus_size += other.corpus_size;
+        self.objective_size += other.objective_size;
+        let offset = self
+            .client_stats
+            .keys()
+            .map(|id| id.0 + 1)
+            .max()
+            .unwrap_or(0);
+        self.client_stats.extend(
+            other
+                .client_stats
+                .iter()
+                .map(|(id, stats)| (ClientId(id.0 + offset), stats.clone())),
+        );
+    }
+
+    /// Loads a snapshot from a JSON file
+    #[cfg(feature = "std")]
+    pub fn load<P>(path: P) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        serde_json::from_slice(&fs::read(path)?).map_err(|e| {
+            Error::serialize(format!(
+                "Could not parse the stats snapshot {}: {e}",
+                path.display()
+            ))
+        })
+    }
+
+    /// Saves the snapshot to a JSON file, replacing it at once, so a crash never leaves half a snapshot behind
+    #[cfg(feature = "std")]
+    pub fn save<P>(&self, path: P) -> Result<(), Error>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let tmp = path.with_extension("tmp");
+        let json = serde_json::to_vec_pretty(self).map_err(|e| {
+            Error::serialize(format!("Could not serialize the stats snapshot: {e}"))
+        })?;
+        fs::write(&tmp, json)?;
+        fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    /// Loads the snapshots of several campaigns and merges them into one
+    #[cfg(feature = "std")]
+    pub fn merge_files<I, P>(paths: I) -> Result<Self, Error>
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<Path>,
+    {
+        let mut merged = Self::default();
+        for path in paths {
+            merged.merge(&Self::load(path)?);
+        }
+        Ok(merged)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use core::time::Duration;
+
+    use libafl_bolts::ClientId;
+
+    use super::StatsSnapshot;
+    use crate::monitors::stats::ClientStatsManager;
+
+    fn manager(executions: u64) -> ClientStatsManager {
+        let mut manager = ClientStatsManager::new();
+        manager.client_stats_insert(ClientId(0)).unwrap();
+        manager
+            .update_client_stats_for(ClientId(0), |client| {
+                client.update_executions(executions, Duration::ZERO);
+                client.update_corpus_size(10);
+            })
+            .unwrap();
+        manager
+    }
+
+    #[test]
+    fn test_restore_snapshot() {
+        let snapshot = manager(1000).snapshot();
+        assert_eq!(snapshot.campaigns, 1);
+        assert_eq!(snapshot.total_execs, 1000);
+
+        // The client restarted its count along with the broker
+        let mut restarted = manager(50);
+        restarted.restore(snapshot.clone());
+        assert_eq!(restarted.global_stats().total_execs, 1050);
+        assert_eq!(restarted.global_stats().corpus_size, 10);
+
+        // Only the broker restarted
+        let mut resumed = manager(1100);
+        resumed.restore(snapshot);
+        assert_eq!(resumed.global_stats().total_execs, 1100);
+    }
+
+    #[test]
+    fn test_merge_snapshots() {
+        let mut merged = manager(1000).snapshot();
+        merged.merge(&manager(500).snapshot());
+        assert_eq!(merged.campaigns, 2);
+        assert_eq!(merged.total_execs, 1500);
+        assert_eq!(merged.corpus_size, 20);
+        assert_eq!(merged.client_stats[&ClientId(1)].executions(), 500);
+
+        let path = std::env::temp_dir().join("libafl_test_merge_snapshots.json");
+        merged.save(&path).unwrap();
+        let merged = StatsSnapshot::merge_files([&path, &path]).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(merged.campaigns, 4);
+        assert_eq!(merged.total_execs, 3000);
+        assert_eq!(merged.client_stats.len(), 4);
+    }
+}
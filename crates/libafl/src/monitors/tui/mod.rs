@@ -267,6 +267,8 @@ pub struct TuiContext {
     pub corpus_size_timed: TimedStats,
     pub objective_size_timed: TimedStats,
     pub execs_per_sec_timed: TimedStats,
+    // The whole campaign, downsampled, see [`ClientStatsManager::history`]
+    pub coverage_timed: TimedStats,
 
     #[cfg(feature = "introspection")]
     pub introspection: HashMap<usize, PerfTuiContext>,
@@ -292,10 +294,16 @@ impl TuiContext {
     #[must_use]
     pub fn new(start_time: Duration) -> Self {
         Self {
-            graphs: vec!["corpus".into(), "objectives".into(), "exec/sec".into()],
+            graphs: vec![
+                "corpus".into(),
+                "objectives".into(),
+                "exec/sec".into(),
+                "coverage".into(),
+            ],
             corpus_size_timed: TimedStats::new(Duration::from_secs(DEFAULT_TIME_WINDOW)),
             objective_size_timed: TimedStats::new(Duration::from_secs(DEFAULT_TIME_WINDOW)),
             execs_per_sec_timed: TimedStats::new(Duration::from_secs(DEFAULT_TIME_WINDOW)),
+            coverage_timed: TimedStats::new(Duration::MAX),
 
             #[cfg(feature = "introspection")]
             introspection: HashMap::default(),
@@ -362,6 +370,15 @@ impl Monitor for TuiMonitor {
 
             ctx.total_process_timing = total_process_timing;
             ctx.execs_per_sec_timed.add(run_time, execsec);
+            ctx.coverage_timed.series = client_stats_manager
+                .history()
+                .samples()
+                .iter()
+                .map(|sample| TimedStat {
+                    time: sample.run_time,
+                    item: sample.edges_hit.unwrap_or(sample.corpus_size),
+                })
+                .collect();
             ctx.start_time = client_stats_manager.start_time();
             ctx.total_execs = totalexec;
             ctx.clients_num = client_stats_manager.client_stats().len();
@@ -72,7 +72,7 @@ impl TuiUi {
                 self.should_quit = true;
             }
             'g' => {
-                self.charts_tab_idx = (self.charts_tab_idx + 1) % 3;
+                self.charts_tab_idx = (self.charts_tab_idx + 1) % 4;
             }
             't' => {
                 self.show_logs = !self.show_logs;
@@ -199,7 +199,11 @@ impl TuiUi {
                 Style::default().fg(Color::LightGreen),
             )),
             Line::from(Span::styled(
-                "objectives (`g` switch)",
+                "objectives",
+                Style::default().fg(Color::LightGreen),
+            )),
+            Line::from(Span::styled(
+                "coverage (`g` switch)",
                 Style::default().fg(Color::LightGreen),
             )),
         ];
@@ -251,6 +255,16 @@ impl TuiUi {
                     &ctx.objective_size_timed,
                 );
             }
+            3 => {
+                let ctx = app.read().unwrap();
+                self.draw_time_chart(
+                    "coverage chart",
+                    "edges (or corpus size)",
+                    f,
+                    chart_layout,
+                    &ctx.coverage_timed,
+                );
+            }
             _ => {}
         }
         self.draw_overall_generic_text(f, app, bottom_layout);
@@ -0,0 +1,522 @@
+//! The [`WebMonitor`] serves a live dashboard of the campaign over HTTP, without any external services.
+//!
+//! Open `http://<listen addr>/` in a browser to see the global and per-client stats, the user stats,
+//! coverage, corpus and objectives over time, and, with the `introspection` feature, where the clients spend their time.
+//! The page receives updates as server-sent events from `/events`.
+//! The same data is available as JSON from `/api/stats` and `/api/history`.
+//!
+//! With [`WebMonitor::objectives_dir`] set, the page lists the objectives in it, such as the
+//! `objectives_dir` of the [`Launcher`](crate::events::launcher::Launcher), for download.
+//!
+//! The server starts with the first update of the monitor, in the process that displays the stats,
+//! which is the broker for multi-process fuzzers.
+//!
+//! ```rust,no_run
+//! use libafl::monitors::WebMonitor;
+//!
+//! let monitor = WebMonitor::new("127.0.0.1:8080").objectives_dir("./crashes");
+//! // let mgr = SimpleEventManager::new(monitor);
+//! ```
+
+use alloc::{
+    string::{String, ToString},
+    sync::Arc,
+    vec::Vec,
+};
+use core::{
+    fmt::{self, Debug, Formatter},
+    net::SocketAddr,
+    time::Duration,
+};
+use std::{
+    fs::{self, File},
+    io::{self, BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    path::{Path, PathBuf},
+    sync::{Condvar, Mutex, MutexGuard},
+    thread,
+};
+
+use libafl_bolts::{ClientId, Error, current_time};
+use serde::Serialize;
+use serde_json::{Map, Value, json};
+
+#[cfg(feature = "introspection")]
+use crate::monitors::stats::perf_stats::{ClientPerfStats, PerfFeature};
+use crate::monitors::{
+    Monitor,
+    stats::{EdgeCoverage, manager::ClientStatsManager},
+};
+
+/// The dashboard page
+const INDEX_HTML: &str = include_str!("index.html");
+
+/// The most points of history the monitor keeps. Beyond that, it keeps every second point.
+const MAX_HISTORY: usize = 2048;
+
+/// The most objectives the page lists
+const MAX_OBJECTIVES: usize = 1000;
+
+/// How long an idle event stream waits before sending a keep-alive
+const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(15);
+
+/// The global stats at one point in time
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct HistoryPoint {
+    /// The run time, in seconds
+    pub run_time: u64,
+    /// The corpus size of all clients
+    pub corpus: u64,
+    /// The objectives of all clients
+    pub objectives: u64,
+    /// The executions of all clients
+    pub executions: u64,
+    /// The executions per second of all clients
+    pub exec_sec: f64,
+    /// The edges covered by the best client
+    pub edges_hit: Option<u64>,
+    /// The size of the coverage map
+    pub edges_total: Option<u64>,
+}
+
+/// What the monitor shares with the server threads
+#[derive(Debug, Default)]
+struct WebState {
+    /// Counts the updates, so that the event streams know when to send
+    version: u64,
+    /// The latest stats, as JSON
+    stats: String,
+    history: Vec<HistoryPoint>,
+    objectives_dir: Option<PathBuf>,
+}
+
+#[derive(Debug, Default)]
+struct WebShared {
+    state: Mutex<WebState>,
+    updated: Condvar,
+}
+
+impl WebShared {
+    fn state(&self) -> MutexGuard<'_, WebState> {
+        self.state.lock().unwrap()
+    }
+}
+
+/// A monitor serving a live web dashboard of the campaign
+#[derive(Clone)]
+pub struct WebMonitor {
+    listen_addr: String,
+    local_addr: Option<SocketAddr>,
+    shared: Arc<WebShared>,
+    update_interval: Duration,
+    last_update: Duration,
+    /// The time between two points of history, doubling whenever the history is full
+    history_interval: Duration,
+    last_history: Duration,
+}
+
+impl Debug for WebMonitor {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WebMonitor")
+            .field("listen_addr", &self.listen_addr)
+            .field("local_addr", &self.local_addr)
+            .field("update_interval", &self.update_interval)
+            .finish_non_exhaustive()
+    }
+}
+
+impl WebMonitor {
+    /// Creates a new [`WebMonitor`], that will serve the dashboard on `listen_addr`, such as `127.0.0.1:8080`
+    #[must_use]
+    pub fn new<A>(listen_addr: A) -> Self
+    where
+        A: Into<String>,
+    {
+        let update_interval = Duration::from_secs(1);
+        Self {
+            listen_addr: listen_addr.into(),
+            local_addr: None,
+            shared: Arc::default(),
+            update_interval,
+            last_update: Duration::ZERO,
+            history_interval: update_interval,
+            last_history: Duration::ZERO,
+        }
+    }
+
+    /// Sets how often the dashboard updates. Defaults to once a second.
+    #[must_use]
+    pub fn update_interval(mut self, update_interval: Duration) -> Self {
+        self.update_interval = update_interval;
+        self.history_interval = update_interval;
+        self
+    }
+
+    /// Lists the objectives in this directory on the dashboard, and serves them for download
+    #[must_use]
+    pub fn objectives_dir<P>(self, objectives_dir: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        self.shared.state().objectives_dir = Some(objectives_dir.into());
+        self
+    }
+
+    /// The address the dashboard is served on, once the server started
+    #[must_use]
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.local_addr
+    }
+
+    /// Binds the listen address, and serves the dashboard from a new thread
+    fn start_server(&mut self) -> Result<(), Error> {
+        let listener = TcpListener::bind(&self.listen_addr)?;
+        let local_addr = listener.local_addr()?;
+        log::info!("Serving the web dashboard on http://{local_addr}/");
+
+        let shared = self.shared.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(stream) = stream else {
+                    continue;
+                };
+                let shared = shared.clone();
+                thread::spawn(move || {
+                    if let Err(e) = serve(&shared, stream) {
+                        log::debug!("Web dashboard connection closed: {e}");
+                    }
+                });
+            }
+        });
+        self.local_addr = Some(local_addr);
+        Ok(())
+    }
+
+    /// Records a point of history, keeping every second point once the history is full
+    fn record_history(&mut self, state: &mut WebState, point: HistoryPoint, cur_time: Duration) {
+        if !state.history.is_empty() && cur_time < self.last_history + self.history_interval {
+            return;
+        }
+        self.last_history = cur_time;
+        state.history.push(point);
+        if state.history.len() >= MAX_HISTORY {
+            let mut index = 0;
+            state.history.retain(|_| {
+                index += 1;
+                index % 2 == 1
+            });
+            self.history_interval *= 2;
+        }
+    }
+}
+
+impl Monitor for WebMonitor {
+    fn display(
+        &mut self,
+        client_stats_manager: &mut ClientStatsManager,
+        _event_msg: &str,
+        _sender_id: ClientId,
+    ) -> Result<(), Error> {
+        let cur_time = current_time();
+        if self.last_update != Duration::ZERO && cur_time < self.last_update + self.update_interval
+        {
+            return Ok(());
+        }
+        self.last_update = cur_time;
+        if self.local_addr.is_none() {
+            self.start_server()?;
+        }
+
+        let edges = client_stats_manager.edges_coverage();
+        let global_stats = client_stats_manager.global_stats();
+        let point = HistoryPoint {
+            run_time: global_stats.run_time.as_secs(),
+            corpus: global_stats.corpus_size,
+            objectives: global_stats.objective_size,
+            executions: global_stats.total_execs,
+            exec_sec: global_stats.execs_per_sec,
+            edges_hit: edges.as_ref().map(|edges| edges.edges_hit),
+            edges_total: edges.as_ref().map(|edges| edges.edges_total),
+        };
+        let mut stats = json!({
+            "run_time": global_stats.run_time_pretty,
+            "clients_count": global_stats.client_stats_count,
+            "exec_sec_pretty": global_stats.execs_per_sec_pretty,
+            "point": point,
+        });
+        stats["edges"] = edges.map_or(
+            Value::Null,
+            |EdgeCoverage {
+                 edges_hit,
+                 edges_total,
+             }| { json!({ "hit": edges_hit, "total": edges_total }) },
+        );
+        stats["user_stats"] = client_stats_manager
+            .aggregated()
+            .iter()
+            .map(|(name, value)| (name.to_string(), Value::String(value.to_string())))
+            .collect::<Map<_, _>>()
+            .into();
+
+        let client_ids: Vec<ClientId> = client_stats_manager
+            .client_stats()
+            .iter()
+            .filter(|(_, client)| client.enabled())
+            .map(|(id, _)| *id)
+            .collect();
+        let mut clients = Vec::with_capacity(client_ids.len());
+        for client_id in client_ids {
+            let exec_sec = client_stats_manager
+                .update_client_stats_for(client_id, |client| client.execs_per_sec(cur_time))?;
+            let client = client_stats_manager.client_stats_for(client_id)?;
+            let user_stats: Map<String, Value> = client
+                .user_stats()
+                .iter()
+                .map(|(name, value)| (name.to_string(), Value::String(value.to_string())))
+                .collect();
+            #[allow(unused_mut)] // mut only with introspection
+            let mut client_json = json!({
+                "id": client_id.0,
+                "corpus": client.corpus_size(),
+                "objectives": client.objective_size(),
+                "executions": client.executions(),
+                "exec_sec": exec_sec,
+                "user_stats": user_stats,
+            });
+            #[cfg(feature = "introspection")]
+            {
+                client_json["perf"] = perf_breakdown(&client.introspection_stats);
+            }
+            clients.push(client_json);
+        }
+        clients.sort_by_key(|client| client["id"].as_u64());
+        stats["clients"] = clients.into();
+
+        let shared = self.shared.clone();
+        let mut state = shared.state();
+        stats["objectives"] = state
+            .objectives_dir
+            .as_deref()
+            .map_or(Value::Null, list_objectives);
+        state.stats = stats.to_string();
+        state.version += 1;
+        self.record_history(&mut state, point, cur_time);
+        drop(state);
+        shared.updated.notify_all();
+        Ok(())
+    }
+}
+
+/// The share of time spent in each part of the fuzzing loop, from 0 to 1
+#[cfg(feature = "introspection")]
+#[expect(clippy::cast_precision_loss)]
+fn perf_breakdown(perf: &ClientPerfStats) -> Value {
+    let elapsed = perf.elapsed_cycles() as f64;
+    if elapsed == 0.0 {
+        return Value::Null;
+    }
+    let stages: Vec<Value> = perf
+        .used_stages()
+        .map(|(stage_index, features)| {
+            let features: Map<String, Value> = features
+                .iter()
+                .enumerate()
+                .filter(|(_, cycles)| **cycles > 0)
+                .map(|(feature_index, cycles)| {
+                    let feature: PerfFeature = feature_index.into();
+                    (format!("{feature:?}"), (*cycles as f64 / elapsed).into())
+                })
+                .collect();
+            json!({ "stage": stage_index, "features": features })
+        })
+        .collect();
+    let feedbacks: Map<String, Value> = perf
+        .feedbacks()
+        .iter()
+        .map(|(name, cycles)| (name.clone(), (*cycles as f64 / elapsed).into()))
+        .collect();
+    json!({
+        "scheduler": perf.scheduler_cycles() as f64 / elapsed,
+        "manager": perf.manager_cycles() as f64 / elapsed,
+        "stages": stages,
+        "feedbacks": feedbacks,
+    })
+}
+
+/// The newest objectives in the directory, with their sizes
+fn list_objectives(dir: &Path) -> Value {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Value::Array(vec![]);
+    };
+    let mut objectives: Vec<(Duration, String, u64)> = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok()?;
+            let name = entry.file_name().into_string().ok()?;
+            // Skip the metadata and lock files of on-disk corpora
+            if !metadata.is_file() || name.starts_with('.') {
+                return None;
+            }
+            let modified = metadata.modified().ok()?.elapsed().unwrap_or_default();
+            Some((modified, name, metadata.len()))
+        })
+        .collect();
+    objectives.sort_unstable();
+    objectives
+        .into_iter()
+        .take(MAX_OBJECTIVES)
+        .map(|(age, name, size)| json!({ "name": name, "size": size, "age": age.as_secs() }))
+        .collect()
+}
+
+/// Answers one HTTP request
+fn serve(shared: &WebShared, stream: TcpStream) -> io::Result<()> {
+    let mut reader = BufReader::new(stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // Skip the headers
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+    let mut stream = reader.into_inner();
+
+    let mut parts = request_line.split(' ');
+    let (Some("GET"), Some(path)) = (parts.next(), parts.next()) else {
+        return respond(&mut stream, "405 Method Not Allowed", "text/plain", b"");
+    };
+    match path {
+        "/" | "/index.html" => respond(
+            &mut stream,
+            "200 OK",
+            "text/html; charset=utf-8",
+            INDEX_HTML.as_bytes(),
+        ),
+        "/api/stats" => {
+            let stats = shared.state().stats.clone();
+            respond(&mut stream, "200 OK", "application/json", stats.as_bytes())
+        }
+        "/api/history" => {
+            let history = serde_json::to_vec(&shared.state().history)?;
+            respond(&mut stream, "200 OK", "application/json", &history)
+        }
+        "/events" => stream_events(shared, stream),
+        _ => {
+            if let Some(name) = path.strip_prefix("/objectives/") {
+                return serve_objective(shared, stream, name);
+            }
+            respond(&mut stream, "404 Not Found", "text/plain", b"Not found")
+        }
+    }
+}
+
+fn respond(
+    stream: &mut TcpStream,
+    status: &str,
+    content_type: &str,
+    body: &[u8],
+) -> io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        body.len()
+    )?;
+    stream.write_all(body)
+}
+
+/// Sends the objective with the given file name, if it is in the objectives dir
+fn serve_objective(shared: &WebShared, mut stream: TcpStream, name: &str) -> io::Result<()> {
+    let objectives_dir = shared.state().objectives_dir.clone();
+    let is_plain_name =
+        !name.is_empty() && !name.starts_with('.') && !name.contains(['/', '\\', '%', '?']);
+    let file = objectives_dir
+        .filter(|_| is_plain_name)
+        .and_then(|dir| File::open(dir.join(name)).ok());
+    let Some(mut file) = file else {
+        return respond(&mut stream, "404 Not Found", "text/plain", b"Not found");
+    };
+    write!(
+        stream,
+        "HTTP/1.1 200 OK\r\nContent-Type: application/octet-stream\r\nContent-Length: {}\r\nContent-Disposition: attachment; filename=\"{name}\"\r\nConnection: close\r\n\r\n",
+        file.metadata()?.len()
+    )?;
+    io::copy(&mut file, &mut stream)?;
+    Ok(())
+}
+
+/// Pushes the history once, and then every update, to the browser, as server-sent events
+fn stream_events(shared: &WebShared, mut stream: TcpStream) -> io::Result<()> {
+    stream.write_all(
+        b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\nConnection: keep-alive\r\n\r\n",
+    )?;
+    let (mut seen, history) = {
+        let state = shared.state();
+        (state.version, serde_json::to_string(&state.history)?)
+    };
+    write!(stream, "event: history\ndata: {history}\n\n")?;
+
+    loop {
+        let state = shared.state();
+        let (state, timeout) = shared
+            .updated
+            .wait_timeout_while(state, KEEP_ALIVE_INTERVAL, |state| state.version == seen)
+            .unwrap();
+        if timeout.timed_out() {
+            drop(state);
+            stream.write_all(b": keep-alive\n\n")?;
+            continue;
+        }
+        seen = state.version;
+        let stats = state.stats.clone();
+        drop(state);
+        write!(stream, "data: {stats}\n\n")?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::String;
+    use std::{
+        io::{Read, Write},
+        net::TcpStream,
+    };
+
+    use libafl_bolts::ClientId;
+
+    use super::WebMonitor;
+    use crate::monitors::{Monitor, stats::ClientStatsManager};
+
+    fn get(monitor: &WebMonitor, path: &str) -> String {
+        let mut stream = TcpStream::connect(monitor.local_addr().unwrap()).unwrap();
+        write!(stream, "GET {path} HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    #[test]
+    fn test_web_monitor() {
+        let mut monitor = WebMonitor::new("127.0.0.1:0");
+        let mut client_stats_manager = ClientStatsManager::default();
+        client_stats_manager
+            .client_stats_insert(ClientId(1))
+            .unwrap();
+        client_stats_manager
+            .update_client_stats_for(ClientId(1), |client| client.update_corpus_size(42))
+            .unwrap();
+        monitor
+            .display(&mut client_stats_manager, "Testcase", ClientId(1))
+            .unwrap();
+
+        let index = get(&monitor, "/");
+        assert!(index.starts_with("HTTP/1.1 200 OK"));
+        assert!(index.contains("EventSource"));
+
+        let stats = get(&monitor, "/api/stats");
+        assert!(stats.contains("\"corpus\":42"));
+        let history = get(&monitor, "/api/history");
+        assert!(history.contains("\"corpus\":42"));
+
+        assert!(get(&monitor, "/objectives/..%2Fsecret").starts_with("HTTP/1.1 404"));
+    }
+}
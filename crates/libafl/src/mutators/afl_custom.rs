@@ -0,0 +1,666 @@
+//! Support for [AFL++ custom mutators](https://aflplus.plus/docs/custom_mutators/), shared libraries exporting `afl_custom_*` functions.
+//!
+//! The [`AflCustomMutatorLibrary`] loads such a library. Its functions map onto `LibAFL` as follows:
+//! - `afl_custom_fuzz` is the [`AflCustomMutator`], to use in any mutational stage,
+//! - `afl_custom_post_process` is the [`AflCustomPostProcess`], the target bytes converter of the fuzzer,
+//!   so the post-processed bytes reach the target while the corpus keeps the original inputs,
+//! - `afl_custom_init_trim`, `afl_custom_trim` and `afl_custom_post_trim` are the [`AflCustomTrimMutator`],
+//!   to use in a [`crate::stages::StdTMinMutationalStage`],
+//! - `afl_custom_queue_new_entry` and `afl_custom_queue_get` are the [`AflCustomQueueScheduler`], wrapping any scheduler.
+
+use alloc::{borrow::Cow, rc::Rc, string::String, vec::Vec};
+use core::{
+    cell::RefCell,
+    ffi::{CStr, c_char, c_uint, c_void},
+    ptr,
+};
+use std::{
+    ffi::CString,
+    os::unix::ffi::OsStrExt,
+    path::{Path, PathBuf},
+};
+
+use ahash::RandomState;
+use libafl_bolts::{Named, ownedref::OwnedSlice, rands::Rand, tuples::MatchName};
+
+use crate::{
+    Error,
+    corpus::{Corpus, CorpusId, Testcase},
+    inputs::{HasMutatorBytes, HasTargetBytes, ResizableMutator, ToTargetBytes},
+    mutators::{MutationResult, Mutator},
+    random_corpus_id_with_disabled,
+    schedulers::{HasQueueCycles, RemovableScheduler, Scheduler},
+    state::{HasCorpus, HasMaxSize, HasRand},
+};
+
+/// The environment variable with the path of the AFL++ custom mutator library
+pub const AFL_CUSTOM_MUTATOR_LIBRARY_ENV: &str = "AFL_CUSTOM_MUTATOR_LIBRARY";
+/// The environment variable to only run the AFL++ custom mutator, without the havoc mutations
+pub const AFL_CUSTOM_MUTATOR_ONLY_ENV: &str = "AFL_CUSTOM_MUTATOR_ONLY";
+
+/// The longest description of a mutation we ask `afl_custom_describe` for
+const MAX_DESCRIPTION_LEN: usize = 64;
+
+type InitFn = unsafe extern "C" fn(afl: *mut c_void, seed: c_uint) -> *mut c_void;
+type DeinitFn = unsafe extern "C" fn(data: *mut c_void);
+type FuzzFn = unsafe extern "C" fn(
+    data: *mut c_void,
+    buf: *mut u8,
+    buf_size: usize,
+    out_buf: *mut *mut u8,
+    add_buf: *mut u8,
+    add_buf_size: usize,
+    max_size: usize,
+) -> usize;
+type FuzzCountFn =
+    unsafe extern "C" fn(data: *mut c_void, buf: *const u8, buf_size: usize) -> c_uint;
+type DescribeFn =
+    unsafe extern "C" fn(data: *mut c_void, max_description_len: usize) -> *const c_char;
+type PostProcessFn = unsafe extern "C" fn(
+    data: *mut c_void,
+    buf: *mut u8,
+    buf_size: usize,
+    out_buf: *mut *mut u8,
+) -> usize;
+type InitTrimFn = unsafe extern "C" fn(data: *mut c_void, buf: *mut u8, buf_size: usize) -> i32;
+type TrimFn = unsafe extern "C" fn(data: *mut c_void, out_buf: *mut *mut u8) -> usize;
+type PostTrimFn = unsafe extern "C" fn(data: *mut c_void, success: u8) -> i32;
+type QueueNewEntryFn = unsafe extern "C" fn(
+    data: *mut c_void,
+    filename_new_queue: *const c_char,
+    filename_orig_queue: *const c_char,
+) -> u8;
+type QueueGetFn = unsafe extern "C" fn(data: *mut c_void, filename: *const c_char) -> u8;
+
+/// Resolves the symbol `name` in the library `handle`
+///
+/// # Safety
+/// `F` must be the function pointer type of the symbol.
+unsafe fn resolve<F>(handle: *mut c_void, name: &CStr) -> Option<F> {
+    assert_eq!(size_of::<F>(), size_of::<*mut c_void>());
+    let symbol = unsafe { libc::dlsym(handle, name.as_ptr()) };
+    (!symbol.is_null()).then(|| unsafe { ptr::read((&raw const symbol).cast::<F>()) })
+}
+
+/// Copies the `len` bytes a custom mutator returned at `out_buf`
+///
+/// # Safety
+/// `out_buf` must point to `len` readable bytes, if `len > 0`
+unsafe fn copy_out(out_buf: *const u8, len: usize) -> Vec<u8> {
+    if out_buf.is_null() || len == 0 {
+        return vec![];
+    }
+    unsafe { core::slice::from_raw_parts(out_buf, len) }.to_vec()
+}
+
+/// The C string of a path, for the library
+fn path_to_cstring(path: &Path) -> Result<CString, Error> {
+    CString::new(path.as_os_str().as_bytes()).map_err(|_| {
+        Error::illegal_argument(format!("Path {} contains a NUL byte", path.display()))
+    })
+}
+
+/// A loaded AFL++ custom mutator library, see the [module docs](self).
+///
+/// `afl_custom_init` is called with a null `afl` pointer, since there is no AFL++ state,
+/// so the library must not rely on it.
+pub struct AflCustomMutatorLibrary {
+    path: PathBuf,
+    handle: *mut c_void,
+    data: *mut c_void,
+    deinit: Option<DeinitFn>,
+    fuzz: Option<FuzzFn>,
+    fuzz_count: Option<FuzzCountFn>,
+    describe: Option<DescribeFn>,
+    post_process: Option<PostProcessFn>,
+    init_trim: Option<InitTrimFn>,
+    trim: Option<TrimFn>,
+    post_trim: Option<PostTrimFn>,
+    queue_new_entry: Option<QueueNewEntryFn>,
+    queue_get: Option<QueueGetFn>,
+    /// The input being trimmed. The library may keep pointers into it until the next `afl_custom_init_trim`.
+    trim_buf: RefCell<Vec<u8>>,
+}
+
+impl core::fmt::Debug for AflCustomMutatorLibrary {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("AflCustomMutatorLibrary")
+            .field("path", &self.path)
+            .field("fuzz", &self.fuzz.is_some())
+            .field("post_process", &self.post_process.is_some())
+            .field("trim", &self.trim.is_some())
+            .field("queue_new_entry", &self.queue_new_entry.is_some())
+            .field("queue_get", &self.queue_get.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+impl AflCustomMutatorLibrary {
+    /// Loads the custom mutator library at `path`, and initializes it with `seed`
+    pub fn load<P>(path: P, seed: u32) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let c_path = path_to_cstring(path)?;
+        let handle = unsafe { libc::dlopen(c_path.as_ptr(), libc::RTLD_NOW | libc::RTLD_LOCAL) };
+        if handle.is_null() {
+            let reason = unsafe { libc::dlerror() };
+            let reason = if reason.is_null() {
+                "unknown error".into()
+            } else {
+                unsafe { CStr::from_ptr(reason) }.to_string_lossy()
+            };
+            return Err(Error::illegal_argument(format!(
+                "Could not load the custom mutator library {}: {reason}",
+                path.display()
+            )));
+        }
+
+        // # Safety
+        // The function pointer types are the ones of the AFL++ custom mutator API.
+        let Some(init) = (unsafe { resolve::<InitFn>(handle, c"afl_custom_init") }) else {
+            unsafe { libc::dlclose(handle) };
+            return Err(Error::illegal_argument(format!(
+                "The custom mutator library {} has no afl_custom_init",
+                path.display()
+            )));
+        };
+        let mut library = unsafe {
+            Self {
+                path: path.to_path_buf(),
+                handle,
+                data: ptr::null_mut(),
+                deinit: resolve(handle, c"afl_custom_deinit"),
+                fuzz: resolve(handle, c"afl_custom_fuzz"),
+                fuzz_count: resolve(handle, c"afl_custom_fuzz_count"),
+                describe: resolve(handle, c"afl_custom_describe"),
+                post_process: resolve(handle, c"afl_custom_post_process"),
+                init_trim: resolve(handle, c"afl_custom_init_trim"),
+                trim: resolve(handle, c"afl_custom_trim"),
+                post_trim: resolve(handle, c"afl_custom_post_trim"),
+                queue_new_entry: resolve(handle, c"afl_custom_queue_new_entry"),
+                queue_get: resolve(handle, c"afl_custom_queue_get"),
+                trim_buf: RefCell::new(vec![]),
+            }
+        };
+        if library.init_trim.is_none() || library.trim.is_none() || library.post_trim.is_none() {
+            library.init_trim = None;
+        }
+
+        library.data = unsafe { init(ptr::null_mut(), seed) };
+        if library.data.is_null() {
+            return Err(Error::illegal_state(format!(
+                "afl_custom_init of {} failed",
+                path.display()
+            )));
+        }
+        Ok(library)
+    }
+
+    /// Loads the custom mutator library named by [`AFL_CUSTOM_MUTATOR_LIBRARY_ENV`], if set
+    pub fn from_env(seed: u32) -> Result<Option<Self>, Error> {
+        std::env::var_os(AFL_CUSTOM_MUTATOR_LIBRARY_ENV)
+            .map(|path| Self::load(path, seed))
+            .transpose()
+    }
+
+    /// The path of the library
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// If the library exports `afl_custom_fuzz`
+    #[must_use]
+    pub fn has_fuzz(&self) -> bool {
+        self.fuzz.is_some()
+    }
+
+    /// If the library exports `afl_custom_post_process`
+    #[must_use]
+    pub fn has_post_process(&self) -> bool {
+        self.post_process.is_some()
+    }
+
+    /// If the library exports `afl_custom_init_trim`, `afl_custom_trim` and `afl_custom_post_trim`
+    #[must_use]
+    pub fn has_trim(&self) -> bool {
+        self.init_trim.is_some()
+    }
+
+    /// Mutates `buf`, splicing with `add_buf`, with `afl_custom_fuzz`.
+    /// Returns `None` if the library does not export it.
+    pub fn fuzz(&self, buf: &mut [u8], add_buf: &mut [u8], max_size: usize) -> Option<Vec<u8>> {
+        let fuzz = self.fuzz?;
+        let mut out_buf = ptr::null_mut();
+        let len = unsafe {
+            fuzz(
+                self.data,
+                buf.as_mut_ptr(),
+                buf.len(),
+                &raw mut out_buf,
+                add_buf.as_mut_ptr(),
+                add_buf.len(),
+                max_size,
+            )
+        };
+        Some(unsafe { copy_out(out_buf, len.min(max_size)) })
+    }
+
+    /// How often AFL++ would call `afl_custom_fuzz` for `buf`, with `afl_custom_fuzz_count`
+    #[must_use]
+    pub fn fuzz_count(&self, buf: &[u8]) -> Option<u32> {
+        let fuzz_count = self.fuzz_count?;
+        Some(unsafe { fuzz_count(self.data, buf.as_ptr(), buf.len()) })
+    }
+
+    /// The description of the last mutation, with `afl_custom_describe`
+    #[must_use]
+    pub fn describe(&self) -> Option<String> {
+        let describe = self.describe?;
+        let description = unsafe { describe(self.data, MAX_DESCRIPTION_LEN) };
+        (!description.is_null()).then(|| {
+            unsafe { CStr::from_ptr(description) }
+                .to_string_lossy()
+                .into_owned()
+        })
+    }
+
+    /// Post-processes the bytes sent to the target, with `afl_custom_post_process`
+    #[must_use]
+    pub fn post_process(&self, buf: &[u8]) -> Option<Vec<u8>> {
+        let post_process = self.post_process?;
+        // The library may process the buffer in place
+        let mut buf = buf.to_vec();
+        let mut out_buf = ptr::null_mut();
+        let len = unsafe { post_process(self.data, buf.as_mut_ptr(), buf.len(), &raw mut out_buf) };
+        Some(unsafe { copy_out(out_buf, len) })
+    }
+
+    /// Starts trimming `buf`, with `afl_custom_init_trim`. Returns the number of trimming steps.
+    pub fn init_trim(&self, buf: &[u8]) -> Option<i32> {
+        let init_trim = self.init_trim?;
+        let mut trim_buf = self.trim_buf.borrow_mut();
+        trim_buf.clear();
+        trim_buf.extend_from_slice(buf);
+        Some(unsafe { init_trim(self.data, trim_buf.as_mut_ptr(), trim_buf.len()) })
+    }
+
+    /// The next trimmed candidate, with `afl_custom_trim`
+    pub fn trim(&self) -> Option<Vec<u8>> {
+        let trim = self.trim?;
+        let mut out_buf = ptr::null_mut();
+        let len = unsafe { trim(self.data, &raw mut out_buf) };
+        Some(unsafe { copy_out(out_buf, len) })
+    }
+
+    /// Reports if the last candidate kept the behavior of the input, with `afl_custom_post_trim`.
+    /// Returns the next trimming step.
+    pub fn post_trim(&self, success: bool) -> Option<i32> {
+        let post_trim = self.post_trim?;
+        Some(unsafe { post_trim(self.data, u8::from(success)) })
+    }
+
+    /// Reports a new queue entry, and the one it was derived from, with `afl_custom_queue_new_entry`
+    pub fn queue_new_entry(&self, new: &Path, orig: Option<&Path>) -> Result<(), Error> {
+        let Some(queue_new_entry) = self.queue_new_entry else {
+            return Ok(());
+        };
+        let new = path_to_cstring(new)?;
+        let orig = orig.map(path_to_cstring).transpose()?;
+        unsafe {
+            queue_new_entry(
+                self.data,
+                new.as_ptr(),
+                orig.as_ref().map_or(ptr::null(), |orig| orig.as_ptr()),
+            );
+        }
+        Ok(())
+    }
+
+    /// Asks if the queue entry should be fuzzed, with `afl_custom_queue_get`. Without it, all entries are.
+    pub fn queue_get(&self, filename: &Path) -> Result<bool, Error> {
+        let Some(queue_get) = self.queue_get else {
+            return Ok(true);
+        };
+        let filename = path_to_cstring(filename)?;
+        Ok(unsafe { queue_get(self.data, filename.as_ptr()) } != 0)
+    }
+}
+
+impl Drop for AflCustomMutatorLibrary {
+    fn drop(&mut self) {
+        unsafe {
+            if let Some(deinit) = self.deinit {
+                if !self.data.is_null() {
+                    deinit(self.data);
+                }
+            }
+            libc::dlclose(self.handle);
+        }
+    }
+}
+
+/// Mutates inputs with `afl_custom_fuzz`, splicing with a random corpus entry
+#[derive(Debug)]
+pub struct AflCustomMutator {
+    library: Rc<AflCustomMutatorLibrary>,
+    name: Cow<'static, str>,
+}
+
+impl AflCustomMutator {
+    /// Creates a new [`AflCustomMutator`], the library must export `afl_custom_fuzz`
+    pub fn new(library: Rc<AflCustomMutatorLibrary>) -> Result<Self, Error> {
+        if !library.has_fuzz() {
+            return Err(Error::illegal_argument(format!(
+                "The custom mutator library {} has no afl_custom_fuzz",
+                library.path().display()
+            )));
+        }
+        let name = Cow::from(format!("AflCustomMutator[{}]", library.path().display()));
+        Ok(Self { library, name })
+    }
+
+    /// The description of the last mutation, if the library exports `afl_custom_describe`
+    #[must_use]
+    pub fn describe(&self) -> Option<String> {
+        self.library.describe()
+    }
+}
+
+impl Named for AflCustomMutator {
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+impl<I, S> Mutator<I, S> for AflCustomMutator
+where
+    I: HasMutatorBytes + ResizableMutator<u8>,
+    S: HasCorpus<I> + HasRand + HasMaxSize,
+{
+    fn mutate(&mut self, state: &mut S, input: &mut I) -> Result<MutationResult, Error> {
+        let mut add_buf = if state.corpus().count() > 1 {
+            let id = random_corpus_id_with_disabled!(state.corpus(), state.rand_mut());
+            let mut testcase = state.corpus().get_from_all(id)?.borrow_mut();
+            testcase
+                .load_input(state.corpus())?
+                .mutator_bytes()
+                .to_vec()
+        } else {
+            vec![]
+        };
+        let mut buf = input.mutator_bytes().to_vec();
+        let Some(mutated) = self.library.fuzz(&mut buf, &mut add_buf, state.max_size()) else {
+            return Ok(MutationResult::Skipped);
+        };
+        if mutated.is_empty() || mutated == input.mutator_bytes() {
+            return Ok(MutationResult::Skipped);
+        }
+        input.resize(mutated.len(), 0);
+        input.mutator_bytes_mut().copy_from_slice(&mutated);
+        Ok(MutationResult::Mutated)
+    }
+
+    #[inline]
+    fn post_exec(&mut self, _state: &mut S, _new_corpus_id: Option<CorpusId>) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+/// The trimming session of an [`AflCustomTrimMutator`]
+#[derive(Debug, Clone, Copy)]
+struct TrimSession {
+    /// The hash of the input being trimmed
+    input_hash: u64,
+    /// The number of trimming steps
+    steps: i32,
+    /// The current trimming step
+    step: i32,
+    /// The length of the last candidate, and of the base it was trimmed from, until we know if it was kept
+    candidate: Option<(usize, usize)>,
+}
+
+/// Trims inputs with `afl_custom_init_trim`, `afl_custom_trim` and `afl_custom_post_trim`,
+/// as the only mutator of a [`crate::stages::StdTMinMutationalStage`].
+///
+/// The stage keeps a shorter candidate, and caps the max size to its length, if it behaves like the input.
+/// This is how the mutator tells the library if the last candidate was a success,
+/// candidates that are not shorter are not executed, and are failures.
+/// Once the library is done, the mutator leaves the input unchanged, which the stage does not execute.
+#[derive(Debug)]
+pub struct AflCustomTrimMutator {
+    library: Rc<AflCustomMutatorLibrary>,
+    session: Option<TrimSession>,
+}
+
+impl AflCustomTrimMutator {
+    /// Creates a new [`AflCustomTrimMutator`], the library must export the trimming functions
+    pub fn new(library: Rc<AflCustomMutatorLibrary>) -> Result<Self, Error> {
+        if !library.has_trim() {
+            return Err(Error::illegal_argument(format!(
+                "The custom mutator library {} has no afl_custom_init_trim, afl_custom_trim or afl_custom_post_trim",
+                library.path().display()
+            )));
+        }
+        Ok(Self {
+            library,
+            session: None,
+        })
+    }
+}
+
+impl Named for AflCustomTrimMutator {
+    fn name(&self) -> &Cow<'static, str> {
+        static NAME: Cow<'static, str> = Cow::Borrowed("AflCustomTrimMutator");
+        &NAME
+    }
+}
+
+impl<I, S> Mutator<I, S> for AflCustomTrimMutator
+where
+    I: HasMutatorBytes + ResizableMutator<u8>,
+    S: HasMaxSize,
+{
+    fn mutate(&mut self, state: &mut S, input: &mut I) -> Result<MutationResult, Error> {
+        let input_hash = RandomState::with_seeds(0, 0, 0, 0).hash_one(input.mutator_bytes());
+        let session = match &mut self.session {
+            Some(session) if session.input_hash == input_hash => session,
+            session => {
+                let steps = self.library.init_trim(input.mutator_bytes()).unwrap_or(0);
+                session.insert(TrimSession {
+                    input_hash,
+                    steps,
+                    step: 0,
+                    candidate: None,
+                })
+            }
+        };
+
+        if let Some((candidate_len, base_len)) = session.candidate.take() {
+            // The stage only executes shorter candidates, and the base shrinks to the ones it keeps
+            let success = candidate_len < base_len && state.max_size() == candidate_len;
+            session.step = self.library.post_trim(success).unwrap_or(session.steps);
+        }
+        if session.step >= session.steps {
+            // Unchanged, so the stage does not execute it
+            return Ok(MutationResult::Mutated);
+        }
+
+        let Some(candidate) = self.library.trim() else {
+            return Ok(MutationResult::Mutated);
+        };
+        session.candidate = Some((candidate.len(), state.max_size()));
+        input.resize(candidate.len(), 0);
+        input.mutator_bytes_mut().copy_from_slice(&candidate);
+        Ok(MutationResult::Mutated)
+    }
+
+    #[inline]
+    fn post_exec(&mut self, _state: &mut S, _new_corpus_id: Option<CorpusId>) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+/// Post-processes the bytes sent to the target with `afl_custom_post_process`.
+/// Use it as the target bytes converter of the fuzzer.
+///
+/// Without a library, or `afl_custom_post_process`, the bytes are sent as they are.
+#[derive(Debug, Clone, Default)]
+pub struct AflCustomPostProcess {
+    library: Option<Rc<AflCustomMutatorLibrary>>,
+}
+
+impl AflCustomPostProcess {
+    /// Creates a new [`AflCustomPostProcess`]
+    #[must_use]
+    pub fn new(library: Option<Rc<AflCustomMutatorLibrary>>) -> Self {
+        Self { library }
+    }
+}
+
+impl<I> ToTargetBytes<I> for AflCustomPostProcess
+where
+    I: HasTargetBytes,
+{
+    fn to_target_bytes<'a>(&mut self, input: &'a I) -> OwnedSlice<'a, u8> {
+        let bytes = input.target_bytes();
+        match self
+            .library
+            .as_ref()
+            .and_then(|library| library.post_process(&bytes))
+        {
+            Some(processed) => OwnedSlice::from(processed),
+            None => bytes,
+        }
+    }
+}
+
+/// Wraps a scheduler to report new corpus entries with `afl_custom_queue_new_entry`,
+/// and to skip the entries `afl_custom_queue_get` rejects.
+///
+/// The library gets the file names of the entries, so the corpus should be on disk.
+/// Without a library, this is the wrapped scheduler.
+#[derive(Debug)]
+pub struct AflCustomQueueScheduler<CS> {
+    inner: CS,
+    library: Option<Rc<AflCustomMutatorLibrary>>,
+}
+
+impl<CS> AflCustomQueueScheduler<CS> {
+    /// Wraps the `inner` scheduler
+    pub fn new(inner: CS, library: Option<Rc<AflCustomMutatorLibrary>>) -> Self {
+        Self { inner, library }
+    }
+
+    /// The wrapped scheduler
+    pub fn inner(&self) -> &CS {
+        &self.inner
+    }
+}
+
+/// The file name of a testcase, if it is on disk
+fn testcase_file<I>(testcase: &Testcase<I>) -> Option<PathBuf> {
+    testcase.file_path().clone()
+}
+
+impl<CS, I, S> Scheduler<I, S> for AflCustomQueueScheduler<CS>
+where
+    CS: Scheduler<I, S>,
+    S: HasCorpus<I>,
+{
+    fn on_add(&mut self, state: &mut S, id: CorpusId) -> Result<(), Error> {
+        self.inner.on_add(state, id)?;
+        let Some(library) = &self.library else {
+            return Ok(());
+        };
+        let (new, parent_id) = {
+            let testcase = state.corpus().get(id)?.borrow();
+            (testcase_file(&testcase), testcase.parent_id())
+        };
+        let orig = match parent_id {
+            Some(parent_id) => testcase_file(&state.corpus().get_from_all(parent_id)?.borrow()),
+            None => None,
+        };
+        if let Some(new) = new {
+            library.queue_new_entry(&new, orig.as_deref())?;
+        }
+        Ok(())
+    }
+
+    fn on_evaluation<OT>(&mut self, state: &mut S, input: &I, observers: &OT) -> Result<(), Error>
+    where
+        OT: MatchName,
+    {
+        self.inner.on_evaluation(state, input, observers)
+    }
+
+    fn next(&mut self, state: &mut S) -> Result<CorpusId, Error> {
+        let mut id = self.inner.next(state)?;
+        let Some(library) = &self.library else {
+            return Ok(id);
+        };
+        // Give up on skipping after a round through the corpus, so we never spin
+        for _ in 0..state.corpus().count() {
+            let file = testcase_file(&state.corpus().get(id)?.borrow());
+            match file {
+                Some(file) if !library.queue_get(&file)? => id = self.inner.next(state)?,
+                _ => break,
+            }
+        }
+        Ok(id)
+    }
+
+    fn set_current_scheduled(
+        &mut self,
+        state: &mut S,
+        next_id: Option<CorpusId>,
+    ) -> Result<(), Error> {
+        self.inner.set_current_scheduled(state, next_id)
+    }
+}
+
+impl<CS, I, S> RemovableScheduler<I, S> for AflCustomQueueScheduler<CS>
+where
+    CS: RemovableScheduler<I, S>,
+{
+    fn on_remove(
+        &mut self,
+        state: &mut S,
+        id: CorpusId,
+        testcase: &Option<Testcase<I>>,
+    ) -> Result<(), Error> {
+        self.inner.on_remove(state, id, testcase)
+    }
+
+    fn on_replace(&mut self, state: &mut S, id: CorpusId, prev: &Testcase<I>) -> Result<(), Error> {
+        self.inner.on_replace(state, id, prev)
+    }
+}
+
+impl<CS> HasQueueCycles for AflCustomQueueScheduler<CS>
+where
+    CS: HasQueueCycles,
+{
+    fn queue_cycles(&self) -> u64 {
+        self.inner.queue_cycles()
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use alloc::string::ToString;
+
+    use super::AflCustomMutatorLibrary;
+
+    #[test]
+    fn test_load_custom_mutator_library() {
+        let missing = AflCustomMutatorLibrary::load("/nonexistent/libcustom.so", 0).unwrap_err();
+        assert!(missing.to_string().contains("Could not load"));
+
+        // libc is a library, but no custom mutator
+        let not_a_mutator = AflCustomMutatorLibrary::load("libc.so.6", 0).unwrap_err();
+        assert!(not_a_mutator.to_string().contains("afl_custom_init"));
+    }
+}
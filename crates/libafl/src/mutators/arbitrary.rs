@@ -0,0 +1,280 @@
+//! Typed mutations of [`ArbitraryInput`]s.
+//!
+//! The [`ArbitraryMutator`] decodes the input as the harness would, re-encodes the value with
+//! [`encode_arbitrary`] and mutates one of its values in the encoding, so that the mutant still
+//! decodes to a value of the same shape. The raw bytes stay available to the usual havoc mutators.
+
+use alloc::{borrow::Cow, vec::Vec};
+use core::{marker::PhantomData, num::NonZero};
+
+use arbitrary::Arbitrary;
+use libafl_bolts::{Named, rands::Rand};
+use serde::Serialize;
+
+use crate::{
+    Error,
+    corpus::CorpusId,
+    inputs::arbitrary::{
+        ArbitraryEnums, ArbitraryInput, ArbitraryNode, ArbitraryNodeKind, decode_arbitrary,
+        encode_arbitrary_with, variant_choice,
+    },
+    mutators::{ARITH_MAX, INTERESTING_32, MutationResult, Mutator},
+    nonzero,
+    state::{HasMaxSize, HasRand},
+};
+
+/// Special floats the [`ArbitraryMutator`] sets floats to
+const INTERESTING_FLOATS: [f64; 10] = [
+    0.0,
+    -0.0,
+    1.0,
+    -1.0,
+    f64::INFINITY,
+    f64::NEG_INFINITY,
+    f64::NAN,
+    f64::MIN_POSITIVE,
+    f64::MAX,
+    f64::EPSILON,
+];
+
+/// Mutates one value of the decoded [`ArbitraryInput`], keeping the rest of its structure, see the [module docs](self).
+///
+/// Integers get arithmetics, interesting values, bit flips or random bytes, bools are flipped, floats and chars
+/// are replaced, strings and byte slices get a byte replaced, enums switch to another variant, `Some` becomes `None`,
+/// and collection elements are removed or duplicated.
+/// Mutants that do not decode back to the same encoding are dropped.
+#[derive(Debug)]
+pub struct ArbitraryMutator<T> {
+    enums: ArbitraryEnums,
+    phantom: PhantomData<fn() -> T>,
+}
+
+impl<T> ArbitraryMutator<T> {
+    /// Creates a new [`ArbitraryMutator`], for values without enums
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            enums: ArbitraryEnums::new(),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Sets the enums of the value, see [`encode_arbitrary_with`]
+    #[must_use]
+    pub fn with_enums(mut self, enums: ArbitraryEnums) -> Self {
+        self.enums = enums;
+        self
+    }
+}
+
+impl<T> Default for ArbitraryMutator<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Named for ArbitraryMutator<T> {
+    fn name(&self) -> &Cow<'static, str> {
+        static NAME: Cow<'static, str> = Cow::Borrowed("ArbitraryMutator");
+        &NAME
+    }
+}
+
+impl<S, T> Mutator<ArbitraryInput<T>, S> for ArbitraryMutator<T>
+where
+    S: HasRand + HasMaxSize,
+    T: for<'a> Arbitrary<'a> + Serialize,
+{
+    fn mutate(
+        &mut self,
+        state: &mut S,
+        input: &mut ArbitraryInput<T>,
+    ) -> Result<MutationResult, Error> {
+        let Ok(value) = input.decode() else {
+            return Ok(MutationResult::Skipped);
+        };
+        // Types the encoding does not support are left to the byte-level mutators
+        let Ok((mut bytes, nodes)) = encode_arbitrary_with(&value, &self.enums) else {
+            return Ok(MutationResult::Skipped);
+        };
+        let candidates = nodes
+            .iter()
+            .filter(|node| node.kind != ArbitraryNodeKind::None && !node.range.is_empty())
+            .collect::<Vec<_>>();
+        let Some(node) = state.rand_mut().choose(candidates) else {
+            return Ok(MutationResult::Skipped);
+        };
+        mutate_node(state.rand_mut(), &mut bytes, node);
+        if bytes.len() > state.max_size()
+            || bytes == input.bytes()
+            || !round_trips::<T>(&bytes, &self.enums)
+        {
+            return Ok(MutationResult::Skipped);
+        }
+        *input.bytes_mut() = bytes;
+        Ok(MutationResult::Mutated)
+    }
+
+    #[inline]
+    fn post_exec(&mut self, _state: &mut S, _new_corpus_id: Option<CorpusId>) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+/// Whether `bytes` decode to a value that encodes back to `bytes`
+fn round_trips<T>(bytes: &[u8], enums: &ArbitraryEnums) -> bool
+where
+    T: for<'a> Arbitrary<'a> + Serialize,
+{
+    decode_arbitrary::<T>(bytes)
+        .and_then(|value| encode_arbitrary_with(&value, enums))
+        .is_ok_and(|(encoded, _)| encoded == bytes)
+}
+
+/// Writes `value`, sign extended, to the little endian integer in `bytes`
+fn write_int(bytes: &mut [u8], value: i128) {
+    let len = bytes.len();
+    bytes.copy_from_slice(&value.to_le_bytes()[..len]);
+}
+
+/// Reads the little endian integer in `bytes`, zero extended
+fn read_int(bytes: &[u8]) -> u128 {
+    let mut buf = [0; 16];
+    buf[..bytes.len()].copy_from_slice(bytes);
+    u128::from_le_bytes(buf)
+}
+
+/// Mutates the value of `node` in the encoded `bytes`
+fn mutate_node<R: Rand>(rand: &mut R, bytes: &mut Vec<u8>, node: &ArbitraryNode) {
+    let range = node.range.clone();
+    match node.kind {
+        ArbitraryNodeKind::Bool => bytes[range.start] ^= 1,
+        ArbitraryNodeKind::Int(_) => {
+            let int = &mut bytes[range];
+            match rand.below(nonzero!(4)) {
+                0 => {
+                    let delta = 1 + rand.below(nonzero!(ARITH_MAX)) as u128;
+                    let value = if rand.coinflip(0.5) {
+                        read_int(int).wrapping_add(delta)
+                    } else {
+                        read_int(int).wrapping_sub(delta)
+                    };
+                    int.copy_from_slice(&value.to_le_bytes()[..int.len()]);
+                }
+                1 => write_int(int, i128::from(*rand.choose(&INTERESTING_32).unwrap())),
+                2 => {
+                    let bit = rand.below(NonZero::new(int.len() * 8).unwrap());
+                    int[bit / 8] ^= 1 << (bit % 8);
+                }
+                _ => int.iter_mut().for_each(|byte| *byte = rand.next() as u8),
+            }
+        }
+        ArbitraryNodeKind::Float(size) => {
+            let float = *rand.choose(&INTERESTING_FLOATS).unwrap();
+            if size == 4 {
+                bytes[range].copy_from_slice(&(float as f32).to_bits().to_le_bytes());
+            } else {
+                bytes[range].copy_from_slice(&float.to_bits().to_le_bytes());
+            }
+        }
+        ArbitraryNodeKind::Char => {
+            let c = char::from_u32(rand.below(nonzero!(0x11_0000)) as u32).unwrap_or('\0');
+            bytes[range].copy_from_slice(&u32::from(c).to_le_bytes());
+        }
+        // Strings and byte slices keep their length, so the lengths at the end of the input stay valid
+        ArbitraryNodeKind::Str => {
+            let pos = range.start + rand.below(NonZero::new(range.len()).unwrap());
+            // Only replace ASCII bytes, to keep the string valid UTF-8
+            if bytes[pos].is_ascii() {
+                bytes[pos] = 0x20 + rand.below(nonzero!(0x5f)) as u8;
+            }
+        }
+        ArbitraryNodeKind::Bytes => {
+            let pos = range.start + rand.below(NonZero::new(range.len()).unwrap());
+            bytes[pos] = rand.next() as u8;
+        }
+        ArbitraryNodeKind::Variant(variants) => {
+            // The fields of the previous variant are kept, the mutant is dropped if they do not decode
+            let variant = rand.below(NonZero::new(variants as usize).unwrap()) as u32;
+            bytes[range.start..range.start + 4]
+                .copy_from_slice(&variant_choice(variant, variants).to_le_bytes());
+        }
+        ArbitraryNodeKind::Some => {
+            bytes.splice(range, [0]);
+        }
+        ArbitraryNodeKind::Element => {
+            if rand.coinflip(0.5) {
+                bytes.drain(range);
+            } else {
+                let element = bytes[range.clone()].to_vec();
+                bytes.splice(range.end..range.end, element);
+            }
+        }
+        ArbitraryNodeKind::None => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{
+        string::{String, ToString},
+        vec,
+    };
+
+    use libafl_bolts::rands::StdRand;
+
+    use crate::{
+        inputs::arbitrary::{ArbitraryInput, ArbitraryNodeKind, encode_arbitrary},
+        mutators::{
+            MutationResult, Mutator,
+            arbitrary::{ArbitraryMutator, mutate_node},
+        },
+        state::NopState,
+    };
+
+    #[test]
+    fn test_mutate_node_keeps_layout() {
+        let value = (7u32, vec![1u8, 2, 3], Some(true), "abc");
+        let (encoded, nodes) = encode_arbitrary(&value).unwrap();
+        let mut rand = StdRand::with_seed(1337);
+        for node in &nodes {
+            for _ in 0..16 {
+                let mut bytes = encoded.clone();
+                mutate_node(&mut rand, &mut bytes, node);
+                match node.kind {
+                    ArbitraryNodeKind::Int(_)
+                    | ArbitraryNodeKind::Bool
+                    | ArbitraryNodeKind::Str => {
+                        assert_eq!(bytes.len(), encoded.len());
+                    }
+                    ArbitraryNodeKind::Some => assert_eq!(bytes.len(), encoded.len() - 1),
+                    ArbitraryNodeKind::Element => assert!(
+                        bytes.len() == encoded.len() - 2 || bytes.len() == encoded.len() + 2
+                    ),
+                    _ => {}
+                }
+                // The prefix before the mutated value is untouched
+                assert_eq!(bytes[..node.range.start], encoded[..node.range.start]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_mutate_trailing_string() {
+        // The trailing string takes the rest of the input, as the harness decodes it
+        let value = (7u32, "abcdef".to_string());
+        let mut input = ArbitraryInput::<(u32, String)>::from_value(&value).unwrap();
+        assert_eq!(input.decode().unwrap(), value);
+        let mut mutator = ArbitraryMutator::new();
+        let mut state = NopState::<ArbitraryInput<(u32, String)>>::new();
+        let mut mutated = 0;
+        for _ in 0..64 {
+            if mutator.mutate(&mut state, &mut input).unwrap() == MutationResult::Mutated {
+                mutated += 1;
+                let (_, string) = input.decode().unwrap();
+                assert_eq!(string.len(), value.1.len());
+            }
+        }
+        assert!(mutated > 0);
+    }
+}
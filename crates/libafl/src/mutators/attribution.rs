@@ -0,0 +1,308 @@
+//! Attribution of executions, new corpus entries and objectives to the mutators and stages that caused them.
+//!
+//! The [`AttributedScheduledMutator`] credits each mutation a [`ScheduledMutator`] applied to an input,
+//! the [`AttributedMutator`] credits any single mutator, see [`ToAttributedMutator`] to wrap all mutators of a tuple,
+//! and the [`AttributedStage`](crate::stages::AttributedStage) credits a stage.
+//! They all count into the [`AttributionMetadata`] of the state,
+//! which the [`AttributionStatsStage`](crate::stages::AttributionStatsStage) publishes as user stats.
+
+use alloc::{
+    borrow::Cow,
+    format,
+    string::{String, ToString},
+};
+
+use hashbrown::HashMap;
+use libafl_bolts::{Named, tuples::MappingFunctor};
+use serde::{Deserialize, Serialize};
+
+use super::MutationId;
+use crate::{
+    Error, HasMetadata,
+    corpus::{Corpus, CorpusId},
+    mutators::{ComposedByMutations, MutationResult, Mutator, MutatorsTuple, ScheduledMutator},
+    state::HasSolutions,
+};
+
+/// What one mutator or stage consumed and produced
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct YieldStats {
+    /// The executions it consumed
+    pub executions: u64,
+    /// The new corpus entries it produced
+    pub corpus: u64,
+    /// The new objectives it produced
+    pub objectives: u64,
+}
+
+/// The [`YieldStats`] of each mutator and stage, by name, see the [module docs](self)
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[cfg_attr(
+    any(not(feature = "serdeany_autoreg"), miri),
+    expect(clippy::unsafe_derive_deserialize)
+)] // for SerdeAny
+pub struct AttributionMetadata {
+    /// The yield of each mutator
+    pub mutators: HashMap<String, YieldStats>,
+    /// The yield of each stage
+    pub stages: HashMap<String, YieldStats>,
+}
+
+libafl_bolts::impl_serdeany!(AttributionMetadata);
+
+impl AttributionMetadata {
+    /// Credits one execution, and what it produced, to the mutator `name`
+    pub fn record_mutator(&mut self, name: &str, corpus: bool, objective: bool) {
+        let stats = entry(&mut self.mutators, name);
+        stats.executions += 1;
+        stats.corpus += u64::from(corpus);
+        stats.objectives += u64::from(objective);
+    }
+
+    /// Credits a run of the stage `name`
+    pub fn record_stage(&mut self, name: &str, yield_stats: YieldStats) {
+        let stats = entry(&mut self.stages, name);
+        stats.executions += yield_stats.executions;
+        stats.corpus += yield_stats.corpus;
+        stats.objectives += yield_stats.objectives;
+    }
+}
+
+fn entry<'a>(map: &'a mut HashMap<String, YieldStats>, name: &str) -> &'a mut YieldStats {
+    if !map.contains_key(name) {
+        map.insert(name.to_string(), YieldStats::default());
+    }
+    map.get_mut(name).unwrap()
+}
+
+/// The number of objectives of the state
+fn objective_count<I, S>(state: &S) -> usize
+where
+    S: HasSolutions<I>,
+{
+    state.solutions().count_all()
+}
+
+/// A [`ScheduledMutator`] wrapper crediting each execution, and what it produced,
+/// to every mutation the wrapped mutator applied to the executed input.
+///
+/// The mutations of the wrapped mutator have to be [`AttributedMutator`]s, see [`ToAttributedMutator`].
+/// They record if they got applied, and get credited on [`Mutator::post_exec`],
+/// which scheduled mutators do not forward to their mutations.
+/// The stacking and scheduling is left to the wrapped mutator, so this works for any [`ScheduledMutator`]:
+///
+/// ```rust
+/// # use libafl::mutators::{
+/// #     AttributedScheduledMutator, HavocScheduledMutator, ToAttributedMutator, havoc_mutations,
+/// # };
+/// # use libafl_bolts::tuples::Map;
+/// let mutator = AttributedScheduledMutator::new(HavocScheduledMutator::new(
+///     havoc_mutations().map(ToAttributedMutator),
+/// ));
+/// ```
+#[derive(Debug)]
+pub struct AttributedScheduledMutator<SM> {
+    name: Cow<'static, str>,
+    scheduled: SM,
+}
+
+impl<SM> Named for AttributedScheduledMutator<SM> {
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+impl<SM> AttributedScheduledMutator<SM>
+where
+    SM: Named,
+{
+    /// Wraps the `scheduled` mutator, its mutations have to be [`AttributedMutator`]s
+    pub fn new(scheduled: SM) -> Self {
+        Self {
+            name: Cow::from(format!("AttributedScheduledMutator[{}]", scheduled.name())),
+            scheduled,
+        }
+    }
+}
+
+impl<I, S, SM> Mutator<I, S> for AttributedScheduledMutator<SM>
+where
+    SM: ScheduledMutator<I, S>,
+    SM::Mutations: MutatorsTuple<I, S>,
+{
+    fn mutate(&mut self, state: &mut S, input: &mut I) -> Result<MutationResult, Error> {
+        self.scheduled.mutate(state, input)
+    }
+
+    fn post_exec(&mut self, state: &mut S, new_corpus_id: Option<CorpusId>) -> Result<(), Error> {
+        self.scheduled.post_exec(state, new_corpus_id)?;
+        self.scheduled
+            .mutations_mut()
+            .post_exec_all(state, new_corpus_id)
+    }
+}
+
+impl<SM> ComposedByMutations for AttributedScheduledMutator<SM>
+where
+    SM: ComposedByMutations,
+{
+    type Mutations = SM::Mutations;
+    #[inline]
+    fn mutations(&self) -> &SM::Mutations {
+        self.scheduled.mutations()
+    }
+
+    #[inline]
+    fn mutations_mut(&mut self) -> &mut SM::Mutations {
+        self.scheduled.mutations_mut()
+    }
+}
+
+impl<I, S, SM> ScheduledMutator<I, S> for AttributedScheduledMutator<SM>
+where
+    SM: ScheduledMutator<I, S>,
+    SM::Mutations: MutatorsTuple<I, S>,
+{
+    fn iterations(&self, state: &mut S, input: &I) -> u64 {
+        self.scheduled.iterations(state, input)
+    }
+
+    fn schedule(&self, state: &mut S, input: &I) -> MutationId {
+        self.scheduled.schedule(state, input)
+    }
+
+    fn scheduled_mutate(&mut self, state: &mut S, input: &mut I) -> Result<MutationResult, Error> {
+        self.scheduled.scheduled_mutate(state, input)
+    }
+}
+
+/// A [`Mutator`] wrapper crediting each execution of an input it mutated, and what it produced, to the wrapped mutator.
+///
+/// It needs its [`Mutator::post_exec`] called, which a [`ScheduledMutator`] does not do for its mutations.
+/// Wrap the scheduled mutator in an [`AttributedScheduledMutator`] for those.
+#[derive(Debug)]
+pub struct AttributedMutator<M> {
+    inner: M,
+    mutated: bool,
+    objectives_before: usize,
+}
+
+impl<M> AttributedMutator<M> {
+    /// Wraps the `inner` mutator
+    pub fn new(inner: M) -> Self {
+        Self {
+            inner,
+            mutated: false,
+            objectives_before: 0,
+        }
+    }
+}
+
+impl<M> Named for AttributedMutator<M>
+where
+    M: Named,
+{
+    fn name(&self) -> &Cow<'static, str> {
+        self.inner.name()
+    }
+}
+
+impl<I, M, S> Mutator<I, S> for AttributedMutator<M>
+where
+    M: Mutator<I, S>,
+    S: HasMetadata + HasSolutions<I>,
+{
+    fn mutate(&mut self, state: &mut S, input: &mut I) -> Result<MutationResult, Error> {
+        let result = self.inner.mutate(state, input)?;
+        // Stacked mutations may run this mutator several times for one execution
+        self.mutated |= result == MutationResult::Mutated;
+        self.objectives_before = objective_count(state);
+        Ok(result)
+    }
+
+    fn post_exec(&mut self, state: &mut S, new_corpus_id: Option<CorpusId>) -> Result<(), Error> {
+        self.inner.post_exec(state, new_corpus_id)?;
+        if core::mem::take(&mut self.mutated) {
+            let objective = objective_count(state) > self.objectives_before;
+            state
+                .metadata_or_insert_with(AttributionMetadata::default)
+                .record_mutator(self.inner.name(), new_corpus_id.is_some(), objective);
+        }
+        Ok(())
+    }
+}
+
+/// Wraps each mutator of a tuple into an [`AttributedMutator`], using [`libafl_bolts::tuples::Map`]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ToAttributedMutator;
+
+impl<M> MappingFunctor<M> for ToAttributedMutator
+where
+    M: Named,
+{
+    type Output = AttributedMutator<M>;
+
+    fn apply(&mut self, from: M) -> Self::Output {
+        AttributedMutator::new(from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use libafl_bolts::{
+        rands::StdRand,
+        tuples::{Map, tuple_list},
+    };
+
+    use super::{AttributedScheduledMutator, AttributionMetadata, ToAttributedMutator, YieldStats};
+    use crate::{
+        HasMetadata,
+        corpus::{Corpus, CorpusId, InMemoryCorpus, Testcase},
+        feedbacks::ConstFeedback,
+        inputs::BytesInput,
+        mutators::{ByteIncMutator, Mutator, MutatorsTuple, scheduled::HavocScheduledMutator},
+        state::{HasSolutions, StdState},
+    };
+
+    #[test]
+    fn test_attribution() {
+        let mut feedback = ConstFeedback::new(false);
+        let mut objective = ConstFeedback::new(false);
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+        let mut input = BytesInput::new(b"abc".to_vec());
+
+        // Havoc stacks several mutations, each execution is still credited once
+        let mut scheduled = AttributedScheduledMutator::new(HavocScheduledMutator::new(
+            tuple_list!(ByteIncMutator::new()).map(ToAttributedMutator),
+        ));
+        scheduled.mutate(&mut state, &mut input).unwrap();
+        scheduled.post_exec(&mut state, Some(CorpusId(0))).unwrap();
+        scheduled.mutate(&mut state, &mut input).unwrap();
+        state
+            .solutions_mut()
+            .add(Testcase::new(input.clone()))
+            .unwrap();
+        scheduled.post_exec(&mut state, None).unwrap();
+
+        let mut mutators = tuple_list!(ByteIncMutator::new()).map(ToAttributedMutator);
+        mutators.mutate_all(&mut state, &mut input).unwrap();
+        mutators.post_exec_all(&mut state, None).unwrap();
+
+        let metadata = state.metadata::<AttributionMetadata>().unwrap();
+        assert_eq!(
+            metadata.mutators["ByteIncMutator"],
+            YieldStats {
+                executions: 3,
+                corpus: 1,
+                objectives: 1,
+            }
+        );
+    }
+}
@@ -1,4 +1,20 @@
-//! This module implements the [`LuaMutator`], where each mutation drops into a Lua VM to mutate bytes in a target-specific way.
+//! This module implements the [`LuaMutator`], where each mutation drops into a Lua VM to mutate bytes in a target-specific way,
+//! and the [`LuaSerdeMutator`], which hands any serializable input to Lua as a table.
+//!
+//! A script evaluates either to a mutation `function (bytes, state)`, or to a table of callbacks:
+//!
+//! ```lua
+//! {
+//!   init = function (state) end,                          -- called once, when the script is loaded
+//!   mutate = function (input, state) return input end,    -- returns the mutated input
+//!   post_exec = function (interesting, state) end,        -- whether the last mutant was added to the corpus
+//!   generate = function (state) return input end,         -- for the `LuaGenerator`
+//!   is_interesting = function (input, exit_kind, state) return false end, -- for the `LuaFeedback`
+//! }
+//! ```
+//!
+//! `state` is a table that persists across all calls of a script.
+//! The [`Tokens`] of the fuzzer state are available to the mutators as the global `tokens`, a list of strings.
 #[cfg(feature = "std")]
 use alloc::boxed::Box;
 use alloc::{
@@ -13,15 +29,20 @@ use std::{fs, path::Path};
 
 #[cfg(all(feature = "lua_mutator", feature = "std"))]
 use libafl_bolts::rands::StdRand;
-use libafl_bolts::{Error, Named, rands::Rand};
-use mlua::{Function, HookTriggers, Lua, VmState, prelude::LuaError};
+use libafl_bolts::{Error, Named, generic_hash_std, rands::Rand};
+use mlua::{
+    FromLuaMulti, Function, HookTriggers, IntoLuaMulti, Lua, Table, Value as LuaValue, VmState,
+    prelude::LuaError,
+};
+use serde::{Serialize, de::DeserializeOwned};
+use serde_json::Value as JsonValue;
 
 use super::MutationResult;
 use crate::{
     HasMetadata,
     corpus::CorpusId,
     inputs::{HasMutatorBytes, ResizableMutator},
-    mutators::Mutator,
+    mutators::{Mutator, Tokens},
     state::{HasMaxSize, HasRand},
 };
 
@@ -77,19 +98,18 @@ pub fn load_lua_mutations<
 }
 
 /// Creates a new [`Lua`] VM, sets the seed using the provided rng state,
-/// creates a function from the provided string, and (optionally) executes it once.
-/// Return the function and a timeout tracker bool that you should set to `false` before running a function
+/// creates a table of callbacks from the provided string, and the persistent `state` table of the script.
+/// Return the callbacks, the state, and a timeout tracker bool that you should set to `false` before running a function
 /// Since the VM keeps counting, this bool is needed to know that we started a new execution.
 /// So, in practice, the timeout / instruction counter has to trigger twice to exit execution.
 /// The `timeout_steps_min` are the minimum amount of steps until execution quits.
 /// In practice, the amount of steps might be up to `2x` that value.
-fn create_lua_fn<S: HasRand>(
+fn create_lua_callbacks<S: HasRand>(
     lua: &Lua,
     state: &mut S,
-    mutator_lua_fn: &str,
+    lua_script: &str,
     timeout_steps_min: Option<u32>,
-    test: bool,
-) -> Result<(Function, Rc<Cell<bool>>), Error> {
+) -> Result<(Table, Table, Rc<Cell<bool>>), Error> {
     #[allow(clippy::cast_possible_truncation)] // we specifically want a u32
     let lua_seed = state.rand_mut().next() as u32;
 
@@ -112,51 +132,319 @@ fn create_lua_fn<S: HasRand>(
                     "Instruction limit reached!".to_string(),
                 ))
             } else {
-                timeouted_once_cb.set(false);
+                timeouted_once_cb.set(true);
                 Ok(VmState::Continue)
             }
         });
     }
 
-    let func = mutator_lua_fn.to_string();
-    let chunk = lua.load(&func);
-
-    let mutator: Function = chunk.eval().map_err(convert_error)?;
+    let callbacks = match lua.load(lua_script).eval().map_err(convert_error)? {
+        LuaValue::Function(mutate) => {
+            let callbacks = lua.create_table().map_err(convert_error)?;
+            callbacks.set("mutate", mutate).map_err(convert_error)?;
+            callbacks
+        }
+        LuaValue::Table(callbacks) => callbacks,
+        other => {
+            return Err(Error::illegal_argument(format!(
+                "A Lua script must evaluate to a function or a table of callbacks, not a {}",
+                other.type_name()
+            )));
+        }
+    };
+    let script_state = lua.create_table().map_err(convert_error)?;
 
-    // Simple test that the mutator works
-    if test {
-        let bytes = vec![1_u8, 2, 3, 4, 5, 6, 7, 8, 9];
-        drop(mutator.call::<Vec<u8>>((bytes,)).map_err(convert_error)?);
+    if let Some(init) = callbacks
+        .get::<Option<Function>>("init")
+        .map_err(convert_error)?
+    {
+        timeouted_once.set(false);
+        init.call::<()>(script_state.clone())
+            .map_err(convert_error)?;
     }
-    Ok((mutator, timeouted_once))
+    Ok((callbacks, script_state, timeouted_once))
 }
 
-/// Inserts a random token at a random position in the `Input`.
-pub struct LuaMutator {
+/// Converts a serialized input to a Lua value. Objects become tables with string keys, arrays become sequences.
+pub fn json_to_lua(lua: &Lua, value: &JsonValue) -> Result<LuaValue, Error> {
+    Ok(match value {
+        JsonValue::Null => LuaValue::Nil,
+        JsonValue::Bool(b) => LuaValue::Boolean(*b),
+        JsonValue::Number(n) => n.as_i64().map_or_else(
+            || LuaValue::Number(n.as_f64().unwrap_or_default()),
+            LuaValue::Integer,
+        ),
+        JsonValue::String(s) => LuaValue::String(lua.create_string(s).map_err(convert_error)?),
+        JsonValue::Array(items) => {
+            let table = lua
+                .create_table_with_capacity(items.len(), 0)
+                .map_err(convert_error)?;
+            for (i, item) in items.iter().enumerate() {
+                table
+                    .raw_set(i + 1, json_to_lua(lua, item)?)
+                    .map_err(convert_error)?;
+            }
+            LuaValue::Table(table)
+        }
+        JsonValue::Object(fields) => {
+            let table = lua
+                .create_table_with_capacity(0, fields.len())
+                .map_err(convert_error)?;
+            for (key, field) in fields {
+                table
+                    .raw_set(key.as_str(), json_to_lua(lua, field)?)
+                    .map_err(convert_error)?;
+            }
+            LuaValue::Table(table)
+        }
+    })
+}
+
+/// Converts a Lua value back to its serialized form, see [`json_to_lua`].
+///
+/// Tables with a sequence part become arrays, others become objects. Empty tables become empty arrays.
+pub fn lua_to_json(value: LuaValue) -> Result<JsonValue, Error> {
+    Ok(match value {
+        LuaValue::Nil => JsonValue::Null,
+        LuaValue::Boolean(b) => JsonValue::Bool(b),
+        LuaValue::Integer(i) => JsonValue::from(i),
+        LuaValue::Number(n) => {
+            serde_json::Number::from_f64(n).map_or(JsonValue::Null, JsonValue::Number)
+        }
+        LuaValue::String(s) => JsonValue::String(s.to_str().map_err(convert_error)?.to_string()),
+        LuaValue::Table(table) => {
+            let len = table.raw_len();
+            if len > 0 {
+                let mut items = Vec::with_capacity(len);
+                for i in 1..=len {
+                    items.push(lua_to_json(table.raw_get(i).map_err(convert_error)?)?);
+                }
+                JsonValue::Array(items)
+            } else {
+                let mut fields = serde_json::Map::new();
+                for pair in table.pairs::<LuaValue, LuaValue>() {
+                    let (key, field) = pair.map_err(convert_error)?;
+                    let key = match key {
+                        LuaValue::String(s) => s.to_str().map_err(convert_error)?.to_string(),
+                        LuaValue::Integer(i) => i.to_string(),
+                        other => {
+                            return Err(Error::illegal_argument(format!(
+                                "Lua table keys must be strings or integers, not {}",
+                                other.type_name()
+                            )));
+                        }
+                    };
+                    fields.insert(key, lua_to_json(field)?);
+                }
+                if fields.is_empty() {
+                    JsonValue::Array(Vec::new())
+                } else {
+                    JsonValue::Object(fields)
+                }
+            }
+        }
+        other => {
+            return Err(Error::illegal_argument(format!(
+                "Lua value of type {} can not be converted to an input",
+                other.type_name()
+            )));
+        }
+    })
+}
+
+/// Converts a Lua value back to a deserializable input, see [`lua_to_json`]
+pub fn lua_to_input<I>(value: LuaValue) -> Result<I, Error>
+where
+    I: DeserializeOwned,
+{
+    json_to_input(lua_to_json(value)?)
+}
+
+fn input_to_json<I>(input: &I) -> Result<JsonValue, Error>
+where
+    I: Serialize,
+{
+    serde_json::to_value(input)
+        .map_err(|e| Error::serialize(format!("Could not serialize the input for Lua: {e}")))
+}
+
+fn json_to_input<I>(json: JsonValue) -> Result<I, Error>
+where
+    I: DeserializeOwned,
+{
+    serde_json::from_value(json).map_err(|e| {
+        Error::serialize(format!(
+            "Could not deserialize the input returned by Lua: {e}"
+        ))
+    })
+}
+
+/// A loaded Lua script, with its callbacks, and the `state` table that persists across all of its calls.
+/// See the [module docs](self) for the callbacks.
+pub struct LuaScript {
     /// The Lua VM
-    #[allow(dead_code)] // We need to keep a handle around.
     lua: Lua,
-    /// The function string we loaded
-    func: String,
-    /// The actual lua function we can call
-    mutator: Function,
-    /// If we should get rid of errors
-    eat_errors: bool,
-    /// If this had an error
-    errored: bool,
+    /// The script we loaded
+    source: String,
+    /// The callbacks the script evaluated to
+    callbacks: Table,
+    /// The table that persists across calls
+    state: Table,
     /// If the timeout handler has been called at least once
     timeout_handler_called_once: Rc<Cell<bool>>,
+    /// The hash of the tokens we last handed to the script
+    tokens_hash: Option<u64>,
 }
 
-impl core::fmt::Debug for LuaMutator {
+impl core::fmt::Debug for LuaScript {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-        f.debug_struct("LuaMutator")
-            .field("func", &self.func)
-            .field("mutator", &self.mutator)
+        f.debug_struct("LuaScript")
+            .field("source", &self.source)
             .field(
                 "timeout_handler_called_once",
                 &self.timeout_handler_called_once,
             )
+            .field("tokens_hash", &self.tokens_hash)
+            .finish_non_exhaustive()
+    }
+}
+
+impl LuaScript {
+    /// Loads a Lua script, running its `init` callback, if any.
+    /// Will block if the script is an endless loop!
+    pub fn new<S: HasRand>(state: &mut S, lua_script: &str) -> Result<Self, Error> {
+        let lua = Lua::new();
+        let (callbacks, script_state, timeouted_once) =
+            create_lua_callbacks(&lua, state, lua_script, Some(DEFAULT_TIMEOUT_STEPS))?;
+        let mut script = Self {
+            lua,
+            source: lua_script.to_string(),
+            callbacks,
+            state: script_state,
+            timeout_handler_called_once: timeouted_once,
+            tokens_hash: None,
+        };
+        script.update_tokens(None)?;
+        Ok(script)
+    }
+
+    /// The Lua VM of this script
+    #[must_use]
+    pub fn lua(&self) -> &Lua {
+        &self.lua
+    }
+
+    /// The source of this script
+    #[must_use]
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// The `state` table that persists across all calls of this script
+    #[must_use]
+    pub fn state(&self) -> &Table {
+        &self.state
+    }
+
+    /// If the script defines the callback `name`
+    pub fn has_callback(&self, name: &str) -> Result<bool, Error> {
+        self.callbacks.contains_key(name).map_err(convert_error)
+    }
+
+    /// Calls the callback `name` with `args`, followed by the `state` table.
+    /// Returns `None` if the script does not define it.
+    pub fn call<A, R>(&self, name: &str, args: A) -> Result<Option<R>, Error>
+    where
+        A: IntoLuaMulti,
+        R: FromLuaMulti,
+    {
+        let Some(callback) = self
+            .callbacks
+            .get::<Option<Function>>(name)
+            .map_err(convert_error)?
+        else {
+            return Ok(None);
+        };
+        let mut args = args.into_lua_multi(&self.lua).map_err(convert_error)?;
+        args.push_back(LuaValue::Table(self.state.clone()));
+        self.timeout_handler_called_once.set(false);
+        callback
+            .call::<R>(args)
+            .map(Some)
+            .map_err(|err| Error::illegal_state(format!("Lua callback {name} failed: {err}")))
+    }
+
+    /// Hands the [`Tokens`] to the script as the global `tokens`, if they changed since the last call
+    pub fn update_tokens(&mut self, tokens: Option<&Tokens>) -> Result<(), Error> {
+        let tokens = tokens.map_or(&[][..], Tokens::tokens);
+        let hash = generic_hash_std(&tokens);
+        if self.tokens_hash == Some(hash) {
+            return Ok(());
+        }
+        let table = self
+            .lua
+            .create_table_with_capacity(tokens.len(), 0)
+            .map_err(convert_error)?;
+        for (i, token) in tokens.iter().enumerate() {
+            table
+                .raw_set(i + 1, self.lua.create_string(token).map_err(convert_error)?)
+                .map_err(convert_error)?;
+        }
+        self.lua
+            .globals()
+            .set("tokens", table)
+            .map_err(convert_error)?;
+        self.tokens_hash = Some(hash);
+        Ok(())
+    }
+
+    /// Converts a serializable input to a Lua value
+    pub fn input_to_lua<I>(&self, input: &I) -> Result<LuaValue, Error>
+    where
+        I: Serialize,
+    {
+        json_to_lua(&self.lua, &input_to_json(input)?)
+    }
+}
+
+/// Calls the `post_exec` callback of the script, if any, with whether the last mutant was interesting
+fn lua_post_exec(script: &LuaScript, new_corpus_id: Option<CorpusId>) -> Result<(), Error> {
+    script
+        .call::<_, ()>("post_exec", new_corpus_id.is_some())
+        .map(|_| ())
+}
+
+/// Turns errors into skipped mutations, if the mutator eats errors
+fn eat_error(
+    script: &LuaScript,
+    eat_errors: bool,
+    errored: &mut bool,
+    result: Result<MutationResult, Error>,
+) -> Result<MutationResult, Error> {
+    match result {
+        Err(err) if eat_errors => {
+            log::debug!("Mutation Errored: {err} in {}", script.source());
+            *errored = true;
+            Ok(MutationResult::Skipped)
+        }
+        result => result,
+    }
+}
+
+/// Mutates bytes inputs in Lua, as a list of integers.
+pub struct LuaMutator {
+    /// The loaded script
+    script: LuaScript,
+    /// If we should get rid of errors
+    eat_errors: bool,
+    /// If this had an error
+    errored: bool,
+}
+
+impl core::fmt::Debug for LuaMutator {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("LuaMutator")
+            .field("script", &self.script)
             .field("eat_errors", &self.eat_errors)
             .field("errored", &self.errored)
             .finish_non_exhaustive()
@@ -168,46 +456,45 @@ impl LuaMutator {
     /// Will block if the mutator is an endless loop!
     #[allow(unused)]
     pub fn new<S: HasRand>(state: &mut S, mutator_lua_fn: &str) -> Result<Self, Error> {
-        let lua = Lua::new();
-        let func = mutator_lua_fn.to_string();
-        let (mutator, timeouted_once) = create_lua_fn(
-            &lua,
-            state,
-            mutator_lua_fn,
-            Some(DEFAULT_TIMEOUT_STEPS),
-            true,
-        )?;
-        Ok(Self {
-            lua,
-            func,
-            mutator,
-            timeout_handler_called_once: timeouted_once,
-            eat_errors: false,
-            errored: false,
-        })
+        Self::with_eat_errors(state, mutator_lua_fn, false)
     }
 
     /// Creates a new lua mutator, will call the mutator with a random bytes sequence to make sure it's not crashing.
     /// Will block if the mutator is an endless loop!
     pub fn eat_errors<S: HasRand>(state: &mut S, mutator_lua_fn: &str) -> Result<Self, Error> {
-        let lua = Lua::new();
-        let func = mutator_lua_fn.to_string();
-        let (mutator, timeouted_once) = create_lua_fn(
-            &lua,
-            state,
-            mutator_lua_fn,
-            Some(DEFAULT_TIMEOUT_STEPS),
-            true,
-        )?;
+        Self::with_eat_errors(state, mutator_lua_fn, true)
+    }
+
+    fn with_eat_errors<S: HasRand>(
+        state: &mut S,
+        mutator_lua_fn: &str,
+        eat_errors: bool,
+    ) -> Result<Self, Error> {
+        let script = LuaScript::new(state, mutator_lua_fn)?;
+        if !script.has_callback("mutate")? {
+            return Err(Error::illegal_argument(
+                "A Lua mutator needs a mutate callback",
+            ));
+        }
+        // Simple test that the mutator works
+        let bytes = vec![1_u8, 2, 3, 4, 5, 6, 7, 8, 9];
+        drop(
+            script
+                .call::<_, Vec<u8>>("mutate", bytes)
+                .map_err(|err| Error::illegal_argument(format!("{err}")))?,
+        );
         Ok(Self {
-            lua,
-            func,
-            mutator,
-            timeout_handler_called_once: timeouted_once,
-            eat_errors: true,
+            script,
+            eat_errors,
             errored: false,
         })
     }
+
+    /// The loaded script
+    #[must_use]
+    pub fn script(&self) -> &LuaScript {
+        &self.script
+    }
 }
 
 impl<I, S> Mutator<I, S> for LuaMutator
@@ -215,37 +502,28 @@ where
     S: HasMetadata + HasRand + HasMaxSize,
     I: HasMutatorBytes + ResizableMutator<u8>,
 {
-    fn mutate(&mut self, _state: &mut S, input: &mut I) -> Result<MutationResult, Error> {
-        self.timeout_handler_called_once.set(false);
-        let bytes = input.mutator_bytes().to_vec();
-        let result = match self.mutator.call::<Vec<u8>>(bytes) {
-            Err(err) => Err(Error::illegal_state(format!("Lua mutation failed: {err}"))),
-            Ok(mutated) => {
-                if mutated.eq(input.mutator_bytes()) {
-                    Ok(MutationResult::Skipped)
-                } else {
+    fn mutate(&mut self, state: &mut S, input: &mut I) -> Result<MutationResult, Error> {
+        let result = self
+            .script
+            .update_tokens(state.metadata::<Tokens>().ok())
+            .and_then(|()| {
+                let bytes = input.mutator_bytes().to_vec();
+                self.script.call::<_, Vec<u8>>("mutate", bytes)
+            })
+            .map(|mutated| match mutated {
+                Some(mutated) if !mutated.eq(input.mutator_bytes()) => {
                     input.resize(mutated.len(), 0);
                     input.mutator_bytes_mut().clone_from_slice(&mutated);
-                    Ok(MutationResult::Mutated)
+                    MutationResult::Mutated
                 }
-            }
-        };
-        if self.eat_errors {
-            log::debug!("Mutation Errored: {}", &self.func);
-            self.errored = true;
-            if result.is_err() {
-                Ok(MutationResult::Skipped)
-            } else {
-                result
-            }
-        } else {
-            result
-        }
+                _ => MutationResult::Skipped,
+            });
+        eat_error(&self.script, self.eat_errors, &mut self.errored, result)
     }
 
     #[inline]
-    fn post_exec(&mut self, _state: &mut S, _new_corpus_id: Option<CorpusId>) -> Result<(), Error> {
-        Ok(())
+    fn post_exec(&mut self, _state: &mut S, new_corpus_id: Option<CorpusId>) -> Result<(), Error> {
+        lua_post_exec(&self.script, new_corpus_id)
     }
 }
 
@@ -255,16 +533,117 @@ impl Named for LuaMutator {
     }
 }
 
+/// Mutates any serializable input in Lua, such as a `ValueInput`, `ListInput` or `MultipartInput`.
+///
+/// The input is handed to the `mutate` callback in its serialized form, converted to Lua values,
+/// see [`json_to_lua`], and the returned value is deserialized into the mutated input.
+pub struct LuaSerdeMutator {
+    /// The loaded script
+    script: LuaScript,
+    /// If we should get rid of errors
+    eat_errors: bool,
+    /// If this had an error
+    errored: bool,
+}
+
+impl core::fmt::Debug for LuaSerdeMutator {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("LuaSerdeMutator")
+            .field("script", &self.script)
+            .field("eat_errors", &self.eat_errors)
+            .field("errored", &self.errored)
+            .finish_non_exhaustive()
+    }
+}
+
+impl LuaSerdeMutator {
+    /// Creates a new lua mutator for serializable inputs.
+    /// Will block if the script is an endless loop!
+    pub fn new<S: HasRand>(state: &mut S, mutator_lua_script: &str) -> Result<Self, Error> {
+        let script = LuaScript::new(state, mutator_lua_script)?;
+        if !script.has_callback("mutate")? {
+            return Err(Error::illegal_argument(
+                "A Lua mutator needs a mutate callback",
+            ));
+        }
+        Ok(Self {
+            script,
+            eat_errors: false,
+            errored: false,
+        })
+    }
+
+    /// Turns the errors of the script into skipped mutations
+    #[must_use]
+    pub fn eat_errors(mut self) -> Self {
+        self.eat_errors = true;
+        self
+    }
+
+    /// The loaded script
+    #[must_use]
+    pub fn script(&self) -> &LuaScript {
+        &self.script
+    }
+}
+
+impl<I, S> Mutator<I, S> for LuaSerdeMutator
+where
+    S: HasMetadata,
+    I: Serialize + DeserializeOwned,
+{
+    fn mutate(&mut self, state: &mut S, input: &mut I) -> Result<MutationResult, Error> {
+        let result = self
+            .script
+            .update_tokens(state.metadata::<Tokens>().ok())
+            .and_then(|()| {
+                let original = input_to_json(&*input)?;
+                let value = json_to_lua(self.script.lua(), &original)?;
+                let mutated = self.script.call::<_, LuaValue>("mutate", value)?;
+                Ok((original, mutated))
+            })
+            .and_then(|(original, mutated)| match mutated {
+                Some(LuaValue::Nil) | None => Ok(MutationResult::Skipped),
+                Some(mutated) => {
+                    let mutated = lua_to_json(mutated)?;
+                    if mutated == original {
+                        return Ok(MutationResult::Skipped);
+                    }
+                    *input = json_to_input(mutated)?;
+                    Ok(MutationResult::Mutated)
+                }
+            });
+        eat_error(&self.script, self.eat_errors, &mut self.errored, result)
+    }
+
+    #[inline]
+    fn post_exec(&mut self, _state: &mut S, new_corpus_id: Option<CorpusId>) -> Result<(), Error> {
+        lua_post_exec(&self.script, new_corpus_id)
+    }
+}
+
+impl Named for LuaSerdeMutator {
+    fn name(&self) -> &Cow<'static, str> {
+        &Cow::Borrowed("LuaSerdeMutator")
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use alloc::string::{String, ToString};
     #[cfg(feature = "std")]
     use std::println;
 
     use libafl_bolts::Error;
 
     use crate::{
-        inputs::BytesInput,
-        mutators::{MutationResult, Mutator, lua::LuaMutator},
+        HasMetadata,
+        corpus::CorpusId,
+        inputs::{BytesInput, ValueInput},
+        mutators::{
+            MutationResult, Mutator, Tokens,
+            lua::{LuaMutator, LuaSerdeMutator},
+        },
         state::NopState,
     };
 
@@ -319,4 +698,78 @@ mod tests {
             "Expected endless loop to raise an 'IllegalArgument' error!"
         );
     }
+
+    #[test]
+    fn test_stateful_script() {
+        let mut state: NopState<BytesInput> = NopState::new();
+        state.add_metadata(Tokens::from([b"XY".to_vec()]));
+
+        let mut lua_mutator = LuaMutator::new(
+            &mut state,
+            r"{
+              init = function (state) state.interesting = 0 end,
+              mutate = function (bytes, state)
+                local token = tokens[1] or 'A'
+                bytes[#bytes + 1] = string.byte(token, 1) + state.interesting
+                return bytes
+              end,
+              post_exec = function (interesting, state)
+                if interesting then state.interesting = state.interesting + 1 end
+              end,
+            }",
+        )
+        .unwrap();
+
+        let mut input = BytesInput::new(vec![]);
+        lua_mutator.mutate(&mut state, &mut input).unwrap();
+        Mutator::<BytesInput, _>::post_exec(&mut lua_mutator, &mut state, Some(CorpusId(0)))
+            .unwrap();
+        lua_mutator.mutate(&mut state, &mut input).unwrap();
+        assert_eq!(input.into_inner(), b"XY".to_vec());
+    }
+
+    #[test]
+    fn test_tokens_update() {
+        let mut state: NopState<BytesInput> = NopState::new();
+        state.add_metadata(Tokens::from([b"A".to_vec()]));
+
+        let mut lua_mutator = LuaMutator::new(
+            &mut state,
+            r"function (bytes)
+                bytes[1] = string.byte(tokens[1] or '?', 1)
+                return bytes
+              end",
+        )
+        .unwrap();
+
+        let mut input = BytesInput::new(vec![0]);
+        lua_mutator.mutate(&mut state, &mut input).unwrap();
+        assert_eq!(input.as_ref(), b"A");
+        // As many tokens as before, but different ones
+        state.add_metadata(Tokens::from([b"B".to_vec()]));
+        lua_mutator.mutate(&mut state, &mut input).unwrap();
+        assert_eq!(input.as_ref(), b"B");
+    }
+
+    #[test]
+    fn test_serde_mutator() {
+        let mut state: NopState<ValueInput<(u64, String)>> = NopState::new();
+
+        let mut lua_mutator = LuaSerdeMutator::new(
+            &mut state,
+            r"{
+              mutate = function (input)
+                return { input[1] + 1, input[2] .. '!' }
+              end,
+            }",
+        )
+        .unwrap();
+
+        let mut input = ValueInput::new((41, "hi".to_string()));
+        assert_eq!(
+            lua_mutator.mutate(&mut state, &mut input).unwrap(),
+            MutationResult::Mutated
+        );
+        assert_eq!(input.into_inner(), (42, "hi!".to_string()));
+    }
 }
@@ -15,6 +15,10 @@ pub mod havoc_mutations;
 pub use havoc_mutations::*;
 pub mod numeric;
 pub use numeric::{int_mutators, mapped_int_mutators};
+pub mod structured;
+pub use structured::{
+    StructuredCrossoverMutator, StructuredField, StructuredFieldMutator, StructuredInput,
+};
 pub mod encoded_mutations;
 pub use encoded_mutations::*;
 pub mod mopt_mutator;
@@ -25,12 +29,28 @@ pub mod grimoire;
 pub use grimoire::*;
 pub mod mapping;
 pub use mapping::*;
+pub mod attribution;
+pub use attribution::{
+    AttributedMutator, AttributedScheduledMutator, AttributionMetadata, ToAttributedMutator,
+    YieldStats,
+};
 pub mod tuneable;
 pub use tuneable::*;
 
 #[cfg(feature = "lua_mutator")]
 pub mod lua;
 
+#[cfg(feature = "python")]
+pub mod python;
+
+#[cfg(all(unix, feature = "std"))]
+pub mod afl_custom;
+
+#[cfg(feature = "arbitrary_input")]
+pub mod arbitrary;
+#[cfg(feature = "arbitrary_input")]
+pub use self::arbitrary::ArbitraryMutator;
+
 #[cfg(feature = "std")]
 pub mod hash;
 #[cfg(feature = "std")]
@@ -0,0 +1,398 @@
+//! Mutators implemented in Python, following the [AFL++ Python custom mutator API](https://aflplus.plus/docs/custom_mutators/#python).
+//!
+//! A [`PythonModule`] is a Python module with some of these functions:
+//!
+//! ```python
+//! def init(seed): ...                           # called once, when the module is loaded
+//! def fuzz(buf, add_buf, max_size): return buf  # mutates the bytearray `buf`, `add_buf` is another corpus entry to splice with
+//! def describe(max_description_length): ...    # a description of the last mutation
+//! def post_process(buf): return buf            # the bytes actually sent to the target
+//! def is_interesting(buf, exit_kind): ...       # for the `PythonFeedback`
+//! def deinit(): ...                             # called once, when the module is dropped
+//! ```
+//!
+//! The [`PythonMutator`] runs `fuzz`, the [`PythonPostProcess`] runs `post_process` as the target bytes converter of the fuzzer,
+//! and the [`crate::feedbacks::PythonFeedback`] runs `is_interesting`.
+//! The interpreter is embedded, and its global interpreter lock is taken for each call,
+//! so the [`PythonMutator`] can compute a batch of mutants at once, see [`PythonMutator::with_batch_size`].
+
+use alloc::{borrow::Cow, ffi::CString, format, rc::Rc, string::String, vec::Vec};
+
+use libafl_bolts::{Error, Named, generic_hash_std, ownedref::OwnedSlice, rands::Rand};
+use pyo3::{
+    prelude::*,
+    types::{PyByteArray, PyBytes, PyModule},
+};
+
+use crate::{
+    corpus::{Corpus, CorpusId},
+    inputs::{HasMutatorBytes, HasTargetBytes, ResizableMutator, ToTargetBytes},
+    mutators::{MutationResult, Mutator},
+    random_corpus_id_with_disabled,
+    state::{HasCorpus, HasMaxSize, HasRand},
+};
+
+/// The environment variable naming the Python module to load, as in AFL++
+pub const AFL_PYTHON_MODULE_ENV: &str = "AFL_PYTHON_MODULE";
+
+/// The longest description of a mutation we ask `describe` for
+const MAX_DESCRIPTION_LEN: usize = 64;
+
+/// Copies the bytes a Python function returned, as `bytes` or `bytearray`
+fn extract_bytes(value: &Bound<'_, PyAny>) -> Result<Vec<u8>, Error> {
+    if let Ok(bytes) = value.downcast::<PyBytes>() {
+        Ok(bytes.as_bytes().to_vec())
+    } else if let Ok(bytes) = value.downcast::<PyByteArray>() {
+        Ok(bytes.to_vec())
+    } else {
+        Err(Error::illegal_argument(format!(
+            "Expected bytes or bytearray from Python, got {value}"
+        )))
+    }
+}
+
+/// A loaded Python module, see the [module docs](self)
+#[derive(Debug)]
+pub struct PythonModule {
+    name: String,
+    module: Py<PyModule>,
+}
+
+impl PythonModule {
+    /// Imports the module `name` from the Python path, and calls its `init` with `seed`
+    pub fn import(name: &str, seed: u64) -> Result<Self, Error> {
+        let module = Python::with_gil(|py| -> Result<_, Error> {
+            Ok(PyModule::import(py, name)?.unbind())
+        })?;
+        Self::init(name, module, seed)
+    }
+
+    /// Loads the module `name` from its source `code`, and calls its `init` with `seed`
+    pub fn from_code(name: &str, code: &str, seed: u64) -> Result<Self, Error> {
+        let c_code = CString::new(code)
+            .map_err(|_| Error::illegal_argument("Python code contains a NUL byte"))?;
+        let c_name = CString::new(name)
+            .map_err(|_| Error::illegal_argument("Python module name contains a NUL byte"))?;
+        let file_name = CString::new(format!("{name}.py")).unwrap();
+        let module = Python::with_gil(|py| -> Result<_, Error> {
+            Ok(PyModule::from_code(py, &c_code, &file_name, &c_name)?.unbind())
+        })?;
+        Self::init(name, module, seed)
+    }
+
+    /// Imports the module named by [`AFL_PYTHON_MODULE_ENV`], if set
+    pub fn from_env(seed: u64) -> Result<Option<Self>, Error> {
+        std::env::var(AFL_PYTHON_MODULE_ENV)
+            .ok()
+            .map(|name| Self::import(&name, seed))
+            .transpose()
+    }
+
+    fn init(name: &str, module: Py<PyModule>, seed: u64) -> Result<Self, Error> {
+        let module = Self {
+            name: name.into(),
+            module,
+        };
+        if module.has_function("init")? {
+            module.with_module(|module| {
+                module.call_method1("init", (seed,))?;
+                Ok(())
+            })?;
+        }
+        Ok(module)
+    }
+
+    /// The name of the module
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// If the module has a function `name`
+    pub fn has_function(&self, name: &str) -> Result<bool, Error> {
+        Ok(Python::with_gil(|py| self.module.bind(py).hasattr(name))?)
+    }
+
+    /// Runs `f` with the module, holding the global interpreter lock
+    pub fn with_module<F, R>(&self, f: F) -> Result<R, Error>
+    where
+        F: for<'py> FnOnce(&Bound<'py, PyModule>) -> Result<R, Error>,
+    {
+        Python::with_gil(|py| f(self.module.bind(py)))
+    }
+
+    /// Calls `fuzz`, with the global interpreter lock held by the caller
+    fn fuzz(
+        module: &Bound<'_, PyModule>,
+        buf: &[u8],
+        add_buf: &[u8],
+        max_size: usize,
+    ) -> Result<Vec<u8>, Error> {
+        let py = module.py();
+        let mut mutated = extract_bytes(&module.call_method1(
+            "fuzz",
+            (
+                PyByteArray::new(py, buf),
+                PyByteArray::new(py, add_buf),
+                max_size,
+            ),
+        )?)?;
+        mutated.truncate(max_size);
+        Ok(mutated)
+    }
+
+    /// The description of the last mutation, if the module has `describe`
+    pub fn describe(&self) -> Result<Option<String>, Error> {
+        if !self.has_function("describe")? {
+            return Ok(None);
+        }
+        self.with_module(|module| {
+            Ok(Some(
+                module
+                    .call_method1("describe", (MAX_DESCRIPTION_LEN,))?
+                    .extract()?,
+            ))
+        })
+    }
+
+    /// Post-processes the bytes sent to the target with `post_process`
+    pub fn post_process(&self, buf: &[u8]) -> Result<Vec<u8>, Error> {
+        self.with_module(|module| {
+            let py = module.py();
+            extract_bytes(&module.call_method1("post_process", (PyByteArray::new(py, buf),))?)
+        })
+    }
+}
+
+impl Drop for PythonModule {
+    fn drop(&mut self) {
+        Python::with_gil(|py| {
+            let module = self.module.bind(py);
+            if module.hasattr("deinit").unwrap_or(false) {
+                if let Err(err) = module.call_method0("deinit") {
+                    log::error!("deinit of Python module {} failed: {err}", self.name);
+                }
+            }
+        });
+    }
+}
+
+/// Mutates inputs with the `fuzz` function of a [`PythonModule`], splicing with a random corpus entry.
+///
+/// With a batch size above 1, one call takes the global interpreter lock once to compute that many mutants
+/// of the same input, and the following calls for that input hand them out.
+#[derive(Debug)]
+pub struct PythonMutator {
+    module: Rc<PythonModule>,
+    name: Cow<'static, str>,
+    batch_size: usize,
+    /// The hash of the input the batch was computed for
+    batch_input: u64,
+    batch: Vec<Vec<u8>>,
+}
+
+impl PythonMutator {
+    /// Creates a new [`PythonMutator`], the module must have a `fuzz` function
+    pub fn new(module: Rc<PythonModule>) -> Result<Self, Error> {
+        if !module.has_function("fuzz")? {
+            return Err(Error::illegal_argument(format!(
+                "The Python module {} has no fuzz function",
+                module.name()
+            )));
+        }
+        let name = Cow::from(format!("PythonMutator[{}]", module.name()));
+        Ok(Self {
+            module,
+            name,
+            batch_size: 1,
+            batch_input: 0,
+            batch: vec![],
+        })
+    }
+
+    /// Computes `batch_size` mutants of an input per call into Python
+    #[must_use]
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// The description of the last mutation, if the module has `describe`.
+    /// With batches, this is the last mutation of the batch.
+    pub fn describe(&self) -> Result<Option<String>, Error> {
+        self.module.describe()
+    }
+}
+
+impl Named for PythonMutator {
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+impl<I, S> Mutator<I, S> for PythonMutator
+where
+    I: HasMutatorBytes + ResizableMutator<u8>,
+    S: HasCorpus<I> + HasRand + HasMaxSize,
+{
+    fn mutate(&mut self, state: &mut S, input: &mut I) -> Result<MutationResult, Error> {
+        let input_hash = generic_hash_std(&input.mutator_bytes());
+        if input_hash != self.batch_input {
+            self.batch.clear();
+            self.batch_input = input_hash;
+        }
+        if self.batch.is_empty() {
+            let add_buf = if state.corpus().count() > 1 {
+                let id = random_corpus_id_with_disabled!(state.corpus(), state.rand_mut());
+                let mut testcase = state.corpus().get_from_all(id)?.borrow_mut();
+                testcase
+                    .load_input(state.corpus())?
+                    .mutator_bytes()
+                    .to_vec()
+            } else {
+                vec![]
+            };
+            let max_size = state.max_size();
+            let buf = input.mutator_bytes();
+            self.batch = self.module.with_module(|module| {
+                (0..self.batch_size)
+                    .map(|_| PythonModule::fuzz(module, buf, &add_buf, max_size))
+                    .collect()
+            })?;
+        }
+
+        let Some(mutated) = self.batch.pop() else {
+            return Ok(MutationResult::Skipped);
+        };
+        if mutated.is_empty() || mutated == input.mutator_bytes() {
+            return Ok(MutationResult::Skipped);
+        }
+        input.resize(mutated.len(), 0);
+        input.mutator_bytes_mut().copy_from_slice(&mutated);
+        Ok(MutationResult::Mutated)
+    }
+
+    #[inline]
+    fn post_exec(&mut self, _state: &mut S, _new_corpus_id: Option<CorpusId>) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+/// Post-processes the bytes sent to the target with the `post_process` function of a [`PythonModule`].
+/// Use it as the target bytes converter of the fuzzer.
+/// If `post_process` raises, the error is logged and the input is sent unprocessed.
+#[derive(Debug, Clone)]
+pub struct PythonPostProcess {
+    module: Rc<PythonModule>,
+}
+
+impl PythonPostProcess {
+    /// Creates a new [`PythonPostProcess`], the module must have a `post_process` function
+    pub fn new(module: Rc<PythonModule>) -> Result<Self, Error> {
+        if !module.has_function("post_process")? {
+            return Err(Error::illegal_argument(format!(
+                "The Python module {} has no post_process function",
+                module.name()
+            )));
+        }
+        Ok(Self { module })
+    }
+}
+
+impl<I> ToTargetBytes<I> for PythonPostProcess
+where
+    I: HasTargetBytes,
+{
+    fn to_target_bytes<'a>(&mut self, input: &'a I) -> OwnedSlice<'a, u8> {
+        let bytes = input.target_bytes();
+        match self.module.post_process(&bytes) {
+            Ok(processed) => OwnedSlice::from(processed),
+            Err(err) => {
+                log::error!(
+                    "post_process of the Python module {} failed, using the unprocessed input: {err}",
+                    self.module.name()
+                );
+                bytes
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::rc::Rc;
+
+    use libafl_bolts::rands::StdRand;
+    use pyo3::types::PyAnyMethods;
+
+    use super::{PythonModule, PythonMutator, PythonPostProcess};
+    use crate::{
+        corpus::InMemoryCorpus,
+        feedbacks::ConstFeedback,
+        inputs::{BytesInput, HasMutatorBytes, ToTargetBytes},
+        mutators::{MutationResult, Mutator},
+        state::StdState,
+    };
+
+    const MODULE: &str = r"
+calls = 0
+
+def fuzz(buf, add_buf, max_size):
+    global calls
+    calls += 1
+    buf.append(calls)
+    return buf
+
+def post_process(buf):
+    return bytes(buf).upper()
+";
+
+    #[test]
+    fn test_python_mutator() {
+        let mut feedback = ConstFeedback::new(false);
+        let mut objective = ConstFeedback::new(false);
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+        let module = Rc::new(PythonModule::from_code("test_mutator", MODULE, 0).unwrap());
+        let mut mutator = PythonMutator::new(module.clone())
+            .unwrap()
+            .with_batch_size(3);
+
+        for _ in 0..3 {
+            let mut input = BytesInput::new(b"abc".to_vec());
+            assert_eq!(
+                mutator.mutate(&mut state, &mut input).unwrap(),
+                MutationResult::Mutated
+            );
+            assert_eq!(input.mutator_bytes().len(), 4);
+        }
+        // All three mutants came from one batch
+        let calls: u8 = module
+            .with_module(|module| Ok(module.getattr("calls")?.extract()?))
+            .unwrap();
+        assert_eq!(calls, 3);
+
+        let mut post_process = PythonPostProcess::new(module).unwrap();
+        let input = BytesInput::new(b"abc".to_vec());
+        assert_eq!(&*post_process.to_target_bytes(&input), b"ABC");
+    }
+
+    #[test]
+    fn test_python_post_process_error() {
+        let module = PythonModule::from_code(
+            "test_post_process_error",
+            "def post_process(buf):\n    raise ValueError('unprocessable')\n",
+            0,
+        )
+        .unwrap();
+        let mut post_process = PythonPostProcess::new(Rc::new(module)).unwrap();
+        let input = BytesInput::new(b"abc".to_vec());
+        assert_eq!(&*post_process.to_target_bytes(&input), b"abc");
+    }
+}
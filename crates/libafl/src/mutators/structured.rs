@@ -0,0 +1,425 @@
+//! Structure-aware mutators for typed records, in the spirit of libFuzzer's `Arbitrary`.
+//!
+//! A [`StructuredField`] knows how to mutate and generate values of its type,
+//! and a [`StructuredInput`] is a struct whose fields can be mutated and crossed over one at a time.
+//! Both are derived with `#[derive(StructuredInput)]` for the input struct, and `#[derive(StructuredField)]`
+//! for nested structs and enums. The input struct also gets a `structured_mutators()` tuple with one
+//! [`StructuredFieldMutator`] per field and a [`StructuredCrossoverMutator`].
+//! Generate initial inputs with the [`crate::generators::StructuredGenerator`].
+//!
+//! ```rust,ignore
+//! #[derive(Debug, Clone, Hash, Serialize, Deserialize, StructuredInput)]
+//! struct Request {
+//!     #[structured(dict = [b"GET", b"POST"])]
+//!     method: Vec<u8>,
+//!     #[structured(range = 1..=8)]
+//!     retries: u8,
+//!     #[structured(len = 0..=4)]
+//!     headers: Vec<Header>,
+//!     #[structured(skip)]
+//!     id: u64,
+//! }
+//! ```
+//!
+//! - `range` bounds integers, including the integers in vectors and options,
+//! - `len` bounds the length of vectors and strings,
+//! - `dict` lists values to assign to the field, now and then, instead of a mutation,
+//! - `skip` leaves the field alone, and generates it with [`Default`].
+
+use alloc::{borrow::Cow, string::String, vec::Vec};
+use core::marker::PhantomData;
+
+use libafl_bolts::{Error, Named, rands::Rand};
+
+use super::{MutationResult, Mutator, numeric::Numeric};
+use crate::{
+    corpus::{Corpus, CorpusId},
+    nonzero, random_corpus_id_with_disabled,
+    state::{HasCorpus, HasRand},
+};
+
+/// The probability to assign a value of the `dict` of a field, instead of mutating it
+pub const STRUCTURED_DICT_PROBABILITY: f64 = 0.25;
+/// The probability to switch an enum to a new random variant, instead of mutating a field of its variant
+pub const STRUCTURED_VARIANT_PROBABILITY: f64 = 0.25;
+/// The longest vectors and strings get without a `len` attribute
+pub const STRUCTURED_DEFAULT_MAX_LEN: usize = 16;
+
+/// The bounds a field attribute puts on the values of a [`StructuredField`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FieldSpec {
+    /// The inclusive bounds of integers
+    pub range: Option<(i128, i128)>,
+    /// The inclusive bounds of the length of vectors and strings
+    pub len: Option<(usize, usize)>,
+}
+
+impl FieldSpec {
+    /// The spec of a field without attributes
+    pub const DEFAULT: Self = Self {
+        range: None,
+        len: None,
+    };
+
+    /// The bounds of the length of vectors and strings
+    #[must_use]
+    pub fn len_bounds(&self) -> (usize, usize) {
+        self.len.unwrap_or((0, STRUCTURED_DEFAULT_MAX_LEN))
+    }
+}
+
+/// A value that can be mutated and generated field-aware, see the [module docs](self)
+pub trait StructuredField: Sized {
+    /// Mutates the value, within the bounds of `spec`
+    fn mutate_field<R: Rand>(&mut self, rand: &mut R, spec: &FieldSpec) -> MutationResult;
+
+    /// Generates a random value, within the bounds of `spec`
+    fn generate_field<R: Rand>(rand: &mut R, spec: &FieldSpec) -> Self;
+}
+
+/// A struct whose fields can be mutated and crossed over one at a time, see the [module docs](self)
+pub trait StructuredInput: StructuredField {
+    /// The names of the fields
+    fn field_names() -> &'static [&'static str];
+
+    /// Mutates the field number `field`
+    fn mutate_nth_field<R: Rand>(&mut self, rand: &mut R, field: usize) -> MutationResult;
+
+    /// Replaces the field number `field` with the one of `other`
+    fn crossover_nth_field(&mut self, other: &Self, field: usize) -> MutationResult;
+}
+
+/// A random integer in the inclusive range `lo..=hi`, which must fit a 64-bit integer
+#[allow(clippy::cast_possible_wrap)] // the remainder is below the span
+fn random_in_range<R: Rand>(rand: &mut R, lo: i128, hi: i128) -> i128 {
+    let span = (hi - lo).unsigned_abs() + 1;
+    lo + (u128::from(rand.next()) % span) as i128
+}
+
+macro_rules! impl_structured_field_for_int {
+    ($($t:ty)*) => ($(
+        impl StructuredField for $t {
+            #[allow(trivial_numeric_casts, clippy::cast_lossless, clippy::cast_possible_truncation, clippy::cast_possible_wrap, clippy::cast_sign_loss)]
+            fn mutate_field<R: Rand>(&mut self, rand: &mut R, spec: &FieldSpec) -> MutationResult {
+                let before = *self;
+                match rand.below(nonzero!(5)) {
+                    0 => self.flip_bit_at(rand.below(nonzero!(<$t>::BITS as usize))),
+                    1 => self.wrapping_inc(),
+                    2 => self.wrapping_dec(),
+                    3 => self.twos_complement(),
+                    _ => self.randomize(rand),
+                }
+                if let Some((lo, hi)) = spec.range {
+                    let (lo, hi) = (lo.max(<$t>::MIN as i128), hi.min(<$t>::MAX as i128));
+                    if lo <= hi && !(lo..=hi).contains(&(*self as i128)) {
+                        *self = random_in_range(rand, lo, hi) as $t;
+                    }
+                }
+                if *self == before {
+                    MutationResult::Skipped
+                } else {
+                    MutationResult::Mutated
+                }
+            }
+
+            #[allow(trivial_numeric_casts, clippy::cast_lossless, clippy::cast_possible_truncation, clippy::cast_possible_wrap, clippy::cast_sign_loss)]
+            fn generate_field<R: Rand>(rand: &mut R, spec: &FieldSpec) -> Self {
+                match spec.range {
+                    Some((lo, hi)) => {
+                        let (lo, hi) = (lo.max(<$t>::MIN as i128), hi.min(<$t>::MAX as i128));
+                        random_in_range(rand, lo, hi.max(lo)) as $t
+                    }
+                    None => rand.next() as $t,
+                }
+            }
+        }
+    )*)
+}
+
+impl_structured_field_for_int!( u8 u16 u32 u64 usize i8 i16 i32 i64 isize );
+
+impl StructuredField for bool {
+    fn mutate_field<R: Rand>(&mut self, _rand: &mut R, _spec: &FieldSpec) -> MutationResult {
+        *self = !*self;
+        MutationResult::Mutated
+    }
+
+    fn generate_field<R: Rand>(rand: &mut R, _spec: &FieldSpec) -> Self {
+        rand.coinflip(0.5)
+    }
+}
+
+impl<T> StructuredField for Vec<T>
+where
+    T: StructuredField,
+{
+    fn mutate_field<R: Rand>(&mut self, rand: &mut R, spec: &FieldSpec) -> MutationResult {
+        let (min, max) = spec.len_bounds();
+        match rand.below(nonzero!(3)) {
+            0 if self.len() < max => {
+                let idx = rand.below_or_zero(self.len() + 1);
+                self.insert(idx, T::generate_field(rand, spec));
+                MutationResult::Mutated
+            }
+            1 if self.len() > min => {
+                let idx = rand.below_or_zero(self.len());
+                self.remove(idx);
+                MutationResult::Mutated
+            }
+            _ if !self.is_empty() => {
+                let idx = rand.below_or_zero(self.len());
+                self[idx].mutate_field(rand, spec)
+            }
+            _ if self.len() < max => {
+                self.push(T::generate_field(rand, spec));
+                MutationResult::Mutated
+            }
+            _ => MutationResult::Skipped,
+        }
+    }
+
+    fn generate_field<R: Rand>(rand: &mut R, spec: &FieldSpec) -> Self {
+        let (min, max) = spec.len_bounds();
+        let len = rand.between(min, max.max(min));
+        (0..len).map(|_| T::generate_field(rand, spec)).collect()
+    }
+}
+
+impl StructuredField for String {
+    /// Strings are mutated as bytes, and kept ASCII
+    fn mutate_field<R: Rand>(&mut self, rand: &mut R, spec: &FieldSpec) -> MutationResult {
+        let mut bytes = core::mem::take(self).into_bytes();
+        let result = bytes.mutate_field(rand, spec);
+        *self = bytes.iter().map(|b| char::from(b & 0x7f)).collect();
+        result
+    }
+
+    fn generate_field<R: Rand>(rand: &mut R, spec: &FieldSpec) -> Self {
+        Vec::<u8>::generate_field(rand, spec)
+            .iter()
+            .map(|b| char::from(b & 0x7f))
+            .collect()
+    }
+}
+
+impl<T> StructuredField for Option<T>
+where
+    T: StructuredField,
+{
+    fn mutate_field<R: Rand>(&mut self, rand: &mut R, spec: &FieldSpec) -> MutationResult {
+        match self {
+            None => *self = Some(T::generate_field(rand, spec)),
+            Some(_) if rand.coinflip(0.1) => *self = None,
+            Some(value) => return value.mutate_field(rand, spec),
+        }
+        MutationResult::Mutated
+    }
+
+    fn generate_field<R: Rand>(rand: &mut R, spec: &FieldSpec) -> Self {
+        rand.coinflip(0.5).then(|| T::generate_field(rand, spec))
+    }
+}
+
+/// Mutates one field of a [`StructuredInput`], see the [module docs](self)
+#[derive(Debug)]
+pub struct StructuredFieldMutator<I> {
+    field: usize,
+    name: Cow<'static, str>,
+    phantom: PhantomData<fn() -> I>,
+}
+
+impl<I> StructuredFieldMutator<I> {
+    /// Creates a new [`StructuredFieldMutator`] for the field number `field`, named `name`
+    pub fn new<N>(field: usize, name: N) -> Self
+    where
+        N: Into<Cow<'static, str>>,
+    {
+        Self {
+            field,
+            name: name.into(),
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<I> Named for StructuredFieldMutator<I> {
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+impl<I, S> Mutator<I, S> for StructuredFieldMutator<I>
+where
+    I: StructuredInput,
+    S: HasRand,
+{
+    fn mutate(&mut self, state: &mut S, input: &mut I) -> Result<MutationResult, Error> {
+        Ok(input.mutate_nth_field(state.rand_mut(), self.field))
+    }
+
+    #[inline]
+    fn post_exec(&mut self, _state: &mut S, _new_corpus_id: Option<CorpusId>) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+/// Replaces a random field of a [`StructuredInput`] with the one of a random corpus entry
+#[derive(Debug)]
+pub struct StructuredCrossoverMutator<I> {
+    phantom: PhantomData<fn() -> I>,
+}
+
+impl<I> StructuredCrossoverMutator<I> {
+    /// Creates a new [`StructuredCrossoverMutator`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<I> Default for StructuredCrossoverMutator<I> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<I> Named for StructuredCrossoverMutator<I> {
+    fn name(&self) -> &Cow<'static, str> {
+        static NAME: Cow<'static, str> = Cow::Borrowed("StructuredCrossoverMutator");
+        &NAME
+    }
+}
+
+impl<I, S> Mutator<I, S> for StructuredCrossoverMutator<I>
+where
+    I: StructuredInput,
+    S: HasCorpus<I> + HasRand,
+{
+    fn mutate(&mut self, state: &mut S, input: &mut I) -> Result<MutationResult, Error> {
+        if state.corpus().count() == 0 {
+            return Ok(MutationResult::Skipped);
+        }
+        let Some(field) = state.rand_mut().choose(0..I::field_names().len()) else {
+            return Ok(MutationResult::Skipped);
+        };
+        let id = random_corpus_id_with_disabled!(state.corpus(), state.rand_mut());
+        // We don't want to cross over with the testcase we're already using
+        if let Some(cur) = state.corpus().current() {
+            if id == *cur {
+                return Ok(MutationResult::Skipped);
+            }
+        }
+        let mut testcase = state.corpus().get_from_all(id)?.borrow_mut();
+        let other = testcase.load_input(state.corpus())?;
+        Ok(input.crossover_nth_field(other, field))
+    }
+
+    #[inline]
+    fn post_exec(&mut self, _state: &mut S, _new_corpus_id: Option<CorpusId>) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "derive"))]
+mod tests {
+    use alloc::{string::String, vec::Vec};
+
+    use libafl_bolts::rands::StdRand;
+    use serde::{Deserialize, Serialize};
+
+    use super::{FieldSpec, StructuredField, StructuredInput};
+    // The derives refer to `libafl`
+    use crate as libafl;
+    use crate::{
+        StructuredField, StructuredInput,
+        corpus::{Corpus, InMemoryCorpus, Testcase},
+        feedbacks::ConstFeedback,
+        inputs::Input,
+        mutators::{MutationResult, MutatorsTuple},
+        state::{HasCorpus, StdState},
+    };
+
+    #[derive(Debug, Clone, Hash, Serialize, Deserialize, StructuredField)]
+    enum Header {
+        Close,
+        Length(#[structured(range = 0..100)] u32),
+        Custom { name: String, value: Vec<u8> },
+    }
+
+    #[derive(Debug, Clone, Hash, Serialize, Deserialize, StructuredInput)]
+    struct Request {
+        #[structured(dict = [b"GET", b"POST"], len = 3..=4)]
+        method: Vec<u8>,
+        #[structured(range = 1..=8)]
+        retries: u8,
+        #[structured(len = 0..=4)]
+        headers: Vec<Header>,
+        keep_alive: Option<bool>,
+        #[structured(skip)]
+        id: u64,
+    }
+
+    impl Input for Request {}
+
+    #[test]
+    fn test_derive_structured_input() {
+        assert_eq!(
+            Request::field_names(),
+            ["method", "retries", "headers", "keep_alive"]
+        );
+        let mut rand = StdRand::with_seed(0);
+        let mut feedback = ConstFeedback::new(false);
+        let mut objective = ConstFeedback::new(false);
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::new(),
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+        let mut request = Request::generate_field(&mut rand, &FieldSpec::DEFAULT);
+        state
+            .corpus_mut()
+            .add(Testcase::new(request.clone()))
+            .unwrap();
+        let mut mutators = Request::structured_mutators();
+        for _ in 0..1000 {
+            request.mutate_field(&mut rand, &FieldSpec::DEFAULT);
+            mutators.mutate_all(&mut state, &mut request).unwrap();
+            assert!((3..=4).contains(&request.method.len()));
+            assert!((1..=8).contains(&request.retries));
+            assert!(request.headers.len() <= 4);
+            assert!(request.headers.iter().all(|header| match header {
+                Header::Length(len) => *len < 100,
+                _ => true,
+            }));
+            assert_eq!(request.id, 0);
+        }
+    }
+
+    #[test]
+    fn test_structured_fields_stay_in_bounds() {
+        let mut rand = StdRand::with_seed(0);
+        let spec = FieldSpec {
+            range: Some((10, 20)),
+            len: Some((2, 4)),
+        };
+        let mut values = Vec::<u32>::generate_field(&mut rand, &spec);
+        let mut text = String::generate_field(&mut rand, &spec);
+        let mut flag = Option::<bool>::generate_field(&mut rand, &spec);
+        for _ in 0..1000 {
+            values.mutate_field(&mut rand, &spec);
+            assert!((2..=4).contains(&values.len()));
+            assert!(values.iter().all(|value| (10..=20).contains(value)));
+
+            text.mutate_field(&mut rand, &spec);
+            assert!((2..=4).contains(&text.len()));
+            assert!(text.is_ascii());
+
+            assert_eq!(flag.mutate_field(&mut rand, &spec), MutationResult::Mutated);
+        }
+    }
+}
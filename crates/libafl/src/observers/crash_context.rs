@@ -0,0 +1,134 @@
+//! The [`CrashContextObserver`] holds the state of a crashed child process, as collected from its core dump.
+//!
+//! The executor must explicitly support this observer, see `StdChildArgs::crash_context` for the
+//! [`crate::executors::CommandExecutor`] and [`crate::executors::ForkserverExecutor`].
+
+use alloc::{borrow::Cow, string::String, vec::Vec};
+use core::{
+    ffi::c_int,
+    fmt::{self, Display, Formatter},
+};
+use std::path::PathBuf;
+
+use libafl_bolts::Named;
+use serde::{Deserialize, Serialize};
+
+use crate::{Error, observers::Observer};
+
+/// A file-backed memory mapping of a crashed process
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MemoryMapping {
+    /// The first address of the mapping
+    pub start: u64,
+    /// The address after the end of the mapping
+    pub end: u64,
+    /// The offset of the mapping in the file
+    pub file_offset: u64,
+    /// The path of the mapped file
+    pub path: String,
+}
+
+/// A frame of the backtrace of a crashed process
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StackFrame {
+    /// The program counter of this frame
+    pub address: u64,
+    /// The path of the module the address belongs to, if known
+    pub module: Option<String>,
+    /// The (demangled) function name, if known
+    pub function: Option<String>,
+    /// The source location as `file:line`, if known
+    pub location: Option<String>,
+}
+
+/// The state of a child process at the time it crashed
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CrashContext {
+    /// The pid of the crashed child
+    pub pid: i32,
+    /// The signal that terminated the child
+    pub signal: c_int,
+    /// The path of the core dump, if it was kept
+    pub core_path: Option<PathBuf>,
+    /// The general purpose registers of the crashing thread, by name
+    pub registers: Vec<(String, u64)>,
+    /// The file-backed memory mappings of the child
+    pub maps: Vec<MemoryMapping>,
+    /// The backtrace of the crashing thread, innermost frame first
+    pub backtrace: Vec<StackFrame>,
+}
+
+impl Display for CrashContext {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "pid {} terminated by signal {}", self.pid, self.signal)?;
+        if let Some(core_path) = &self.core_path {
+            writeln!(f, "core dump: {}", core_path.display())?;
+        }
+        for (i, frame) in self.backtrace.iter().enumerate() {
+            write!(f, "#{i} {:#018x}", frame.address)?;
+            if let Some(function) = &frame.function {
+                write!(f, " in {function}")?;
+            }
+            if let Some(location) = &frame.location {
+                write!(f, " at {location}")?;
+            }
+            if let Some(module) = &frame.module {
+                write!(f, " ({module})")?;
+            }
+            writeln!(f)?;
+        }
+        for (name, value) in &self.registers {
+            writeln!(f, "{name:>8}: {value:#018x}")?;
+        }
+        Ok(())
+    }
+}
+
+/// An observer holding the [`CrashContext`] of the last run, if the child crashed.
+///
+/// Only works for supported executors, which collect the context from the core dump of the child.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrashContextObserver {
+    name: Cow<'static, str>,
+    last_context: Option<CrashContext>,
+}
+
+impl CrashContextObserver {
+    /// Creates a new [`CrashContextObserver`] with the given name.
+    #[must_use]
+    pub fn new(name: &'static str) -> Self {
+        Self {
+            name: Cow::from(name),
+            last_context: None,
+        }
+    }
+
+    /// Called by the executor with the context of the crashed child
+    pub fn observe(&mut self, context: CrashContext) {
+        self.last_context = Some(context);
+    }
+
+    /// The context of the crash in the last run, if any
+    #[must_use]
+    pub fn last_context(&self) -> Option<&CrashContext> {
+        self.last_context.as_ref()
+    }
+}
+
+impl<I, S> Observer<I, S> for CrashContextObserver {
+    fn pre_exec(&mut self, _state: &mut S, _input: &I) -> Result<(), Error> {
+        self.last_context = None;
+        Ok(())
+    }
+
+    fn pre_exec_child(&mut self, _state: &mut S, _input: &I) -> Result<(), Error> {
+        self.last_context = None;
+        Ok(())
+    }
+}
+
+impl Named for CrashContextObserver {
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
@@ -20,6 +20,19 @@ pub use map::*;
 
 pub mod value;
 
+pub mod resource_limits;
+pub use resource_limits::{LimitedResource, ResourceLimitObserver};
+
+#[cfg(feature = "std")]
+pub mod crash_context;
+#[cfg(feature = "std")]
+pub use crash_context::{CrashContext, CrashContextObserver, MemoryMapping, StackFrame};
+
+#[cfg(all(feature = "std", unix))]
+pub mod process_exit;
+#[cfg(all(feature = "std", unix))]
+pub use process_exit::{ProcessExit, ProcessExitObserver};
+
 /// List observer
 pub mod list;
 use core::{fmt::Debug, time::Duration};
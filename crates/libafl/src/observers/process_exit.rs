@@ -0,0 +1,172 @@
+//! The [`ProcessExitObserver`] records how a child process exited: signal, exit code and core dump.
+//!
+//! The executor must explicitly support this observer, see `StdChildArgs::process_exit_observer` for the
+//! [`crate::executors::CommandExecutor`] and [`crate::executors::ForkserverExecutor`].
+
+use alloc::{borrow::Cow, vec::Vec};
+use core::ffi::c_int;
+use std::os::unix::process::ExitStatusExt;
+
+use libafl_bolts::Named;
+use nix::sys::wait::WaitStatus;
+use serde::{Deserialize, Serialize};
+
+use crate::{Error, observers::Observer};
+
+/// The exit codes the sanitizers use by default when they detect a bug
+/// (`LeakSanitizer`: 23, `ThreadSanitizer`: 66, `MemorySanitizer`: 77).
+///
+/// `AddressSanitizer` and `UndefinedBehaviorSanitizer` exit with `1`, so they cannot be told apart from a regular error exit.
+pub const DEFAULT_SANITIZER_EXIT_CODES: [c_int; 3] = [23, 66, 77];
+
+/// How a child process exited
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ProcessExit {
+    /// The signal that terminated the child, if any
+    pub signal: Option<c_int>,
+    /// The exit code, if the child exited normally
+    pub exit_code: Option<c_int>,
+    /// If the child dumped a core
+    pub core_dumped: bool,
+    /// The exit code, if it is one of the sanitizer exit codes of the [`ProcessExitObserver`]
+    pub sanitizer_exit_code: Option<c_int>,
+}
+
+impl ProcessExit {
+    /// Decodes a raw status, as returned by `waitpid` or reported by a forkserver
+    #[must_use]
+    pub fn from_raw_status(status: c_int) -> Self {
+        if libc::WIFSIGNALED(status) {
+            Self {
+                signal: Some(libc::WTERMSIG(status)),
+                core_dumped: libc::WCOREDUMP(status),
+                ..Self::default()
+            }
+        } else if libc::WIFEXITED(status) {
+            Self {
+                exit_code: Some(libc::WEXITSTATUS(status)),
+                ..Self::default()
+            }
+        } else {
+            Self::default()
+        }
+    }
+
+    /// Converts an exit status of [`std::process::Child`]
+    #[must_use]
+    pub fn from_exit_status(status: &std::process::ExitStatus) -> Self {
+        Self::from_raw_status(status.into_raw())
+    }
+
+    /// Converts a [`WaitStatus`], returns [`None`] if the child did not terminate
+    #[must_use]
+    pub fn from_wait_status(status: &WaitStatus) -> Option<Self> {
+        match status {
+            WaitStatus::Exited(_, code) => Some(Self {
+                exit_code: Some(*code),
+                ..Self::default()
+            }),
+            WaitStatus::Signaled(_, signal, core_dumped) => Some(Self {
+                signal: Some(*signal as c_int),
+                core_dumped: *core_dumped,
+                ..Self::default()
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// An observer recording how the child process of the last run exited.
+///
+/// Only works for supported executors. After a timeout, or if the executor could not tell, no exit is recorded.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProcessExitObserver {
+    name: Cow<'static, str>,
+    sanitizer_exit_codes: Vec<c_int>,
+    last_exit: Option<ProcessExit>,
+}
+
+impl ProcessExitObserver {
+    /// Creates a new [`ProcessExitObserver`] with the given name, knowing the [`DEFAULT_SANITIZER_EXIT_CODES`].
+    #[must_use]
+    pub fn new(name: &'static str) -> Self {
+        Self {
+            name: Cow::from(name),
+            sanitizer_exit_codes: DEFAULT_SANITIZER_EXIT_CODES.to_vec(),
+            last_exit: None,
+        }
+    }
+
+    /// Sets the exit codes the sanitizers of the target are configured to use (e.g. via `exitcode=` in `ASAN_OPTIONS`)
+    #[must_use]
+    pub fn with_sanitizer_exit_codes<C>(mut self, sanitizer_exit_codes: C) -> Self
+    where
+        C: IntoIterator<Item = c_int>,
+    {
+        self.sanitizer_exit_codes = sanitizer_exit_codes.into_iter().collect();
+        self
+    }
+
+    /// Called by the executor with the exit of the last run
+    pub fn observe(&mut self, exit: ProcessExit) {
+        let sanitizer_exit_code = exit
+            .exit_code
+            .filter(|code| self.sanitizer_exit_codes.contains(code));
+        self.last_exit = Some(ProcessExit {
+            sanitizer_exit_code,
+            ..exit
+        });
+    }
+
+    /// Called by the executor with the raw status of the last run, as returned by `waitpid`
+    pub fn observe_raw_status(&mut self, status: c_int) {
+        self.observe(ProcessExit::from_raw_status(status));
+    }
+
+    /// How the child process of the last run exited, if known
+    #[must_use]
+    pub fn last_exit(&self) -> Option<&ProcessExit> {
+        self.last_exit.as_ref()
+    }
+}
+
+impl<I, S> Observer<I, S> for ProcessExitObserver {
+    fn pre_exec(&mut self, _state: &mut S, _input: &I) -> Result<(), Error> {
+        self.last_exit = None;
+        Ok(())
+    }
+
+    fn pre_exec_child(&mut self, _state: &mut S, _input: &I) -> Result<(), Error> {
+        self.last_exit = None;
+        Ok(())
+    }
+}
+
+impl Named for ProcessExitObserver {
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ProcessExit, ProcessExitObserver};
+
+    #[test]
+    fn test_process_exit() {
+        let mut observer = ProcessExitObserver::new("exit");
+
+        // exit(77)
+        observer.observe_raw_status(77 << 8);
+        let exit = observer.last_exit().unwrap();
+        assert_eq!(exit.exit_code, Some(77));
+        assert_eq!(exit.sanitizer_exit_code, Some(77));
+        assert_eq!(exit.signal, None);
+
+        // SIGFPE, with core dump
+        let exit = ProcessExit::from_raw_status(libc::SIGFPE | 0x80);
+        assert_eq!(exit.signal, Some(libc::SIGFPE));
+        assert!(exit.core_dumped);
+        assert_eq!(exit.exit_code, None);
+    }
+}
@@ -0,0 +1,89 @@
+//! An observer for the resource limits a child violated, see `executors::resource_limits`.
+
+use alloc::borrow::Cow;
+
+use libafl_bolts::Named;
+use serde::{Deserialize, Serialize};
+
+use crate::{Error, observers::Observer};
+
+/// A resource limit violation the executors can detect
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum LimitedResource {
+    /// The child exceeded the maximum file size (`RLIMIT_FSIZE`) and got killed by `SIGXFSZ`
+    FileSize,
+    /// The cgroup ran out of memory (`memory.max`) and the kernel killed a process.
+    ///
+    /// Only suspected if a process crashed after the cgroup hit its limit, without an OOM kill.
+    Memory,
+    /// The child aborted or segfaulted while `RLIMIT_AS` or `RLIMIT_DATA` was set.
+    ///
+    /// This is what a failed allocation usually ends in, but genuine crashes look the same,
+    /// so this is only ever suspected, use a cgroup if you need to tell them apart.
+    MemoryRlimit,
+    /// The cgroup hit its process limit, so a `fork` failed (`pids.max`)
+    Processes,
+}
+
+/// Observes which resource limit, if any, the last run violated, or may have violated.
+///
+/// Only executors configured with `ResourceLimits::observer` fill this observer.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResourceLimitObserver {
+    name: Cow<'static, str>,
+    violation: Option<LimitedResource>,
+    suspected: Option<LimitedResource>,
+}
+
+impl ResourceLimitObserver {
+    /// Creates a new [`ResourceLimitObserver`] with the given name.
+    #[must_use]
+    pub fn new(name: &'static str) -> Self {
+        Self {
+            name: Cow::from(name),
+            violation: None,
+            suspected: None,
+        }
+    }
+
+    /// Called by the executor with the violation and the suspected violation of the last run
+    pub fn observe(
+        &mut self,
+        violation: Option<LimitedResource>,
+        suspected: Option<LimitedResource>,
+    ) {
+        self.violation = violation;
+        self.suspected = suspected;
+    }
+
+    /// The resource limit the last run violated, if any
+    #[must_use]
+    pub fn violation(&self) -> Option<LimitedResource> {
+        self.violation
+    }
+
+    /// The resource limit the crash of the last run may be caused by, without evidence of a violation.
+    /// The run is still reported as a crash.
+    #[must_use]
+    pub fn suspected(&self) -> Option<LimitedResource> {
+        self.suspected
+    }
+}
+
+impl<I, S> Observer<I, S> for ResourceLimitObserver {
+    fn pre_exec(&mut self, _state: &mut S, _input: &I) -> Result<(), Error> {
+        self.observe(None, None);
+        Ok(())
+    }
+
+    fn pre_exec_child(&mut self, _state: &mut S, _input: &I) -> Result<(), Error> {
+        self.observe(None, None);
+        Ok(())
+    }
+}
+
+impl Named for ResourceLimitObserver {
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
@@ -0,0 +1,257 @@
+//! Stages to attribute executions, new corpus entries and objectives to the stages that caused them,
+//! and to publish the yield of each mutator and stage as user stats.
+//! See [`crate::mutators::attribution`].
+
+use alloc::{
+    borrow::{Cow, ToOwned},
+    format,
+    vec::Vec,
+};
+use core::{marker::PhantomData, time::Duration};
+
+use libafl_bolts::{Named, current_time, tuples::MappingFunctor};
+
+use crate::{
+    Error, HasMetadata,
+    corpus::Corpus,
+    events::{Event, EventFirer, EventWithStats},
+    monitors::stats::{AggregatorOps, UserStats, UserStatsValue},
+    mutators::attribution::{AttributionMetadata, YieldStats},
+    stages::{Restartable, Stage},
+    state::{HasCorpus, HasExecutions, HasSolutions},
+};
+
+/// The default interval at which the [`AttributionStatsStage`] publishes the stats
+pub const ATTRIBUTION_STATS_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Credits the executions, new corpus entries and new objectives of each run of the inner stage to it,
+/// in the [`AttributionMetadata`] of the state.
+#[derive(Debug)]
+pub struct AttributedStage<I, ST> {
+    name: Cow<'static, str>,
+    inner: ST,
+    phantom: PhantomData<fn() -> I>,
+}
+
+impl<I, ST> AttributedStage<I, ST> {
+    /// Wraps the `inner` stage, crediting it as `name`
+    pub fn new<N>(name: N, inner: ST) -> Self
+    where
+        N: Into<Cow<'static, str>>,
+    {
+        Self {
+            name: name.into(),
+            inner,
+            phantom: PhantomData,
+        }
+    }
+
+    /// The wrapped stage
+    pub fn inner(&self) -> &ST {
+        &self.inner
+    }
+}
+
+impl<I, ST> Named for AttributedStage<I, ST> {
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+impl<E, EM, I, S, ST, Z> Stage<E, EM, S, Z> for AttributedStage<I, ST>
+where
+    S: HasCorpus<I> + HasSolutions<I> + HasExecutions + HasMetadata,
+    ST: Stage<E, EM, S, Z>,
+{
+    fn perform(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut S,
+        manager: &mut EM,
+    ) -> Result<(), Error> {
+        let executions = *state.executions();
+        let corpus = state.corpus().count_all();
+        let objectives = state.solutions().count_all();
+        let result = self.inner.perform(fuzzer, executor, state, manager);
+        let yield_stats = YieldStats {
+            executions: state.executions().saturating_sub(executions),
+            corpus: state.corpus().count_all().saturating_sub(corpus) as u64,
+            objectives: state.solutions().count_all().saturating_sub(objectives) as u64,
+        };
+        state
+            .metadata_or_insert_with(AttributionMetadata::default)
+            .record_stage(&self.name, yield_stats);
+        result
+    }
+}
+
+impl<I, S, ST> Restartable<S> for AttributedStage<I, ST>
+where
+    ST: Restartable<S>,
+{
+    fn should_restart(&mut self, state: &mut S) -> Result<bool, Error> {
+        self.inner.should_restart(state)
+    }
+
+    fn clear_progress(&mut self, state: &mut S) -> Result<(), Error> {
+        self.inner.clear_progress(state)
+    }
+}
+
+/// Wraps each stage of a tuple into an [`AttributedStage`] credited by the name of the stage,
+/// using [`libafl_bolts::tuples::Map`]
+#[derive(Debug)]
+pub struct ToAttributedStage<I> {
+    phantom: PhantomData<fn() -> I>,
+}
+
+impl<I> ToAttributedStage<I> {
+    /// Creates a new [`ToAttributedStage`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<I> Default for ToAttributedStage<I> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<I, ST> MappingFunctor<ST> for ToAttributedStage<I>
+where
+    ST: Named,
+{
+    type Output = AttributedStage<I, ST>;
+
+    fn apply(&mut self, from: ST) -> Self::Output {
+        AttributedStage::new(from.name().clone(), from)
+    }
+}
+
+/// Publishes the [`AttributionMetadata`] of the state as user stats, once every interval.
+///
+/// Each mutator gets a `mutator/<name>` stat, and each stage a `stage/<name>` stat,
+/// the ratio of new corpus entries to executions. Those that found objectives also get
+/// a `mutator/<name>/objectives` or `stage/<name>/objectives` stat, the ratio of objectives to executions.
+#[derive(Debug)]
+pub struct AttributionStatsStage<I> {
+    interval: Duration,
+    last_report: Duration,
+    phantom: PhantomData<fn() -> I>,
+}
+
+impl<I> AttributionStatsStage<I> {
+    /// Creates a new [`AttributionStatsStage`], publishing every [`ATTRIBUTION_STATS_INTERVAL`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_interval(ATTRIBUTION_STATS_INTERVAL)
+    }
+
+    /// Creates a new [`AttributionStatsStage`], publishing every `interval`
+    #[must_use]
+    pub fn with_interval(interval: Duration) -> Self {
+        Self {
+            interval,
+            last_report: Duration::ZERO,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<I> Default for AttributionStatsStage<I> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Fires the user stats for one mutator or stage
+fn fire_yield_stats<EM, I, S>(
+    manager: &mut EM,
+    state: &mut S,
+    name: &str,
+    yield_stats: YieldStats,
+) -> Result<(), Error>
+where
+    EM: EventFirer<I, S>,
+    S: HasExecutions,
+{
+    let mut stats = vec![(Cow::from(name.to_owned()), yield_stats.corpus)];
+    if yield_stats.objectives > 0 {
+        stats.push((
+            Cow::from(format!("{name}/objectives")),
+            yield_stats.objectives,
+        ));
+    }
+    for (name, found) in stats {
+        let executions = *state.executions();
+        manager.fire(
+            state,
+            EventWithStats::with_current_time(
+                Event::UpdateUserStats {
+                    name,
+                    value: UserStats::new(
+                        UserStatsValue::Ratio(found, yield_stats.executions),
+                        AggregatorOps::Avg,
+                    ),
+                    phantom: PhantomData,
+                },
+                executions,
+            ),
+        )?;
+    }
+    Ok(())
+}
+
+impl<E, EM, I, S, Z> Stage<E, EM, S, Z> for AttributionStatsStage<I>
+where
+    EM: EventFirer<I, S>,
+    S: HasExecutions + HasMetadata,
+{
+    fn perform(
+        &mut self,
+        _fuzzer: &mut Z,
+        _executor: &mut E,
+        state: &mut S,
+        manager: &mut EM,
+    ) -> Result<(), Error> {
+        let now = current_time();
+        if now.saturating_sub(self.last_report) < self.interval {
+            return Ok(());
+        }
+        self.last_report = now;
+        let Ok(metadata) = state.metadata::<AttributionMetadata>() else {
+            return Ok(());
+        };
+        let mut stats = metadata
+            .mutators
+            .iter()
+            .map(|(name, yield_stats)| (format!("mutator/{name}"), *yield_stats))
+            .chain(
+                metadata
+                    .stages
+                    .iter()
+                    .map(|(name, yield_stats)| (format!("stage/{name}"), *yield_stats)),
+            )
+            .collect::<Vec<_>>();
+        stats.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+        for (name, yield_stats) in stats {
+            fire_yield_stats(manager, state, &name, yield_stats)?;
+        }
+        Ok(())
+    }
+}
+
+impl<I, S> Restartable<S> for AttributionStatsStage<I> {
+    fn should_restart(&mut self, _state: &mut S) -> Result<bool, Error> {
+        Ok(true)
+    }
+
+    fn clear_progress(&mut self, _state: &mut S) -> Result<(), Error> {
+        Ok(())
+    }
+}
@@ -14,6 +14,9 @@ use core::{fmt, marker::PhantomData};
 
 #[cfg(feature = "std")]
 pub use afl_stats::{AflStatsStage, CalibrationTime, FuzzTime, SyncTime};
+pub use attribution::{
+    ATTRIBUTION_STATS_INTERVAL, AttributedStage, AttributionStatsStage, ToAttributedStage,
+};
 pub use calibrate::{CalibrationStage, run_target_with_timing};
 pub use colorization::*;
 #[cfg(all(feature = "std", unix))]
@@ -29,7 +32,9 @@ use libafl_bolts::{
     tuples::{HasConstLen, IntoVec},
 };
 pub use logics::*;
-pub use mutational::{MutationalStage, StdMutationalStage};
+pub use mutational::{BatchedMultiMutationalStage, MutationalStage, StdMutationalStage};
+#[cfg(feature = "otlp_monitor")]
+pub use otlp::OtlpStage;
 pub use power::{PowerMutationalStage, StdPowerMutationalStage};
 use serde::{Deserialize, Serialize};
 #[cfg(feature = "std")]
@@ -65,6 +70,7 @@ pub use replay::*;
 
 #[cfg(feature = "std")]
 pub mod afl_stats;
+pub mod attribution;
 pub mod calibrate;
 pub mod colorization;
 #[cfg(all(feature = "std", unix))]
@@ -76,6 +82,8 @@ pub mod generalization;
 pub mod generation;
 pub mod logics;
 pub mod nop;
+#[cfg(feature = "otlp_monitor")]
+pub mod otlp;
 pub mod power;
 #[cfg(feature = "std")]
 pub mod sync;
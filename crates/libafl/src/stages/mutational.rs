@@ -4,6 +4,7 @@
 use alloc::{
     borrow::{Cow, ToOwned},
     string::ToString,
+    vec::Vec,
 };
 use core::{marker::PhantomData, num::NonZeroUsize};
 
@@ -14,6 +15,7 @@ use crate::monitors::stats::PerfFeature;
 use crate::{
     Error, HasMetadata, HasNamedMetadata,
     corpus::{Corpus, CorpusId, HasCurrentCorpusId, Testcase},
+    executors::BatchExecutor,
     fuzzer::Evaluator,
     inputs::Input,
     mark_feature_time,
@@ -384,3 +386,107 @@ impl<E, EM, I, M, S, Z> MultiMutationalStage<E, EM, I, M, S, Z> {
         }
     }
 }
+
+/// A [`MultiMutationalStage`] that runs the generated inputs concurrently, in batches, using a [`BatchExecutor`].
+///
+/// Each batch is dispatched to the executor first, then the inputs get evaluated one after the other as usual,
+/// picking up the results of the concurrent runs.
+#[derive(Debug, Clone)]
+pub struct BatchedMultiMutationalStage<E, EM, I, M, S, Z> {
+    name: Cow<'static, str>,
+    mutator: M,
+    phantom: PhantomData<(E, EM, I, S, Z)>,
+}
+
+/// The unique id for batched multi mutational stage
+static mut BATCHED_MULTI_MUTATIONAL_STAGE_ID: usize = 0;
+/// The name for batched multi mutational stage
+pub static BATCHED_MULTI_MUTATIONAL_STAGE_NAME: &str = "batchedmultimutational";
+
+impl<E, EM, I, M, S, Z> Named for BatchedMultiMutationalStage<E, EM, I, M, S, Z> {
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+impl<E, EM, I, M, S, Z> Stage<E, EM, S, Z> for BatchedMultiMutationalStage<E, EM, I, M, S, Z>
+where
+    E: BatchExecutor<I, S, Z>,
+    I: Clone + MutatedTransform<I, S>,
+    M: MultiMutator<I, S>,
+    S: HasRand + HasNamedMetadata + HasCurrentTestcase<I> + HasCurrentCorpusId,
+    Z: Evaluator<E, EM, I, S>,
+{
+    #[inline]
+    fn perform(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut S,
+        manager: &mut EM,
+    ) -> Result<(), Error> {
+        let mut testcase = state.current_testcase_mut()?;
+        let Ok(input) = I::try_transform_from(&mut testcase, state) else {
+            return Ok(());
+        };
+        drop(testcase);
+
+        let generated = self.mutator.multi_mutate(state, &input, None)?;
+        let mut transformed = Vec::with_capacity(generated.len());
+        for new_input in generated {
+            transformed.push(new_input.try_transform_into(state)?);
+        }
+
+        let batch_size = executor.max_batch_size().max(1);
+        let mut remaining = transformed.into_iter().peekable();
+        while remaining.peek().is_some() {
+            let (batch, posts): (Vec<_>, Vec<_>) = remaining.by_ref().take(batch_size).unzip();
+            executor.dispatch_batch(fuzzer, state, &batch)?;
+            for (untransformed, post) in batch.iter().zip(posts) {
+                let (_, corpus_id) =
+                    fuzzer.evaluate_filtered(state, executor, manager, untransformed)?;
+                self.mutator.multi_post_exec(state, corpus_id)?;
+                post.post_exec(state, corpus_id)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<E, EM, I, M, S, Z> Restartable<S> for BatchedMultiMutationalStage<E, EM, I, M, S, Z>
+where
+    S: HasMetadata + HasNamedMetadata + HasCurrentCorpusId,
+{
+    #[inline]
+    fn should_restart(&mut self, state: &mut S) -> Result<bool, Error> {
+        // Make sure we don't get stuck crashing on a single testcase
+        RetryCountRestartHelper::should_restart(state, &self.name, 3)
+    }
+
+    #[inline]
+    fn clear_progress(&mut self, state: &mut S) -> Result<(), Error> {
+        RetryCountRestartHelper::clear_progress(state, &self.name)
+    }
+}
+
+impl<E, EM, I, M, S, Z> BatchedMultiMutationalStage<E, EM, I, M, S, Z> {
+    /// Creates a new [`BatchedMultiMutationalStage`]
+    pub fn new(mutator: M) -> Self {
+        // unsafe but impossible that you create two threads both instantiating this instance
+        let stage_id = unsafe {
+            let ret = BATCHED_MULTI_MUTATIONAL_STAGE_ID;
+            BATCHED_MULTI_MUTATIONAL_STAGE_ID += 1;
+            ret
+        };
+        Self {
+            name: Cow::Owned(
+                BATCHED_MULTI_MUTATIONAL_STAGE_NAME.to_owned()
+                    + ":"
+                    + stage_id.to_string().as_str(),
+            ),
+            mutator,
+            phantom: PhantomData,
+        }
+    }
+}
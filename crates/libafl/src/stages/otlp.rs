@@ -0,0 +1,83 @@
+//! A stage that wraps another stage and records a span for each of its runs, for OpenTelemetry.
+//! See [`crate::monitors::otlp`].
+
+use alloc::borrow::Cow;
+
+use libafl_bolts::{Error, current_time};
+
+use crate::{
+    monitors::otlp::OtlpExporter,
+    stages::{Restartable, Stage},
+    state::HasExecutions,
+};
+
+/// Records a `stage` span for each run of the inner stage to the [`OtlpExporter::global`] exporter,
+/// with the name of the stage, and the executions it took, as attributes.
+/// The spans recorded while the inner stage runs, such as those of an [`OtlpExecutor`](crate::executors::OtlpExecutor),
+/// are its children.
+#[derive(Debug)]
+pub struct OtlpStage<ST> {
+    name: Cow<'static, str>,
+    inner: ST,
+}
+
+impl<ST> OtlpStage<ST> {
+    /// Wraps the `inner` stage, naming it `name` in the spans
+    pub fn new<N>(name: N, inner: ST) -> Self
+    where
+        N: Into<Cow<'static, str>>,
+    {
+        Self {
+            name: name.into(),
+            inner,
+        }
+    }
+
+    /// The wrapped stage
+    pub fn inner(&self) -> &ST {
+        &self.inner
+    }
+}
+
+impl<E, M, S, ST, Z> Stage<E, M, S, Z> for OtlpStage<ST>
+where
+    S: HasExecutions,
+    ST: Stage<E, M, S, Z>,
+{
+    fn perform(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut S,
+        manager: &mut M,
+    ) -> Result<(), Error> {
+        let Some(exporter) = OtlpExporter::global() else {
+            return self.inner.perform(fuzzer, executor, state, manager);
+        };
+        let executions = *state.executions();
+        let mut span = exporter.span("stage", current_time());
+        let result = exporter.in_span(&span, || {
+            self.inner.perform(fuzzer, executor, state, manager)
+        });
+        span.end = current_time();
+        exporter.record(
+            span.attribute("stage.name", self.name.clone().into_owned())
+                .attribute("stage.executions", *state.executions() - executions)
+                .attribute("stage.ok", result.is_ok()),
+        );
+        result
+    }
+}
+
+impl<S, ST> Restartable<S> for OtlpStage<ST>
+where
+    ST: Restartable<S>,
+{
+    fn should_restart(&mut self, state: &mut S) -> Result<bool, Error> {
+        self.inner.should_restart(state)
+    }
+
+    fn clear_progress(&mut self, state: &mut S) -> Result<(), Error> {
+        self.inner.clear_progress(state)
+    }
+}
@@ -104,6 +104,8 @@ pub mod shmem;
 pub mod staterestore;
 #[cfg(feature = "alloc")]
 pub mod subrange;
+#[cfg(feature = "std")]
+pub mod tls;
 // TODO: reenable once ahash works in no-alloc
 #[cfg(any(feature = "xxh3", feature = "alloc"))]
 pub mod tuples;
@@ -103,12 +103,14 @@ use crate::os::unix_signals::setup_signal_handler;
 use crate::os::unix_signals::{Signal, SignalHandler, siginfo_t, ucontext_t};
 #[cfg(all(windows, feature = "std"))]
 use crate::os::windows_exceptions::{CtrlHandler, setup_ctrl_handler};
+#[cfg(feature = "tls")]
+use crate::tls::TlsConfig;
 use crate::{
     ClientId, Error,
     shmem::{ShMem, ShMemDescription, ShMemId, ShMemProvider},
 };
 #[cfg(feature = "std")]
-use crate::{IP_LOCALHOST, current_time};
+use crate::{IP_LOCALHOST, current_time, tls::MaybeTlsStream};
 
 /// The max number of pages a [`client`] may have mapped that were not yet read by the [`broker`]
 /// Usually, this value should not exceed `1`, else the broker cannot keep up with the amount of incoming messages.
@@ -123,6 +125,9 @@ const LLMP_CFG_INITIAL_MAP_SIZE: usize = 1 << 20;
 /// What byte count to align messages to
 /// [`LlmpMsg`] sizes (including header) will always be rounded up to be a multiple of this value.
 const LLMP_CFG_ALIGNNMENT: usize = 64;
+/// How long a TLS handshake between two brokers, including the first request, may take
+#[cfg(feature = "tls")]
+const LLMP_TLS_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
 
 /// A msg fresh from the press: No tag got sent by the user yet
 const LLMP_TAG_UNSET: Tag = Tag(0xDEADAF);
@@ -274,6 +279,9 @@ pub enum TcpRequest {
         /// Tell the broker that remove the client with this `client_id`. `client_id` is equal to the one of event restarter
         client_id: ClientId,
     },
+    /// Upgrade this connection to TLS, before sending the actual request.
+    /// Needs the `tls` feature, and a [`TlsConfig`] on both brokers.
+    StartTls,
 }
 
 impl TryFrom<&Vec<u8>> for TcpRequest {
@@ -468,8 +476,9 @@ fn tcp_bind(port: u16) -> Result<TcpListener, Error> {
 
 /// Send one message as `u32` len and `[u8;len]` bytes
 #[cfg(feature = "std")]
-pub fn send_tcp_msg<T>(stream: &mut TcpStream, msg: &T) -> Result<(), Error>
+pub fn send_tcp_msg<S, T>(stream: &mut S, msg: &T) -> Result<(), Error>
 where
+    S: Write,
     T: Serialize,
 {
     let msg = postcard::to_allocvec(msg)?;
@@ -495,14 +504,14 @@ where
 
 /// Receive one message of `u32` len and `[u8; len]` bytes
 #[cfg(feature = "std")]
-pub fn recv_tcp_msg(stream: &mut TcpStream) -> Result<Vec<u8>, Error> {
+pub fn recv_tcp_msg<S>(stream: &mut S) -> Result<Vec<u8>, Error>
+where
+    S: Read,
+{
     // Always receive one be u32 of size, then the command.
 
     #[cfg(feature = "llmp_debug")]
-    log::trace!(
-        "LLMP TCP: Waiting for packet... (Timeout: {:?})",
-        stream.read_timeout().unwrap_or(None)
-    );
+    log::trace!("LLMP TCP: Waiting for packet...");
 
     let mut size_bytes = [0_u8; 4];
     stream.read_exact(&mut size_bytes)?;
@@ -2150,6 +2159,9 @@ pub struct LlmpBrokerInner<SHM, SP> {
     clients_to_remove: Vec<ClientId>,
     /// The `ShMemProvider` to use
     shmem_provider: SP,
+    /// Secures broker-to-broker connections, if set
+    #[cfg(feature = "tls")]
+    tls_config: Option<TlsConfig>,
 }
 
 /// The broker (node 0)
@@ -2936,6 +2948,8 @@ where
             exit_cleanly_after: None,
             num_clients_seen: 0,
             shmem_provider,
+            #[cfg(feature = "tls")]
+            tls_config: None,
         })
     }
 
@@ -2986,6 +3000,15 @@ where
         self.exit_cleanly_after = Some(n_clients);
     }
 
+    /// Secure all broker-to-broker connections with TLS, authenticating the remote brokers with the pinned certificates.
+    ///
+    /// Remote brokers connecting without TLS get rejected. Local clients are not affected.
+    /// Only applies to listeners launched afterwards, so use [`Self::launch_listener`] instead of `create_attach_to_tcp`.
+    #[cfg(feature = "tls")]
+    pub fn set_tls_config(&mut self, tls_config: TlsConfig) {
+        self.tls_config = Some(tls_config);
+    }
+
     /// Add a client to this broker.
     /// Will set an appropriate [`ClientId`] before pushing the client to the internal vec.
     /// Will increase `num_clients_seen`.
@@ -3051,6 +3074,22 @@ where
             .to_string_lossy()
             .into();
 
+        #[cfg(feature = "tls")]
+        let mut stream = if let Some(tls_config) = &self.tls_config {
+            send_tcp_msg(&mut stream, &TcpRequest::StartTls)?;
+            stream.set_read_timeout(Some(LLMP_TLS_HANDSHAKE_TIMEOUT))?;
+            stream.set_write_timeout(Some(LLMP_TLS_HANDSHAKE_TIMEOUT))?;
+            let stream = tls_config.connect(stream)?;
+            stream.tcp().set_read_timeout(None)?;
+            stream.tcp().set_write_timeout(None)?;
+            log::info!("B2B: Upgraded connection to TLS");
+            stream
+        } else {
+            MaybeTlsStream::from(stream)
+        };
+        #[cfg(not(feature = "tls"))]
+        let mut stream = MaybeTlsStream::from(stream);
+
         send_tcp_msg(&mut stream, &TcpRequest::RemoteBrokerHello { hostname })?;
 
         let broker_id = match recv_tcp_msg(&mut stream)?.try_into()? {
@@ -3207,7 +3246,7 @@ where
     #[cfg(feature = "std")]
     #[expect(clippy::too_many_lines)]
     fn b2b_thread_on(
-        mut stream: TcpStream,
+        mut stream: MaybeTlsStream,
         b2b_client_id: ClientId,
         broker_shmem_description: &ShMemDescription,
     ) -> Result<ShMemDescription, Error> {
@@ -3226,6 +3265,7 @@ where
 
             // The background thread blocks on the incoming connection for 15 seconds (if no data is available), then checks if it should forward own messages, then blocks some more.
             stream
+                .tcp()
                 .set_read_timeout(Some(_LLMP_B2B_BLOCK_TIME))
                 .expect("Failed to set tcp stream timeout");
 
@@ -3253,7 +3293,7 @@ where
             #[cfg(feature = "llmp_debug")]
             log::info!("B2B: Starting proxy loop :)");
 
-            let peer_address = stream.peer_addr().unwrap();
+            let peer_address = stream.tcp().peer_addr().unwrap();
 
             loop {
                 // first, forward all data we have.
@@ -3355,7 +3395,7 @@ where
     /// handles a single tcp request in the current context.
     #[cfg(feature = "std")]
     fn handle_tcp_request(
-        mut stream: TcpStream,
+        mut stream: MaybeTlsStream,
         request: &TcpRequest,
         current_client_id: &mut ClientId,
         sender: &mut LlmpSender<SHM, SP>,
@@ -3385,6 +3425,9 @@ where
                 }
                 current_client_id.0 += 1;
             }
+            TcpRequest::StartTls => {
+                log::error!("Received a nested TLS upgrade request, ignoring.");
+            }
             TcpRequest::RemoteBrokerHello { hostname } => {
                 log::info!("B2B new client: {hostname}");
 
@@ -3413,6 +3456,46 @@ where
         }
     }
 
+    /// Runs the TLS handshake after a [`TcpRequest::StartTls`], then receives the actual request over TLS.
+    ///
+    /// Both are bounded by [`LLMP_TLS_HANDSHAKE_TIMEOUT`], so that a stalling peer cannot block the listener.
+    #[cfg(feature = "tls")]
+    fn accept_tls(
+        stream: TcpStream,
+        tls_config: Option<&TlsConfig>,
+    ) -> Result<(MaybeTlsStream, TcpRequest), Error> {
+        let tls_config = tls_config
+            .ok_or_else(|| Error::illegal_state("TLS requested, but TLS is not configured"))?;
+        stream.set_read_timeout(Some(LLMP_TLS_HANDSHAKE_TIMEOUT))?;
+        stream.set_write_timeout(Some(LLMP_TLS_HANDSHAKE_TIMEOUT))?;
+        let mut stream = tls_config.accept(stream)?;
+        let req = recv_tcp_msg(&mut stream)?.try_into()?;
+        stream.tcp().set_read_timeout(None)?;
+        stream.tcp().set_write_timeout(None)?;
+        Ok((stream, req))
+    }
+
+    /// Receives and deserializes a single [`TcpRequest`], logging errors
+    #[cfg(feature = "std")]
+    fn recv_tcp_request(stream: &mut TcpStream) -> Option<TcpRequest> {
+        let buf = match recv_tcp_msg(stream) {
+            Ok(buf) => buf,
+            Err(e) => {
+                log::error!("Error receving from tcp: {e:?}");
+                return None;
+            }
+        };
+
+        // log::info!("{:#?}", buf);
+        match buf.try_into() {
+            Ok(req) => Some(req),
+            Err(e) => {
+                log::error!("Could not deserialize tcp message: {e:?}");
+                None
+            }
+        }
+    }
+
     #[cfg(feature = "std")]
     /// Launches a thread using a listener socket, on which new clients may connect to this broker
     pub fn launch_listener(&mut self, listener: Listener) -> Result<thread::JoinHandle<()>, Error> {
@@ -3442,6 +3525,9 @@ where
         let tcp_out_shmem_description = tcp_out_shmem.shmem.description();
         let listener_id = self.register_client(tcp_out_shmem);
 
+        #[cfg(feature = "tls")]
+        let tls_config = self.tls_config.clone();
+
         let ret = thread::spawn(move || {
             // Create a new ShMemProvider for this background thread.
             let mut shmem_provider_bg = SP::new().unwrap();
@@ -3482,23 +3568,37 @@ where
                             }
                         }
 
-                        let buf = match recv_tcp_msg(&mut stream) {
-                            Ok(buf) => buf,
-                            Err(e) => {
-                                log::error!("Error receving from tcp: {e:?}");
-                                continue;
-                            }
+                        let Some(req) = Self::recv_tcp_request(&mut stream) else {
+                            continue;
                         };
 
-                        // log::info!("{:#?}", buf);
-                        let req = match buf.try_into() {
-                            Ok(req) => req,
-                            Err(e) => {
-                                log::error!("Could not deserialize tcp message: {e:?}");
-                                continue;
+                        let (stream, req) = if matches!(req, TcpRequest::StartTls) {
+                            #[cfg(feature = "tls")]
+                            let upgraded = Self::accept_tls(stream, tls_config.as_ref());
+                            #[cfg(not(feature = "tls"))]
+                            let upgraded = Err(Error::unsupported(
+                                "TLS requested, but the `tls` feature is disabled",
+                            ));
+                            match upgraded {
+                                Ok(upgraded) => upgraded,
+                                Err(e) => {
+                                    log::error!("Rejected remote broker {addr:?}: {e:?}");
+                                    continue;
+                                }
                             }
+                        } else {
+                            (MaybeTlsStream::from(stream), req)
                         };
 
+                        #[cfg(feature = "tls")]
+                        if tls_config.is_some()
+                            && !stream.is_tls()
+                            && matches!(req, TcpRequest::RemoteBrokerHello { .. })
+                        {
+                            log::error!("Rejected remote broker {addr:?} connecting without TLS");
+                            continue;
+                        }
+
                         Self::handle_tcp_request(
                             stream,
                             &req,
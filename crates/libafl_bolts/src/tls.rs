@@ -0,0 +1,472 @@
+//! Authenticated and encrypted TCP links between fuzzer nodes.
+//!
+//! A [`MaybeTlsStream`] is a [`TcpStream`] that may be wrapped in TLS. With the `tls` feature, a [`TlsConfig`]
+//! secures the links of llmp broker-to-broker connections, and of the TCP-based event managers in `libafl`.
+//! Both sides of a link authenticate each other: every node presents its own certificate, and only accepts
+//! peers whose certificate is one of the certificates pinned from local files.
+//! This works with self-signed certificates, no certificate authority is involved.
+
+#[cfg(feature = "tls")]
+use alloc::boxed::Box;
+use std::{
+    io::{self, Read, Write},
+    net::TcpStream,
+};
+
+#[cfg(feature = "tls")]
+pub use tls_config::{TLS_SERVER_NAME, TLS_SESSION_ACK, TlsConfig};
+
+/// A TCP stream, optionally secured with TLS
+#[derive(Debug)]
+pub enum MaybeTlsStream {
+    /// A plaintext TCP stream
+    Plain(TcpStream),
+    /// The client side of a TLS connection
+    #[cfg(feature = "tls")]
+    Client(Box<rustls::StreamOwned<rustls::ClientConnection, TcpStream>>),
+    /// The server side of a TLS connection
+    #[cfg(feature = "tls")]
+    Server(Box<rustls::StreamOwned<rustls::ServerConnection, TcpStream>>),
+}
+
+impl MaybeTlsStream {
+    /// The underlying [`TcpStream`], e.g. to set timeouts.
+    ///
+    /// Reading from or writing to it directly corrupts a TLS connection.
+    #[must_use]
+    pub fn tcp(&self) -> &TcpStream {
+        match self {
+            Self::Plain(stream) => stream,
+            #[cfg(feature = "tls")]
+            Self::Client(stream) => &stream.sock,
+            #[cfg(feature = "tls")]
+            Self::Server(stream) => &stream.sock,
+        }
+    }
+
+    /// If the peer on the other side of this stream got authenticated via TLS
+    #[must_use]
+    pub fn is_tls(&self) -> bool {
+        !matches!(self, Self::Plain(_))
+    }
+}
+
+impl From<TcpStream> for MaybeTlsStream {
+    fn from(stream: TcpStream) -> Self {
+        Self::Plain(stream)
+    }
+}
+
+impl Read for MaybeTlsStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Self::Plain(stream) => stream.read(buf),
+            #[cfg(feature = "tls")]
+            Self::Client(stream) => stream.read(buf),
+            #[cfg(feature = "tls")]
+            Self::Server(stream) => stream.read(buf),
+        }
+    }
+}
+
+impl Write for MaybeTlsStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Self::Plain(stream) => stream.write(buf),
+            #[cfg(feature = "tls")]
+            Self::Client(stream) => stream.write(buf),
+            #[cfg(feature = "tls")]
+            Self::Server(stream) => stream.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Self::Plain(stream) => stream.flush(),
+            #[cfg(feature = "tls")]
+            Self::Client(stream) => stream.flush(),
+            #[cfg(feature = "tls")]
+            Self::Server(stream) => stream.flush(),
+        }
+    }
+}
+
+#[cfg(feature = "tls")]
+mod tls_config {
+    use alloc::{boxed::Box, format, sync::Arc, vec::Vec};
+    use std::{
+        io::{Read, Write},
+        net::TcpStream,
+        path::Path,
+    };
+
+    use rustls::{
+        CertificateError, ClientConfig, ClientConnection, DigitallySignedStruct, DistinguishedName,
+        ServerConfig, ServerConnection, SignatureScheme, StreamOwned,
+        client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+        crypto::{CryptoProvider, ring, verify_tls12_signature, verify_tls13_signature},
+        pki_types::{
+            CertificateDer, PrivateKeyDer, ServerName, UnixTime,
+            pem::{self, PemObject},
+        },
+        server::danger::{ClientCertVerified, ClientCertVerifier},
+    };
+
+    use super::MaybeTlsStream;
+    use crate::Error;
+
+    /// The server name the nodes use for each other. Certificates are pinned, so the name is never checked.
+    pub const TLS_SERVER_NAME: &str = "libafl-node";
+
+    /// Sent by the server once it accepted the client, so that the client knows the session is established.
+    ///
+    /// With TLS 1.3, the client finishes its handshake before the server has verified the client certificate.
+    pub const TLS_SESSION_ACK: u8 = 0x4c;
+
+    /// Accepts exactly the pinned certificates, for both servers and clients
+    #[derive(Debug)]
+    struct PinnedCertVerifier {
+        pinned: Vec<CertificateDer<'static>>,
+        provider: Arc<CryptoProvider>,
+    }
+
+    impl PinnedCertVerifier {
+        fn verify(&self, end_entity: &CertificateDer<'_>) -> Result<(), rustls::Error> {
+            if self.pinned.iter().any(|pinned| pinned == end_entity) {
+                Ok(())
+            } else {
+                Err(rustls::Error::InvalidCertificate(
+                    CertificateError::ApplicationVerificationFailure,
+                ))
+            }
+        }
+    }
+
+    impl ServerCertVerifier for PinnedCertVerifier {
+        fn verify_server_cert(
+            &self,
+            end_entity: &CertificateDer<'_>,
+            _intermediates: &[CertificateDer<'_>],
+            _server_name: &ServerName<'_>,
+            _ocsp_response: &[u8],
+            _now: UnixTime,
+        ) -> Result<ServerCertVerified, rustls::Error> {
+            self.verify(end_entity)?;
+            Ok(ServerCertVerified::assertion())
+        }
+
+        fn verify_tls12_signature(
+            &self,
+            message: &[u8],
+            cert: &CertificateDer<'_>,
+            dss: &DigitallySignedStruct,
+        ) -> Result<HandshakeSignatureValid, rustls::Error> {
+            verify_tls12_signature(
+                message,
+                cert,
+                dss,
+                &self.provider.signature_verification_algorithms,
+            )
+        }
+
+        fn verify_tls13_signature(
+            &self,
+            message: &[u8],
+            cert: &CertificateDer<'_>,
+            dss: &DigitallySignedStruct,
+        ) -> Result<HandshakeSignatureValid, rustls::Error> {
+            verify_tls13_signature(
+                message,
+                cert,
+                dss,
+                &self.provider.signature_verification_algorithms,
+            )
+        }
+
+        fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+            self.provider
+                .signature_verification_algorithms
+                .supported_schemes()
+        }
+    }
+
+    impl ClientCertVerifier for PinnedCertVerifier {
+        fn root_hint_subjects(&self) -> &[DistinguishedName] {
+            &[]
+        }
+
+        fn verify_client_cert(
+            &self,
+            end_entity: &CertificateDer<'_>,
+            _intermediates: &[CertificateDer<'_>],
+            _now: UnixTime,
+        ) -> Result<ClientCertVerified, rustls::Error> {
+            self.verify(end_entity)?;
+            Ok(ClientCertVerified::assertion())
+        }
+
+        fn verify_tls12_signature(
+            &self,
+            message: &[u8],
+            cert: &CertificateDer<'_>,
+            dss: &DigitallySignedStruct,
+        ) -> Result<HandshakeSignatureValid, rustls::Error> {
+            verify_tls12_signature(
+                message,
+                cert,
+                dss,
+                &self.provider.signature_verification_algorithms,
+            )
+        }
+
+        fn verify_tls13_signature(
+            &self,
+            message: &[u8],
+            cert: &CertificateDer<'_>,
+            dss: &DigitallySignedStruct,
+        ) -> Result<HandshakeSignatureValid, rustls::Error> {
+            verify_tls13_signature(
+                message,
+                cert,
+                dss,
+                &self.provider.signature_verification_algorithms,
+            )
+        }
+
+        fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+            self.provider
+                .signature_verification_algorithms
+                .supported_schemes()
+        }
+    }
+
+    /// The TLS configuration of a node: its own certificate and key, and the pinned certificates of its peers.
+    ///
+    /// Both sides of each connection have to present a pinned certificate (mutual authentication),
+    /// peers failing to do so get rejected during the handshake.
+    #[derive(Debug, Clone)]
+    pub struct TlsConfig {
+        server: Arc<ServerConfig>,
+        client: Arc<ClientConfig>,
+    }
+
+    impl TlsConfig {
+        /// Creates a new [`TlsConfig`] from the node's certificate chain and private key,
+        /// and the certificates of all peers this node may talk to.
+        pub fn new(
+            cert_chain: Vec<CertificateDer<'static>>,
+            private_key: PrivateKeyDer<'static>,
+            pinned_certs: Vec<CertificateDer<'static>>,
+        ) -> Result<Self, Error> {
+            if pinned_certs.is_empty() {
+                return Err(Error::illegal_argument(
+                    "TLS needs at least one pinned peer certificate",
+                ));
+            }
+            let provider = Arc::new(ring::default_provider());
+            let verifier = Arc::new(PinnedCertVerifier {
+                pinned: pinned_certs,
+                provider: provider.clone(),
+            });
+            let tls_err = |err: rustls::Error| Error::illegal_argument(format!("TLS: {err}"));
+
+            let server = ServerConfig::builder_with_provider(provider.clone())
+                .with_safe_default_protocol_versions()
+                .map_err(tls_err)?
+                .with_client_cert_verifier(verifier.clone())
+                .with_single_cert(cert_chain.clone(), private_key.clone_key())
+                .map_err(tls_err)?;
+            let client = ClientConfig::builder_with_provider(provider)
+                .with_safe_default_protocol_versions()
+                .map_err(tls_err)?
+                .dangerous()
+                .with_custom_certificate_verifier(verifier)
+                .with_client_auth_cert(cert_chain, private_key)
+                .map_err(tls_err)?;
+
+            Ok(Self {
+                server: Arc::new(server),
+                client: Arc::new(client),
+            })
+        }
+
+        /// Creates a new [`TlsConfig`] from PEM files: the node's certificate chain and private key,
+        /// and one file per pinned peer certificate (which may include this node's own certificate).
+        pub fn from_pem_files<P, K, C>(
+            cert_chain: P,
+            private_key: K,
+            pinned_certs: &[C],
+        ) -> Result<Self, Error>
+        where
+            P: AsRef<Path>,
+            K: AsRef<Path>,
+            C: AsRef<Path>,
+        {
+            let cert_chain = load_certs(cert_chain.as_ref())?;
+            let private_key =
+                PrivateKeyDer::from_pem_file(private_key.as_ref()).map_err(|err| {
+                    Error::illegal_argument(format!(
+                        "Could not load private key {}: {err}",
+                        private_key.as_ref().display()
+                    ))
+                })?;
+            let mut pinned = Vec::new();
+            for path in pinned_certs {
+                pinned.extend(load_certs(path.as_ref())?);
+            }
+            Self::new(cert_chain, private_key, pinned)
+        }
+
+        /// The [`ServerConfig`] for accepting peers
+        #[must_use]
+        pub fn server_config(&self) -> Arc<ServerConfig> {
+            self.server.clone()
+        }
+
+        /// The [`ClientConfig`] for connecting to peers
+        #[must_use]
+        pub fn client_config(&self) -> Arc<ClientConfig> {
+            self.client.clone()
+        }
+
+        /// The name servers get connected to, see [`TLS_SERVER_NAME`]
+        #[must_use]
+        pub fn server_name() -> ServerName<'static> {
+            ServerName::try_from(TLS_SERVER_NAME).unwrap()
+        }
+
+        /// Runs the server side of the handshake on a freshly accepted stream, and acknowledges the session.
+        ///
+        /// This blocks until the handshake is done, set timeouts on the `stream` to bound it.
+        pub fn accept(&self, stream: TcpStream) -> Result<MaybeTlsStream, Error> {
+            let conn = ServerConnection::new(self.server_config())
+                .map_err(|err| Error::illegal_state(format!("TLS: {err}")))?;
+            let mut stream = StreamOwned::new(conn, stream);
+            while stream.conn.is_handshaking() {
+                stream
+                    .conn
+                    .complete_io(&mut stream.sock)
+                    .map_err(|err| Error::os_error(err, "TLS handshake with client failed"))?;
+            }
+            stream
+                .write_all(&[TLS_SESSION_ACK])
+                .and_then(|()| stream.flush())
+                .map_err(|err| Error::os_error(err, "Could not acknowledge TLS session"))?;
+            Ok(MaybeTlsStream::Server(Box::new(stream)))
+        }
+
+        /// Runs the client side of the handshake on a freshly connected stream,
+        /// and waits for the server to acknowledge the session (see [`TLS_SESSION_ACK`]).
+        ///
+        /// This blocks until the server answers, set timeouts on the `stream` to bound it.
+        pub fn connect(&self, stream: TcpStream) -> Result<MaybeTlsStream, Error> {
+            let conn = ClientConnection::new(self.client_config(), Self::server_name())
+                .map_err(|err| Error::illegal_state(format!("TLS: {err}")))?;
+            let mut stream = StreamOwned::new(conn, stream);
+            while stream.conn.is_handshaking() {
+                stream
+                    .conn
+                    .complete_io(&mut stream.sock)
+                    .map_err(|err| Error::os_error(err, "TLS handshake with server failed"))?;
+            }
+            let mut ack = [0];
+            stream
+                .read_exact(&mut ack)
+                .map_err(|err| Error::os_error(err, "TLS session was rejected by the server"))?;
+            if ack[0] != TLS_SESSION_ACK {
+                return Err(Error::illegal_state(format!(
+                    "Unexpected TLS session acknowledgement {:#x}",
+                    ack[0]
+                )));
+            }
+            Ok(MaybeTlsStream::Client(Box::new(stream)))
+        }
+    }
+
+    /// Loads all certificates from a PEM file
+    fn load_certs(path: &Path) -> Result<Vec<CertificateDer<'static>>, Error> {
+        let pem_err = |err: pem::Error| {
+            Error::illegal_argument(format!(
+                "Could not load certificates from {}: {err}",
+                path.display()
+            ))
+        };
+        let certs = CertificateDer::pem_file_iter(path)
+            .map_err(pem_err)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(pem_err)?;
+        if certs.is_empty() {
+            return Err(Error::illegal_argument(format!(
+                "No certificates in {}",
+                path.display()
+            )));
+        }
+        Ok(certs)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use alloc::{format, string::String, vec, vec::Vec};
+        use std::{
+            io::{Read, Write},
+            net::{TcpListener, TcpStream},
+            thread,
+        };
+
+        use super::TlsConfig;
+
+        fn test_data(name: &str) -> String {
+            format!("{}/test_data/tls/{name}", env!("CARGO_MANIFEST_DIR"))
+        }
+
+        fn node(name: &str, pinned: &[&str]) -> TlsConfig {
+            let pinned: Vec<_> = pinned
+                .iter()
+                .map(|peer| test_data(&format!("{peer}.pem")))
+                .collect();
+            TlsConfig::from_pem_files(
+                test_data(&format!("{name}.pem")),
+                test_data(&format!("{name}.key")),
+                &pinned,
+            )
+            .unwrap()
+        }
+
+        #[test]
+        fn test_mutual_auth() {
+            let server = node("node_a", &["node_b"]);
+            let client = node("node_b", &["node_a"]);
+            let rogue = node("rogue", &["node_a"]);
+
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let addr = listener.local_addr().unwrap();
+            let server_thread = thread::spawn(move || {
+                let mut results = vec![];
+                for _ in 0..2 {
+                    let (stream, _) = listener.accept().unwrap();
+                    results.push(server.accept(stream).map(|mut stream| {
+                        let mut buf = [0; 5];
+                        stream.read_exact(&mut buf).unwrap();
+                        stream.write_all(&buf).unwrap();
+                        buf
+                    }));
+                }
+                results
+            });
+
+            let mut stream = client.connect(TcpStream::connect(addr).unwrap()).unwrap();
+            assert!(stream.is_tls());
+            stream.write_all(b"hello").unwrap();
+            let mut buf = [0; 5];
+            stream.read_exact(&mut buf).unwrap();
+            assert_eq!(&buf, b"hello");
+
+            // The server does not know the rogue certificate
+            assert!(rogue.connect(TcpStream::connect(addr).unwrap()).is_err());
+
+            let results = server_thread.join().unwrap();
+            assert_eq!(results[0].as_ref().unwrap(), b"hello");
+            assert!(results[1].is_err());
+        }
+    }
+}
@@ -41,10 +41,14 @@
     )
 )]
 
+extern crate alloc;
+
 use proc_macro::TokenStream;
 use quote::quote;
 use syn::{Data::Struct, DeriveInput, Field, Fields::Named, Type, parse_macro_input};
 
+mod structured;
+
 /// Derive macro to implement `SerdeAny`, to use a type in a `SerdeAnyMap`
 #[proc_macro_derive(SerdeAny)]
 pub fn libafl_serdeany_derive(input: TokenStream) -> TokenStream {
@@ -144,3 +148,50 @@ fn libafl_display_field_by_type(it: &Field) -> proc_macro2::TokenStream {
         write!(f, #fmt, self.#ident)?;
     }
 }
+
+/// Derive macro to implement `StructuredInput` and `StructuredField` for a struct, to mutate it field-aware.
+///
+/// Also adds a `structured_mutators()` function, returning a tuple with one `StructuredFieldMutator` per field,
+/// and a `StructuredCrossoverMutator`. Field types must implement `StructuredField` and [`Clone`].
+/// `libafl` and `libafl_bolts` must be dependencies of the crate using it.
+///
+/// Fields take `#[structured(...)]` attributes:
+/// - `range = 1..=8` bounds integers, including the integers in vectors and options,
+/// - `len = 0..=4` bounds the length of vectors and strings,
+/// - `dict = [b"GET", b"POST"]` lists values to assign to the field, now and then, instead of a mutation,
+/// - `skip` leaves the field alone, and generates it with [`Default`].
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// use libafl_derive::StructuredInput;
+///
+/// #[derive(Debug, Clone, Hash, Serialize, Deserialize, StructuredInput)]
+/// struct Request {
+///     #[structured(dict = [b"GET", b"POST"])]
+///     method: Vec<u8>,
+///     #[structured(range = 1..=8)]
+///     retries: u8,
+/// }
+///
+/// let mutator = HavocScheduledMutator::new(Request::structured_mutators());
+/// ```
+#[proc_macro_derive(StructuredInput, attributes(structured))]
+pub fn libafl_structured_input(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    structured::derive_structured_input(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Derive macro to implement `StructuredField` for a struct or an enum, to nest it in a `StructuredInput`.
+///
+/// Fields take the same `#[structured(...)]` attributes as with [`macro@StructuredInput`].
+/// Enums switch to a new random variant now and then, and otherwise mutate a field of their variant.
+#[proc_macro_derive(StructuredField, attributes(structured))]
+pub fn libafl_structured_field(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    structured::derive_structured_field(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
@@ -0,0 +1,433 @@
+//! Code generation for `#[derive(StructuredInput)]` and `#[derive(StructuredField)]`
+
+use alloc::{format, string::ToString, vec::Vec};
+
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote};
+use syn::{
+    Attribute, Data, DeriveInput, Expr, ExprArray, ExprRange, Fields, Index, LitStr, Member,
+    RangeLimits, Result, Variant,
+};
+
+/// The `#[structured(...)]` attributes of a field
+#[derive(Default)]
+struct FieldAttrs {
+    range: Option<TokenStream>,
+    len: Option<TokenStream>,
+    dict: Vec<Expr>,
+    skip: bool,
+}
+
+/// The inclusive bounds of a range attribute, as a tuple of `ty`
+fn range_bounds(
+    range: &ExprRange,
+    ty: &TokenStream,
+    min: &TokenStream,
+    max: &TokenStream,
+) -> TokenStream {
+    let start = range
+        .start
+        .as_ref()
+        .map_or_else(|| min.clone(), |start| quote!((#start) as #ty));
+    let end = match (&range.end, &range.limits) {
+        (None, _) => max.clone(),
+        (Some(end), RangeLimits::Closed(_)) => quote!((#end) as #ty),
+        (Some(end), RangeLimits::HalfOpen(_)) => quote!((#end) as #ty - 1),
+    };
+    quote!(::core::option::Option::Some((#start, #end)))
+}
+
+fn parse_field_attrs(attrs: &[Attribute]) -> Result<FieldAttrs> {
+    let mut field_attrs = FieldAttrs::default();
+    for attr in attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("structured"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("range") {
+                let range: ExprRange = meta.value()?.parse()?;
+                field_attrs.range = Some(range_bounds(
+                    &range,
+                    &quote!(i128),
+                    &quote!(i128::MIN),
+                    &quote!(i128::MAX),
+                ));
+            } else if meta.path.is_ident("len") {
+                let range: ExprRange = meta.value()?.parse()?;
+                field_attrs.len = Some(range_bounds(
+                    &range,
+                    &quote!(usize),
+                    &quote!(0),
+                    &quote!(usize::MAX),
+                ));
+            } else if meta.path.is_ident("dict") {
+                let dict: ExprArray = meta.value()?.parse()?;
+                field_attrs.dict = dict.elems.into_iter().collect();
+            } else if meta.path.is_ident("skip") {
+                field_attrs.skip = true;
+            } else {
+                return Err(meta.error("expected `range`, `len`, `dict` or `skip`"));
+            }
+            Ok(())
+        })?;
+    }
+    Ok(field_attrs)
+}
+
+/// A field to mutate, with the place to access it, and its attributes
+struct StructuredFieldInfo {
+    name: alloc::string::String,
+    member: Member,
+    attrs: FieldAttrs,
+}
+
+fn parse_fields(fields: &Fields) -> Result<Vec<StructuredFieldInfo>> {
+    fields
+        .iter()
+        .enumerate()
+        .map(|(idx, field)| {
+            let member = field
+                .ident
+                .clone()
+                .map_or_else(|| Member::Unnamed(Index::from(idx)), Member::Named);
+            let name = field
+                .ident
+                .as_ref()
+                .map_or_else(|| idx.to_string(), ToString::to_string);
+            Ok(StructuredFieldInfo {
+                name,
+                member,
+                attrs: parse_field_attrs(&field.attrs)?,
+            })
+        })
+        .collect()
+}
+
+/// The [`FieldSpec`] of a field
+fn field_spec(attrs: &FieldAttrs) -> TokenStream {
+    let none = quote!(::core::option::Option::None);
+    let range = attrs.range.as_ref().unwrap_or(&none);
+    let len = attrs.len.as_ref().unwrap_or(&none);
+    quote! {
+        libafl::mutators::structured::FieldSpec {
+            range: #range,
+            len: #len,
+        }
+    }
+}
+
+/// Picks a value of the dict of a field, if it has one, now and then
+fn dict_choice(
+    attrs: &FieldAttrs,
+    otherwise: &TokenStream,
+    assign: impl Fn(TokenStream) -> TokenStream,
+) -> TokenStream {
+    if attrs.dict.is_empty() {
+        return otherwise.clone();
+    }
+    let dict_len = attrs.dict.len();
+    let arms = attrs.dict.iter().enumerate().map(|(idx, value)| {
+        let value = assign(quote!(::core::convert::Into::into(#value)));
+        if idx + 1 == dict_len {
+            quote!(_ => #value,)
+        } else {
+            quote!(#idx => #value,)
+        }
+    });
+    quote! {
+        if rand.coinflip(libafl::mutators::structured::STRUCTURED_DICT_PROBABILITY) {
+            match rand.below_or_zero(#dict_len) {
+                #(#arms)*
+            }
+        } else {
+            #otherwise
+        }
+    }
+}
+
+/// Mutates the field at `place`
+fn mutate_field(field: &StructuredFieldInfo, place: &TokenStream) -> TokenStream {
+    let spec = field_spec(&field.attrs);
+    let mutate = quote! {
+        libafl::mutators::structured::StructuredField::mutate_field(&mut #place, rand, &#spec)
+    };
+    dict_choice(&field.attrs, &mutate, |value| {
+        quote! {{
+            #place = #value;
+            libafl::mutators::MutationResult::Mutated
+        }}
+    })
+}
+
+/// Generates a value for a field
+fn generate_field(field: &StructuredFieldInfo) -> TokenStream {
+    if field.attrs.skip {
+        return quote!(::core::default::Default::default());
+    }
+    let spec = field_spec(&field.attrs);
+    let generate = quote! {
+        libafl::mutators::structured::StructuredField::generate_field(rand, &#spec)
+    };
+    dict_choice(&field.attrs, &generate, |value| value)
+}
+
+/// Builds `path` from generated fields
+fn construct(path: &TokenStream, fields: &Fields, infos: &[StructuredFieldInfo]) -> TokenStream {
+    let values = infos.iter().map(generate_field);
+    match fields {
+        Fields::Named(_) => {
+            let members = infos.iter().map(|info| &info.member);
+            quote!(#path { #(#members: #values),* })
+        }
+        Fields::Unnamed(_) => quote!(#path(#(#values),*)),
+        Fields::Unit => path.clone(),
+    }
+}
+
+/// The `match` arms mutating the n-th non-skipped field of `self`
+fn mutate_nth_arms(infos: &[StructuredFieldInfo]) -> Vec<TokenStream> {
+    infos
+        .iter()
+        .filter(|info| !info.attrs.skip)
+        .enumerate()
+        .map(|(idx, info)| {
+            let member = &info.member;
+            let mutate = mutate_field(info, &quote!(self.#member));
+            quote!(#idx => #mutate,)
+        })
+        .collect()
+}
+
+fn struct_fields(input: &DeriveInput) -> Result<&Fields> {
+    match &input.data {
+        Data::Struct(data) => Ok(&data.fields),
+        _ => Err(syn::Error::new_spanned(
+            &input.ident,
+            "`StructuredInput` can only be derived for structs",
+        )),
+    }
+}
+
+fn impl_struct_field(input: &DeriveInput, fields: &Fields) -> Result<TokenStream> {
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let infos = parse_fields(fields)?;
+    let field_count = infos.iter().filter(|info| !info.attrs.skip).count();
+    let arms = mutate_nth_arms(&infos);
+    let construct = construct(&quote!(Self), fields, &infos);
+    Ok(quote! {
+        impl #impl_generics libafl::mutators::structured::StructuredField for #ident #ty_generics #where_clause {
+            #[allow(clippy::match_single_binding)]
+            fn mutate_field<R: libafl_bolts::rands::Rand>(
+                &mut self,
+                rand: &mut R,
+                _spec: &libafl::mutators::structured::FieldSpec,
+            ) -> libafl::mutators::MutationResult {
+                match rand.below_or_zero(#field_count) {
+                    #(#arms)*
+                    _ => libafl::mutators::MutationResult::Skipped,
+                }
+            }
+
+            fn generate_field<R: libafl_bolts::rands::Rand>(
+                rand: &mut R,
+                _spec: &libafl::mutators::structured::FieldSpec,
+            ) -> Self {
+                #construct
+            }
+        }
+    })
+}
+
+fn impl_enum_field(input: &DeriveInput, variants: &[&Variant]) -> Result<TokenStream> {
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    if variants.is_empty() {
+        return Err(syn::Error::new_spanned(
+            ident,
+            "`StructuredField` can not be derived for empty enums",
+        ));
+    }
+
+    let mut generate_arms = Vec::new();
+    let mut count_arms = Vec::new();
+    let mut mutate_arms = Vec::new();
+    for (idx, variant) in variants.iter().enumerate() {
+        let variant_ident = &variant.ident;
+        let infos = parse_fields(&variant.fields)?;
+        let construct = construct(&quote!(Self::#variant_ident), &variant.fields, &infos);
+        if idx + 1 == variants.len() {
+            generate_arms.push(quote!(_ => #construct,));
+        } else {
+            generate_arms.push(quote!(#idx => #construct,));
+        }
+
+        // Bind the non-skipped fields as `__field_<n>`
+        let bindings = infos
+            .iter()
+            .enumerate()
+            .map(|(n, info)| {
+                if info.attrs.skip {
+                    quote!(_)
+                } else {
+                    let binding = format_ident!("__field_{}", n);
+                    quote!(#binding)
+                }
+            })
+            .collect::<Vec<_>>();
+        let pattern = match &variant.fields {
+            Fields::Named(_) => {
+                let members = infos.iter().map(|info| &info.member);
+                quote!(Self::#variant_ident { #(#members: #bindings),* })
+            }
+            Fields::Unnamed(_) => quote!(Self::#variant_ident(#(#bindings),*)),
+            Fields::Unit => quote!(Self::#variant_ident),
+        };
+        let field_count = infos.iter().filter(|info| !info.attrs.skip).count();
+        count_arms.push(quote!(#pattern => #field_count,));
+        let field_arms = infos
+            .iter()
+            .enumerate()
+            .filter(|(_, info)| !info.attrs.skip)
+            .enumerate()
+            .map(|(idx, (n, info))| {
+                let binding = format_ident!("__field_{}", n);
+                let mutate = mutate_field(info, &quote!(*#binding));
+                quote!(#idx => #mutate,)
+            });
+        mutate_arms.push(quote! {
+            #pattern => match field {
+                #(#field_arms)*
+                _ => libafl::mutators::MutationResult::Skipped,
+            },
+        });
+    }
+    let variant_count = variants.len();
+
+    Ok(quote! {
+        impl #impl_generics libafl::mutators::structured::StructuredField for #ident #ty_generics #where_clause {
+            #[allow(clippy::match_single_binding)]
+            fn mutate_field<R: libafl_bolts::rands::Rand>(
+                &mut self,
+                rand: &mut R,
+                spec: &libafl::mutators::structured::FieldSpec,
+            ) -> libafl::mutators::MutationResult {
+                let field_count = match self {
+                    #(#count_arms)*
+                };
+                if field_count == 0
+                    || rand.coinflip(libafl::mutators::structured::STRUCTURED_VARIANT_PROBABILITY)
+                {
+                    *self = Self::generate_field(rand, spec);
+                    return libafl::mutators::MutationResult::Mutated;
+                }
+                let field = rand.below_or_zero(field_count);
+                match self {
+                    #(#mutate_arms)*
+                }
+            }
+
+            #[allow(clippy::match_single_binding)]
+            fn generate_field<R: libafl_bolts::rands::Rand>(
+                rand: &mut R,
+                _spec: &libafl::mutators::structured::FieldSpec,
+            ) -> Self {
+                match rand.below_or_zero(#variant_count) {
+                    #(#generate_arms)*
+                }
+            }
+        }
+    })
+}
+
+/// Implements `StructuredField` for a struct or an enum
+pub(crate) fn derive_structured_field(input: &DeriveInput) -> Result<TokenStream> {
+    match &input.data {
+        Data::Struct(data) => impl_struct_field(input, &data.fields),
+        Data::Enum(data) => impl_enum_field(input, &data.variants.iter().collect::<Vec<_>>()),
+        Data::Union(_) => Err(syn::Error::new_spanned(
+            &input.ident,
+            "`StructuredField` can not be derived for unions",
+        )),
+    }
+}
+
+/// Implements `StructuredField` and `StructuredInput` for a struct, and adds its `structured_mutators()`
+pub(crate) fn derive_structured_input(input: &DeriveInput) -> Result<TokenStream> {
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let fields = struct_fields(input)?;
+    let field_impl = impl_struct_field(input, fields)?;
+    let infos = parse_fields(fields)?;
+    let mutated = infos
+        .iter()
+        .filter(|info| !info.attrs.skip)
+        .collect::<Vec<_>>();
+
+    let names = mutated
+        .iter()
+        .map(|info| LitStr::new(&info.name, Span::call_site()));
+    let arms = mutate_nth_arms(&infos);
+    let crossover_arms = mutated.iter().enumerate().map(|(idx, info)| {
+        let member = &info.member;
+        quote! {
+            #idx => {
+                self.#member = ::core::clone::Clone::clone(&other.#member);
+                libafl::mutators::MutationResult::Mutated
+            }
+        }
+    });
+
+    // The tuple of one mutator per field, and the crossover
+    let mut mutators_type = quote!((
+        libafl::mutators::structured::StructuredCrossoverMutator<Self>,
+        ()
+    ));
+    let mut mutators = quote!((
+        libafl::mutators::structured::StructuredCrossoverMutator::new(),
+        ()
+    ));
+    for (idx, info) in mutated.iter().enumerate().rev() {
+        let name = LitStr::new(&format!("{ident}::{}", info.name), Span::call_site());
+        mutators_type =
+            quote!((libafl::mutators::structured::StructuredFieldMutator<Self>, #mutators_type));
+        mutators = quote!((libafl::mutators::structured::StructuredFieldMutator::new(#idx, #name), #mutators));
+    }
+
+    Ok(quote! {
+        #field_impl
+
+        impl #impl_generics libafl::mutators::structured::StructuredInput for #ident #ty_generics #where_clause {
+            fn field_names() -> &'static [&'static str] {
+                &[#(#names),*]
+            }
+
+            fn mutate_nth_field<R: libafl_bolts::rands::Rand>(
+                &mut self,
+                rand: &mut R,
+                field: usize,
+            ) -> libafl::mutators::MutationResult {
+                match field {
+                    #(#arms)*
+                    _ => libafl::mutators::MutationResult::Skipped,
+                }
+            }
+
+            #[allow(unused_variables)]
+            fn crossover_nth_field(&mut self, other: &Self, field: usize) -> libafl::mutators::MutationResult {
+                match field {
+                    #(#crossover_arms)*
+                    _ => libafl::mutators::MutationResult::Skipped,
+                }
+            }
+        }
+
+        impl #impl_generics #ident #ty_generics #where_clause {
+            /// The field-aware mutators of this input: one per field, and a crossover
+            #[must_use]
+            pub fn structured_mutators() -> #mutators_type {
+                #mutators
+            }
+        }
+    })
+}
@@ -1,5 +1,7 @@
 //! An `afl`-style forkserver fuzzer.
 //! Use this if your target has complex state that needs to be reset.
+#[cfg(feature = "python")]
+use alloc::rc::Rc;
 use core::{net::SocketAddr, time::Duration};
 use std::{fs, path::PathBuf};
 
@@ -30,6 +32,14 @@ use libafl::{
     stages::{CalibrationStage, StdMutationalStage, StdPowerMutationalStage, TracingStage},
     state::{HasCorpus, StdState},
 };
+#[cfg(feature = "python")]
+use libafl::{
+    feedbacks::PythonFeedback,
+    mutators::python::{PythonModule, PythonMutator},
+    stages::OptionalStage,
+};
+#[cfg(feature = "python")]
+use libafl_bolts::current_nanos;
 use libafl_bolts::{
     AsSliceMut, StdTargetArgs,
     core_affinity::Cores,
@@ -81,6 +91,11 @@ pub struct ForkserverBytesCoverageSugar<'a> {
     /// Fuzz `iterations` number of times, instead of indefinitely; implies use of `fuzz_loop_for`
     #[builder(default = None)]
     iterations: Option<u64>,
+    /// A Python module following the AFL++ custom mutator API, see `libafl::mutators::python`.
+    /// Its `fuzz` function runs in an extra mutational stage and its `is_interesting` function, if any,
+    /// as an extra feedback. Needs the `python` feature.
+    #[builder(default = None)]
+    python_module: Option<String>,
 }
 
 impl ForkserverBytesCoverageSugar<'_> {
@@ -99,6 +114,12 @@ impl ForkserverBytesCoverageSugar<'_> {
 
         let timeout = Duration::from_secs(self.timeout.unwrap_or(DEFAULT_TIMEOUT_SECS));
 
+        #[cfg(not(feature = "python"))]
+        assert!(
+            self.python_module.is_none(),
+            "A Python module needs the python feature of libafl_sugar"
+        );
+
         let mut out_dir = self.output_dir.clone();
         if fs::create_dir(&out_dir).is_err() {
             log::info!("Out dir at {} already exists.", out_dir.display());
@@ -153,13 +174,33 @@ impl ForkserverBytesCoverageSugar<'_> {
 
             let calibration = CalibrationStage::new(&map_feedback);
 
+            // The Python module, with a stage running its mutator and a feedback running its `is_interesting`
+            #[cfg(feature = "python")]
+            let (python_stages, python_feedback) = {
+                let module = self
+                    .python_module
+                    .as_deref()
+                    .map(|name| PythonModule::import(name, current_nanos()))
+                    .transpose()?
+                    .map(Rc::new);
+                let feedback = PythonFeedback::if_defined(module.clone())?;
+                let mutator = module.map(PythonMutator::new).transpose()?;
+                let stage =
+                    OptionalStage::new(mutator.map(|m| tuple_list!(StdMutationalStage::new(m))));
+                (tuple_list!(stage), feedback)
+            };
+            #[cfg(not(feature = "python"))]
+            let (python_stages, python_feedback) = ((), ());
+
             // Feedback to rate the interestingness of an input
-            // This one is composed by two Feedbacks in OR
+            // This one is composed by three Feedbacks in OR
             let mut feedback = feedback_or!(
                 // New maximization map feedback linked to the edges observer and the feedback state
                 map_feedback,
                 // Time feedback, this one does not need a feedback state
-                TimeFeedback::new(&time_observer)
+                TimeFeedback::new(&time_observer),
+                // The `is_interesting` function of the Python module, if any
+                python_feedback
             );
 
             // A feedback to choose if an input is a solution or not
@@ -295,7 +336,7 @@ impl ForkserverBytesCoverageSugar<'_> {
                 )));
 
                 // The order of the stages matter!
-                let mut stages = tuple_list!(calibration, tracing, i2s, power);
+                let mut stages = tuple_list!(calibration, tracing, i2s, power).merge(python_stages);
 
                 if let Some(iters) = self.iterations {
                     fuzzer.fuzz_loop_for(
@@ -310,7 +351,7 @@ impl ForkserverBytesCoverageSugar<'_> {
                 }
             } else {
                 // The order of the stages matter!
-                let mut stages = tuple_list!(calibration, power);
+                let mut stages = tuple_list!(calibration, power).merge(python_stages);
 
                 if let Some(iters) = self.iterations {
                     fuzzer.fuzz_loop_for(
@@ -367,12 +408,14 @@ pub mod pybind {
         iterations: Option<u64>,
         tokens_file: Option<PathBuf>,
         timeout: Option<u64>,
+        python_module: Option<String>,
     }
 
     #[pymethods]
     impl ForkserverBytesCoverageSugar {
         /// Create a new [`ForkserverBytesCoverageSugar`]
         #[new]
+        #[expect(clippy::too_many_arguments)]
         #[pyo3(signature = (
             input_dirs,
             output_dir,
@@ -380,7 +423,8 @@ pub mod pybind {
             cores,
             iterations=None,
             tokens_file=None,
-            timeout=None
+            timeout=None,
+            python_module=None
         ))]
         fn new(
             input_dirs: Vec<PathBuf>,
@@ -390,6 +434,7 @@ pub mod pybind {
             iterations: Option<u64>,
             tokens_file: Option<PathBuf>,
             timeout: Option<u64>,
+            python_module: Option<String>,
         ) -> Self {
             Self {
                 input_dirs,
@@ -399,6 +444,7 @@ pub mod pybind {
                 iterations,
                 tokens_file,
                 timeout,
+                python_module,
             }
         }
 
@@ -416,6 +462,7 @@ pub mod pybind {
                 .timeout(self.timeout)
                 .tokens_file(self.tokens_file.clone())
                 .iterations(self.iterations)
+                .python_module(self.python_module.clone())
                 .build()
                 .run();
         }
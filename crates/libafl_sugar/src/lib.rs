@@ -41,6 +41,8 @@
     )
 )]
 
+extern crate alloc;
+
 pub mod inprocess;
 pub use inprocess::InProcessBytesCoverageSugar;
 
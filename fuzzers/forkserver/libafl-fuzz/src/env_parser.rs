@@ -2,7 +2,9 @@ use std::{collections::HashMap, path::PathBuf, time::Duration};
 
 use libafl::{
     executors::forkserver::AFL_MAP_SIZE_ENV_VAR,
-    stages::afl_stats::AFL_FUZZER_STATS_UPDATE_INTERVAL_SECS, Error,
+    mutators::afl_custom::{AFL_CUSTOM_MUTATOR_LIBRARY_ENV, AFL_CUSTOM_MUTATOR_ONLY_ENV},
+    stages::afl_stats::AFL_FUZZER_STATS_UPDATE_INTERVAL_SECS,
+    Error,
 };
 use libafl_bolts::core_affinity::Cores;
 
@@ -114,6 +116,12 @@ pub fn parse_envs(opt: &mut Opt) -> Result<(), Error> {
     } else {
         opt.foreign_sync_interval = Duration::from_secs(AFL_DEFAULT_FOREIGN_SYNC_INTERVAL);
     }
+    if let Ok(res) = std::env::var(AFL_CUSTOM_MUTATOR_LIBRARY_ENV) {
+        opt.custom_mutator_library = Some(PathBuf::from(res));
+    }
+    if let Ok(res) = std::env::var(AFL_CUSTOM_MUTATOR_ONLY_ENV) {
+        opt.custom_mutator_only = parse_bool(&res)?;
+    }
     if let Ok(res) = std::env::var("AFL_USE_FASAN") {
         opt.frida_asan = parse_bool(&res)?;
     }
@@ -26,7 +26,13 @@ use libafl::{
     },
     fuzzer::StdFuzzer,
     inputs::BytesInput,
-    mutators::{havoc_mutations, tokens_mutations, AflppRedQueen, HavocScheduledMutator, Tokens},
+    mutators::{
+        afl_custom::{
+            AflCustomMutator, AflCustomMutatorLibrary, AflCustomPostProcess,
+            AflCustomQueueScheduler, AflCustomTrimMutator,
+        },
+        havoc_mutations, tokens_mutations, AflppRedQueen, HavocScheduledMutator, Tokens,
+    },
     observers::{CanTrack, HitcountsMapObserver, StdMapObserver, TimeObserver},
     schedulers::{
         powersched::{BaseSchedule, PowerSchedule},
@@ -36,8 +42,9 @@ use libafl::{
         afl_stats::{AflStatsStage, CalibrationTime, FuzzTime, SyncTime},
         mutational::MultiMutationalStage,
         time_tracker::TimeTrackingStageWrapper,
-        CalibrationStage, ColorizationStage, IfStage, StagesTuple, StdMutationalStage,
-        StdPowerMutationalStage, SyncFromDiskStage, VerifyTimeoutsStage,
+        CalibrationStage, ColorizationStage, IfStage, ObserverEqualityFactory, OptionalStage,
+        StagesTuple, StdMutationalStage, StdPowerMutationalStage, StdTMinMutationalStage,
+        SyncFromDiskStage, VerifyTimeoutsStage,
     },
     state::{
         HasCorpus, HasCurrentTestcase, HasExecutions, HasLastReportTime, HasStartTime, StdState,
@@ -75,6 +82,9 @@ use crate::{
     SHMEM_ENV_VAR,
 };
 
+/// How many candidates `afl_custom_trim` may propose per queue entry, counting again after each kept one
+const CUSTOM_TRIM_RUNS: usize = 1 << 10;
+
 pub type LibaflFuzzState =
     StdState<CachedOnDiskCorpus<BytesInput>, BytesInput, StdRand, OnDiskCorpus<BytesInput>>;
 
@@ -263,6 +273,21 @@ define_run_client!(state, mgr, fuzzer_dir, core_id, opt, is_main_node, {
         .unwrap()
     });
 
+    // Load the AFL++ custom mutator library, if configured
+    if opt.custom_mutator_only && opt.custom_mutator_library.is_none() {
+        return Err(Error::illegal_argument(
+            "AFL_CUSTOM_MUTATOR_ONLY requires AFL_CUSTOM_MUTATOR_LIBRARY",
+        ));
+    }
+    let custom_library = opt
+        .custom_mutator_library
+        .as_ref()
+        .map(|path| {
+            AflCustomMutatorLibrary::load(path, opt.rng_seed.unwrap_or_else(current_nanos) as u32)
+        })
+        .transpose()?
+        .map(Rc::new);
+
     // Create our Mutational Stage.
     // We can either have a simple MutationalStage (for Queue scheduling)
     // Or one that utilizes scheduling metadadata (Weighted Random scheduling)
@@ -275,7 +300,41 @@ define_run_client!(state, mgr, fuzzer_dir, core_id, opt, is_main_node, {
             PhantomData,
         )
     };
-    let mutational_stage = TimeTrackingStageWrapper::<FuzzTime, _, _>::new(inner_mutational_stage);
+    // With AFL_CUSTOM_MUTATOR_ONLY, only the custom mutator runs
+    let mutational_stage = TimeTrackingStageWrapper::<FuzzTime, _, _>::new(OptionalStage::new(
+        (!opt.custom_mutator_only).then(|| tuple_list!(inner_mutational_stage)),
+    ));
+
+    // Create the Mutational Stage of the custom mutator, if the library has `afl_custom_fuzz`
+    let custom_mutator = custom_library
+        .as_ref()
+        .filter(|library| library.has_fuzz())
+        .map(|library| AflCustomMutator::new(library.clone()))
+        .transpose()?;
+    let custom_mutational_stage =
+        TimeTrackingStageWrapper::<FuzzTime, _, _>::new(OptionalStage::new(
+            custom_mutator.map(|mutator| tuple_list!(StdMutationalStage::new(mutator))),
+        ));
+
+    // Trim each queue entry once, when it is first fuzzed, if the library has `afl_custom_trim`.
+    // Candidates are kept if they hit the same edges.
+    let custom_trim_mutator = custom_library
+        .as_ref()
+        .filter(|library| library.has_trim())
+        .map(|library| AflCustomTrimMutator::new(library.clone()))
+        .transpose()?;
+    let custom_trim_stage = IfStage::new(
+        |_fuzzer: &mut _, _executor: &mut _, state: &mut LibaflFuzzState, _manager: &mut _| {
+            Ok(state.current_testcase()?.scheduled_count() == 1)
+        },
+        tuple_list!(OptionalStage::new(custom_trim_mutator.map(|mutator| {
+            tuple_list!(StdTMinMutationalStage::new(
+                mutator,
+                ObserverEqualityFactory::new(&edges_observer),
+                CUSTOM_TRIM_RUNS,
+            ))
+        }))),
+    );
     let strategy = opt.power_schedule.unwrap_or(BaseSchedule::EXPLORE);
 
     // Create our ColorizationStage
@@ -300,8 +359,17 @@ define_run_client!(state, mgr, fuzzer_dir, core_id, opt, is_main_node, {
         );
     }
 
+    // Let the custom mutator library see new queue entries and skip some of them
+    let scheduler = AflCustomQueueScheduler::new(scheduler, custom_library.clone());
+
     // Create our Fuzzer
-    let mut fuzzer = StdFuzzer::new(scheduler, feedback, objective);
+    // The custom mutator library may post-process the bytes sent to the target
+    let mut fuzzer = StdFuzzer::builder()
+        .target_bytes_converter(AflCustomPostProcess::new(custom_library))
+        .scheduler(scheduler)
+        .feedback(feedback)
+        .objective(objective)
+        .build();
 
     // Set LD_PRELOAD (Linux) && DYLD_INSERT_LIBRARIES (OSX) for target.
     if let Some(preload_env) = &opt.afl_preload {
@@ -526,7 +594,9 @@ define_run_client!(state, mgr, fuzzer_dir, core_id, opt, is_main_node, {
         // The order of the stages matter!
         let mut stages = tuple_list!(
             calibration,
+            custom_trim_stage,
             cmplog,
+            custom_mutational_stage,
             mutational_stage,
             timeout_verify_stage,
             afl_stats_stage,
@@ -546,6 +616,8 @@ define_run_client!(state, mgr, fuzzer_dir, core_id, opt, is_main_node, {
         // The order of the stages matter!
         let mut stages = tuple_list!(
             calibration,
+            custom_trim_stage,
+            custom_mutational_stage,
             mutational_stage,
             timeout_verify_stage,
             afl_stats_stage,
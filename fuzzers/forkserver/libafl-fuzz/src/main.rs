@@ -322,6 +322,12 @@ struct Opt {
     #[clap(skip)]
     persistent_record: usize,
 
+    // Custom mutator config
+    #[clap(skip)]
+    custom_mutator_library: Option<PathBuf>,
+    #[clap(skip)]
+    custom_mutator_only: bool,
+
     // TODO:
     #[clap(skip)]
     frida_persistent_addr: Option<String>,